@@ -0,0 +1,73 @@
+/// Append-only history of the deployments of an account, recorded by jayce after publishing each
+/// package.
+module jayce_registry::registry {
+    use std::signer;
+    use std::string::String;
+    use std::vector;
+    use aptos_framework::event;
+    use aptos_framework::timestamp;
+
+    struct Deployment has copy, drop, store {
+        /// The name of the package in its Move.toml.
+        package_name: String,
+        /// The upgrade number of the package after the deployment, 0 for the first publication.
+        upgrade_number: u64,
+        package_address: address,
+        /// The digest of the sources of the package, as in its on-chain metadata.
+        source_digest: String,
+        timestamp_secs: u64,
+    }
+
+    /// The deployments recorded by an account, oldest first. They can't be changed or removed.
+    struct DeploymentHistory has key {
+        deployments: vector<Deployment>,
+    }
+
+    #[event]
+    struct DeploymentRecorded has drop, store {
+        deployer: address,
+        deployment: Deployment,
+    }
+
+    /// Records a deployment in the history of the deployer.
+    public entry fun record(
+        deployer: &signer,
+        package_name: String,
+        upgrade_number: u64,
+        package_address: address,
+        source_digest: String,
+    ) acquires DeploymentHistory {
+        let deployer_address = signer::address_of(deployer);
+        if (!exists<DeploymentHistory>(deployer_address)) {
+            move_to(deployer, DeploymentHistory { deployments: vector::empty() });
+        };
+        let deployment = Deployment {
+            package_name,
+            upgrade_number,
+            package_address,
+            source_digest,
+            timestamp_secs: timestamp::now_seconds(),
+        };
+        let history = borrow_global_mut<DeploymentHistory>(deployer_address);
+        vector::push_back(&mut history.deployments, deployment);
+        event::emit(DeploymentRecorded { deployer: deployer_address, deployment });
+    }
+
+    /// Returns the deployments recorded by `deployer`, oldest first.
+    #[view]
+    public fun deployments(deployer: address): vector<Deployment> acquires DeploymentHistory {
+        if (!exists<DeploymentHistory>(deployer)) {
+            return vector::empty()
+        };
+        borrow_global<DeploymentHistory>(deployer).deployments
+    }
+
+    /// Returns the number of deployments recorded by `deployer`.
+    #[view]
+    public fun deployment_count(deployer: address): u64 acquires DeploymentHistory {
+        if (!exists<DeploymentHistory>(deployer)) {
+            return 0
+        };
+        vector::length(&borrow_global<DeploymentHistory>(deployer).deployments)
+    }
+}
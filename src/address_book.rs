@@ -0,0 +1,87 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use aptos_sdk::move_types::account_address::AccountAddress;
+use serde::{Deserialize, Serialize};
+
+use crate::error::Result;
+
+/// Addresses of the deployed packages keyed by network then address name, shared across
+/// deployments so that packages can depend on packages deployed by previous runs.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct AddressBook {
+    #[serde(flatten)]
+    pub networks: BTreeMap<String, BTreeMap<String, AccountAddress>>,
+}
+
+impl AddressBook {
+    /// Reads an address book, which is empty if the file doesn't exist yet.
+    pub fn from_path(path: &Path) -> Result<AddressBook> {
+        if !path.exists() {
+            return Ok(AddressBook::default());
+        }
+        let content = fs::read_to_string(path)?;
+        Ok(toml::from_str(&content).map_err(anyhow::Error::from)?)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        fs::write(
+            path,
+            toml::to_string_pretty(self).map_err(anyhow::Error::from)?,
+        )?;
+        Ok(())
+    }
+
+    pub fn addresses(&self, network: &str) -> BTreeMap<String, AccountAddress> {
+        self.networks.get(network).cloned().unwrap_or_default()
+    }
+
+    pub fn insert(&mut self, network: &str, address_name: &str, address: AccountAddress) {
+        self.networks
+            .entry(network.to_string())
+            .or_default()
+            .insert(address_name.to_string(), address);
+    }
+}
+
+/// Records the address of a deployed package in the address book at `path`.
+pub fn record_address(
+    path: &Path,
+    network: &str,
+    address_name: &str,
+    address: AccountAddress,
+) -> Result<()> {
+    let mut address_book = AddressBook::from_path(path)?;
+    address_book.insert(network, address_name, address);
+    address_book.save(path)
+}
+
+#[cfg(test)]
+mod test {
+    use aptos_sdk::move_types::account_address::AccountAddress;
+
+    use crate::address_book::{record_address, AddressBook};
+
+    #[test]
+    fn test_address_book() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("address_book.toml");
+        assert_eq!(
+            AddressBook::from_path(&path).unwrap(),
+            AddressBook::default()
+        );
+
+        record_address(&path, "testnet", "lib_addr", AccountAddress::ONE).unwrap();
+        record_address(&path, "testnet", "cpu_addr", AccountAddress::TWO).unwrap();
+        record_address(&path, "mainnet", "lib_addr", AccountAddress::THREE).unwrap();
+        record_address(&path, "testnet", "lib_addr", AccountAddress::THREE).unwrap();
+
+        let address_book = AddressBook::from_path(&path).unwrap();
+        let testnet = address_book.addresses("testnet");
+        assert_eq!(testnet["lib_addr"], AccountAddress::THREE);
+        assert_eq!(testnet["cpu_addr"], AccountAddress::TWO);
+        assert_eq!(address_book.addresses("mainnet").len(), 1);
+        assert!(address_book.addresses("devnet").is_empty());
+    }
+}
@@ -0,0 +1,206 @@
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use aptos_sdk::crypto::HashValue;
+use tracing::{debug, warn};
+
+use crate::error::Result;
+use crate::git::remote_commit;
+use crate::move_package::MoveTomlFile;
+
+/// A cache of compiled packages in `~/.cache/jayce`, keyed by the hash of their sources, their
+/// `Move.toml`, the sources of their local dependencies, the commits of their git dependencies and
+/// the compilation options.
+pub struct ArtifactCache {
+    dir: PathBuf,
+}
+
+impl ArtifactCache {
+    /// Opens the cache in the cache directory of jayce.
+    pub fn open() -> Option<ArtifactCache> {
+        Some(ArtifactCache { dir: cache_dir()? })
+    }
+
+    /// Returns the cached artifact of a key, if there is one.
+    pub fn get(&self, key: &HashValue) -> Option<Vec<u8>> {
+        let content = fs::read(self.dir.join(key.to_hex())).ok()?;
+        debug!("Artifact cache hit: {}", key);
+        Some(content)
+    }
+
+    /// The key of a package compiled with `options`, see [`package_hash`]. There is none if it
+    /// can't be computed, e.g. when the commits of git dependencies can't be fetched, and the
+    /// package is then compiled without the cache.
+    pub fn key(&self, package_dir: &Path, options: &str) -> Option<HashValue> {
+        match package_hash(package_dir, options) {
+            Ok(key) => Some(key),
+            Err(err) => {
+                warn!(
+                    "Not using the artifact cache for {}: {}",
+                    package_dir.to_str().unwrap(),
+                    err
+                );
+                None
+            }
+        }
+    }
+
+    /// Stores an artifact. Failures are logged, since the cache is only an optimization.
+    pub fn put(&self, key: &HashValue, content: &[u8]) {
+        if let Err(err) = fs::create_dir_all(&self.dir)
+            .and_then(|_| fs::write(self.dir.join(key.to_hex()), content))
+        {
+            warn!("Failed to write to the artifact cache: {}", err);
+        }
+    }
+}
+
+/// The cache directory of jayce, `$XDG_CACHE_HOME/jayce` or `~/.cache/jayce`.
+pub fn cache_dir() -> Option<PathBuf> {
+    let cache_home = std::env::var("XDG_CACHE_HOME")
+        .map(PathBuf::from)
+        .or_else(|_| std::env::var("HOME").map(|home| Path::new(&home).join(".cache")))
+        .ok()?;
+    Some(cache_home.join("jayce"))
+}
+
+/// Hashes the sources and `Move.toml` of a package and of its local dependencies, and the commits
+/// of their git dependencies, along with the options it is compiled with, e.g. its named
+/// addresses. The commits of the branches and tags of git dependencies are fetched.
+pub fn package_hash(package_dir: &Path, options: &str) -> Result<HashValue> {
+    let mut input = options.as_bytes().to_vec();
+    let mut packages = BTreeSet::new();
+    let mut git_dependencies = BTreeSet::new();
+    collect_packages(package_dir, &mut packages, &mut git_dependencies)?;
+    // A branch or a tag may have moved since the package was cached.
+    for (url, rev) in &git_dependencies {
+        input.extend(format!("{} {}", url, remote_commit(url, rev)?).as_bytes());
+    }
+    for package in &packages {
+        input.extend(package.to_str().unwrap().as_bytes());
+        for file in package_files(package)? {
+            input.extend(
+                file.strip_prefix(package)
+                    .unwrap()
+                    .to_str()
+                    .unwrap()
+                    .as_bytes(),
+            );
+            input.extend(fs::read(&file)?);
+        }
+    }
+    Ok(HashValue::sha3_256_of(&input))
+}
+
+/// Collects a package and its local dependencies, recursively, and the URLs and revisions of
+/// their git dependencies.
+fn collect_packages(
+    package_dir: &Path,
+    packages: &mut BTreeSet<PathBuf>,
+    git_dependencies: &mut BTreeSet<(String, String)>,
+) -> Result<()> {
+    let package_dir = fs::canonicalize(package_dir)?;
+    if !packages.insert(package_dir.clone()) {
+        return Ok(());
+    }
+    for dependency in MoveTomlFile::from_package_dir(&package_dir)?
+        .dependencies
+        .values()
+    {
+        if let Some(local) = &dependency.local {
+            collect_packages(&package_dir.join(local), packages, git_dependencies)?;
+        }
+        if let (Some(git), Some(rev)) = (&dependency.git, &dependency.rev) {
+            git_dependencies.insert((git.clone(), rev.clone()));
+        }
+    }
+    Ok(())
+}
+
+/// Lists the files of a package, skipping hidden and `build` directories, in a stable order.
+fn package_files(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut entries = fs::read_dir(dir)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<std::io::Result<Vec<_>>>()?;
+    entries.sort();
+    let mut files = vec![];
+    for path in entries {
+        let name = path.file_name().unwrap().to_str().unwrap_or_default();
+        if name.starts_with('.') || name == "build" {
+            continue;
+        }
+        if path.is_dir() {
+            files.extend(package_files(&path)?);
+        } else {
+            files.push(path);
+        }
+    }
+    Ok(files)
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+
+    use crate::artifact_cache::package_hash;
+    use crate::git::git;
+
+    #[test]
+    fn test_package_hash() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let root = temp_dir.path();
+        let libs = root.join("libs");
+        fs::create_dir_all(libs.join("sources")).unwrap();
+        fs::create_dir_all(libs.join("build")).unwrap();
+        fs::write(
+            libs.join("Move.toml"),
+            "[package]\nname = \"libs\"\n\n[addresses]\nlib_addr = \"_\"\n",
+        )
+        .unwrap();
+        fs::write(libs.join("sources/lib.move"), "module lib_addr::lib {}").unwrap();
+
+        let hash = package_hash(&libs, "lib_addr=0x1").unwrap();
+        assert_ne!(hash, package_hash(&libs, "lib_addr=0x2").unwrap());
+        fs::write(libs.join("build/lib.mv"), "bytecode").unwrap();
+        assert_eq!(hash, package_hash(&libs, "lib_addr=0x1").unwrap());
+        fs::write(libs.join("sources/lib.move"), "module lib_addr::lib { }").unwrap();
+        assert_ne!(hash, package_hash(&libs, "lib_addr=0x1").unwrap());
+
+        // A git dependency is hashed with the commit its branch points to.
+        let framework = root.join("framework");
+        fs::create_dir_all(&framework).unwrap();
+        git(&framework, &["init", "-q"]).unwrap();
+        let commit = |message: &str| {
+            git(
+                &framework,
+                &[
+                    "-c",
+                    "user.name=jayce",
+                    "-c",
+                    "user.email=jayce@example.com",
+                    "commit",
+                    "-q",
+                    "--allow-empty",
+                    "-m",
+                    message,
+                ],
+            )
+            .unwrap()
+        };
+        commit("first");
+        fs::write(
+            libs.join("Move.toml"),
+            format!(
+                "[package]\nname = \"libs\"\n\n[addresses]\nlib_addr = \"_\"\n\n\
+                [dependencies]\nFramework = {{ git = \"{}\", rev = \"HEAD\" }}\n",
+                framework.to_str().unwrap()
+            ),
+        )
+        .unwrap();
+        let hash = package_hash(&libs, "lib_addr=0x1").unwrap();
+        assert_eq!(hash, package_hash(&libs, "lib_addr=0x1").unwrap());
+        commit("second");
+        assert_ne!(hash, package_hash(&libs, "lib_addr=0x1").unwrap());
+    }
+}
@@ -1,12 +1,55 @@
 use std::collections::BTreeMap;
 use std::env;
 use std::path::PathBuf;
+use std::process;
+use std::time::Duration;
 
-use anyhow::{ensure, Result};
+use anyhow::Result;
 use aptos_sdk::move_types::account_address::AccountAddress;
-use clap::{CommandFactory, Parser, Subcommand};
-use jayce::deploy_config::{AptosNetwork, DeployConfig, DeployModuleType, PartialDeployConfig};
-use jayce::tasks::deploy_contracts::deploy_contracts;
+use clap::parser::ValueSource;
+use clap::{ArgAction, ArgMatches, Args, CommandFactory, FromArgMatches, Parser, Subcommand};
+use clap_complete::engine::ArgValueCompleter;
+use clap_complete::{CompleteEnv, Shell};
+use jayce::color::{init_colors, ColorChoice};
+use jayce::completions::{
+    address_names, config_files, function_ids, write_registration, COMPLETE_ENV,
+};
+use jayce::deploy_config::{
+    read_mnemonic_file, resolve_private_key, select_private_key, AptosNetwork, CompilerOptions,
+    ConfigFormat, ConfigLayers, DeployConfig, DeployModuleType, PartialDeployConfig,
+    DEFAULT_DERIVATION_PATH,
+};
+use jayce::deploy_report::ReportMode;
+use jayce::entry_function::{EntryFunctionCall, ScriptCall};
+use jayce::error::{exit_code, JayceError};
+use jayce::github_actions;
+use jayce::logging::{init_logging, LogFormat};
+use jayce::redact::{install_panic_hook, redact};
+use jayce::registry::RegistryConfig;
+use jayce::report_writer::ReportFormat;
+use jayce::retry::RetryPolicy;
+use jayce::tasks::account::generate_account;
+use jayce::tasks::build::build;
+use jayce::tasks::call::call;
+use jayce::tasks::config::{get_config_value, set_config_value, show_config};
+use jayce::tasks::deploy_contracts::{deploy_contracts, deploy_contracts_to_networks};
+use jayce::tasks::diff::diff_reports;
+use jayce::tasks::faucet::faucet;
+use jayce::tasks::fund::fund;
+use jayce::tasks::init::init;
+use jayce::tasks::key::{ephemeral_key, export_key, import_key};
+use jayce::tasks::localnet::{localnet_status, run_localnet, start_localnet, stop_localnet};
+use jayce::tasks::multisig::{approve_proposals, execute_proposals};
+use jayce::tasks::report::{verify_report, verify_report_signature};
+use jayce::tasks::reproduce::reproduce;
+use jayce::tasks::run_script::run_script_command;
+use jayce::tasks::status::{status_from_address_book, status_from_report};
+use jayce::tasks::verify::{verify, verify_source};
+use jayce::tasks::view::view;
+use jayce::telemetry::init_telemetry;
+use jayce::tui::{start_dashboard, stop_dashboard};
+use jayce::utils::DEFAULT_FAUCET_AMOUNT;
+use tracing::error;
 
 #[derive(Parser, Debug)]
 #[command(name = "jayce")]
@@ -17,154 +60,1412 @@ struct Cli {
     command: Option<Commands>,
 
     /// Display the version of the CLI tool
-    #[clap(short, long)]
+    #[clap(short = 'V', long)]
     version: bool,
+
+    /// Increase the verbosity of the logs, -v for debug and -vv for trace
+    #[arg(short, long, action = ArgAction::Count, global = true)]
+    verbose: u8,
+
+    /// Only log errors
+    #[arg(short, long, global = true, conflicts_with = "verbose")]
+    quiet: bool,
+
+    /// The format of the logs
+    #[arg(long, global = true, default_value_t = LogFormat::Text)]
+    log_format: LogFormat,
+
+    /// When to color the logs, prompts and progress. `auto` colors them when stderr is a terminal
+    /// and NO_COLOR isn't set
+    #[arg(long, global = true, default_value_t = ColorChoice::Auto)]
+    color: ColorChoice,
+
+    /// Run as if jayce was started in this directory
+    #[arg(short = 'C', long, global = true)]
+    workdir: Option<PathBuf>,
+
+    /// Export the spans and metrics to this OpenTelemetry collector over OTLP/HTTP, e.g.
+    /// http://localhost:4318
+    #[arg(long, global = true)]
+    otlp_endpoint: Option<String>,
+
+    /// Write GitHub Actions annotations, step summary and outputs, enabled by default when
+    /// GITHUB_ACTIONS is set
+    #[arg(long, global = true, default_value_t = false)]
+    gha: bool,
 }
 
 #[derive(Subcommand, Clone, Debug, PartialEq)]
 enum Commands {
     /// Deploy contracts
-    Deploy {
-        /// The private key used for deployment
+    Deploy(DeployArgs),
+    /// Call an entry function of a deployed package
+    Call {
+        /// The function to call, e.g. verifier_addr::verifier::register_fact
+        #[arg(long, add = ArgValueCompleter::new(function_ids))]
+        function_id: String,
+        /// Type arguments of the function, separated by spaces
+        #[arg(long, num_args = 1..)]
+        type_args: Vec<String>,
+        /// Arguments of the function as <type>:<value>, separated by spaces, e.g.
+        /// address:lib_addr u64:1
+        #[arg(long, num_args = 1..)]
+        args: Vec<String>,
+        /// The private key signing the transaction, or `env:<VARIABLE>` / `file:<PATH>` to read it
+        /// from an environment variable or a file
+        #[arg(long)]
+        private_key: String,
+        /// The network of the transaction, defaults to the network of the deployment report
         #[arg(long)]
+        network: Option<AptosNetwork>,
+        /// REST url for the network, used for local network
+        #[arg(long)]
+        rest_url: Option<String>,
+        /// The deployment report used to resolve address names
+        #[arg(long, default_value = "deploy-report.json")]
+        report_path: PathBuf,
+        /// A map of deployed addresses, overriding the deployment report, e.g.
+        /// addr_1=0x1,addr_2=0x2
+        #[arg(
+            long,
+            value_parser = aptos::common::utils::parse_map::<String, AccountAddress>,
+            default_value = ""
+        )]
+        deployed_addresses: BTreeMap<String, AccountAddress>,
+        /// Automatically confirm prompts
+        #[arg(short, long, default_value_t = false)]
+        yes: bool,
+    },
+    /// Compile and run a Move script of a package
+    RunScript {
+        /// The package containing the script
+        #[arg(long)]
+        package_dir: PathBuf,
+        /// The name of the script, e.g. init for scripts/init.move
+        #[arg(long)]
+        script: String,
+        /// Type arguments of the script, separated by spaces
+        #[arg(long, num_args = 1..)]
+        type_args: Vec<String>,
+        /// Arguments of the script as <type>:<value>, separated by spaces, e.g.
+        /// address:lib_addr u64:1
+        #[arg(long, num_args = 1..)]
+        args: Vec<String>,
+        /// The private key signing the transaction, or `env:<VARIABLE>` / `file:<PATH>` to read it
+        /// from an environment variable or a file
+        #[arg(long, required_unless_present = "profile")]
         private_key: Option<String>,
-        /// The type of module to deploy
-        #[arg(long, default_value_t = DeployModuleType::Object)]
-        module_type: DeployModuleType,
-        /// Paths to the modules to be deployed, separated by commas
+        /// An aptos CLI profile signing the transaction instead of the private key
+        #[arg(long, conflicts_with = "private_key")]
+        profile: Option<String>,
+        /// The network of the transaction, defaults to the network of the deployment report
+        #[arg(long)]
+        network: Option<AptosNetwork>,
+        /// REST url for the network, used for local network
+        #[arg(long)]
+        rest_url: Option<String>,
+        /// The deployment report used to resolve address names
+        #[arg(long, default_value = "deploy-report.json")]
+        report_path: PathBuf,
+        /// A map of deployed addresses, overriding the deployment report, e.g.
+        /// addr_1=0x1,addr_2=0x2
+        #[arg(
+            long,
+            value_parser = aptos::common::utils::parse_map::<String, AccountAddress>,
+            default_value = ""
+        )]
+        deployed_addresses: BTreeMap<String, AccountAddress>,
+        /// Automatically confirm prompts
+        #[arg(short, long, default_value_t = false)]
+        yes: bool,
+    },
+    /// Manage accounts
+    Account {
+        #[command(subcommand)]
+        command: AccountCommands,
+    },
+    /// Fund an account from the faucet of the network
+    Faucet {
+        /// The account to fund
+        #[arg(long)]
+        address: AccountAddress,
+        /// The amount to fund, in octas
+        #[arg(long, default_value_t = DEFAULT_FAUCET_AMOUNT)]
+        amount: u64,
+        /// The network of the account
+        #[arg(long, default_value = "devnet")]
+        network: AptosNetwork,
+        /// REST url for the network, used for local network
+        #[arg(long)]
+        rest_url: Option<String>,
+        /// Faucet url for the network, used for local network. Repeat it to fall back on other
+        /// faucets when one fails
+        #[arg(long = "faucet-url")]
+        faucet_urls: Vec<String>,
+        /// Maximum number of attempts of the faucet requests failing with transient errors
+        #[arg(long)]
+        retry_attempts: Option<u32>,
+    },
+    /// Transfer APT to an account, e.g. to fund a deployer on mainnet
+    Fund {
+        /// The account to fund
+        #[arg(long)]
+        to: AccountAddress,
+        /// The amount to transfer, in octas
+        #[arg(long)]
+        amount: u64,
+        /// The private key of the funding account, or `env:<VARIABLE>` / `file:<PATH>` /
+        /// `keystore:<PATH>` to read it from an environment variable, a file or a keystore
+        #[arg(long, required_unless_present = "profile")]
+        private_key: Option<String>,
+        /// An aptos CLI profile of the funding account instead of the private key
+        #[arg(long, conflicts_with = "private_key")]
+        profile: Option<String>,
+        /// The network of the accounts
+        #[arg(long, default_value = "devnet")]
+        network: AptosNetwork,
+        /// REST url for the network, used for local network
+        #[arg(long)]
+        rest_url: Option<String>,
+        /// Transfer without confirming at a prompt
+        #[arg(short, long, default_value_t = false)]
+        yes: bool,
+    },
+    /// Print the script registering the completions of jayce in a shell, e.g.
+    /// `source <(jayce completions bash)`
+    Completions {
+        /// The shell to complete jayce in
+        shell: Shell,
+    },
+    /// Inspect the configuration of a deployment
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommands,
+    },
+    /// Create a deploy configuration file interactively
+    Init {
+        /// The directory searched for Move packages
+        #[arg(long, default_value = ".")]
+        root: PathBuf,
+        /// The path of the configuration file to write
+        #[arg(long, default_value = "deploy.toml")]
+        output: PathBuf,
+    },
+    /// Manage encrypted keystores
+    Key {
+        #[command(subcommand)]
+        command: KeyCommands,
+    },
+    /// Manage a local network
+    Localnet {
+        #[command(subcommand)]
+        command: LocalnetCommands,
+    },
+    /// Approve and execute multisig deployment proposals
+    Multisig {
+        #[command(subcommand)]
+        command: MultisigCommands,
+    },
+    /// Check deployment reports
+    Report {
+        #[command(subcommand)]
+        command: ReportCommands,
+    },
+    /// Check the deployed packages against the chain and the local sources
+    Status {
+        /// The deployment report listing the packages to check
+        #[arg(long, default_value = "deploy-report.json")]
+        report_path: PathBuf,
+        /// Check the addresses of an address book instead of a deployment report
+        #[arg(long)]
+        address_book: Option<PathBuf>,
+        /// The network of the address book
+        #[arg(long, default_value = "devnet")]
+        network: AptosNetwork,
+        /// REST url for the network, used for local network
+        #[arg(long)]
+        rest_url: Option<String>,
+    },
+    /// Print what changed between two deployment reports
+    Diff {
+        /// The earlier deployment report
+        old_report: PathBuf,
+        /// The later deployment report
+        new_report: PathBuf,
+    },
+    /// Execute a view function of a deployed package and print its result as JSON
+    View {
+        /// The function to execute, e.g. verifier_addr::fact_registry::is_valid
+        #[arg(long, add = ArgValueCompleter::new(function_ids))]
+        function_id: String,
+        /// Type arguments of the function, separated by spaces
+        #[arg(long, num_args = 1..)]
+        type_args: Vec<String>,
+        /// Arguments of the function as <type>:<value>, separated by spaces, e.g.
+        /// address:lib_addr u64:1
+        #[arg(long, num_args = 1..)]
+        args: Vec<String>,
+        /// The network to query, defaults to the network of the deployment report
+        #[arg(long)]
+        network: Option<AptosNetwork>,
+        /// REST url for the network, used for local network
+        #[arg(long)]
+        rest_url: Option<String>,
+        /// The deployment report used to resolve address names
+        #[arg(long, default_value = "deploy-report.json")]
+        report_path: PathBuf,
+        /// A map of deployed addresses, overriding the deployment report, e.g.
+        /// addr_1=0x1,addr_2=0x2
+        #[arg(
+            long,
+            value_parser = aptos::common::utils::parse_map::<String, AccountAddress>,
+            default_value = ""
+        )]
+        deployed_addresses: BTreeMap<String, AccountAddress>,
+    },
+    /// Compile every package as a deployment would, without publishing anything
+    Build {
+        /// Paths to the packages to compile, separated by commas. Glob patterns and directories
+        /// are expanded into the Move packages they contain
         #[arg(long, num_args = 1.., value_delimiter = ',')]
         modules_path: Option<Vec<PathBuf>>,
-        /// Names of the addresses corresponding to the modules (must identify with your Move.toml), separated by commas
-        #[arg(long, num_args = 1.., value_delimiter = ',')]
+        /// Names of the addresses corresponding to the packages, separated by commas. Inferred
+        /// from the Move.toml files when omitted
+        #[arg(
+            long,
+            num_args = 1..,
+            value_delimiter = ',',
+            add = ArgValueCompleter::new(address_names)
+        )]
         addresses_name: Option<Vec<String>>,
-        /// The network to deploy to
-        #[arg(long, default_value_t = AptosNetwork::Devnet)]
-        network: AptosNetwork,
-        /// The path to the output JSON file for the deployment report
+        /// Compile every Move package found in this directory
+        #[arg(long, conflicts_with_all = ["modules_path", "addresses_name"])]
+        workspace: Option<PathBuf>,
+        /// A map of already deployed addresses, e.g. addr_1=0x1,addr_2=0x2. Other addresses are
+        /// replaced with placeholders
+        #[arg(long, value_parser = aptos::common::utils::parse_map::<String, AccountAddress>)]
+        deployed_addresses: Option<BTreeMap<String, AccountAddress>>,
+        /// Path to the configuration file
+        #[arg(long, add = ArgValueCompleter::new(config_files))]
+        config_path: Option<PathBuf>,
+        /// Format of the configuration file, detected from its extension by default
+        #[arg(long)]
+        config_format: Option<ConfigFormat>,
+        /// The environment of the configuration file, e.g. testnet
+        #[arg(long = "env", requires = "config_path")]
+        environment: Option<String>,
+        /// Recompile the packages even if they didn't change since their last build
+        #[arg(long, default_value_t = false)]
+        no_cache: bool,
+        /// Write the compiled packages to a bundle deployable with `deploy --bundle`: a directory,
+        /// or an archive ending with .tar.gz, .tgz, .tar or .zip
+        #[arg(long)]
+        artifacts_dir: Option<PathBuf>,
+        #[command(flatten)]
+        compiler: CompilerArgs,
+    },
+    /// Verify that the deployed modules match the local sources
+    Verify {
+        /// The path to the deployment report produced by `deploy`
         #[arg(long, default_value = "deploy-report.json")]
-        output_json: PathBuf,
-        /// A map of already deployed addresses, e.g. addr_1=0x1,addr_2=0x2
-        #[arg(long, value_parser = aptos::common::utils::parse_map::<String, AccountAddress>, default_value = "")]
-        deployed_addresses: BTreeMap<String, AccountAddress>,
+        report_path: PathBuf,
         /// REST url for the network, used for local network
         #[arg(long)]
         rest_url: Option<String>,
-        /// Faucet url for the network, used when private key is not provided
+    },
+    /// Verify with the aptos CLI that the published sources of the packages match the local ones
+    VerifySource {
+        /// The path to the deployment report produced by `deploy`
+        #[arg(long, default_value = "deploy-report.json")]
+        report_path: PathBuf,
+        /// REST url for the network, used for local network
         #[arg(long)]
-        faucet_url: Option<String>,
-        /// Publish your code onchain
+        rest_url: Option<String>,
+    },
+    /// Rebuild the packages of a deployment report from the git commit and compiler options it
+    /// records, and check that the bytecode matches the published checksums
+    Reproduce {
+        /// The path to the deployment report produced by `deploy`
+        #[arg(long, default_value = "deploy-report.json")]
+        report_path: PathBuf,
+    },
+}
+
+#[derive(Subcommand, Clone, Debug, PartialEq)]
+enum AccountCommands {
+    /// Generate an account, write its private key to a file and print its address
+    Generate {
+        /// The path of the private key file to create
+        #[arg(long)]
+        key_file: PathBuf,
+        /// Fund the account from the faucet of the network
         #[arg(long, default_value_t = false)]
-        publish_code: bool,
+        fund: bool,
+        /// The network of the faucet
+        #[arg(long, default_value = "devnet")]
+        network: AptosNetwork,
+        /// REST url for the network, used for local network
+        #[arg(long)]
+        rest_url: Option<String>,
+        /// Faucet url for the network, used for local network. Repeat it to fall back on other
+        /// faucets when one fails
+        #[arg(long = "faucet-url")]
+        faucet_urls: Vec<String>,
+    },
+}
+
+/// The options of a deployment, overriding the configuration file.
+#[derive(Args, Clone, Debug, PartialEq)]
+struct DeployArgs {
+    /// The private key used for deployment, or `env:<VARIABLE>` / `file:<PATH>` /
+    /// `keystore:<PATH>` to read it from an environment variable, a file or a keystore
+    #[arg(long)]
+    private_key: Option<String>,
+    /// Path to a file containing the private key used for deployment
+    #[arg(long)]
+    private_key_file: Option<PathBuf>,
+    /// Path to a file containing the mnemonic phrase of a wallet, from which the private key
+    /// is derived
+    #[arg(long, conflicts_with_all = ["private_key", "private_key_file"])]
+    mnemonic_file: Option<PathBuf>,
+    /// The BIP-44 derivation path of the account of the mnemonic phrase
+    #[arg(long, default_value = DEFAULT_DERIVATION_PATH)]
+    derivation_path: String,
+    /// An existing aptos CLI profile providing the private key, network and REST URL
+    #[arg(long, env = "JAYCE_PROFILE")]
+    profile: Option<String>,
+    /// The account of the deployer, when its key was rotated. Defaults to the account the key
+    /// was rotated into
+    #[arg(long, env = "JAYCE_SENDER_ADDRESS")]
+    sender_address: Option<AccountAddress>,
+    /// The type of module to deploy
+    #[arg(long, env = "JAYCE_MODULE_TYPE", default_value_t = DeployModuleType::Object)]
+    module_type: DeployModuleType,
+    /// Paths to the modules to be deployed, separated by commas. Glob patterns and directories
+    /// are expanded into the Move packages they contain
+    #[arg(long, env = "JAYCE_MODULES_PATH", num_args = 1.., value_delimiter = ',')]
+    modules_path: Option<Vec<PathBuf>>,
+    /// Deploy every Move package found in this directory, inferring their address names and
+    /// deployment order, instead of --modules-path and --addresses-name
+    #[arg(long, env = "JAYCE_WORKSPACE", conflicts_with_all = ["modules_path", "addresses_name"])]
+    workspace: Option<PathBuf>,
+    /// Names of the addresses corresponding to the modules (must identify with your Move.toml),
+    /// separated by commas. Inferred from the Move.toml files when omitted
+    #[arg(
+        long,
+        env = "JAYCE_ADDRESSES_NAME",
+        num_args = 1..,
+        value_delimiter = ',',
+        add = ArgValueCompleter::new(address_names)
+    )]
+    addresses_name: Option<Vec<String>>,
+    /// The networks to deploy to, separated by commas, e.g. testnet,devnet
+    #[arg(
+        long,
+        env = "JAYCE_NETWORK",
+        value_delimiter = ',',
+        default_value = "devnet"
+    )]
+    network: Vec<AptosNetwork>,
+    /// The path to the output JSON file for the deployment report
+    #[arg(long, env = "JAYCE_OUTPUT_JSON", default_value = "deploy-report.json")]
+    output_json: PathBuf,
+    /// The format of the deployment report
+    #[arg(long, env = "JAYCE_OUTPUT_FORMAT", default_value_t = ReportFormat::Json)]
+    output_format: ReportFormat,
+    /// How the deployment report is combined with an existing one
+    #[arg(long, env = "JAYCE_REPORT_MODE", default_value_t = ReportMode::Overwrite)]
+    report_mode: ReportMode,
+    /// A map of already deployed addresses, e.g. addr_1=0x1,addr_2=0x2
+    #[arg(
+        long,
+        env = "JAYCE_DEPLOYED_ADDRESSES",
+        value_parser = aptos::common::utils::parse_map::<String, AccountAddress>,
+        default_value = ""
+    )]
+    deployed_addresses: BTreeMap<String, AccountAddress>,
+    /// REST url for the network, used for local network
+    #[arg(long, env = "JAYCE_REST_URL")]
+    rest_url: Option<String>,
+    /// Faucet url for the network, used when private key is not provided
+    #[arg(long, env = "JAYCE_FAUCET_URL")]
+    faucet_url: Option<String>,
+    /// Publish your code onchain
+    #[arg(long, env = "JAYCE_PUBLISH_CODE", default_value_t = false)]
+    publish_code: bool,
+    /// Automatically confirm prompts
+    #[arg(short, long, env = "JAYCE_YES", default_value_t = false)]
+    yes: bool,
+    /// Deploy to mainnet without typing the network name to confirm, required with --yes
+    #[arg(long, env = "JAYCE_ALLOW_MAINNET", default_value_t = false)]
+    allow_mainnet: bool,
+    /// Resume a failed deployment from the state file
+    #[arg(long, env = "JAYCE_RESUME", default_value_t = false)]
+    resume: bool,
+    /// Show a dashboard of the packages, logs, gas spent and network status instead of the
+    /// logs. Prompts can't be answered in the dashboard, so it requires --yes
+    #[arg(long, default_value_t = false, requires = "yes")]
+    tui: bool,
+    /// Take over the lock of another deployment in the same directory, e.g. one that was
+    /// killed
+    #[arg(long, env = "JAYCE_FORCE", default_value_t = false)]
+    force: bool,
+    /// The path to the state file recording the progress of the deployment
+    #[arg(long, env = "JAYCE_STATE_PATH", default_value = ".jayce-state.json")]
+    state_path: PathBuf,
+    /// The path of the file the private key of a generated account is written to
+    #[arg(
+        long,
+        env = "JAYCE_GENERATED_KEY_PATH",
+        default_value = ".jayce-generated-key"
+    )]
+    generated_key_path: PathBuf,
+    /// Print the private key of a generated account, which then shows up in logs
+    #[arg(long, env = "JAYCE_PRINT_KEY", default_value_t = false)]
+    print_key: bool,
+    /// Simulate the deployment and print the predicted addresses and gas costs without
+    /// submitting any transaction
+    #[arg(long, env = "JAYCE_DRY_RUN", default_value_t = false)]
+    dry_run: bool,
+    /// Don't check the deployer's balance against the estimated cost of the deployment
+    #[arg(long, env = "JAYCE_SKIP_BALANCE_CHECK", default_value_t = false)]
+    skip_balance_check: bool,
+    /// Propose the deployment to this multisig account instead of publishing the packages
+    #[arg(long, env = "JAYCE_MULTISIG_ADDRESS")]
+    multisig_address: Option<AccountAddress>,
+    /// The private key of an account paying the gas of the publish transactions
+    #[arg(long, env = "JAYCE_FEE_PAYER_PRIVATE_KEY")]
+    fee_payer_private_key: Option<String>,
+    /// Maximum amount of gas units of each transaction
+    #[arg(long, env = "JAYCE_MAX_GAS")]
+    max_gas: Option<u64>,
+    /// Gas unit price of each transaction, in Octas
+    #[arg(long, env = "JAYCE_GAS_UNIT_PRICE")]
+    gas_unit_price: Option<u64>,
+    /// Number of seconds before each transaction expires
+    #[arg(long, env = "JAYCE_EXPIRATION_SECS")]
+    expiration_secs: Option<u64>,
+    /// Maximum fee of each publish transaction, in Octas. Packages are simulated first, and
+    /// publishing one costing more is aborted, or confirmed interactively without --yes
+    #[arg(long, env = "JAYCE_MAX_FEE")]
+    max_fee: Option<u64>,
+    /// Simulate each publication before submitting it, failing with the reason of the
+    /// simulation failure. Defaults to true on mainnet
+    #[arg(long, env = "JAYCE_SIMULATE_FIRST")]
+    simulate_first: Option<bool>,
+    /// Number of seconds to wait for each publish transaction submitted through the REST API to
+    /// be committed. The transactions of the aptos CLI are waited for by the aptos CLI
+    #[arg(long, env = "JAYCE_CONFIRMATION_TIMEOUT_SECS")]
+    confirmation_timeout_secs: Option<u64>,
+    /// Delay between two polls of a pending publish transaction submitted through the REST API,
+    /// in milliseconds
+    #[arg(long, env = "JAYCE_POLL_INTERVAL_MS")]
+    poll_interval_ms: Option<u64>,
+    /// Whether the network supports chunked publish, defaults to the support of the network
+    #[arg(long, env = "JAYCE_CHUNKED_PUBLISH")]
+    chunked_publish: Option<bool>,
+    /// Fail instead of using chunked publish for packages that are too large
+    #[arg(long, env = "JAYCE_NO_CHUNKED", default_value_t = false)]
+    no_chunked: bool,
+    /// The address of the chunked publish module, defaults to the one of the network
+    #[arg(long, env = "JAYCE_LARGE_PACKAGES_MODULE_ADDRESS")]
+    large_packages_module_address: Option<AccountAddress>,
+    /// Publish packages larger than the transaction size limit in a single transaction instead
+    /// of with chunked publish
+    #[arg(long, env = "JAYCE_OVERRIDE_SIZE_CHECK", default_value_t = false)]
+    override_size_check: bool,
+    /// The size of the chunks of chunked publish in bytes, defaults to the one of the aptos CLI
+    #[arg(long, env = "JAYCE_CHUNK_SIZE")]
+    chunk_size: Option<usize>,
+    /// The address book file recording the deployed packages of every network, e.g.
+    /// jayce-addresses.toml
+    #[arg(long, env = "JAYCE_ADDRESS_BOOK")]
+    address_book: Option<PathBuf>,
+    /// Keep deploying the packages that don't depend on a package that failed to deploy
+    #[arg(long, env = "JAYCE_KEEP_GOING", default_value_t = false)]
+    keep_going: bool,
+    /// Recompile the packages instead of reading them from the artifact cache
+    #[arg(long, env = "JAYCE_NO_CACHE", default_value_t = false)]
+    no_cache: bool,
+    #[command(flatten)]
+    compiler: CompilerArgs,
+    /// Only deploy the packages whose sources changed since the commit recorded in the
+    /// report, and the packages depending on them
+    #[arg(long, env = "JAYCE_CHANGED_ONLY", default_value_t = false)]
+    changed_only: bool,
+    /// Sign the report with the key of the deployer, see `jayce report verify-signature`
+    #[arg(long, env = "JAYCE_SIGN_REPORT", default_value_t = false)]
+    sign_report: bool,
+    /// Record every deployed package in an on-chain registry, published at the deployer's
+    /// account unless the [registry] table sets its address
+    #[arg(long, env = "JAYCE_REGISTRY", default_value_t = false)]
+    registry: bool,
+    /// Submit the transactions back-to-back from a locally tracked sequence number, then wait
+    /// for all of them, instead of waiting for each one
+    #[arg(long, env = "JAYCE_PIPELINE", default_value_t = false)]
+    pipeline: bool,
+    /// Copy the published package-metadata.bcs and bytecode modules of every package to a
+    /// subdirectory of this directory per run
+    #[arg(long, env = "JAYCE_ARTIFACTS_DIR")]
+    artifacts_dir: Option<PathBuf>,
+    /// Publish the packages of a bundle written by `build --artifacts-dir`, without compiling
+    /// them again
+    #[arg(
+        long,
+        env = "JAYCE_BUNDLE",
+        conflicts_with_all = ["modules_path", "addresses_name", "workspace"]
+    )]
+    bundle: Option<PathBuf>,
+    /// Publish the sources of the packages (`--included-artifacts sparse`) and verify them
+    /// against the published packages
+    #[arg(long, env = "JAYCE_VERIFY_SOURCE", default_value_t = false)]
+    verify_source: bool,
+    /// Append the lifecycle events of the packages (package_started, compiled, submitted,
+    /// confirmed, skipped, failed) to this file, as JSON Lines
+    #[arg(long, env = "JAYCE_EVENTS_FILE")]
+    events_file: Option<PathBuf>,
+    /// Write the lifecycle events of the packages to stdout, as JSON Lines
+    #[arg(
+        long,
+        env = "JAYCE_JSON_EVENTS",
+        default_value_t = false,
+        conflicts_with = "events_file"
+    )]
+    json_events: bool,
+    /// A previous deployment report, whose packages are used as deployed addresses
+    #[arg(long, env = "JAYCE_FROM_REPORT")]
+    from_report: Option<PathBuf>,
+    /// Maximum number of attempts of the faucet requests and transaction submissions failing
+    /// with transient errors
+    #[arg(long, env = "JAYCE_RETRY_ATTEMPTS")]
+    retry_attempts: Option<u32>,
+    /// Delay before the first retry, in milliseconds, doubled after each retry
+    #[arg(long, env = "JAYCE_RETRY_BACKOFF_MS")]
+    retry_backoff_ms: Option<u64>,
+    /// Path to the configuration file
+    #[arg(long, add = ArgValueCompleter::new(config_files))]
+    config_path: Option<PathBuf>,
+    /// Format of the configuration file, detected from its extension by default
+    #[arg(long)]
+    config_format: Option<ConfigFormat>,
+    /// The environment of the configuration file to deploy to, e.g. testnet
+    #[arg(long = "env", requires = "config_path")]
+    environment: Option<String>,
+}
+
+#[derive(Subcommand, Clone, Debug, PartialEq)]
+enum ConfigCommands {
+    /// Print the configuration a deployment with these options would use, merged from the
+    /// command line, the configuration file and the defaults, with the source of each field
+    Show(DeployArgs),
+    /// Print the value of a key of a TOML configuration file, e.g. package_options.lib_addr.freeze
+    Get {
+        key: String,
+        /// Path to the configuration file
+        #[arg(long, default_value = "deploy.toml", add = ArgValueCompleter::new(config_files))]
+        config_path: PathBuf,
+    },
+    /// Set a key of a TOML configuration file, keeping its comments
+    Set {
+        key: String,
+        /// The value, parsed as TOML, e.g. testnet, true or ["a", "b"]
+        value: String,
+        /// Path to the configuration file
+        #[arg(long, default_value = "deploy.toml", add = ArgValueCompleter::new(config_files))]
+        config_path: PathBuf,
+    },
+}
+
+#[derive(Subcommand, Clone, Debug, PartialEq)]
+enum KeyCommands {
+    /// Encrypt a private key into a keystore file
+    Import {
+        /// The private key to encrypt, or `env:<VARIABLE>` / `file:<PATH>` to read it from an
+        /// environment variable or a file
+        #[arg(long)]
+        private_key: String,
+        /// The path of the keystore file to write
+        #[arg(long)]
+        keystore: PathBuf,
+    },
+    /// Decrypt a keystore file and print its private key
+    Export {
+        /// The path of the keystore file
+        #[arg(long)]
+        keystore: PathBuf,
+        /// Print the private key without asking for confirmation
+        #[arg(short, long, default_value_t = false)]
+        yes: bool,
+    },
+    /// Generate the ephemeral key of a keyless signer and print the nonce of its JWT
+    Ephemeral {
+        /// The path of the ephemeral key file to write
+        #[arg(long, default_value = ".jayce-ephemeral-key.json")]
+        path: PathBuf,
+        /// How long the key is valid for, in seconds
+        #[arg(long, default_value_t = 3600)]
+        expiry_secs: u64,
+    },
+}
+
+#[derive(Subcommand, Clone, Debug, PartialEq)]
+enum LocalnetCommands {
+    /// Start a local network in the background and wait until its REST API and faucet are ready
+    Start {
+        /// The directory holding the data of the local network
+        #[arg(long, default_value = ".jayce/localnet")]
+        test_dir: PathBuf,
+        /// Stop the running local network and delete its data before starting
+        #[arg(long, default_value_t = false)]
+        force_restart: bool,
+        /// How long to wait for the local network to be ready, in seconds
+        #[arg(long, default_value_t = 120)]
+        timeout_secs: u64,
+        /// The file recording the running local network
+        #[arg(long, default_value = ".jayce/localnet.json")]
+        state_path: PathBuf,
+    },
+    /// Stop the local network started with `start`
+    Stop {
+        /// The file recording the running local network
+        #[arg(long, default_value = ".jayce/localnet.json")]
+        state_path: PathBuf,
+    },
+    /// Print whether the local network started with `start` is running and ready
+    Status {
+        /// The file recording the running local network
+        #[arg(long, default_value = ".jayce/localnet.json")]
+        state_path: PathBuf,
+    },
+    /// Run a local network in the foreground until it is interrupted
+    Run {
+        /// The directory holding the data of the local network
+        #[arg(long, default_value = ".jayce/localnet")]
+        test_dir: PathBuf,
+        /// Delete the data of the previous local network before starting
+        #[arg(long, default_value_t = false)]
+        force_restart: bool,
+    },
+}
+
+#[derive(Subcommand, Clone, Debug, PartialEq)]
+enum MultisigCommands {
+    /// Approve the proposals recorded in a deployment report
+    Approve {
+        /// The path to the deployment report produced by `deploy --multisig-address`
+        #[arg(long, default_value = "deploy-report.json")]
+        report_path: PathBuf,
+        /// The private key of a multisig owner, or `env:<VARIABLE>` / `file:<PATH>` /
+        /// `keystore:<PATH>` to read it from an environment variable, a file or a keystore
+        #[arg(long)]
+        private_key: String,
+        /// REST url for the network, used for local network
+        #[arg(long)]
+        rest_url: Option<String>,
         /// Automatically confirm prompts
         #[arg(short, long, default_value_t = false)]
         yes: bool,
-        /// Path to the toml configuration file
+    },
+    /// Execute the approved proposals recorded in a deployment report
+    Execute {
+        /// The path to the deployment report produced by `deploy --multisig-address`
+        #[arg(long, default_value = "deploy-report.json")]
+        report_path: PathBuf,
+        /// The private key of a multisig owner, or `env:<VARIABLE>` / `file:<PATH>` /
+        /// `keystore:<PATH>` to read it from an environment variable, a file or a keystore
         #[arg(long)]
-        config_path: Option<PathBuf>,
+        private_key: String,
+        /// REST url for the network, used for local network
+        #[arg(long)]
+        rest_url: Option<String>,
+        /// Automatically confirm prompts
+        #[arg(short, long, default_value_t = false)]
+        yes: bool,
     },
 }
 
-#[allow(clippy::needless_return)]
+#[derive(Subcommand, Clone, Debug, PartialEq)]
+enum ReportCommands {
+    /// Check the packages and transactions of a report against the chain
+    Verify {
+        /// The deployment report to verify
+        #[arg(long, default_value = "deploy-report.json")]
+        report_path: PathBuf,
+        /// REST url for the network, used for local network
+        #[arg(long)]
+        rest_url: Option<String>,
+    },
+    /// Check the signature of a report written with --sign-report
+    VerifySignature {
+        /// The signed deployment report
+        #[arg(long, default_value = "deploy-report.json")]
+        report_path: PathBuf,
+        /// The account whose key must have signed the report, when it isn't the account of the
+        /// report, e.g. its key was rotated
+        #[arg(long)]
+        expected_signer: Option<AccountAddress>,
+    },
+}
+
+/// Options of the Move compiler, overriding the `[compiler]` table of the configuration file.
+#[derive(Args, Clone, Debug, PartialEq)]
+struct CompilerArgs {
+    /// Use the git dependencies already fetched instead of fetching their latest version
+    #[arg(long, default_value_t = false)]
+    skip_fetch_latest_git_deps: bool,
+    /// The compiler version, e.g. 2.0
+    #[arg(long)]
+    compiler_version: Option<String>,
+    /// The Move language version, e.g. 2.1
+    #[arg(long)]
+    language_version: Option<String>,
+    /// The bytecode version of the compiled modules
+    #[arg(long)]
+    bytecode_version: Option<u32>,
+    /// Compile the packages in a container of this image, e.g. aptoslabs/tools@sha256:<digest>,
+    /// so the bytecode is the same on every machine
+    #[arg(long, value_name = "IMAGE")]
+    build_in_docker: Option<String>,
+}
+
+/// The ids of the arguments of [CompilerArgs].
+const COMPILER_ARGS: [&str; 5] = [
+    "skip_fetch_latest_git_deps",
+    "compiler_version",
+    "language_version",
+    "bytecode_version",
+    "build_in_docker",
+];
+
+impl CompilerArgs {
+    fn options(self) -> CompilerOptions {
+        CompilerOptions {
+            skip_fetch_latest_git_deps: self.skip_fetch_latest_git_deps.then_some(true),
+            compiler_version: self.compiler_version,
+            language_version: self.language_version,
+            bytecode_version: self.bytecode_version,
+            docker_image: self.build_in_docker,
+        }
+    }
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
-    let args = Cli::parse();
-    let args_str: Vec<String> = env::args().collect();
+    CompleteEnv::with_factory(Cli::command)
+        .var(COMPLETE_ENV)
+        .complete();
+    install_panic_hook();
+    let matches = Cli::command().get_matches();
+    let args = Cli::from_arg_matches(&matches).unwrap_or_else(|err| err.exit());
+    let telemetry = match args
+        .otlp_endpoint
+        .as_deref()
+        .map(init_telemetry)
+        .transpose()
+    {
+        Ok(telemetry) => telemetry,
+        Err(err) => {
+            eprintln!("{}", redact(&err.to_string()));
+            process::exit(err.exit_code());
+        }
+    };
+    init_colors(args.color);
+    let tui = matches!(
+        args.command,
+        Some(Commands::Deploy(DeployArgs { tui: true, .. }))
+    );
+    init_logging(
+        args.verbose,
+        args.quiet,
+        &args.log_format,
+        telemetry.as_ref(),
+        tui,
+    );
+    if tui {
+        if let Err(err) = start_dashboard() {
+            eprintln!("Failed to start the dashboard: {}", err);
+            process::exit(exit_code::FAILURE);
+        }
+    }
+    let gha = args.gha || github_actions::detected();
+    let result = run(args, &matches).await;
+    stop_dashboard();
+    // Exports what was recorded before exiting.
+    drop(telemetry);
+    if let Err(err) = result {
+        error!("{:#}", err);
+        if gha {
+            let message = redact(&format!("{:#}", err));
+            github_actions::print_command(&github_actions::annotation(
+                "error",
+                "jayce failed",
+                &message,
+            ));
+        }
+        process::exit(
+            err.downcast_ref::<JayceError>()
+                .map_or(exit_code::FAILURE, JayceError::exit_code),
+        );
+    }
+    Ok(())
+}
+
+#[allow(clippy::needless_return)]
+async fn run(args: Cli, matches: &ArgMatches) -> Result<()> {
     if args.version {
         println!(env!("APP_VERSION"));
         return Ok(());
     }
+    if let Some(workdir) = &args.workdir {
+        env::set_current_dir(workdir)?;
+    }
     match args.command {
         None => {
             Cli::command().print_help()?;
             Ok(())
         }
         Some(command) => match command {
-            Commands::Deploy {
+            Commands::Deploy(deploy_args) => {
+                let deploy_matches = matches.subcommand_matches("deploy").unwrap();
+                let mut partial_deploy_config =
+                    deploy_layers(deploy_args, deploy_matches, args.gha)?.merge()?;
+                let networks = partial_deploy_config.networks.take().unwrap_or_default();
+                if networks.len() > 1 {
+                    return Ok(
+                        deploy_contracts_to_networks(partial_deploy_config, &networks).await?,
+                    );
+                }
+                if let Some(network) = networks.into_iter().next() {
+                    partial_deploy_config.network = Some(network);
+                }
+                let deploy_config = DeployConfig::try_from(partial_deploy_config)?;
+
+                Ok(deploy_contracts(deploy_config).await?)
+            }
+            Commands::RunScript {
+                package_dir,
+                script,
+                type_args,
+                args,
                 private_key,
-                addresses_name,
+                profile,
                 network,
-                output_json,
+                rest_url,
+                report_path,
                 deployed_addresses,
+                yes,
+            } => Ok(run_script_command(
+                &ScriptCall {
+                    package_dir,
+                    script,
+                    type_args,
+                    args,
+                },
+                private_key
+                    .map(|private_key| resolve_private_key(&private_key))
+                    .transpose()?
+                    .as_deref(),
+                profile.as_deref(),
+                network,
                 rest_url,
-                faucet_url,
-                publish_code,
+                &report_path,
+                deployed_addresses,
                 yes,
-                config_path,
-                module_type,
-                modules_path,
+            )
+            .await?),
+            Commands::Call {
+                function_id,
+                type_args,
+                args,
+                private_key,
+                network,
+                rest_url,
+                report_path,
+                deployed_addresses,
+                yes,
+            } => Ok(call(
+                &EntryFunctionCall {
+                    function_id,
+                    type_args,
+                    args,
+                },
+                &resolve_private_key(&private_key)?,
+                network,
+                rest_url,
+                &report_path,
+                deployed_addresses,
+                yes,
+            )
+            .await?),
+            Commands::Account { command } => match command {
+                AccountCommands::Generate {
+                    key_file,
+                    fund,
+                    network,
+                    rest_url,
+                    faucet_urls,
+                } => Ok(generate_account(
+                    &key_file,
+                    fund,
+                    &network,
+                    &faucet_urls,
+                    rest_url,
+                    &RetryPolicy::default(),
+                )
+                .await?),
+            },
+            Commands::Faucet {
+                address,
+                amount,
+                network,
+                rest_url,
+                faucet_urls,
+                retry_attempts,
             } => {
-                let mut partial_deploy_config = if let Some(config_path) = config_path {
-                    PartialDeployConfig::from_path(config_path.to_str().unwrap())?
-                } else {
-                    PartialDeployConfig {
-                        private_key: None,
-                        module_type: None,
-                        modules_path: None,
-                        addresses_name: None,
-                        network: None,
-                        yes: None,
-                        output_json: None,
-                        deployed_addresses: None,
-                        rest_url: None,
-                        faucet_url: None,
-                        publish_code: None,
+                let mut retry = RetryPolicy::default();
+                if let Some(retry_attempts) = retry_attempts {
+                    retry.max_attempts = retry_attempts;
+                }
+                Ok(faucet(&network, &faucet_urls, rest_url, address, amount, &retry).await?)
+            }
+            Commands::Fund {
+                to,
+                amount,
+                private_key,
+                profile,
+                network,
+                rest_url,
+                yes,
+            } => Ok(fund(
+                private_key
+                    .map(|private_key| resolve_private_key(&private_key))
+                    .transpose()?
+                    .as_deref(),
+                profile.as_deref(),
+                to,
+                amount,
+                &network,
+                rest_url,
+                yes,
+            )
+            .await?),
+            Commands::Completions { shell } => Ok(write_registration(shell)?),
+            Commands::Config { command } => match command {
+                ConfigCommands::Show(deploy_args) => {
+                    let show_matches = matches
+                        .subcommand_matches("config")
+                        .and_then(|matches| matches.subcommand_matches("show"))
+                        .unwrap();
+                    let layers = deploy_layers(deploy_args, show_matches, args.gha)?;
+                    let mut partial_deploy_config = layers.merge()?;
+                    let networks = partial_deploy_config.networks.take().unwrap_or_default();
+                    if networks.len() <= 1 {
+                        if let Some(network) = networks.into_iter().next() {
+                            partial_deploy_config.network = Some(network);
+                        }
+                        let deploy_config = DeployConfig::try_from(partial_deploy_config)?;
+                        print!("{}", show_config(&deploy_config, &layers)?);
+                        return Ok(());
+                    }
+                    for network in &networks {
+                        let deploy_config =
+                            DeployConfig::try_from(partial_deploy_config.for_network(network)?)?;
+                        println!("# {}", network);
+                        print!("{}", show_config(&deploy_config, &layers)?);
                     }
-                };
-                if private_key.is_some() {
-                    partial_deploy_config.private_key = private_key;
+                    Ok(())
+                }
+                ConfigCommands::Get { key, config_path } => {
+                    println!("{}", get_config_value(&config_path, &key)?);
+                    Ok(())
                 }
-                if partial_deploy_config.module_type.is_none()
-                    || args_str.contains(&"--module-type".to_string())
-                {
-                    partial_deploy_config.module_type = Some(module_type);
+                ConfigCommands::Set {
+                    key,
+                    value,
+                    config_path,
+                } => Ok(set_config_value(&config_path, &key, &value)?),
+            },
+            Commands::Init { root, output } => Ok(init(&root, &output)?),
+            Commands::Key { command } => match command {
+                KeyCommands::Import {
+                    private_key,
+                    keystore,
+                } => Ok(import_key(&resolve_private_key(&private_key)?, &keystore)?),
+                KeyCommands::Export { keystore, yes } => Ok(export_key(&keystore, yes)?),
+                KeyCommands::Ephemeral { path, expiry_secs } => {
+                    Ok(ephemeral_key(&path, expiry_secs)?)
                 }
+            },
+            Commands::Localnet { command } => match command {
+                LocalnetCommands::Start {
+                    test_dir,
+                    force_restart,
+                    timeout_secs,
+                    state_path,
+                } => Ok(start_localnet(
+                    &state_path,
+                    &test_dir,
+                    force_restart,
+                    Duration::from_secs(timeout_secs),
+                )
+                .await?),
+                LocalnetCommands::Stop { state_path } => Ok(stop_localnet(&state_path)?),
+                LocalnetCommands::Status { state_path } => Ok(localnet_status(&state_path).await?),
+                LocalnetCommands::Run {
+                    test_dir,
+                    force_restart,
+                } => Ok(run_localnet(&test_dir, force_restart).await?),
+            },
+            Commands::Status {
+                report_path,
+                address_book,
+                network,
+                rest_url,
+            } => match address_book {
+                Some(address_book) => Ok(status_from_address_book(
+                    &address_book,
+                    &network,
+                    &network.to_string(),
+                    rest_url,
+                )
+                .await?),
+                None => Ok(status_from_report(&report_path, rest_url).await?),
+            },
+            Commands::Diff {
+                old_report,
+                new_report,
+            } => Ok(diff_reports(&old_report, &new_report)?),
+            Commands::Multisig { command } => match command {
+                MultisigCommands::Approve {
+                    report_path,
+                    private_key,
+                    rest_url,
+                    yes,
+                } => Ok(approve_proposals(
+                    &report_path,
+                    &resolve_private_key(&private_key)?,
+                    rest_url,
+                    yes,
+                )
+                .await?),
+                MultisigCommands::Execute {
+                    report_path,
+                    private_key,
+                    rest_url,
+                    yes,
+                } => Ok(execute_proposals(
+                    &report_path,
+                    &resolve_private_key(&private_key)?,
+                    rest_url,
+                    yes,
+                )
+                .await?),
+            },
+            Commands::Report { command } => match command {
+                ReportCommands::Verify {
+                    report_path,
+                    rest_url,
+                } => Ok(verify_report(&report_path, rest_url).await?),
+                ReportCommands::VerifySignature {
+                    report_path,
+                    expected_signer,
+                } => Ok(verify_report_signature(&report_path, expected_signer)?),
+            },
+            Commands::Build {
+                modules_path,
+                addresses_name,
+                workspace,
+                deployed_addresses,
+                config_path,
+                config_format,
+                environment,
+                no_cache,
+                artifacts_dir,
+                compiler,
+            } => {
+                let mut partial_deploy_config =
+                    load_config(config_path, config_format, environment)?;
                 if modules_path.is_some() {
                     partial_deploy_config.modules_path = modules_path;
+                    partial_deploy_config.packages = None;
                 }
                 if addresses_name.is_some() {
                     partial_deploy_config.addresses_name = addresses_name;
                 }
-                if partial_deploy_config.network.is_none()
-                    || args_str.contains(&"--network".to_string())
-                {
-                    partial_deploy_config.network = Some(network);
+                if workspace.is_some() {
+                    partial_deploy_config.workspace = workspace;
+                    partial_deploy_config.modules_path = None;
+                    partial_deploy_config.addresses_name = None;
+                    partial_deploy_config.packages = None;
                 }
-                if partial_deploy_config.yes.is_none()
-                    || args_str.contains(&"--yes".to_string())
-                    || args_str.contains(&"-y".to_string())
-                {
-                    partial_deploy_config.yes = Some(yes);
+                if deployed_addresses.is_some() {
+                    partial_deploy_config.deployed_addresses = deployed_addresses;
                 }
-                if partial_deploy_config.output_json.is_none()
-                    || args_str.contains(&"--output-json".to_string())
-                {
-                    partial_deploy_config.output_json = Some(output_json);
-                }
-                if partial_deploy_config.deployed_addresses.is_none()
-                    || args_str.contains(&"--deployed-addresses".to_string())
-                {
-                    partial_deploy_config.deployed_addresses = Some(deployed_addresses);
-                }
-                if rest_url.is_some() {
-                    partial_deploy_config.rest_url = rest_url;
-                }
-                if faucet_url.is_some() {
-                    partial_deploy_config.faucet_url = faucet_url;
-                }
-                if partial_deploy_config.publish_code.is_none()
-                    || args_str.contains(&"--publish-code".to_string())
-                {
-                    partial_deploy_config.publish_code = Some(publish_code);
-                }
-
-                let deploy_config = DeployConfig::from(partial_deploy_config);
-                ensure!(
-                    deploy_config.modules_path.len() == deploy_config.addresses_name.len(),
-                    "Modules path and addresses name must have the same length"
+                partial_deploy_config.compiler = Some(
+                    compiler
+                        .options()
+                        .or(&partial_deploy_config.compiler.take().unwrap_or_default()),
                 );
-
-                deploy_contracts(deploy_config).await
+                Ok(build(partial_deploy_config, no_cache, artifacts_dir.as_deref()).await?)
             }
+            Commands::Verify {
+                report_path,
+                rest_url,
+            } => Ok(verify(&report_path, rest_url).await?),
+            Commands::VerifySource {
+                report_path,
+                rest_url,
+            } => Ok(verify_source(&report_path, rest_url).await?),
+            Commands::Reproduce { report_path } => Ok(reproduce(&report_path).await?),
+            Commands::View {
+                function_id,
+                type_args,
+                args,
+                network,
+                rest_url,
+                report_path,
+                deployed_addresses,
+            } => Ok(view(
+                &EntryFunctionCall {
+                    function_id,
+                    type_args,
+                    args,
+                },
+                network,
+                rest_url,
+                &report_path,
+                deployed_addresses,
+            )
+            .await?),
         },
     }
 }
+
+/// Builds the layers of the configuration of a deployment from the arguments of `deploy`. The
+/// flags and environment layers only hold the arguments passed on the command line and by their
+/// `JAYCE_*` environment variables, according to `matches`, and the defaults layer the default
+/// values of the others.
+fn deploy_layers(deploy_args: DeployArgs, matches: &ArgMatches, gha: bool) -> Result<ConfigLayers> {
+    let config_file = load_config(
+        deploy_args.config_path.clone(),
+        deploy_args.config_format.clone(),
+        deploy_args.environment.clone(),
+    )?;
+    let source = |id: &str| matches.value_source(id);
+    let environment = deploy_layer(&deploy_args, config_file.retry.as_ref(), |id| {
+        source(id) == Some(ValueSource::EnvVariable)
+    })
+    .or(PartialDeployConfig::from_env());
+    let retry = environment.retry.as_ref().or(config_file.retry.as_ref());
+    let mut flags = deploy_layer(&deploy_args, retry, |id| {
+        source(id) == Some(ValueSource::CommandLine)
+    });
+    let DeployArgs {
+        private_key,
+        private_key_file,
+        mnemonic_file,
+        derivation_path,
+        compiler,
+        ..
+    } = deploy_args.clone();
+    flags.private_key = match mnemonic_file {
+        Some(mnemonic_file) => Some(read_mnemonic_file(&mnemonic_file, &derivation_path)?),
+        None => select_private_key(private_key, private_key_file, None, None)?,
+    };
+    flags.compiler = COMPILER_ARGS
+        .iter()
+        .any(|id| source(id) == Some(ValueSource::CommandLine))
+        .then(|| compiler.options());
+    flags.github_actions = gha.then_some(true);
+    let defaults = PartialDeployConfig {
+        module_type: Some(deploy_args.module_type),
+        networks: Some(deploy_args.network),
+        yes: Some(deploy_args.yes),
+        allow_mainnet: Some(deploy_args.allow_mainnet),
+        output_json: Some(deploy_args.output_json),
+        output_format: Some(deploy_args.output_format),
+        report_mode: Some(deploy_args.report_mode),
+        deployed_addresses: Some(deploy_args.deployed_addresses),
+        publish_code: Some(deploy_args.publish_code),
+        resume: Some(deploy_args.resume),
+        force: Some(deploy_args.force),
+        state_path: Some(deploy_args.state_path),
+        generated_key_path: Some(deploy_args.generated_key_path),
+        print_key: Some(deploy_args.print_key),
+        dry_run: Some(deploy_args.dry_run),
+        skip_balance_check: Some(deploy_args.skip_balance_check),
+        no_chunked: Some(deploy_args.no_chunked),
+        override_size_check: Some(deploy_args.override_size_check),
+        keep_going: Some(deploy_args.keep_going),
+        no_cache: Some(deploy_args.no_cache),
+        changed_only: Some(deploy_args.changed_only),
+        sign_report: Some(deploy_args.sign_report),
+        pipeline: Some(deploy_args.pipeline),
+        verify_source: Some(deploy_args.verify_source),
+        json_events: Some(deploy_args.json_events),
+        github_actions: Some(github_actions::detected()),
+        ..Default::default()
+    };
+    Ok(ConfigLayers {
+        defaults,
+        config_file,
+        environment,
+        flags,
+    })
+}
+
+/// The arguments of `deploy` mapping to a key of the configuration whose id satisfies `set`,
+/// e.g. the ones passed on the command line. The retry options complete `retry`, the retry
+/// policy of a lower layer.
+fn deploy_layer(
+    args: &DeployArgs,
+    retry: Option<&RetryPolicy>,
+    set: impl Fn(&str) -> bool,
+) -> PartialDeployConfig {
+    let retry_attempts = args.retry_attempts.filter(|_| set("retry_attempts"));
+    let retry_backoff_ms = args.retry_backoff_ms.filter(|_| set("retry_backoff_ms"));
+    let retry = (retry_attempts.is_some() || retry_backoff_ms.is_some()).then(|| {
+        let mut retry = retry.cloned().unwrap_or_default();
+        if let Some(retry_attempts) = retry_attempts {
+            retry.max_attempts = retry_attempts;
+        }
+        if let Some(retry_backoff_ms) = retry_backoff_ms {
+            retry.initial_backoff_ms = retry_backoff_ms;
+        }
+        retry
+    });
+    PartialDeployConfig {
+        profile: args.profile.clone().filter(|_| set("profile")),
+        sender_address: args.sender_address.filter(|_| set("sender_address")),
+        module_type: set("module_type").then(|| args.module_type.clone()),
+        modules_path: args.modules_path.clone().filter(|_| set("modules_path")),
+        addresses_name: args
+            .addresses_name
+            .clone()
+            .filter(|_| set("addresses_name")),
+        workspace: args.workspace.clone().filter(|_| set("workspace")),
+        networks: set("network").then(|| args.network.clone()),
+        yes: set("yes").then_some(args.yes),
+        allow_mainnet: set("allow_mainnet").then_some(args.allow_mainnet),
+        output_json: set("output_json").then(|| args.output_json.clone()),
+        output_format: set("output_format").then(|| args.output_format.clone()),
+        report_mode: set("report_mode").then(|| args.report_mode.clone()),
+        deployed_addresses: set("deployed_addresses").then(|| args.deployed_addresses.clone()),
+        rest_url: args.rest_url.clone().filter(|_| set("rest_url")),
+        faucet_url: args.faucet_url.clone().filter(|_| set("faucet_url")),
+        publish_code: set("publish_code").then_some(args.publish_code),
+        resume: set("resume").then_some(args.resume),
+        force: set("force").then_some(args.force),
+        state_path: set("state_path").then(|| args.state_path.clone()),
+        generated_key_path: set("generated_key_path").then(|| args.generated_key_path.clone()),
+        print_key: set("print_key").then_some(args.print_key),
+        dry_run: set("dry_run").then_some(args.dry_run),
+        skip_balance_check: set("skip_balance_check").then_some(args.skip_balance_check),
+        multisig_address: args.multisig_address.filter(|_| set("multisig_address")),
+        fee_payer_private_key: args
+            .fee_payer_private_key
+            .clone()
+            .filter(|_| set("fee_payer_private_key")),
+        max_gas: args.max_gas.filter(|_| set("max_gas")),
+        gas_unit_price: args.gas_unit_price.filter(|_| set("gas_unit_price")),
+        expiration_secs: args.expiration_secs.filter(|_| set("expiration_secs")),
+        max_fee: args.max_fee.filter(|_| set("max_fee")),
+        simulate_first: args.simulate_first.filter(|_| set("simulate_first")),
+        confirmation_timeout_secs: args
+            .confirmation_timeout_secs
+            .filter(|_| set("confirmation_timeout_secs")),
+        poll_interval_ms: args.poll_interval_ms.filter(|_| set("poll_interval_ms")),
+        chunked_publish: args.chunked_publish.filter(|_| set("chunked_publish")),
+        no_chunked: set("no_chunked").then_some(args.no_chunked),
+        large_packages_module_address: args
+            .large_packages_module_address
+            .filter(|_| set("large_packages_module_address")),
+        override_size_check: set("override_size_check").then_some(args.override_size_check),
+        chunk_size: args.chunk_size.filter(|_| set("chunk_size")),
+        address_book: args.address_book.clone().filter(|_| set("address_book")),
+        keep_going: set("keep_going").then_some(args.keep_going),
+        no_cache: set("no_cache").then_some(args.no_cache),
+        changed_only: set("changed_only").then_some(args.changed_only),
+        sign_report: set("sign_report").then_some(args.sign_report),
+        registry: (set("registry") && args.registry).then(RegistryConfig::default),
+        pipeline: set("pipeline").then_some(args.pipeline),
+        artifacts_dir: args.artifacts_dir.clone().filter(|_| set("artifacts_dir")),
+        bundle: args.bundle.clone().filter(|_| set("bundle")),
+        verify_source: set("verify_source").then_some(args.verify_source),
+        events_file: args.events_file.clone().filter(|_| set("events_file")),
+        json_events: set("json_events").then_some(args.json_events),
+        from_report: args.from_report.clone().filter(|_| set("from_report")),
+        retry,
+        ..Default::default()
+    }
+}
+
+/// Reads the configuration file if there is one, and applies the selected environment.
+fn load_config(
+    config_path: Option<PathBuf>,
+    config_format: Option<ConfigFormat>,
+    environment: Option<String>,
+) -> Result<PartialDeployConfig> {
+    let mut partial_deploy_config = match (config_path, config_format) {
+        (Some(config_path), Some(config_format)) => {
+            PartialDeployConfig::from_path_as(config_path.to_str().unwrap(), &config_format)?
+        }
+        (Some(config_path), None) => PartialDeployConfig::from_path(config_path.to_str().unwrap())?,
+        (None, _) => PartialDeployConfig::default(),
+    };
+    if let Some(environment) = &environment {
+        partial_deploy_config.apply_environment(environment)?;
+    }
+    Ok(partial_deploy_config)
+}
+
+#[cfg(test)]
+mod test {
+    use std::cell::RefCell;
+    use std::ffi::OsStr;
+
+    use clap::{Args, Command, FromArgMatches};
+
+    use super::{deploy_layer, DeployArgs, COMPILER_ARGS};
+
+    #[test]
+    fn test_deploy_layer_args() {
+        let command = DeployArgs::augment_args(Command::new("deploy"));
+        let matches = command.clone().get_matches_from(["deploy"]);
+        let args = DeployArgs::from_arg_matches(&matches).unwrap();
+        let ids = RefCell::new(vec![]);
+        deploy_layer(&args, None, |id| {
+            ids.borrow_mut().push(id.to_string());
+            true
+        });
+        let ids = ids.into_inner();
+        assert!(ids.contains(&"network".to_string()));
+        for id in &ids {
+            let arg = command.get_arguments().find(|arg| arg.get_id() == id);
+            let arg = arg.unwrap_or_else(|| panic!("{} is not an argument of deploy", id));
+            let env = format!("JAYCE_{}", id.to_uppercase());
+            assert_eq!(
+                arg.get_env(),
+                Some(OsStr::new(&env)),
+                "environment variable of {}",
+                id
+            );
+        }
+        for id in COMPILER_ARGS {
+            assert!(
+                command.get_arguments().any(|arg| arg.get_id() == id),
+                "{}",
+                id
+            );
+        }
+        for arg in command.get_arguments() {
+            if arg.get_env().is_some() {
+                assert!(
+                    ids.contains(&arg.get_id().to_string()),
+                    "{} is ignored",
+                    arg.get_id()
+                );
+            }
+        }
+    }
+}
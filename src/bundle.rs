@@ -0,0 +1,454 @@
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use aptos_sdk::crypto::HashValue;
+use aptos_sdk::move_types::account_address::AccountAddress;
+use aptos_sdk::move_types::identifier::Identifier;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+use zip::write::SimpleFileOptions;
+use zip::{ZipArchive, ZipWriter};
+
+use crate::deploy_config::{CompilerOptions, IncludedArtifacts, UpgradePolicy};
+use crate::deploy_report::PackageChecksums;
+use crate::error::{JayceError, Result};
+use crate::move_package::built_module_names;
+
+/// The manifest of a bundle, at its root.
+pub const MANIFEST_FILE: &str = "bundle.json";
+
+const METADATA_FILE: &str = "package-metadata.bcs";
+
+/// Compiled packages written by `jayce build --artifacts-dir`, to publish exactly the artifacts
+/// that were reviewed without compiling them again. A bundle is a directory, or a `.tar.gz`,
+/// `.tgz`, `.tar` or `.zip` archive of one, holding a manifest and a directory per package with
+/// its `package-metadata.bcs` and bytecode modules.
+pub struct Bundle {
+    dir: PathBuf,
+    manifest: BundleManifest,
+    /// Whether `dir` was extracted from an archive, and is removed with the bundle.
+    extracted: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct BundleManifest {
+    /// The packages, in deployment order.
+    pub packages: Vec<BundlePackage>,
+}
+
+/// A compiled package of a bundle.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct BundlePackage {
+    pub address_name: String,
+    /// The name of the package in its `Move.toml`.
+    pub name: String,
+    /// The directory of the sources of the package when it was compiled.
+    pub module_path: PathBuf,
+    pub upgrade_policy: UpgradePolicy,
+    /// The named addresses the package was compiled with, which it must be published with.
+    pub named_addresses: BTreeMap<String, AccountAddress>,
+    /// The names of the modules, in publishing order.
+    pub modules: Vec<String>,
+    /// The compiler options the package was compiled with, recorded in the deployment report.
+    pub compiler: CompilerOptions,
+    /// The artifacts the package is published with.
+    pub included_artifacts: IncludedArtifacts,
+    /// The checksums of the artifacts, checked before they are published.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub checksums: Option<PackageChecksums>,
+}
+
+impl Bundle {
+    /// Opens a bundle, extracting it to a temporary directory if it is an archive.
+    pub fn open(path: &Path) -> Result<Bundle> {
+        let (dir, extracted) = if path.is_dir() {
+            (path.to_path_buf(), false)
+        } else {
+            let path_hash = HashValue::sha3_256_of(path.to_str().unwrap().as_bytes()).to_hex();
+            let dir = std::env::temp_dir().join(format!(
+                "jayce-bundle-{}-{}",
+                std::process::id(),
+                &path_hash[..16]
+            ));
+            if dir.exists() {
+                fs::remove_dir_all(&dir)?;
+            }
+            extract_archive(path, &dir)?;
+            (dir, true)
+        };
+        let manifest_path = dir.join(MANIFEST_FILE);
+        let manifest = fs::read(&manifest_path)
+            .map_err(|err| {
+                JayceError::Package(format!(
+                    "{} is not a bundle, it has no {}: {}",
+                    path.to_str().unwrap(),
+                    MANIFEST_FILE,
+                    err
+                ))
+            })
+            .and_then(|content| Ok(serde_json::from_slice(&content)?))?;
+        let bundle = Bundle {
+            dir,
+            manifest,
+            extracted,
+        };
+        for package in bundle.packages() {
+            bundle.payload(package)?;
+        }
+        Ok(bundle)
+    }
+
+    /// The directory of the bundle, extracted if it was an archive.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub fn packages(&self) -> &[BundlePackage] {
+        &self.manifest.packages
+    }
+
+    /// Returns the package of an address name, if the bundle has it.
+    pub fn package(&self, address_name: &str) -> Option<&BundlePackage> {
+        self.manifest
+            .packages
+            .iter()
+            .find(|package| package.address_name == address_name)
+    }
+
+    /// Reads the serialized metadata and modules of a package, in publishing order, checking them
+    /// against the checksums of the manifest.
+    pub fn payload(&self, package: &BundlePackage) -> Result<(Vec<u8>, Vec<Vec<u8>>)> {
+        // The names are paths in the bundle.
+        if let Some(name) = std::iter::once(&package.address_name)
+            .chain(&package.modules)
+            .find(|name| !Identifier::is_valid(name.as_str()))
+        {
+            return Err(JayceError::Package(format!(
+                "Invalid name '{}' in the bundle manifest",
+                name
+            )));
+        }
+        let package_dir = self.dir.join(&package.address_name);
+        let metadata = fs::read(package_dir.join(METADATA_FILE))?;
+        let code = package
+            .modules
+            .iter()
+            .map(|module| fs::read(package_dir.join(format!("{}.mv", module))))
+            .collect::<std::io::Result<Vec<_>>>()?;
+        let checksums = PackageChecksums::from_artifacts(
+            &metadata,
+            package.modules.iter().cloned().zip(code.clone()),
+        );
+        match &package.checksums {
+            Some(expected) if *expected == checksums => Ok((metadata, code)),
+            Some(_) => Err(JayceError::Verification(format!(
+                "The artifacts of '{}' don't match the checksums of the bundle manifest",
+                package.address_name
+            ))),
+            None => Err(JayceError::Package(format!(
+                "Package '{}' of the bundle has no checksums, build the bundle again",
+                package.address_name
+            ))),
+        }
+    }
+
+    /// Returns the checksums of the artifacts of a package, checked against the artifacts.
+    pub fn checksums(&self, package: &BundlePackage) -> Result<PackageChecksums> {
+        self.payload(package)?;
+        Ok(package.checksums.clone().unwrap())
+    }
+
+    /// Copies the artifacts of a package to `destination`.
+    pub fn copy_artifacts(&self, package: &BundlePackage, destination: &Path) -> Result<()> {
+        fs::create_dir_all(destination)?;
+        for entry in fs::read_dir(self.dir.join(&package.address_name))? {
+            let path = entry?.path();
+            fs::copy(&path, destination.join(path.file_name().unwrap()))?;
+        }
+        Ok(())
+    }
+}
+
+impl Drop for Bundle {
+    fn drop(&mut self) {
+        if self.extracted {
+            let _ = fs::remove_dir_all(&self.dir);
+        }
+    }
+}
+
+impl BundlePackage {
+    /// Checks that the package is published with the named addresses it was compiled with, since
+    /// the addresses are part of its bytecode.
+    pub fn check_named_addresses(
+        &self,
+        named_addresses: &BTreeMap<String, AccountAddress>,
+    ) -> Result<()> {
+        for (name, compiled_address) in &self.named_addresses {
+            let Some(address) = named_addresses.get(name) else {
+                return Err(JayceError::Package(format!(
+                    "Package '{}' of the bundle was compiled with {} = {}, but {} has no address",
+                    self.address_name, name, compiled_address, name
+                )));
+            };
+            if address != compiled_address {
+                return Err(JayceError::Package(format!(
+                    "Package '{}' of the bundle was compiled with {} = {}, but would be \
+                    published with {}",
+                    self.address_name, name, compiled_address, address
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Adds a compiled package to the bundle in `dir`, from its payload and the last build of its
+/// sources, which names its modules.
+pub fn write_bundle_package(
+    dir: &Path,
+    package: &mut BundlePackage,
+    metadata: &[u8],
+    code: &[Vec<u8>],
+) -> Result<()> {
+    let names = built_module_names(&package.module_path, code)?;
+    let package_dir = dir.join(&package.address_name);
+    fs::create_dir_all(&package_dir)?;
+    fs::write(package_dir.join(METADATA_FILE), metadata)?;
+    for (name, module) in names.iter().zip(code) {
+        fs::write(package_dir.join(format!("{}.mv", name)), module)?;
+    }
+    package.modules = names.clone();
+    let modules = names.into_iter().zip(code.iter().cloned());
+    package.checksums = Some(PackageChecksums::from_artifacts(metadata, modules));
+    Ok(())
+}
+
+/// Writes the manifest of the bundle in `dir`, packed into the archive `output` when it has the
+/// extension of an archive.
+pub fn write_bundle(dir: &Path, manifest: &BundleManifest, output: &Path) -> Result<()> {
+    fs::write(
+        dir.join(MANIFEST_FILE),
+        serde_json::to_string_pretty(manifest)?,
+    )?;
+    if dir != output {
+        write_archive(dir, output)?;
+        fs::remove_dir_all(dir)?;
+    }
+    Ok(())
+}
+
+/// Whether a path is the path of an archive a bundle can be packed into.
+pub fn is_archive(path: &Path) -> bool {
+    archive_kind(path).is_some()
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum ArchiveKind {
+    TarGz,
+    Tar,
+    Zip,
+}
+
+fn archive_kind(path: &Path) -> Option<ArchiveKind> {
+    let name = path.file_name()?.to_str()?;
+    if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+        Some(ArchiveKind::TarGz)
+    } else if name.ends_with(".tar") {
+        Some(ArchiveKind::Tar)
+    } else if name.ends_with(".zip") {
+        Some(ArchiveKind::Zip)
+    } else {
+        None
+    }
+}
+
+fn extract_archive(path: &Path, dir: &Path) -> Result<()> {
+    let file = File::open(path).map_err(|err| {
+        JayceError::Package(format!(
+            "Failed to open the bundle {}: {}",
+            path.to_str().unwrap(),
+            err
+        ))
+    })?;
+    match archive_kind(path) {
+        Some(ArchiveKind::TarGz) => tar::Archive::new(GzDecoder::new(file)).unpack(dir)?,
+        Some(ArchiveKind::Tar) => tar::Archive::new(file).unpack(dir)?,
+        Some(ArchiveKind::Zip) => ZipArchive::new(file)
+            .and_then(|mut archive| archive.extract(dir))
+            .map_err(|err| JayceError::Package(format!("Invalid zip archive: {}", err)))?,
+        None => {
+            return Err(JayceError::Package(format!(
+                "Unsupported bundle {}, expected a directory or a .tar.gz, .tgz, .tar or .zip \
+                archive",
+                path.to_str().unwrap()
+            )))
+        }
+    }
+    Ok(())
+}
+
+fn write_archive(dir: &Path, output: &Path) -> Result<()> {
+    let file = File::create(output)?;
+    match archive_kind(output) {
+        Some(ArchiveKind::TarGz) => {
+            let mut builder = tar::Builder::new(GzEncoder::new(file, Compression::default()));
+            builder.append_dir_all(".", dir)?;
+            builder.into_inner()?.finish()?;
+        }
+        Some(ArchiveKind::Tar) => {
+            let mut builder = tar::Builder::new(file);
+            builder.append_dir_all(".", dir)?;
+            builder.finish()?;
+        }
+        Some(ArchiveKind::Zip) => {
+            let zip_error = |err: zip::result::ZipError| {
+                JayceError::Package(format!("Failed to write the zip archive: {}", err))
+            };
+            let mut writer = ZipWriter::new(file);
+            let mut paths = vec![];
+            collect_files(dir, &mut paths)?;
+            for path in paths {
+                let name = path.strip_prefix(dir).unwrap().to_str().unwrap();
+                writer
+                    .start_file(name.replace('\\', "/"), SimpleFileOptions::default())
+                    .map_err(zip_error)?;
+                writer.write_all(&fs::read(&path)?)?;
+            }
+            writer.finish().map_err(zip_error)?;
+        }
+        None => unreachable!("The bundle is written to a directory"),
+    }
+    Ok(())
+}
+
+fn collect_files(dir: &Path, files: &mut Vec<PathBuf>) -> Result<()> {
+    let mut entries = fs::read_dir(dir)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<std::io::Result<Vec<_>>>()?;
+    entries.sort();
+    for path in entries {
+        if path.is_dir() {
+            collect_files(&path, files)?;
+        } else {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::BTreeMap;
+    use std::fs;
+    use std::path::PathBuf;
+
+    use aptos_sdk::move_types::account_address::AccountAddress;
+
+    use crate::bundle::{write_bundle, Bundle, BundleManifest, BundlePackage};
+    use crate::deploy_config::{CompilerOptions, IncludedArtifacts, UpgradePolicy};
+    use crate::deploy_report::PackageChecksums;
+
+    #[test]
+    fn test_bundle_archives() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let root = temp_dir.path();
+        let package = BundlePackage {
+            address_name: "lib_addr".to_string(),
+            name: "Lib".to_string(),
+            module_path: PathBuf::from("libs"),
+            upgrade_policy: UpgradePolicy::Compatible,
+            named_addresses: BTreeMap::from([("lib_addr".to_string(), AccountAddress::ONE)]),
+            modules: vec!["math".to_string(), "lib".to_string()],
+            compiler: CompilerOptions::default(),
+            included_artifacts: IncludedArtifacts::None,
+            checksums: Some(PackageChecksums::from_artifacts(
+                &[1],
+                [("math".to_string(), vec![2]), ("lib".to_string(), vec![3])],
+            )),
+        };
+        let manifest = BundleManifest {
+            packages: vec![package.clone()],
+        };
+
+        for output in ["bundle.tar.gz", "bundle.zip", "bundle"] {
+            let dir = root.join(output.replace('.', "_"));
+            fs::create_dir_all(dir.join("lib_addr")).unwrap();
+            fs::write(dir.join("lib_addr/package-metadata.bcs"), [1]).unwrap();
+            fs::write(dir.join("lib_addr/math.mv"), [2]).unwrap();
+            fs::write(dir.join("lib_addr/lib.mv"), [3]).unwrap();
+            let output = if output == "bundle" {
+                dir.clone()
+            } else {
+                root.join(output)
+            };
+            write_bundle(&dir, &manifest, &output).unwrap();
+
+            let bundle = Bundle::open(&output).unwrap();
+            assert_eq!(bundle.packages(), &[package.clone()]);
+            assert_eq!(
+                bundle.payload(&package).unwrap(),
+                (vec![1], vec![vec![2], vec![3]])
+            );
+            let extracted = bundle.dir().to_path_buf();
+            drop(bundle);
+            assert_eq!(extracted.exists(), output == dir);
+        }
+
+        // Tampered artifacts and names escaping the bundle are rejected.
+        let dir = root.join("bundle");
+        fs::write(dir.join("lib_addr/lib.mv"), [4]).unwrap();
+        assert!(Bundle::open(&dir)
+            .err()
+            .unwrap()
+            .to_string()
+            .contains("don't match the checksums"));
+        let escaping = BundlePackage {
+            address_name: "../lib_addr".to_string(),
+            ..package.clone()
+        };
+        let manifest = BundleManifest {
+            packages: vec![escaping],
+        };
+        write_bundle(&dir, &manifest, &dir).unwrap();
+        assert!(Bundle::open(&dir)
+            .err()
+            .unwrap()
+            .to_string()
+            .contains("Invalid name"));
+    }
+
+    #[test]
+    fn test_check_named_addresses() {
+        let package = BundlePackage {
+            address_name: "verifier_addr".to_string(),
+            name: "Verifier".to_string(),
+            module_path: PathBuf::from("verifier"),
+            upgrade_policy: UpgradePolicy::Compatible,
+            named_addresses: BTreeMap::from([
+                ("verifier_addr".to_string(), AccountAddress::TWO),
+                ("lib_addr".to_string(), AccountAddress::ONE),
+            ]),
+            modules: vec![],
+            compiler: CompilerOptions::default(),
+            included_artifacts: IncludedArtifacts::None,
+            checksums: None,
+        };
+        let mut named_addresses = BTreeMap::from([
+            ("verifier_addr".to_string(), AccountAddress::TWO),
+            ("lib_addr".to_string(), AccountAddress::ONE),
+            ("std".to_string(), AccountAddress::ONE),
+        ]);
+        assert!(package.check_named_addresses(&named_addresses).is_ok());
+
+        named_addresses.insert("verifier_addr".to_string(), AccountAddress::THREE);
+        assert!(package.check_named_addresses(&named_addresses).is_err());
+
+        named_addresses.remove("verifier_addr");
+        assert!(package.check_named_addresses(&named_addresses).is_err());
+    }
+}
@@ -0,0 +1,66 @@
+use std::env;
+use std::io::{self, IsTerminal};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use clap::ValueEnum;
+use dialoguer::theme::{ColorfulTheme, SimpleTheme, Theme};
+use strum_macros::Display;
+
+static COLORS: AtomicBool = AtomicBool::new(true);
+
+/// When the output is colored, from `--color`.
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum, Display)]
+#[strum(serialize_all = "snake_case")]
+pub enum ColorChoice {
+    /// Colored when stderr, where the logs, prompts and progress are written, is a terminal and
+    /// `NO_COLOR` isn't set.
+    Auto,
+    Always,
+    Never,
+}
+
+/// Enables or disables the colors of the logs, prompts and progress of jayce and of the libraries
+/// it uses.
+pub fn init_colors(choice: ColorChoice) {
+    let no_color = env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+    let enabled = resolve_colors(choice, no_color, io::stderr().is_terminal());
+    COLORS.store(enabled, Ordering::SeqCst);
+    console::set_colors_enabled(enabled);
+    console::set_colors_enabled_stderr(enabled);
+}
+
+fn resolve_colors(choice: ColorChoice, no_color: bool, terminal: bool) -> bool {
+    match choice {
+        ColorChoice::Auto => terminal && !no_color,
+        ColorChoice::Always => true,
+        ColorChoice::Never => false,
+    }
+}
+
+/// Whether the output is colored.
+pub fn colors_enabled() -> bool {
+    COLORS.load(Ordering::SeqCst)
+}
+
+/// The theme of the prompts, in plain ASCII without colors when the output isn't colored.
+pub fn prompt_theme() -> Box<dyn Theme> {
+    if colors_enabled() {
+        Box::new(ColorfulTheme::default())
+    } else {
+        Box::new(SimpleTheme)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::color::{resolve_colors, ColorChoice};
+
+    #[test]
+    fn test_resolve_colors() {
+        assert!(resolve_colors(ColorChoice::Auto, false, true));
+        assert!(!resolve_colors(ColorChoice::Auto, true, true));
+        assert!(!resolve_colors(ColorChoice::Auto, false, false));
+        assert!(resolve_colors(ColorChoice::Always, true, false));
+        assert!(!resolve_colors(ColorChoice::Never, false, true));
+    }
+}
@@ -0,0 +1,164 @@
+use std::ffi::OsStr;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use clap_complete::engine::CompletionCandidate;
+use clap_complete::env::Shells;
+use clap_complete::Shell;
+
+use crate::deploy_report::DeployReport;
+use crate::error::{JayceError, Result};
+
+/// The environment variable under which jayce completes the command line of `shell`, see
+/// `clap_complete::CompleteEnv`.
+pub const COMPLETE_ENV: &str = "COMPLETE";
+
+/// The deployment report address names are completed from.
+const REPORT_PATH: &str = "deploy-report.json";
+
+const CONFIG_EXTENSIONS: [&str; 4] = ["toml", "yaml", "yml", "json"];
+
+/// Prints the script registering the completions of jayce in `shell`. The completions are
+/// computed by jayce itself, so that they include the address names of the deployment report.
+pub fn write_registration(shell: Shell) -> Result<()> {
+    let completer = Shells::builtins()
+        .completer(&shell.to_string())
+        .ok_or_else(|| JayceError::Config(format!("Unsupported shell {}", shell)))?;
+    completer.write_registration(COMPLETE_ENV, "jayce", "jayce", "jayce", &mut io::stdout())?;
+    Ok(())
+}
+
+/// Completes the address names of the deployment report of the working directory.
+pub fn address_names(current: &OsStr) -> Vec<CompletionCandidate> {
+    let Ok(report) = DeployReport::from_path(Path::new(REPORT_PATH)) else {
+        return vec![];
+    };
+    // Comma-separated lists are completed one name at a time.
+    let current = current.to_string_lossy();
+    let (previous, current) = match current.rsplit_once(',') {
+        Some((previous, current)) => (format!("{},", previous), current),
+        None => (String::new(), current.as_ref()),
+    };
+    address_name_candidates(&report, current)
+        .into_iter()
+        .map(|address_name| CompletionCandidate::new(format!("{}{}", previous, address_name)))
+        .collect()
+}
+
+/// Completes the address of a function id with the address names of the deployment report, e.g.
+/// `verifier_addr::`.
+pub fn function_ids(current: &OsStr) -> Vec<CompletionCandidate> {
+    let Ok(report) = DeployReport::from_path(Path::new(REPORT_PATH)) else {
+        return vec![];
+    };
+    let current = current.to_string_lossy();
+    if current.contains("::") {
+        return vec![];
+    }
+    address_name_candidates(&report, &current)
+        .into_iter()
+        .map(|address_name| CompletionCandidate::new(format!("{}::", address_name)))
+        .collect()
+}
+
+/// Completes the configuration files, and the directories that may contain some.
+pub fn config_files(current: &OsStr) -> Vec<CompletionCandidate> {
+    let current = current.to_string_lossy();
+    let (dir, prefix) = match current.rfind('/') {
+        Some(index) => (&current[..=index], &current[index + 1..]),
+        None => ("", current.as_ref()),
+    };
+    let Ok(entries) = fs::read_dir(if dir.is_empty() { "." } else { dir }) else {
+        return vec![];
+    };
+    let mut candidates: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.is_dir()
+                || path
+                    .extension()
+                    .and_then(|extension| extension.to_str())
+                    .is_some_and(|extension| CONFIG_EXTENSIONS.contains(&extension))
+        })
+        .filter(|path| {
+            path.file_name()
+                .is_some_and(|name| name.to_string_lossy().starts_with(prefix))
+        })
+        .collect();
+    candidates.sort();
+    candidates
+        .into_iter()
+        .map(|path| {
+            let name = path.file_name().unwrap().to_string_lossy();
+            let suffix = if path.is_dir() { "/" } else { "" };
+            CompletionCandidate::new(format!("{}{}{}", dir, name, suffix))
+        })
+        .collect()
+}
+
+/// The address names of a report starting with `prefix`.
+fn address_name_candidates(report: &DeployReport, prefix: &str) -> Vec<String> {
+    report
+        .info
+        .iter()
+        .map(|tx_report| tx_report.address_name.clone())
+        .filter(|address_name| address_name.starts_with(prefix))
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::BTreeMap;
+    use std::path::PathBuf;
+
+    use aptos_sdk::move_types::account_address::AccountAddress;
+
+    use crate::completions::address_name_candidates;
+    use crate::deploy_config::AptosNetwork;
+    use crate::deploy_report::{DeployReport, DeployStatus, TxReport};
+
+    #[test]
+    fn test_address_name_candidates() {
+        let tx_report = |address_name: &str| TxReport {
+            module_path: PathBuf::from("libs"),
+            address_name: address_name.to_string(),
+            deployed_at: AccountAddress::TWO,
+            sender: None,
+            tx_info: vec![],
+            freeze_tx_info: None,
+            init_tx_info: vec![],
+            registry_tx_info: None,
+            proposal_id: None,
+            payload_path: None,
+            explorer_url: None,
+            tx_explorer_urls: vec![],
+            upgrade_policy: None,
+            upgrade_number: None,
+            artifacts_path: None,
+            checksums: None,
+            compiler: None,
+            included_artifacts: None,
+            jayce_version: None,
+            status: DeployStatus::Deployed,
+            error: None,
+        };
+        let report = DeployReport {
+            account: AccountAddress::ONE,
+            fee_payer: None,
+            network: AptosNetwork::Testnet,
+            gas: None,
+            git_commit: None,
+            git_commits: BTreeMap::new(),
+            signature: None,
+            info: vec![tx_report("lib_addr"), tx_report("verifier_addr")],
+        };
+
+        assert_eq!(
+            address_name_candidates(&report, "ver"),
+            vec!["verifier_addr".to_string()]
+        );
+        assert_eq!(address_name_candidates(&report, "").len(), 2);
+    }
+}
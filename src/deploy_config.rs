@@ -1,13 +1,42 @@
 use std::collections::BTreeMap;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::{env, fs};
 
+use aptos_sdk::crypto::ValidCryptoMaterialStringExt;
 use aptos_sdk::move_types::account_address::AccountAddress;
+use aptos_sdk::types::LocalAccount;
 use clap::ValueEnum;
-use config::{Config as ConfigLoader, File, FileFormat};
+use config::{Config as ConfigLoader, ConfigError, File, FileFormat};
 use serde::{Deserialize, Serialize};
 use strum_macros::Display;
+use url::Url;
 
-#[derive(Deserialize, Clone, Debug, PartialEq, ValueEnum, Display)]
+use crate::deploy_report::{ReportMode, JAYCE_VERSION};
+use crate::entry_function::EntryFunctionCall;
+use crate::error::{JayceError, Result};
+use crate::git::{is_git_source, resolve_git_source};
+use crate::hooks::{Hook, Hooks};
+use crate::keystore::read_keystore;
+use crate::move_package::{expand_modules_path, find_packages, infer_address_names};
+use crate::redact::register_secret;
+use crate::registry::RegistryConfig;
+use crate::report_writer::ReportFormat;
+use crate::retry::RetryPolicy;
+use crate::signer::SignerConfig;
+use crate::tasks::deploy_contracts::MAX_PUBLISH_PACKAGE_SIZE;
+use crate::webhooks::Webhook;
+
+/// Environment variable holding the private key used for deployment.
+pub const PRIVATE_KEY_ENV: &str = "JAYCE_PRIVATE_KEY";
+/// Derivation path of the first account of the Aptos wallets.
+pub const DEFAULT_DERIVATION_PATH: &str = "m/44'/637'/0'/0'/0'";
+/// Default time to wait for a transaction to be committed, the one of the aptos CLI.
+pub const DEFAULT_CONFIRMATION_TIMEOUT_SECS: u64 = 60;
+/// Default delay between two polls of a pending transaction.
+pub const DEFAULT_POLL_INTERVAL_MS: u64 = 500;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, ValueEnum, Display)]
+#[serde(rename_all(serialize = "snake_case"))]
 #[strum(serialize_all = "snake_case")]
 pub enum DeployModuleType {
     Account,
@@ -21,9 +50,233 @@ pub enum AptosNetwork {
     Testnet,
     Devnet,
     Local,
+    MovementMainnet,
+    MovementTestnet,
+    /// A network described by the `custom_network` table of the configuration file.
+    Custom,
+}
+
+/// The format of a deploy configuration file.
+#[derive(Clone, Debug, PartialEq, ValueEnum, Display)]
+#[strum(serialize_all = "snake_case")]
+pub enum ConfigFormat {
+    Toml,
+    Yaml,
+    Json,
+}
+
+impl ConfigFormat {
+    /// Detects the format from the extension of the file, defaulting to TOML.
+    pub fn from_extension(path: &Path) -> ConfigFormat {
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("yaml" | "yml") => ConfigFormat::Yaml,
+            Some("json") => ConfigFormat::Json,
+            _ => ConfigFormat::Toml,
+        }
+    }
+
+    fn file_format(&self) -> FileFormat {
+        match self {
+            ConfigFormat::Toml => FileFormat::Toml,
+            ConfigFormat::Yaml => FileFormat::Yaml,
+            ConfigFormat::Json => FileFormat::Json,
+        }
+    }
+}
+
+/// The upgrade policy of a published package.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Display)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum UpgradePolicy {
+    /// The package can be upgraded with compatible changes.
+    Compatible,
+    /// The package can never be upgraded.
+    Immutable,
+}
+
+impl UpgradePolicy {
+    /// The value of the policy in `0x1::code::UpgradePolicy`.
+    pub fn code(&self) -> u8 {
+        match self {
+            UpgradePolicy::Compatible => 1,
+            UpgradePolicy::Immutable => 2,
+        }
+    }
+}
+
+/// The artifacts published with a package, as the `--included-artifacts` of the aptos CLI.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Display)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum IncludedArtifacts {
+    /// Only the bytecode and the metadata needed to run the package.
+    None,
+    /// The sources, without source maps.
+    Sparse,
+    /// The sources and their source maps.
+    All,
+}
+
+/// A network that is not built into jayce, e.g. a private network or a fork.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct CustomNetwork {
+    pub name: String,
+    /// The expected chain id, checked against the REST API before deploying.
+    pub chain_id: Option<u8>,
+    pub rest_url: String,
+    pub faucet_url: Option<String>,
+    pub explorer_url: Option<String>,
+}
+
+/// Settings of a package, from the `[package_options.<address name>]` tables.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct PackageOptions {
+    /// Addresses of named addresses of the package, overriding the deployed addresses. A value is
+    /// either a literal address (`0x1`) or the address name of another deployed package.
+    pub overrides: BTreeMap<String, String>,
+    /// The upgrade policy of the package, overriding the one of its `Move.toml`.
+    pub upgrade_policy: Option<UpgradePolicy>,
+    /// Freezes the code of the object package after publishing it, so that it can't be upgraded.
+    pub freeze: bool,
+    /// Private key of the account publishing the package, instead of the deployer.
+    pub private_key: Option<String>,
+    /// An aptos CLI profile publishing the package, instead of the deployer.
+    pub profile: Option<String>,
+    /// Compiler options of the package, overriding the ones of the deployment.
+    pub compiler: CompilerOptions,
+    /// How the package is deployed, overriding the module type of the deployment.
+    pub module_type: Option<DeployModuleType>,
+    /// The artifacts published with the package, overriding the ones of `publish_code`.
+    pub included_artifacts: Option<IncludedArtifacts>,
+    /// Private keys of the accounts co-signing the publish transaction, as the secondary signers
+    /// of a multi-agent transaction.
+    pub secondary_signers: Vec<String>,
+    /// The entry function publishing a package with secondary signers, taking the publisher and
+    /// the secondary signers followed by the metadata and the code of the package. The publish
+    /// functions of the framework only take the publisher.
+    pub publish_function: Option<String>,
+}
+
+/// A package of the `[[packages]]` tables, replacing the `modules_path` and `addresses_name`
+/// arrays.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct PackageConfig {
+    pub path: PathBuf,
+    pub address_name: String,
+    /// How the package is deployed, overriding the module type of the deployment.
+    pub module_type: Option<DeployModuleType>,
+    /// Named addresses of the package, as the `overrides` of its options.
+    #[serde(default)]
+    pub overrides: BTreeMap<String, String>,
+    /// Hooks run around the deployment of the package.
+    pub hooks: Option<Hooks>,
+}
+
+impl PackageOptions {
+    /// Returns `deployed_addresses` with the overrides of the package of `address_name` applied.
+    pub fn named_addresses(
+        &self,
+        address_name: &str,
+        deployed_addresses: &BTreeMap<String, AccountAddress>,
+    ) -> Result<BTreeMap<String, AccountAddress>> {
+        let mut named_addresses = deployed_addresses.clone();
+        for (named_address, value) in &self.overrides {
+            let address = if value.starts_with("0x") {
+                AccountAddress::from_hex_literal(value).map_err(|err| {
+                    JayceError::Config(format!(
+                        "Invalid override of '{}' for '{}': {}",
+                        named_address, address_name, err
+                    ))
+                })?
+            } else {
+                *deployed_addresses.get(value).ok_or_else(|| {
+                    JayceError::Dependency(format!(
+                        "'{}' should be deployed before '{}'",
+                        value, address_name
+                    ))
+                })?
+            };
+            named_addresses.insert(named_address.clone(), address);
+        }
+        Ok(named_addresses)
+    }
+}
+
+/// Options of the Move compiler, from the `[compiler]` table and the `compiler` table of the
+/// package options, forwarded to the aptos CLI commands compiling the packages.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct CompilerOptions {
+    /// Uses the git dependencies already fetched instead of fetching their latest version.
+    pub skip_fetch_latest_git_deps: Option<bool>,
+    /// The compiler version, e.g. `2.0`.
+    pub compiler_version: Option<String>,
+    /// The Move language version, e.g. `2.1`.
+    pub language_version: Option<String>,
+    /// The bytecode version of the compiled modules.
+    pub bytecode_version: Option<u32>,
+    /// The container image the packages are compiled in, e.g.
+    /// `aptoslabs/tools@sha256:<digest>`, instead of the compiler of jayce.
+    pub docker_image: Option<String>,
+}
+
+impl CompilerOptions {
+    /// Returns these options, with the unset ones taken from `defaults`.
+    pub fn or(&self, defaults: &CompilerOptions) -> CompilerOptions {
+        CompilerOptions {
+            skip_fetch_latest_git_deps: self
+                .skip_fetch_latest_git_deps
+                .or(defaults.skip_fetch_latest_git_deps),
+            compiler_version: self
+                .compiler_version
+                .clone()
+                .or_else(|| defaults.compiler_version.clone()),
+            language_version: self
+                .language_version
+                .clone()
+                .or_else(|| defaults.language_version.clone()),
+            bytecode_version: self.bytecode_version.or(defaults.bytecode_version),
+            docker_image: self
+                .docker_image
+                .clone()
+                .or_else(|| defaults.docker_image.clone()),
+        }
+    }
+
+    /// The options changing the compiled bytecode, as part of the key of the artifact cache. The
+    /// compiler is the one of the image, or the aptos CLI built into this version of jayce.
+    pub fn cache_key(&self) -> String {
+        match &self.docker_image {
+            Some(docker_image) => format!("{} --docker-image {}", self.args(), docker_image),
+            None => format!("{} --jayce-version {}", self.args(), JAYCE_VERSION)
+                .trim_start()
+                .to_string(),
+        }
+    }
+
+    /// Builds the aptos CLI options of the compiler.
+    pub fn args(&self) -> String {
+        let mut args = vec![];
+        if self.skip_fetch_latest_git_deps == Some(true) {
+            args.push("--skip-fetch-latest-git-deps".to_string());
+        }
+        if let Some(compiler_version) = &self.compiler_version {
+            args.push(format!("--compiler-version {}", compiler_version));
+        }
+        if let Some(language_version) = &self.language_version {
+            args.push(format!("--language-version {}", language_version));
+        }
+        if let Some(bytecode_version) = self.bytecode_version {
+            args.push(format!("--bytecode-version {}", bytecode_version));
+        }
+        args.join(" ")
+    }
 }
 
-#[derive(Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct DeployConfig {
     pub private_key: Option<String>,
     pub module_type: DeployModuleType,
@@ -31,58 +284,1325 @@ pub struct DeployConfig {
     pub addresses_name: Vec<String>,
     pub network: AptosNetwork,
     pub yes: bool,
+    /// Deploys to mainnet without typing the network name at a prompt.
+    pub allow_mainnet: bool,
     pub output_json: PathBuf,
+    pub output_format: ReportFormat,
+    pub report_mode: ReportMode,
     pub deployed_addresses: BTreeMap<String, AccountAddress>,
+    /// Fixed addresses of named addresses that aren't deployed packages, e.g. of external
+    /// dependencies, added to the named addresses of every package.
+    pub named_addresses: BTreeMap<String, AccountAddress>,
     pub rest_url: Option<String>,
     pub faucet_url: Option<String>,
+    /// Faucets tried in order when `faucet_url` fails, e.g. because it is rate limited.
+    pub faucet_urls: Vec<String>,
     pub publish_code: bool,
+    pub resume: bool,
+    /// Takes over the lock of a deployment running in the same directory.
+    pub force: bool,
+    pub state_path: PathBuf,
+    /// Where the private key of an account generated for the deployment is written.
+    pub generated_key_path: PathBuf,
+    /// Prints the private key of a generated account instead of only writing it to a file.
+    pub print_key: bool,
+    pub dry_run: bool,
+    /// Skips the check of the deployer's balance against the estimated cost of the deployment.
+    pub skip_balance_check: bool,
+    pub multisig_address: Option<AccountAddress>,
+    pub fee_payer_private_key: Option<String>,
+    pub max_gas: Option<u64>,
+    pub gas_unit_price: Option<u64>,
+    pub expiration_secs: Option<u64>,
+    /// Maximum fee of a publish transaction, in Octas, checked against its simulation.
+    pub max_fee: Option<u64>,
+    /// Whether each publication is simulated before it is submitted, defaults to true on mainnet.
+    pub simulate_first: Option<bool>,
+    /// How long to wait for a transaction published through the REST API to be committed. The
+    /// transactions submitted by the aptos CLI are waited for by the aptos CLI.
+    pub confirmation_timeout_secs: u64,
+    /// Delay between two polls of a pending transaction published through the REST API.
+    pub poll_interval_ms: u64,
+    /// Entry functions to call after publishing a package, keyed by address name.
+    pub initializers: BTreeMap<String, Vec<EntryFunctionCall>>,
+    /// Hooks run around the whole deployment.
+    pub hooks: Hooks,
+    /// Hooks run around the deployment of a package, keyed by address name.
+    pub package_hooks: BTreeMap<String, Hooks>,
+    /// Settings of the packages, keyed by address name.
+    pub package_options: BTreeMap<String, PackageOptions>,
+    /// Compiler options of every package.
+    pub compiler: CompilerOptions,
+    /// An existing aptos CLI profile signing the transactions instead of the private key.
+    pub profile: Option<String>,
+    /// The account of the deployer, when its key was rotated. Defaults to the account the key was
+    /// rotated into, or to the address derived from the key.
+    pub sender_address: Option<AccountAddress>,
+    pub custom_network: Option<CustomNetwork>,
+    /// Whether the network supports chunked publish, defaults to the support of the network.
+    pub chunked_publish: Option<bool>,
+    pub retry: RetryPolicy,
+    /// Fail instead of using chunked publish for packages that are too large.
+    pub no_chunked: bool,
+    /// The address of the chunked publish module, defaults to the one of the network.
+    pub large_packages_module_address: Option<AccountAddress>,
+    /// Publishes packages larger than the transaction size limit in a single transaction instead
+    /// of with chunked publish.
+    pub override_size_check: bool,
+    /// The size of the chunks of chunked publish in bytes, defaults to the one of the aptos CLI.
+    pub chunk_size: Option<usize>,
+    /// The address book pre-populating `deployed_addresses` and recording the deployed packages.
+    pub address_book: Option<PathBuf>,
+    /// Keeps deploying the packages that don't depend on a package that failed to deploy.
+    pub keep_going: bool,
+    /// Recompiles the packages instead of reading them from the artifact cache.
+    pub no_cache: bool,
+    /// Only deploys the packages whose sources changed since the commit of the report, and the
+    /// packages depending on them.
+    pub changed_only: bool,
+    /// Signs the report with the key of the deployer.
+    pub sign_report: bool,
+    /// Submits the transactions back-to-back from a locally tracked sequence number, then waits
+    /// for all of them.
+    pub pipeline: bool,
+    /// The directory the published artifacts of every package are copied to, in a subdirectory
+    /// per run.
+    pub artifacts_dir: Option<PathBuf>,
+    /// The bundle of `jayce build --artifacts-dir` the packages are published from, replacing
+    /// `modules_path` and `addresses_name`.
+    pub bundle: Option<PathBuf>,
+    /// Publishes the sources of the packages and verifies them against the published bytecode.
+    pub verify_source: bool,
+    /// The file the lifecycle events of the packages are appended to, as JSON Lines.
+    pub events_file: Option<PathBuf>,
+    /// Writes the lifecycle events of the packages to stdout, as JSON Lines.
+    pub json_events: bool,
+    /// Webhooks notified when the deployment starts, when each package is deployed and when the
+    /// deployment fails.
+    pub webhooks: Vec<Webhook>,
+    /// Records every deployed package in an on-chain registry.
+    pub registry: Option<RegistryConfig>,
+    /// Annotates the GitHub Actions workflow run and exports the deployed addresses as outputs.
+    pub github_actions: bool,
+    /// A previous deployment report whose packages are added to `deployed_addresses`.
+    pub from_report: Option<PathBuf>,
+    /// A key management service signing the transactions instead of the private key.
+    pub signer: Option<SignerConfig>,
 }
 
-#[derive(Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
 pub struct PartialDeployConfig {
     pub private_key: Option<String>,
     pub module_type: Option<DeployModuleType>,
     pub modules_path: Option<Vec<PathBuf>>,
     pub addresses_name: Option<Vec<String>>,
+    /// A directory whose Move packages are all deployed, replacing `modules_path` and
+    /// `addresses_name`.
+    pub workspace: Option<PathBuf>,
+    /// The packages to deploy with their settings, replacing `modules_path` and `addresses_name`.
+    pub packages: Option<Vec<PackageConfig>>,
     pub network: Option<AptosNetwork>,
+    /// The networks to deploy the packages to, one after the other.
+    pub networks: Option<Vec<AptosNetwork>>,
     pub yes: Option<bool>,
+    pub allow_mainnet: Option<bool>,
     pub output_json: Option<PathBuf>,
+    pub output_format: Option<ReportFormat>,
+    pub report_mode: Option<ReportMode>,
     pub deployed_addresses: Option<BTreeMap<String, AccountAddress>>,
+    pub named_addresses: Option<BTreeMap<String, AccountAddress>>,
     pub rest_url: Option<String>,
     pub faucet_url: Option<String>,
+    pub faucet_urls: Option<Vec<String>>,
     pub publish_code: Option<bool>,
+    pub resume: Option<bool>,
+    pub force: Option<bool>,
+    pub state_path: Option<PathBuf>,
+    pub generated_key_path: Option<PathBuf>,
+    pub print_key: Option<bool>,
+    pub dry_run: Option<bool>,
+    pub skip_balance_check: Option<bool>,
+    pub multisig_address: Option<AccountAddress>,
+    pub fee_payer_private_key: Option<String>,
+    pub max_gas: Option<u64>,
+    pub gas_unit_price: Option<u64>,
+    pub expiration_secs: Option<u64>,
+    pub max_fee: Option<u64>,
+    pub simulate_first: Option<bool>,
+    pub confirmation_timeout_secs: Option<u64>,
+    pub poll_interval_ms: Option<u64>,
+    pub initializers: Option<BTreeMap<String, Vec<EntryFunctionCall>>>,
+    pub hooks: Option<Hooks>,
+    pub package_hooks: Option<BTreeMap<String, Hooks>>,
+    pub package_options: Option<BTreeMap<String, PackageOptions>>,
+    pub compiler: Option<CompilerOptions>,
+    pub profile: Option<String>,
+    pub sender_address: Option<AccountAddress>,
+    pub custom_network: Option<CustomNetwork>,
+    pub chunked_publish: Option<bool>,
+    pub retry: Option<RetryPolicy>,
+    pub no_chunked: Option<bool>,
+    pub large_packages_module_address: Option<AccountAddress>,
+    pub override_size_check: Option<bool>,
+    pub chunk_size: Option<usize>,
+    pub address_book: Option<PathBuf>,
+    pub keep_going: Option<bool>,
+    pub no_cache: Option<bool>,
+    pub changed_only: Option<bool>,
+    pub sign_report: Option<bool>,
+    pub pipeline: Option<bool>,
+    pub artifacts_dir: Option<PathBuf>,
+    /// A bundle of compiled packages to publish, replacing `modules_path`, `addresses_name`,
+    /// `workspace` and `packages`.
+    pub bundle: Option<PathBuf>,
+    pub verify_source: Option<bool>,
+    pub events_file: Option<PathBuf>,
+    pub json_events: Option<bool>,
+    pub webhooks: Option<Vec<Webhook>>,
+    pub registry: Option<RegistryConfig>,
+    pub github_actions: Option<bool>,
+    pub from_report: Option<PathBuf>,
+    pub signer: Option<SignerConfig>,
+    pub environments: Option<BTreeMap<String, EnvironmentConfig>>,
+    /// The configuration file the values were read from, used to locate invalid values.
+    #[serde(skip)]
+    pub config_path: Option<PathBuf>,
+}
+
+/// Values of an `[environments.<name>]` table, overriding the ones of the configuration file when
+/// the environment is selected with `--env`.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(deny_unknown_fields)]
+pub struct EnvironmentConfig {
+    pub private_key: Option<String>,
+    pub profile: Option<String>,
+    pub network: Option<AptosNetwork>,
+    pub custom_network: Option<CustomNetwork>,
+    pub rest_url: Option<String>,
+    pub faucet_url: Option<String>,
+    pub faucet_urls: Option<Vec<String>>,
+    pub fee_payer_private_key: Option<String>,
+    pub max_gas: Option<u64>,
+    pub gas_unit_price: Option<u64>,
+    pub expiration_secs: Option<u64>,
+    pub max_fee: Option<u64>,
+    pub output_json: Option<PathBuf>,
+    pub output_format: Option<ReportFormat>,
+    pub state_path: Option<PathBuf>,
+    pub deployed_addresses: Option<BTreeMap<String, AccountAddress>>,
+    /// Added to the named addresses of the configuration file, overriding the ones with the same
+    /// name.
+    pub named_addresses: Option<BTreeMap<String, AccountAddress>>,
+}
+
+/// A profile of the aptos CLI configuration (`.aptos/config.yaml`).
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct AptosProfile {
+    pub private_key: Option<String>,
+    /// The account of the profile, which differs from the one of its key after a rotation.
+    pub account: Option<AccountAddress>,
+    pub network: Option<String>,
+    pub rest_url: Option<String>,
+    pub faucet_url: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct AptosConfigYaml {
+    profiles: BTreeMap<String, AptosProfile>,
+}
+
+impl AptosProfile {
+    /// Loads a profile from the aptos CLI configuration of the current directory, or from the
+    /// global one in the home directory.
+    pub fn load(name: &str) -> Result<AptosProfile> {
+        let mut config_paths = vec![PathBuf::from(".aptos/config.yaml")];
+        if let Ok(home) = env::var("HOME") {
+            config_paths.push(Path::new(&home).join(".aptos/config.yaml"));
+        }
+        for config_path in config_paths.iter().filter(|path| path.exists()) {
+            let config_yaml: AptosConfigYaml = ConfigLoader::builder()
+                .add_source(File::new(config_path.to_str().unwrap(), FileFormat::Yaml))
+                .build()
+                .and_then(|content| content.try_deserialize())
+                .map_err(|err| JayceError::Config(err.to_string()))?;
+            if let Some(profile) = config_yaml.profiles.get(name) {
+                return Ok(profile.clone());
+            }
+        }
+        Err(JayceError::Config(format!(
+            "Aptos CLI profile '{}' not found",
+            name
+        )))
+    }
 }
 
 impl PartialDeployConfig {
-    pub fn from_path(path: &str) -> anyhow::Result<PartialDeployConfig> {
+    /// Fills the private key, network and URLs that are not set with the ones of an aptos CLI
+    /// profile.
+    pub fn apply_profile(&mut self, profile: AptosProfile) {
+        if self.private_key.is_none() {
+            self.private_key = profile
+                .private_key
+                .map(|private_key| private_key.trim_start_matches("ed25519-priv-").to_string());
+        }
+        if self.network.is_none() {
+            self.network = profile
+                .network
+                .and_then(|network| AptosNetwork::from_str(&network, true).ok());
+        }
+        if self.rest_url.is_none() {
+            self.rest_url = profile.rest_url;
+        }
+        if self.faucet_url.is_none() {
+            self.faucet_url = profile.faucet_url;
+        }
+        if self.sender_address.is_none() {
+            self.sender_address = profile.account;
+        }
+    }
+
+    /// Loads the private keys of the aptos CLI profiles publishing packages, which give the
+    /// addresses the packages are published from.
+    pub fn apply_package_profiles(&mut self) -> Result<()> {
+        for (address_name, options) in self.package_options.iter_mut().flatten() {
+            if let Some(profile) = &options.profile {
+                if options.private_key.is_some() {
+                    return Err(JayceError::Config(format!(
+                        "Package '{}' can't set both a private key and a profile",
+                        address_name
+                    )));
+                }
+                options.private_key = AptosProfile::load(profile)?
+                    .private_key
+                    .map(|private_key| private_key.trim_start_matches("ed25519-priv-").to_string());
+            }
+        }
+        Ok(())
+    }
+
+    /// Overrides the values of the configuration with the ones of an environment.
+    pub fn apply_environment(&mut self, name: &str) -> Result<()> {
+        let environment = self
+            .environments
+            .as_mut()
+            .and_then(|environments| environments.remove(name))
+            .ok_or_else(|| {
+                JayceError::Config(format!(
+                    "Environment {} not found in the configuration file",
+                    name
+                ))
+            })?;
+        if environment.private_key.is_some() {
+            self.private_key = environment.private_key;
+        }
+        if environment.profile.is_some() {
+            self.profile = environment.profile;
+        }
+        if environment.network.is_some() {
+            self.network = environment.network;
+            self.networks = None;
+        }
+        if environment.custom_network.is_some() {
+            self.custom_network = environment.custom_network;
+        }
+        if environment.rest_url.is_some() {
+            self.rest_url = environment.rest_url;
+        }
+        if environment.faucet_url.is_some() {
+            self.faucet_url = environment.faucet_url;
+        }
+        if environment.faucet_urls.is_some() {
+            self.faucet_urls = environment.faucet_urls;
+        }
+        if environment.fee_payer_private_key.is_some() {
+            self.fee_payer_private_key = environment.fee_payer_private_key;
+        }
+        if environment.max_gas.is_some() {
+            self.max_gas = environment.max_gas;
+        }
+        if environment.gas_unit_price.is_some() {
+            self.gas_unit_price = environment.gas_unit_price;
+        }
+        if environment.expiration_secs.is_some() {
+            self.expiration_secs = environment.expiration_secs;
+        }
+        if environment.max_fee.is_some() {
+            self.max_fee = environment.max_fee;
+        }
+        if environment.output_json.is_some() {
+            self.output_json = environment.output_json;
+        }
+        if environment.output_format.is_some() {
+            self.output_format = environment.output_format;
+        }
+        if environment.state_path.is_some() {
+            self.state_path = environment.state_path;
+        }
+        if environment.deployed_addresses.is_some() {
+            self.deployed_addresses = environment.deployed_addresses;
+        }
+        if let Some(named_addresses) = environment.named_addresses {
+            self.named_addresses
+                .get_or_insert_with(BTreeMap::new)
+                .extend(named_addresses);
+        }
+        Ok(())
+    }
+
+    /// Returns the configuration deploying to one of the networks of a multi-network deployment.
+    /// The report and the state file of the network are suffixed with its name, and the
+    /// environment named after the network is applied if there is one.
+    pub fn for_network(&self, network: &AptosNetwork) -> Result<PartialDeployConfig> {
+        let mut partial = self.clone();
+        partial.networks = None;
+        partial.network = Some(network.clone());
+        partial.output_json = partial
+            .output_json
+            .map(|output_json| network_path(&output_json, network));
+        partial.state_path = partial
+            .state_path
+            .map(|state_path| network_path(&state_path, network));
+        partial.generated_key_path = partial
+            .generated_key_path
+            .map(|generated_key_path| network_path(&generated_key_path, network));
+        let name = network.to_string();
+        if partial
+            .environments
+            .as_ref()
+            .is_some_and(|environments| environments.contains_key(&name))
+        {
+            partial.apply_environment(&name)?;
+        }
+        Ok(partial)
+    }
+
+    /// Returns this configuration, with the unset values taken from `lower`, a layer of lower
+    /// precedence. Values replacing each other are taken together: setting the network or the
+    /// networks replaces both, setting the workspace replaces the packages, their paths and their
+    /// address names, and setting the packages or their paths replaces the workspace and the
+    /// packages. The compiler options are merged one by one, and a `[registry]` table setting an
+    /// address isn't replaced by the default one.
+    pub fn or(self, lower: PartialDeployConfig) -> PartialDeployConfig {
+        let (network, networks) = if self.network.is_some() || self.networks.is_some() {
+            (self.network, self.networks)
+        } else {
+            (lower.network, lower.networks)
+        };
+        let (workspace, packages, modules_path, addresses_name) = if self.workspace.is_some() {
+            (self.workspace, self.packages, None, None)
+        } else if self.modules_path.is_some() || self.packages.is_some() {
+            (
+                None,
+                self.packages,
+                self.modules_path.or(lower.modules_path),
+                self.addresses_name.or(lower.addresses_name),
+            )
+        } else {
+            (
+                lower.workspace,
+                lower.packages,
+                lower.modules_path,
+                self.addresses_name.or(lower.addresses_name),
+            )
+        };
+        let compiler = match (self.compiler, lower.compiler) {
+            (Some(compiler), Some(lower_compiler)) => Some(compiler.or(&lower_compiler)),
+            (compiler, lower_compiler) => compiler.or(lower_compiler),
+        };
+        let registry = match self.registry {
+            Some(registry) if registry != RegistryConfig::default() => Some(registry),
+            registry => lower.registry.or(registry),
+        };
+        PartialDeployConfig {
+            private_key: self.private_key.or(lower.private_key),
+            module_type: self.module_type.or(lower.module_type),
+            modules_path,
+            addresses_name,
+            workspace,
+            packages,
+            network,
+            networks,
+            yes: self.yes.or(lower.yes),
+            allow_mainnet: self.allow_mainnet.or(lower.allow_mainnet),
+            output_json: self.output_json.or(lower.output_json),
+            output_format: self.output_format.or(lower.output_format),
+            report_mode: self.report_mode.or(lower.report_mode),
+            deployed_addresses: self.deployed_addresses.or(lower.deployed_addresses),
+            named_addresses: self.named_addresses.or(lower.named_addresses),
+            rest_url: self.rest_url.or(lower.rest_url),
+            faucet_url: self.faucet_url.or(lower.faucet_url),
+            faucet_urls: self.faucet_urls.or(lower.faucet_urls),
+            publish_code: self.publish_code.or(lower.publish_code),
+            resume: self.resume.or(lower.resume),
+            force: self.force.or(lower.force),
+            state_path: self.state_path.or(lower.state_path),
+            generated_key_path: self.generated_key_path.or(lower.generated_key_path),
+            print_key: self.print_key.or(lower.print_key),
+            dry_run: self.dry_run.or(lower.dry_run),
+            skip_balance_check: self.skip_balance_check.or(lower.skip_balance_check),
+            multisig_address: self.multisig_address.or(lower.multisig_address),
+            fee_payer_private_key: self.fee_payer_private_key.or(lower.fee_payer_private_key),
+            max_gas: self.max_gas.or(lower.max_gas),
+            gas_unit_price: self.gas_unit_price.or(lower.gas_unit_price),
+            expiration_secs: self.expiration_secs.or(lower.expiration_secs),
+            max_fee: self.max_fee.or(lower.max_fee),
+            simulate_first: self.simulate_first.or(lower.simulate_first),
+            confirmation_timeout_secs: self
+                .confirmation_timeout_secs
+                .or(lower.confirmation_timeout_secs),
+            poll_interval_ms: self.poll_interval_ms.or(lower.poll_interval_ms),
+            initializers: self.initializers.or(lower.initializers),
+            hooks: self.hooks.or(lower.hooks),
+            package_hooks: self.package_hooks.or(lower.package_hooks),
+            package_options: self.package_options.or(lower.package_options),
+            compiler,
+            profile: self.profile.or(lower.profile),
+            sender_address: self.sender_address.or(lower.sender_address),
+            custom_network: self.custom_network.or(lower.custom_network),
+            chunked_publish: self.chunked_publish.or(lower.chunked_publish),
+            retry: self.retry.or(lower.retry),
+            no_chunked: self.no_chunked.or(lower.no_chunked),
+            large_packages_module_address: self
+                .large_packages_module_address
+                .or(lower.large_packages_module_address),
+            override_size_check: self.override_size_check.or(lower.override_size_check),
+            chunk_size: self.chunk_size.or(lower.chunk_size),
+            address_book: self.address_book.or(lower.address_book),
+            keep_going: self.keep_going.or(lower.keep_going),
+            no_cache: self.no_cache.or(lower.no_cache),
+            changed_only: self.changed_only.or(lower.changed_only),
+            sign_report: self.sign_report.or(lower.sign_report),
+            pipeline: self.pipeline.or(lower.pipeline),
+            artifacts_dir: self.artifacts_dir.or(lower.artifacts_dir),
+            bundle: self.bundle.or(lower.bundle),
+            verify_source: self.verify_source.or(lower.verify_source),
+            events_file: self.events_file.or(lower.events_file),
+            json_events: self.json_events.or(lower.json_events),
+            webhooks: self.webhooks.or(lower.webhooks),
+            registry,
+            github_actions: self.github_actions.or(lower.github_actions),
+            from_report: self.from_report.or(lower.from_report),
+            signer: self.signer.or(lower.signer),
+            environments: self.environments.or(lower.environments),
+            config_path: self.config_path.or(lower.config_path),
+        }
+    }
+
+    /// Reads the private key of `JAYCE_PRIVATE_KEY`, the other environment variables being read
+    /// along with the flags of `deploy`.
+    pub fn from_env() -> PartialDeployConfig {
+        PartialDeployConfig {
+            private_key: env::var(PRIVATE_KEY_ENV).ok(),
+            ..Default::default()
+        }
+    }
+
+    pub fn from_path(path: &str) -> Result<PartialDeployConfig> {
+        Self::from_path_as(path, &ConfigFormat::from_extension(Path::new(path)))
+    }
+
+    pub fn from_path_as(path: &str, format: &ConfigFormat) -> Result<PartialDeployConfig> {
+        let content = fs::read_to_string(path).map_err(|err| {
+            JayceError::Config(format!("Failed to read config file {}: {}", path, err))
+        })?;
         let content = ConfigLoader::builder()
-            .add_source(File::new(path, FileFormat::Toml))
-            .build()?;
-        let args: PartialDeployConfig = content.try_deserialize()?;
+            .add_source(File::from_str(
+                &interpolate_env(path, &content)?,
+                format.file_format(),
+            ))
+            .build()
+            .map_err(|err| JayceError::Config(err.to_string()))?;
+        let mut args: PartialDeployConfig = content.try_deserialize().map_err(|err| {
+            let location = match &err {
+                ConfigError::Type { key: Some(key), .. } => {
+                    config_location(Path::new(path), key.split('.').next().unwrap())
+                }
+                _ => format!("{}: ", path),
+            };
+            JayceError::Config(format!("{}{}", location, err))
+        })?;
+        args.resolve_paths(Path::new(path).parent().unwrap_or(Path::new("")));
+        args.config_path = Some(PathBuf::from(path));
 
         Ok(args)
     }
+
+    /// Expands the glob patterns and directories of `modules_path`, or the workspace, into the
+    /// packages they contain. When `addresses_name` isn't set, the address name of each package is
+    /// inferred from its `Move.toml`. The `[[packages]]` tables are split into `modules_path`,
+    /// `addresses_name` and the options and hooks of the packages.
+    pub fn expand_modules_path(&mut self) -> Result<()> {
+        // The packages of a bundle are read from its manifest when deploying.
+        if self.bundle.is_some() {
+            self.modules_path = Some(vec![]);
+            self.addresses_name = Some(vec![]);
+            self.workspace = None;
+            self.packages = None;
+            return Ok(());
+        }
+        if let Some(packages) = self.packages.take() {
+            return self.expand_packages(packages);
+        }
+        if let Some(workspace) = &self.workspace {
+            if self.modules_path.is_some() || self.addresses_name.is_some() {
+                return Err(JayceError::Config(
+                    "'workspace' can't be combined with 'modules_path' or 'addresses_name'"
+                        .to_string(),
+                ));
+            }
+            let packages = find_packages(workspace)?;
+            if packages.is_empty() {
+                return Err(JayceError::Package(format!(
+                    "No Move package found in {}",
+                    workspace.to_str().unwrap()
+                )));
+            }
+            self.addresses_name = Some(infer_address_names(&packages)?);
+            self.modules_path = Some(packages);
+            return Ok(());
+        }
+        let Some(modules_path) = &self.modules_path else {
+            return Ok(());
+        };
+        let (modules_path, expanded) = expand_modules_path(modules_path)?;
+        let infer_names = match &self.addresses_name {
+            None => true,
+            Some(addresses_name) => expanded && addresses_name.is_empty(),
+        };
+        // Packages without a Move.toml are reported by `validate`.
+        if infer_names
+            && modules_path
+                .iter()
+                .all(|package_dir| package_dir.join("Move.toml").is_file())
+        {
+            self.addresses_name = Some(infer_address_names(&modules_path)?);
+        }
+        self.modules_path = Some(modules_path);
+        Ok(())
+    }
+
+    fn expand_packages(&mut self, packages: Vec<PackageConfig>) -> Result<()> {
+        if self.workspace.is_some() || self.modules_path.is_some() || self.addresses_name.is_some()
+        {
+            return Err(JayceError::Config(
+                "'packages' can't be combined with 'workspace', 'modules_path' or \
+                'addresses_name'"
+                    .to_string(),
+            ));
+        }
+        let mut modules_path = vec![];
+        let mut addresses_name = vec![];
+        for package in packages {
+            if addresses_name.contains(&package.address_name) {
+                return Err(JayceError::Config(format!(
+                    "Package '{}' is declared twice",
+                    package.address_name
+                )));
+            }
+            let options = self
+                .package_options
+                .get_or_insert_with(BTreeMap::new)
+                .entry(package.address_name.clone())
+                .or_default();
+            if package.module_type.is_some() {
+                options.module_type = package.module_type;
+            }
+            options.overrides.extend(package.overrides);
+            if let Some(hooks) = package.hooks {
+                let package_hooks = self.package_hooks.get_or_insert_with(BTreeMap::new);
+                if package_hooks.contains_key(&package.address_name) {
+                    return Err(JayceError::Config(format!(
+                        "The hooks of '{}' are set in both 'packages' and 'package_hooks'",
+                        package.address_name
+                    )));
+                }
+                package_hooks.insert(package.address_name.clone(), hooks);
+            }
+            modules_path.push(resolve_git_source(&package.path)?);
+            addresses_name.push(package.address_name);
+        }
+        self.modules_path = Some(modules_path);
+        self.addresses_name = Some(addresses_name);
+        Ok(())
+    }
+
+    /// Resolves the relative paths of the configuration file against the directory of the file,
+    /// so that the configuration doesn't depend on the directory jayce is run from.
+    fn resolve_paths(&mut self, config_dir: &Path) {
+        let resolve = |path: &mut PathBuf| {
+            if path.is_relative() && !is_git_source(path) {
+                *path = config_dir.join(&*path);
+            }
+        };
+        self.modules_path.iter_mut().flatten().for_each(resolve);
+        self.workspace.iter_mut().for_each(resolve);
+        for package in self.packages.iter_mut().flatten() {
+            resolve(&mut package.path);
+        }
+        self.output_json.iter_mut().for_each(resolve);
+        self.state_path.iter_mut().for_each(resolve);
+        self.generated_key_path.iter_mut().for_each(resolve);
+        self.address_book.iter_mut().for_each(resolve);
+        self.artifacts_dir.iter_mut().for_each(resolve);
+        self.bundle.iter_mut().for_each(resolve);
+        self.events_file.iter_mut().for_each(resolve);
+        self.from_report.iter_mut().for_each(resolve);
+        for environment in self
+            .environments
+            .iter_mut()
+            .flat_map(|environments| environments.values_mut())
+        {
+            environment.output_json.iter_mut().for_each(resolve);
+            environment.state_path.iter_mut().for_each(resolve);
+        }
+        for hooks in self
+            .hooks
+            .iter_mut()
+            .chain(
+                self.package_hooks
+                    .iter_mut()
+                    .flat_map(|hooks| hooks.values_mut()),
+            )
+            .chain(
+                self.packages
+                    .iter_mut()
+                    .flatten()
+                    .filter_map(|package| package.hooks.as_mut()),
+            )
+        {
+            for hook in hooks
+                .pre_deploy
+                .iter_mut()
+                .chain(hooks.post_deploy.iter_mut())
+            {
+                if let Hook::Script(call) = hook {
+                    resolve(&mut call.package_dir);
+                }
+            }
+        }
+    }
+
+    /// Collects every missing or invalid value of the configuration, prefixed with the location of
+    /// the value in the configuration file when it is known.
+    pub fn validate(&self) -> Vec<String> {
+        let location = |key: &str| match &self.config_path {
+            Some(config_path) => config_location(config_path, key),
+            None => String::new(),
+        };
+        let required = [
+            ("network", self.network.is_some()),
+            ("module-type", self.module_type.is_some()),
+            ("modules-path", self.modules_path.is_some()),
+            ("addresses-name", self.addresses_name.is_some()),
+            ("yes", self.yes.is_some()),
+            ("allow-mainnet", self.allow_mainnet.is_some()),
+            ("output-json", self.output_json.is_some()),
+            ("output-format", self.output_format.is_some()),
+            ("report-mode", self.report_mode.is_some()),
+            ("deployed-addresses", self.deployed_addresses.is_some()),
+            ("publish-code", self.publish_code.is_some()),
+            ("resume", self.resume.is_some()),
+            ("force", self.force.is_some()),
+            ("state-path", self.state_path.is_some()),
+            ("generated-key-path", self.generated_key_path.is_some()),
+            ("print-key", self.print_key.is_some()),
+            ("dry-run", self.dry_run.is_some()),
+            ("skip-balance-check", self.skip_balance_check.is_some()),
+            ("keep-going", self.keep_going.is_some()),
+            ("no-cache", self.no_cache.is_some()),
+            ("pipeline", self.pipeline.is_some()),
+            ("changed-only", self.changed_only.is_some()),
+            ("sign-report", self.sign_report.is_some()),
+            ("verify-source", self.verify_source.is_some()),
+            ("json-events", self.json_events.is_some()),
+            ("gha", self.github_actions.is_some()),
+            ("no-chunked", self.no_chunked.is_some()),
+            ("override-size-check", self.override_size_check.is_some()),
+        ];
+        let mut errors: Vec<String> = required
+            .into_iter()
+            .filter(|(_, is_set)| !is_set)
+            .map(|(argument, _)| format!("Missing argument '{}'", argument))
+            .collect();
+
+        if let (Some(modules_path), Some(addresses_name)) =
+            (&self.modules_path, &self.addresses_name)
+        {
+            if modules_path.len() != addresses_name.len() {
+                errors.push(format!(
+                    "{}'addresses_name' has {} entries, but 'modules_path' has {}",
+                    location("addresses_name"),
+                    addresses_name.len(),
+                    modules_path.len()
+                ));
+            }
+        }
+        for module_path in self.modules_path.iter().flatten() {
+            if !module_path.join("Move.toml").is_file() {
+                errors.push(format!(
+                    "{}Package {} doesn't contain a Move.toml",
+                    location("modules_path"),
+                    module_path.to_str().unwrap()
+                ));
+            }
+        }
+        for named_address in self.named_addresses.iter().flatten().map(|(name, _)| name) {
+            if self
+                .addresses_name
+                .iter()
+                .flatten()
+                .any(|name| name == named_address)
+            {
+                errors.push(format!(
+                    "{}'{}' is the address name of a package, it can't be a named address",
+                    location(named_address),
+                    named_address
+                ));
+            }
+        }
+        for (address_name, options) in self.package_options.iter().flatten() {
+            if options.overrides.contains_key(address_name) {
+                errors.push(format!(
+                    "{}Package '{}' can't override its own address",
+                    location("overrides"),
+                    address_name
+                ));
+            }
+            for (named_address, value) in &options.overrides {
+                if value.starts_with("0x") && AccountAddress::from_hex_literal(value).is_err() {
+                    errors.push(format!(
+                        "{}Invalid address '{}' overriding '{}'",
+                        location(named_address),
+                        value,
+                        named_address
+                    ));
+                }
+            }
+        }
+        for (address_name, _) in self
+            .package_options
+            .iter()
+            .flatten()
+            .filter(|(_, options)| {
+                options.freeze
+                    && options.module_type.as_ref().or(self.module_type.as_ref())
+                        == Some(&DeployModuleType::Account)
+            })
+        {
+            errors.push(format!(
+                "{}Package '{}' can't be frozen, only object packages can",
+                location("freeze"),
+                address_name
+            ));
+        }
+        for (address_name, _) in self
+            .package_options
+            .iter()
+            .flatten()
+            .filter(|(_, options)| {
+                !options.secondary_signers.is_empty() && options.publish_function.is_none()
+            })
+        {
+            errors.push(format!(
+                "{}Package '{}' has secondary signers but no 'publish_function' taking them",
+                location("secondary_signers"),
+                address_name
+            ));
+        }
+        if self.verify_source == Some(true) {
+            for (address_name, _) in self
+                .package_options
+                .iter()
+                .flatten()
+                .filter(|(_, options)| options.included_artifacts == Some(IncludedArtifacts::None))
+            {
+                errors.push(format!(
+                    "{}Package '{}' is published without its sources, it can't be verified",
+                    location("included_artifacts"),
+                    address_name
+                ));
+            }
+        }
+        for webhook in self.webhooks.iter().flatten() {
+            if Url::parse(&webhook.url).is_err() {
+                errors.push(format!("{}Invalid webhook URL", location("url")));
+            }
+        }
+        if self.network == Some(AptosNetwork::Custom) && self.custom_network.is_none() {
+            errors.push(format!(
+                "{}Network 'custom' requires a 'custom_network' table",
+                location("network")
+            ));
+        }
+        if let Some(chunk_size) = self.chunk_size {
+            if chunk_size == 0 || chunk_size > MAX_PUBLISH_PACKAGE_SIZE {
+                errors.push(format!(
+                    "{}'chunk_size' must be between 1 and {} bytes",
+                    location("chunk_size"),
+                    MAX_PUBLISH_PACKAGE_SIZE
+                ));
+            }
+        }
+        errors
+    }
 }
 
-impl From<PartialDeployConfig> for DeployConfig {
-    fn from(value: PartialDeployConfig) -> Self {
-        DeployConfig {
+/// Suffixes the name of a file with a network, e.g. `deploy-report-testnet.json`.
+fn network_path(path: &Path, network: &AptosNetwork) -> PathBuf {
+    let stem = path
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or_default();
+    match path.extension().and_then(|extension| extension.to_str()) {
+        Some(extension) => path.with_file_name(format!("{}-{}.{}", stem, network, extension)),
+        None => path.with_file_name(format!("{}-{}", stem, network)),
+    }
+}
+
+/// Expands the `${VARIABLE}` and `${VARIABLE:-default}` references of a configuration file with
+/// the values of the environment variables. Comment lines are left untouched.
+fn interpolate_env(path: &str, content: &str) -> Result<String> {
+    let mut interpolated = String::with_capacity(content.len());
+    for (index, line) in content.split_inclusive('\n').enumerate() {
+        let mut rest = line;
+        if !line.trim_start().starts_with('#') {
+            while let Some(start) = rest.find("${") {
+                interpolated.push_str(&rest[..start]);
+                let end = rest[start..].find('}').ok_or_else(|| {
+                    JayceError::Config(format!("{}:{}: Unterminated '${{'", path, index + 1))
+                })? + start;
+                let reference = &rest[start + 2..end];
+                let (variable, default) = match reference.split_once(":-") {
+                    Some((variable, default)) => (variable, Some(default)),
+                    None => (reference, None),
+                };
+                match (env::var(variable), default) {
+                    (Ok(value), _) => interpolated.push_str(&value),
+                    (Err(_), Some(default)) => interpolated.push_str(default),
+                    (Err(_), None) => {
+                        return Err(JayceError::Config(format!(
+                            "{}:{}: Environment variable {} is not set",
+                            path,
+                            index + 1,
+                            variable
+                        )))
+                    }
+                }
+                rest = &rest[end + 1..];
+            }
+        }
+        interpolated.push_str(rest);
+    }
+    Ok(interpolated)
+}
+
+/// Returns the `<path>:<line>: ` prefix of the line setting `key` in a TOML, YAML or JSON
+/// configuration file, or `<path>: ` if the line isn't found.
+fn config_location(path: &Path, key: &str) -> String {
+    let line = fs::read_to_string(path).ok().and_then(|content| {
+        content.lines().position(|line| {
+            let line = line.trim_start();
+            let line = line.strip_prefix('"').unwrap_or(line);
+            line.strip_prefix(key).is_some_and(|rest| {
+                let rest = rest.strip_prefix('"').unwrap_or(rest).trim_start();
+                rest.starts_with('=') || rest.starts_with(':')
+            })
+        })
+    });
+    match line {
+        Some(line) => format!("{}:{}: ", path.to_str().unwrap(), line + 1),
+        None => format!("{}: ", path.to_str().unwrap()),
+    }
+}
+
+fn missing(argument: &str) -> JayceError {
+    JayceError::Config(format!("Missing argument '{}'", argument))
+}
+
+impl TryFrom<PartialDeployConfig> for DeployConfig {
+    type Error = JayceError;
+
+    fn try_from(mut value: PartialDeployConfig) -> Result<Self> {
+        value.expand_modules_path()?;
+        let errors = value.validate();
+        if !errors.is_empty() {
+            return Err(JayceError::Config(format!(
+                "Invalid deploy configuration:\n  {}",
+                errors.join("\n  ")
+            )));
+        }
+        let network = value.network.ok_or_else(|| missing("network"))?;
+        let custom_network = match network {
+            AptosNetwork::Custom => value.custom_network,
+            _ => None,
+        };
+        for private_key in value
+            .private_key
+            .iter()
+            .chain(&value.fee_payer_private_key)
+            .chain(
+                value
+                    .package_options
+                    .iter()
+                    .flat_map(|package_options| package_options.values())
+                    .flat_map(|options| {
+                        options.private_key.iter().chain(&options.secondary_signers)
+                    }),
+            )
+        {
+            register_secret(private_key);
+        }
+        Ok(DeployConfig {
             private_key: value.private_key,
-            module_type: value.module_type.expect("Missing argument 'module type'"),
-            modules_path: value.modules_path.expect("Missing argument 'modules-path'"),
+            module_type: value.module_type.ok_or_else(|| missing("module-type"))?,
+            modules_path: value.modules_path.ok_or_else(|| missing("modules-path"))?,
             addresses_name: value
                 .addresses_name
-                .expect("Missing argument 'addresses-name'"),
-            network: value.network.expect("Missing argument 'network'"),
-            yes: value.yes.expect("Missing argument 'yes'"),
-            output_json: value.output_json.expect("Missing argument 'output-json'"),
+                .ok_or_else(|| missing("addresses-name"))?,
+            network,
+            yes: value.yes.ok_or_else(|| missing("yes"))?,
+            allow_mainnet: value
+                .allow_mainnet
+                .ok_or_else(|| missing("allow-mainnet"))?,
+            output_json: value.output_json.ok_or_else(|| missing("output-json"))?,
+            output_format: value
+                .output_format
+                .ok_or_else(|| missing("output-format"))?,
+            report_mode: value.report_mode.ok_or_else(|| missing("report-mode"))?,
             deployed_addresses: value
                 .deployed_addresses
-                .expect("Missing argument 'deployed-addresses'"),
-            rest_url: value.rest_url,
-            faucet_url: value.faucet_url,
-            publish_code: value.publish_code.expect("Missing argument 'publish-code'"),
+                .ok_or_else(|| missing("deployed-addresses"))?,
+            named_addresses: value.named_addresses.unwrap_or_default(),
+            rest_url: value.rest_url.or_else(|| {
+                custom_network
+                    .as_ref()
+                    .map(|custom_network| custom_network.rest_url.clone())
+            }),
+            faucet_url: value.faucet_url.or_else(|| {
+                custom_network
+                    .as_ref()
+                    .and_then(|custom_network| custom_network.faucet_url.clone())
+            }),
+            faucet_urls: value.faucet_urls.unwrap_or_default(),
+            publish_code: value.publish_code.ok_or_else(|| missing("publish-code"))?,
+            resume: value.resume.ok_or_else(|| missing("resume"))?,
+            force: value.force.ok_or_else(|| missing("force"))?,
+            state_path: value.state_path.ok_or_else(|| missing("state-path"))?,
+            generated_key_path: value
+                .generated_key_path
+                .ok_or_else(|| missing("generated-key-path"))?,
+            print_key: value.print_key.ok_or_else(|| missing("print-key"))?,
+            dry_run: value.dry_run.ok_or_else(|| missing("dry-run"))?,
+            skip_balance_check: value
+                .skip_balance_check
+                .ok_or_else(|| missing("skip-balance-check"))?,
+            multisig_address: value.multisig_address,
+            fee_payer_private_key: value.fee_payer_private_key,
+            max_gas: value.max_gas,
+            gas_unit_price: value.gas_unit_price,
+            expiration_secs: value.expiration_secs,
+            max_fee: value.max_fee,
+            simulate_first: value.simulate_first,
+            confirmation_timeout_secs: value
+                .confirmation_timeout_secs
+                .unwrap_or(DEFAULT_CONFIRMATION_TIMEOUT_SECS),
+            poll_interval_ms: value.poll_interval_ms.unwrap_or(DEFAULT_POLL_INTERVAL_MS),
+            initializers: value.initializers.unwrap_or_default(),
+            hooks: value.hooks.unwrap_or_default(),
+            package_hooks: value.package_hooks.unwrap_or_default(),
+            package_options: value.package_options.unwrap_or_default(),
+            compiler: value.compiler.unwrap_or_default(),
+            profile: value.profile,
+            sender_address: value.sender_address,
+            custom_network,
+            chunked_publish: value.chunked_publish,
+            retry: value.retry.unwrap_or_default(),
+            no_chunked: value.no_chunked.ok_or_else(|| missing("no-chunked"))?,
+            large_packages_module_address: value.large_packages_module_address,
+            override_size_check: value
+                .override_size_check
+                .ok_or_else(|| missing("override-size-check"))?,
+            chunk_size: value.chunk_size,
+            address_book: value.address_book,
+            keep_going: value.keep_going.ok_or_else(|| missing("keep-going"))?,
+            no_cache: value.no_cache.ok_or_else(|| missing("no-cache"))?,
+            pipeline: value.pipeline.ok_or_else(|| missing("pipeline"))?,
+            changed_only: value.changed_only.ok_or_else(|| missing("changed-only"))?,
+            sign_report: value.sign_report.ok_or_else(|| missing("sign-report"))?,
+            artifacts_dir: value.artifacts_dir,
+            bundle: value.bundle,
+            verify_source: value
+                .verify_source
+                .ok_or_else(|| missing("verify-source"))?,
+            events_file: value.events_file,
+            json_events: value.json_events.ok_or_else(|| missing("json-events"))?,
+            webhooks: value.webhooks.unwrap_or_default(),
+            registry: value.registry,
+            github_actions: value.github_actions.ok_or_else(|| missing("gha"))?,
+            from_report: value.from_report,
+            signer: value.signer,
+        })
+    }
+}
+
+impl DeployConfig {
+    /// The artifacts published with a package: the ones of its options if it has some, otherwise
+    /// `all` with `publish_code`, `sparse` with `verify_source` and `none` without either.
+    pub fn included_artifacts(&self, address_name: &str) -> IncludedArtifacts {
+        if let Some(included_artifacts) = self
+            .package_options
+            .get(address_name)
+            .and_then(|options| options.included_artifacts.clone())
+        {
+            return included_artifacts;
+        }
+        if self.publish_code {
+            IncludedArtifacts::All
+        } else if self.verify_source {
+            IncludedArtifacts::Sparse
+        } else {
+            IncludedArtifacts::None
+        }
+    }
+
+    /// The upgrade policy the options of a package set, overriding the one of its `Move.toml`.
+    pub fn upgrade_policy(&self, address_name: &str) -> Option<&UpgradePolicy> {
+        self.package_options
+            .get(address_name)
+            .and_then(|options| options.upgrade_policy.as_ref())
+    }
+
+    /// The chain id the REST API must serve, if it is known.
+    pub fn expected_chain_id(&self) -> Option<u8> {
+        match &self.custom_network {
+            Some(custom_network) => custom_network.chain_id,
+            None => self.network.chain_id(),
+        }
+    }
+
+    /// Returns the explorer page of an account or a transaction, using the explorer of the custom
+    /// network if there is one.
+    pub fn explorer_url(&self, kind: &str, id: &str) -> Option<String> {
+        match &self.custom_network {
+            Some(custom_network) => custom_network.explorer_url.as_ref().map(|explorer_url| {
+                format!("{}/{}/{}", explorer_url.trim_end_matches('/'), kind, id)
+            }),
+            None => self.network.explorer_url(kind, id),
+        }
+    }
+
+    /// Returns the faucets to fund accounts from, in order: `faucet_url`, or the faucet of the
+    /// network, then `faucet_urls`.
+    pub fn faucets(&self) -> Vec<String> {
+        self.faucet_url
+            .clone()
+            .or_else(|| self.network.faucet_url())
+            .into_iter()
+            .chain(self.faucet_urls.iter().cloned())
+            .collect()
+    }
+
+    /// Returns the fixed named addresses with the deployed addresses added, as resolved by the
+    /// scripts and entry functions of the deployment.
+    pub fn known_addresses(
+        &self,
+        deployed_addresses: &BTreeMap<String, AccountAddress>,
+    ) -> BTreeMap<String, AccountAddress> {
+        let mut addresses = self.named_addresses.clone();
+        addresses.extend(deployed_addresses.clone());
+        addresses
+    }
+
+    /// Returns the addresses of the named addresses of a package: the fixed named addresses and
+    /// the deployed addresses, with the overrides of the package applied.
+    pub fn package_addresses(
+        &self,
+        address_name: &str,
+        deployed_addresses: &BTreeMap<String, AccountAddress>,
+    ) -> Result<BTreeMap<String, AccountAddress>> {
+        let known_addresses = self.known_addresses(deployed_addresses);
+        match self.package_options.get(address_name) {
+            Some(options) => options.named_addresses(address_name, &known_addresses),
+            None => Ok(known_addresses),
+        }
+    }
+
+    /// How a package is deployed: the module type of its options, otherwise the one of the
+    /// deployment.
+    pub fn package_module_type(&self, address_name: &str) -> DeployModuleType {
+        self.package_options
+            .get(address_name)
+            .and_then(|options| options.module_type.clone())
+            .unwrap_or_else(|| self.module_type.clone())
+    }
+
+    /// The compiler options of a package, with the unset ones taken from the ones of the
+    /// deployment.
+    pub fn compiler_options(&self, address_name: &str) -> CompilerOptions {
+        match self.package_options.get(address_name) {
+            Some(options) => options.compiler.or(&self.compiler),
+            None => self.compiler.clone(),
+        }
+    }
+
+    /// Builds the aptos CLI options compiling a package.
+    pub fn compiler_args(&self, address_name: &str) -> String {
+        self.compiler_options(address_name).args()
+    }
+
+    /// Whether a package is compiled in a container, whose bytecode must then be published as is
+    /// rather than compiled again by the aptos CLI.
+    pub fn builds_in_docker(&self) -> bool {
+        self.addresses_name
+            .iter()
+            .any(|address_name| self.compiler_options(address_name).docker_image.is_some())
+    }
+
+    /// The name of the network, or of the custom network if there is one.
+    pub fn network_name(&self) -> String {
+        match &self.custom_network {
+            Some(custom_network) => custom_network.name.clone(),
+            None => self.network.to_string(),
+        }
+    }
+
+    pub fn large_packages_module_address(&self) -> Option<AccountAddress> {
+        self.large_packages_module_address
+            .or_else(|| self.network.large_packages_module_address())
+    }
+
+    pub fn chunked_publish_supported(&self) -> bool {
+        self.chunked_publish
+            .unwrap_or_else(|| self.network.supports_chunked_publish())
+    }
+
+    /// Whether the publications are simulated before they are submitted: always with `max_fee`,
+    /// otherwise with `simulate_first`, which defaults to true on mainnet.
+    pub fn simulates_first(&self) -> bool {
+        self.max_fee.is_some()
+            || self
+                .simulate_first
+                .unwrap_or(self.network == AptosNetwork::Mainnet)
+    }
+}
+
+/// Resolves a private key value, which is either the key itself, `env:<VARIABLE>` to read it from
+/// an environment variable, `file:<PATH>` to read it from a file, or `keystore:<PATH>` to decrypt
+/// it from a keystore file.
+pub fn resolve_private_key(value: &str) -> Result<String> {
+    let private_key = resolve_private_key_value(value)?;
+    register_secret(&private_key);
+    Ok(private_key)
+}
+
+fn resolve_private_key_value(value: &str) -> Result<String> {
+    if let Some(variable) = value.strip_prefix("env:") {
+        env::var(variable).map_err(|_| {
+            JayceError::Config(format!(
+                "Environment variable {} of the private key is not set",
+                variable
+            ))
+        })
+    } else if let Some(path) = value.strip_prefix("file:") {
+        read_private_key_file(Path::new(path))
+    } else if let Some(path) = value.strip_prefix("keystore:") {
+        read_keystore(Path::new(path))
+    } else {
+        Ok(value.to_string())
+    }
+}
+
+pub fn read_private_key_file(path: &Path) -> Result<String> {
+    fs::read_to_string(path)
+        .map(|content| content.trim().to_string())
+        .map_err(|err| {
+            JayceError::Config(format!(
+                "Failed to read private key file {}: {}",
+                path.to_str().unwrap(),
+                err
+            ))
+        })
+}
+
+/// Derives the private key of a BIP-44 derivation path from the mnemonic phrase of a file, the way
+/// wallets do.
+pub fn read_mnemonic_file(path: &Path, derivation_path: &str) -> Result<String> {
+    let content = fs::read_to_string(path).map_err(|err| {
+        JayceError::Config(format!(
+            "Failed to read mnemonic file {}: {}",
+            path.to_str().unwrap(),
+            err
+        ))
+    })?;
+    let mnemonic = content.split_whitespace().collect::<Vec<_>>().join(" ");
+    let account = LocalAccount::from_derive_path(derivation_path, &mnemonic, 0).map_err(|err| {
+        JayceError::Config(format!(
+            "Failed to derive the private key of {}: {}",
+            derivation_path, err
+        ))
+    })?;
+    Ok(account.private_key().to_encoded_string()?)
+}
+
+/// Selects the private key with the following precedence: `--private-key`, `--private-key-file`,
+/// the `JAYCE_PRIVATE_KEY` environment variable, then the configuration file.
+pub fn select_private_key(
+    cli_private_key: Option<String>,
+    cli_private_key_file: Option<PathBuf>,
+    env_private_key: Option<String>,
+    config_private_key: Option<String>,
+) -> Result<Option<String>> {
+    if let Some(private_key) = cli_private_key {
+        return resolve_private_key(&private_key).map(Some);
+    }
+    if let Some(path) = cli_private_key_file {
+        return read_private_key_file(&path).map(Some);
+    }
+    if let Some(private_key) = env_private_key {
+        return Ok(Some(private_key));
+    }
+    config_private_key
+        .map(|private_key| resolve_private_key(&private_key))
+        .transpose()
+}
+
+/// The layers a deploy configuration is merged from, from the lowest precedence to the highest.
+#[derive(Debug, Clone, Default)]
+pub struct ConfigLayers {
+    /// The default values of the flags.
+    pub defaults: PartialDeployConfig,
+    /// The configuration file, with its environment applied.
+    pub config_file: PartialDeployConfig,
+    /// The values of the environment variables.
+    pub environment: PartialDeployConfig,
+    /// The flags passed on the command line.
+    pub flags: PartialDeployConfig,
+}
+
+impl ConfigLayers {
+    /// Merges the layers, each value being taken from the layer of highest precedence setting it.
+    /// The aptos CLI profile then fills the private key, network and URLs set by no layer but the
+    /// defaults.
+    pub fn merge(&self) -> Result<PartialDeployConfig> {
+        // Only the private key of the configuration file is resolved, and only when it is used.
+        let private_key = select_private_key(
+            self.flags.private_key.clone(),
+            None,
+            self.environment.private_key.clone(),
+            self.config_file.private_key.clone(),
+        )?;
+        let mut partial_deploy_config = self
+            .flags
+            .clone()
+            .or(self.environment.clone())
+            .or(self.config_file.clone());
+        partial_deploy_config.private_key = private_key;
+        if let Some(profile) = &partial_deploy_config.profile {
+            let profile = AptosProfile::load(profile)?;
+            partial_deploy_config.apply_profile(profile);
         }
+        partial_deploy_config.apply_package_profiles()?;
+        Ok(partial_deploy_config.or(self.defaults.clone()))
     }
 }
 
@@ -92,7 +1612,13 @@ impl AptosNetwork {
             AptosNetwork::Mainnet => Some("https://api.mainnet.aptoslabs.com/v1".to_string()),
             AptosNetwork::Testnet => Some("https://api.testnet.aptoslabs.com/v1".to_string()),
             AptosNetwork::Devnet => Some("https://api.devnet.aptoslabs.com/v1".to_string()),
-            AptosNetwork::Local => None,
+            AptosNetwork::Local | AptosNetwork::Custom => None,
+            AptosNetwork::MovementMainnet => {
+                Some("https://mainnet.movementnetwork.xyz/v1".to_string())
+            }
+            AptosNetwork::MovementTestnet => {
+                Some("https://testnet.bardock.movementnetwork.xyz/v1".to_string())
+            }
         }
     }
 
@@ -101,14 +1627,87 @@ impl AptosNetwork {
             AptosNetwork::Mainnet => None,
             AptosNetwork::Testnet => Some("https://faucet.testnet.aptoslabs.com".to_string()),
             AptosNetwork::Devnet => Some("https://faucet.devnet.aptoslabs.com".to_string()),
-            AptosNetwork::Local => None,
+            AptosNetwork::Local | AptosNetwork::Custom => None,
+            AptosNetwork::MovementMainnet => None,
+            AptosNetwork::MovementTestnet => {
+                Some("https://faucet.testnet.bardock.movementnetwork.xyz".to_string())
+            }
+        }
+    }
+
+    /// The chain id of the network, `None` if it isn't fixed.
+    pub fn chain_id(&self) -> Option<u8> {
+        match self {
+            AptosNetwork::Mainnet => Some(1),
+            AptosNetwork::Testnet => Some(2),
+            AptosNetwork::MovementMainnet => Some(126),
+            AptosNetwork::MovementTestnet => Some(250),
+            AptosNetwork::Devnet | AptosNetwork::Local | AptosNetwork::Custom => None,
+        }
+    }
+
+    /// Returns the explorer page of an account or a transaction (`kind` being `account` or `txn`)
+    /// on the network.
+    pub fn explorer_url(&self, kind: &str, id: &str) -> Option<String> {
+        match self {
+            AptosNetwork::Mainnet
+            | AptosNetwork::Testnet
+            | AptosNetwork::Devnet
+            | AptosNetwork::Local => Some(format!(
+                "https://explorer.aptoslabs.com/{}/{}?network={}",
+                kind, id, self
+            )),
+            AptosNetwork::MovementMainnet => Some(format!(
+                "https://explorer.movementnetwork.xyz/{}/{}?network=mainnet",
+                kind, id
+            )),
+            AptosNetwork::MovementTestnet => Some(format!(
+                "https://explorer.movementnetwork.xyz/{}/{}?network=bardock+testnet",
+                kind, id
+            )),
+            AptosNetwork::Custom => None,
+        }
+    }
+
+    /// Whether the chunked publish module is deployed on the network.
+    pub fn supports_chunked_publish(&self) -> bool {
+        matches!(
+            self,
+            AptosNetwork::Mainnet
+                | AptosNetwork::Testnet
+                | AptosNetwork::Devnet
+                | AptosNetwork::Local
+        )
+    }
+
+    /// The address of the chunked publish module when it isn't the one of mainnet and testnet.
+    /// Devnet and local networks ship it in the `aptos_experimental` package at `0x7`.
+    pub fn large_packages_module_address(&self) -> Option<AccountAddress> {
+        match self {
+            AptosNetwork::Devnet | AptosNetwork::Local => {
+                Some(AccountAddress::from_hex_literal("0x7").unwrap())
+            }
+            _ => None,
         }
     }
 }
 
 #[cfg(test)]
 mod test {
-    use crate::deploy_config::PartialDeployConfig;
+    use std::collections::BTreeMap;
+    use std::path::PathBuf;
+    use std::{env, fs};
+
+    use aptos_sdk::move_types::account_address::AccountAddress;
+
+    use crate::deploy_config::{
+        read_mnemonic_file, select_private_key, AptosNetwork, CompilerOptions, ConfigFormat,
+        ConfigLayers, DeployModuleType, EnvironmentConfig, IncludedArtifacts, PackageOptions,
+        PartialDeployConfig, DEFAULT_DERIVATION_PATH,
+    };
+    use crate::deploy_report::JAYCE_VERSION;
+    use crate::registry::RegistryConfig;
+    use crate::tasks::deploy_contracts::MAX_PUBLISH_PACKAGE_SIZE;
 
     #[test]
     fn test_read_deploy_config() {
@@ -116,4 +1715,515 @@ mod test {
             PartialDeployConfig::from_path("examples/config-files/deploy-contracts.toml").unwrap();
         dbg!(x);
     }
+
+    #[test]
+    fn test_validate_deploy_config() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let config_path = temp_dir.path().join("validate.toml");
+        fs::write(
+            &config_path,
+            "network = \"custom\"\nmodules_path = [\"missing\"]\naddresses_name = []\n\
+            chunk_size = 0\n",
+        )
+        .unwrap();
+        let partial = PartialDeployConfig::from_path(config_path.to_str().unwrap()).unwrap();
+        assert_eq!(partial.network, Some(AptosNetwork::Custom));
+        // Paths are relative to the configuration file.
+        let missing = temp_dir.path().join("missing");
+        assert_eq!(partial.modules_path, Some(vec![missing.clone()]));
+
+        let errors = partial.validate();
+        let path = config_path.to_str().unwrap();
+        assert!(errors.contains(&"Missing argument 'module-type'".to_string()));
+        assert!(errors.contains(&format!(
+            "{}:3: 'addresses_name' has 0 entries, but 'modules_path' has 1",
+            path
+        )));
+        assert!(errors.contains(&format!(
+            "{}:2: Package {} doesn't contain a Move.toml",
+            path,
+            missing.to_str().unwrap()
+        )));
+        assert!(errors.contains(&format!(
+            "{}:1: Network 'custom' requires a 'custom_network' table",
+            path
+        )));
+        assert!(errors.contains(&format!(
+            "{}:4: 'chunk_size' must be between 1 and {} bytes",
+            path, MAX_PUBLISH_PACKAGE_SIZE
+        )));
+    }
+
+    #[test]
+    fn test_interpolate_env() {
+        env::set_var("JAYCE_TEST_REST_URL", "http://localhost:8080/v1");
+        env::remove_var("JAYCE_TEST_UNSET");
+        let temp_dir = tempfile::tempdir().unwrap();
+        let config_path = temp_dir.path().join("interpolate.toml");
+        fs::write(
+            &config_path,
+            "# ${JAYCE_TEST_UNSET}\n\
+            rest_url = \"${JAYCE_TEST_REST_URL}\"\n\
+            faucet_url = \"${JAYCE_TEST_UNSET:-http://localhost:8081}\"\n",
+        )
+        .unwrap();
+        let partial = PartialDeployConfig::from_path(config_path.to_str().unwrap()).unwrap();
+        assert_eq!(
+            partial.rest_url,
+            Some("http://localhost:8080/v1".to_string())
+        );
+        assert_eq!(
+            partial.faucet_url,
+            Some("http://localhost:8081".to_string())
+        );
+
+        fs::write(&config_path, "\nprivate_key = \"${JAYCE_TEST_UNSET}\"\n").unwrap();
+        assert_eq!(
+            PartialDeployConfig::from_path(config_path.to_str().unwrap())
+                .unwrap_err()
+                .to_string(),
+            format!(
+                "Config error: {}:2: Environment variable JAYCE_TEST_UNSET is not set",
+                config_path.to_str().unwrap()
+            )
+        );
+    }
+
+    #[test]
+    fn test_packages() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let config_path = temp_dir.path().join("packages.toml");
+        fs::write(
+            &config_path,
+            "module_type = \"object\"\n\n\
+            [[packages]]\n\
+            path = \"libs\"\n\
+            address_name = \"lib_addr\"\n\
+            module_type = \"account\"\n\n\
+            [[packages]]\n\
+            path = \"cpu\"\n\
+            address_name = \"cpu_addr\"\n\
+            overrides = { std_addr = \"0x1\" }\n\
+            hooks = { post_deploy = [\"echo deployed\"] }\n",
+        )
+        .unwrap();
+        let mut partial = PartialDeployConfig::from_path(config_path.to_str().unwrap()).unwrap();
+        partial.expand_modules_path().unwrap();
+        assert_eq!(
+            partial.modules_path,
+            Some(vec![
+                temp_dir.path().join("libs"),
+                temp_dir.path().join("cpu")
+            ])
+        );
+        assert_eq!(
+            partial.addresses_name,
+            Some(vec!["lib_addr".to_string(), "cpu_addr".to_string()])
+        );
+        let package_options = partial.package_options.unwrap();
+        assert_eq!(
+            package_options["lib_addr"].module_type,
+            Some(DeployModuleType::Account)
+        );
+        assert_eq!(package_options["cpu_addr"].module_type, None);
+        assert_eq!(package_options["cpu_addr"].overrides["std_addr"], "0x1");
+        assert_eq!(
+            partial.package_hooks.unwrap()["cpu_addr"].post_deploy.len(),
+            1
+        );
+
+        fs::write(
+            &config_path,
+            "modules_path = [\"cpu\"]\n\n\
+            [[packages]]\n\
+            path = \"cpu\"\n\
+            address_name = \"cpu_addr\"\n",
+        )
+        .unwrap();
+        let mut partial = PartialDeployConfig::from_path(config_path.to_str().unwrap()).unwrap();
+        assert!(partial.expand_modules_path().is_err());
+    }
+
+    #[test]
+    fn test_read_yaml_and_json_config() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let yaml_path = temp_dir.path().join("config.yaml");
+        fs::write(
+            &yaml_path,
+            "network: testnet\nmodules_path:\n  - missing\naddresses_name: []\n",
+        )
+        .unwrap();
+        let partial = PartialDeployConfig::from_path(yaml_path.to_str().unwrap()).unwrap();
+        assert_eq!(partial.network, Some(AptosNetwork::Testnet));
+        assert!(partial.validate().contains(&format!(
+            "{}:4: 'addresses_name' has 0 entries, but 'modules_path' has 1",
+            yaml_path.to_str().unwrap()
+        )));
+
+        let json_path = temp_dir.path().join("config");
+        fs::write(
+            &json_path,
+            "{\n  \"network\": \"devnet\",\n  \"yes\": true\n}\n",
+        )
+        .unwrap();
+        let partial =
+            PartialDeployConfig::from_path_as(json_path.to_str().unwrap(), &ConfigFormat::Json)
+                .unwrap();
+        assert_eq!(partial.network, Some(AptosNetwork::Devnet));
+        assert_eq!(partial.yes, Some(true));
+    }
+
+    #[test]
+    fn test_compiler_options() {
+        let defaults = CompilerOptions {
+            skip_fetch_latest_git_deps: Some(true),
+            compiler_version: Some("2.0".to_string()),
+            ..Default::default()
+        };
+        let options = CompilerOptions {
+            skip_fetch_latest_git_deps: Some(false),
+            language_version: Some("2.1".to_string()),
+            bytecode_version: Some(7),
+            ..Default::default()
+        };
+        assert_eq!(
+            defaults.args(),
+            "--skip-fetch-latest-git-deps --compiler-version 2.0"
+        );
+        assert_eq!(
+            options.or(&defaults).args(),
+            "--compiler-version 2.0 --language-version 2.1 --bytecode-version 7"
+        );
+        assert_eq!(CompilerOptions::default().args(), "");
+        // The aptos CLI built into jayce changes with its version.
+        assert_eq!(
+            CompilerOptions::default().cache_key(),
+            format!("--jayce-version {}", JAYCE_VERSION)
+        );
+
+        let docker = CompilerOptions {
+            docker_image: Some("aptoslabs/tools:nightly".to_string()),
+            ..Default::default()
+        };
+        let options = options.or(&docker);
+        // The image isn't an option of the aptos CLI, but changes the bytecode.
+        assert_eq!(
+            options.args(),
+            "--language-version 2.1 --bytecode-version 7"
+        );
+        assert_eq!(
+            options.cache_key(),
+            "--language-version 2.1 --bytecode-version 7 --docker-image aptoslabs/tools:nightly"
+        );
+    }
+
+    #[test]
+    fn test_package_overrides() {
+        let options = PackageOptions {
+            overrides: BTreeMap::from([
+                ("std_addr".to_string(), "0x1".to_string()),
+                ("external_addr".to_string(), "lib_addr".to_string()),
+            ]),
+            ..Default::default()
+        };
+        let deployed_addresses = BTreeMap::from([("lib_addr".to_string(), AccountAddress::TWO)]);
+        let named_addresses = options
+            .named_addresses("cpu_addr", &deployed_addresses)
+            .unwrap();
+        assert_eq!(named_addresses["std_addr"], AccountAddress::ONE);
+        assert_eq!(named_addresses["external_addr"], AccountAddress::TWO);
+        assert_eq!(named_addresses["lib_addr"], AccountAddress::TWO);
+
+        assert!(options
+            .named_addresses("cpu_addr", &BTreeMap::new())
+            .is_err());
+    }
+
+    #[test]
+    fn test_included_artifacts() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let config_path = temp_dir.path().join("included_artifacts.toml");
+        fs::write(
+            &config_path,
+            "verify_source = true\n\
+            [package_options.lib_addr]\n\
+            included_artifacts = \"none\"\n\
+            [package_options.verifier_addr]\n\
+            included_artifacts = \"all\"\n",
+        )
+        .unwrap();
+        let partial = PartialDeployConfig::from_path(config_path.to_str().unwrap()).unwrap();
+        let package_options = partial.package_options.clone().unwrap();
+        assert_eq!(
+            package_options["verifier_addr"].included_artifacts,
+            Some(IncludedArtifacts::All)
+        );
+        assert!(partial
+            .validate()
+            .iter()
+            .any(|error| error.contains("Package 'lib_addr' is published without its sources")));
+    }
+
+    #[test]
+    fn test_apply_environment() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let config_path = temp_dir.path().join("environments.toml");
+        fs::write(
+            &config_path,
+            "network = \"devnet\"\n\
+            output_json = \"deploy-report.json\"\n\
+            max_gas = 1000\n\n\
+            [named_addresses]\n\
+            std = \"0x1\"\n\
+            oracle = \"0xabc\"\n\n\
+            [environments.mainnet]\n\
+            network = \"mainnet\"\n\
+            private_key = \"env:MAINNET_KEY\"\n\
+            output_json = \"mainnet-report.json\"\n\
+            named_addresses = { oracle = \"0xdef\" }\n",
+        )
+        .unwrap();
+        let mut partial = PartialDeployConfig::from_path(config_path.to_str().unwrap()).unwrap();
+        assert!(partial.clone().apply_environment("testnet").is_err());
+
+        partial.apply_environment("mainnet").unwrap();
+        assert_eq!(partial.network, Some(AptosNetwork::Mainnet));
+        assert_eq!(partial.private_key, Some("env:MAINNET_KEY".to_string()));
+        assert_eq!(
+            partial.output_json,
+            Some(temp_dir.path().join("mainnet-report.json"))
+        );
+        assert_eq!(partial.max_gas, Some(1000));
+        assert_eq!(
+            partial.named_addresses,
+            Some(BTreeMap::from([
+                (
+                    "oracle".to_string(),
+                    AccountAddress::from_hex_literal("0xdef").unwrap()
+                ),
+                ("std".to_string(), AccountAddress::ONE),
+            ]))
+        );
+    }
+
+    #[test]
+    fn test_for_network() {
+        let partial = PartialDeployConfig {
+            networks: Some(vec![AptosNetwork::Testnet, AptosNetwork::Devnet]),
+            output_json: Some(PathBuf::from("reports/deploy-report.json")),
+            state_path: Some(PathBuf::from(".jayce-state.json")),
+            environments: Some(BTreeMap::from([(
+                "devnet".to_string(),
+                EnvironmentConfig {
+                    max_gas: Some(1000),
+                    ..Default::default()
+                },
+            )])),
+            ..Default::default()
+        };
+
+        let testnet = partial.for_network(&AptosNetwork::Testnet).unwrap();
+        assert_eq!(testnet.network, Some(AptosNetwork::Testnet));
+        assert_eq!(testnet.networks, None);
+        assert_eq!(
+            testnet.output_json,
+            Some(PathBuf::from("reports/deploy-report-testnet.json"))
+        );
+        assert_eq!(
+            testnet.state_path,
+            Some(PathBuf::from(".jayce-state-testnet.json"))
+        );
+        assert_eq!(testnet.max_gas, None);
+
+        let devnet = partial.for_network(&AptosNetwork::Devnet).unwrap();
+        assert_eq!(devnet.max_gas, Some(1000));
+    }
+
+    #[test]
+    fn test_config_layers_precedence() {
+        let names = ["defaults", "config_file", "environment", "flags"];
+        // Every combination of the layers setting the same values.
+        for combination in 0..1 << names.len() {
+            let layer = |index: usize| {
+                let set = combination & (1 << index) != 0;
+                PartialDeployConfig {
+                    private_key: (set && index > 0).then(|| format!("0x{}", names[index])),
+                    output_json: set.then(|| PathBuf::from(names[index])),
+                    yes: set.then_some(index % 2 == 0),
+                    ..Default::default()
+                }
+            };
+            let layers = ConfigLayers {
+                defaults: layer(0),
+                config_file: layer(1),
+                environment: layer(2),
+                flags: layer(3),
+            };
+            let merged = layers.merge().unwrap();
+
+            let highest = (0..names.len())
+                .rev()
+                .find(|index| combination & (1 << index) != 0);
+            assert_eq!(
+                merged.output_json,
+                highest.map(|index| PathBuf::from(names[index]))
+            );
+            assert_eq!(merged.yes, highest.map(|index| index % 2 == 0));
+            let highest_key = highest.filter(|index| *index > 0);
+            assert_eq!(
+                merged.private_key,
+                highest_key.map(|index| format!("0x{}", names[index]))
+            );
+        }
+    }
+
+    #[test]
+    fn test_config_layers_groups() {
+        let layers = ConfigLayers {
+            defaults: PartialDeployConfig {
+                networks: Some(vec![AptosNetwork::Devnet]),
+                module_type: Some(DeployModuleType::Object),
+                ..Default::default()
+            },
+            config_file: PartialDeployConfig {
+                network: Some(AptosNetwork::Testnet),
+                modules_path: Some(vec![PathBuf::from("libs")]),
+                addresses_name: Some(vec!["lib_addr".to_string()]),
+                compiler: Some(CompilerOptions {
+                    compiler_version: Some("2.0".to_string()),
+                    ..Default::default()
+                }),
+                registry: Some(RegistryConfig {
+                    address: Some(AccountAddress::ONE),
+                }),
+                // Not resolved since the flags set the private key.
+                private_key: Some("env:JAYCE_TEST_UNSET_KEY".to_string()),
+                ..Default::default()
+            },
+            environment: PartialDeployConfig::default(),
+            flags: PartialDeployConfig {
+                private_key: Some("0xflags".to_string()),
+                workspace: Some(PathBuf::from("move")),
+                compiler: Some(CompilerOptions {
+                    language_version: Some("2.1".to_string()),
+                    ..Default::default()
+                }),
+                registry: Some(RegistryConfig::default()),
+                ..Default::default()
+            },
+        };
+        let merged = layers.merge().unwrap();
+        assert_eq!(merged.private_key, Some("0xflags".to_string()));
+        // The network of the configuration file replaces the default networks.
+        assert_eq!(merged.network, Some(AptosNetwork::Testnet));
+        assert_eq!(merged.networks, None);
+        assert_eq!(merged.module_type, Some(DeployModuleType::Object));
+        // The workspace replaces the packages of the configuration file.
+        assert_eq!(merged.workspace, Some(PathBuf::from("move")));
+        assert_eq!(merged.modules_path, None);
+        assert_eq!(merged.addresses_name, None);
+        assert_eq!(
+            merged.compiler,
+            Some(CompilerOptions {
+                compiler_version: Some("2.0".to_string()),
+                language_version: Some("2.1".to_string()),
+                ..Default::default()
+            })
+        );
+        assert_eq!(
+            merged.registry,
+            Some(RegistryConfig {
+                address: Some(AccountAddress::ONE)
+            })
+        );
+
+        // Paths set by the flags keep the address names of the configuration file.
+        let flags = PartialDeployConfig {
+            modules_path: Some(vec![PathBuf::from("contracts")]),
+            ..Default::default()
+        };
+        let merged = flags.or(layers.config_file.clone());
+        assert_eq!(merged.modules_path, Some(vec![PathBuf::from("contracts")]));
+        assert_eq!(merged.addresses_name, Some(vec!["lib_addr".to_string()]));
+    }
+
+    #[test]
+    fn test_private_key_precedence() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let key_file = temp_dir.path().join("private_key");
+        fs::write(&key_file, "0xfile\n").unwrap();
+        env::set_var("JAYCE_TEST_CONFIG_KEY", "0xconfig_env");
+
+        let select = |cli: Option<&str>, file: bool, env: Option<&str>, config: Option<&str>| {
+            select_private_key(
+                cli.map(|key| key.to_string()),
+                file.then(|| key_file.clone()),
+                env.map(|key| key.to_string()),
+                config.map(|key| key.to_string()),
+            )
+            .unwrap()
+        };
+        assert_eq!(
+            select(Some("0xcli"), true, Some("0xenv"), Some("0xconfig")),
+            Some("0xcli".to_string())
+        );
+        assert_eq!(
+            select(None, true, Some("0xenv"), Some("0xconfig")),
+            Some("0xfile".to_string())
+        );
+        assert_eq!(
+            select(None, false, Some("0xenv"), Some("0xconfig")),
+            Some("0xenv".to_string())
+        );
+        assert_eq!(
+            select(None, false, None, Some("0xconfig")),
+            Some("0xconfig".to_string())
+        );
+        assert_eq!(
+            select(None, false, None, Some("env:JAYCE_TEST_CONFIG_KEY")),
+            Some("0xconfig_env".to_string())
+        );
+        assert_eq!(
+            select(
+                None,
+                false,
+                None,
+                Some(&format!("file:{}", key_file.to_str().unwrap()))
+            ),
+            Some("0xfile".to_string())
+        );
+        assert_eq!(select(None, false, None, None), None);
+        assert!(select_private_key(
+            None,
+            None,
+            None,
+            Some("env:JAYCE_TEST_UNSET_KEY".to_string())
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_read_mnemonic_file() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let mnemonic_file = temp_dir.path().join("mnemonic");
+        fs::write(
+            &mnemonic_file,
+            "shoot island position soft burden budget\ntooth cruel issue economy destroy above\n",
+        )
+        .unwrap();
+
+        let first = read_mnemonic_file(&mnemonic_file, DEFAULT_DERIVATION_PATH).unwrap();
+        assert_eq!(
+            first,
+            read_mnemonic_file(&mnemonic_file, DEFAULT_DERIVATION_PATH).unwrap()
+        );
+        assert_ne!(
+            first,
+            read_mnemonic_file(&mnemonic_file, "m/44'/637'/1'/0'/0'").unwrap()
+        );
+        assert!(read_mnemonic_file(&mnemonic_file, "not a path").is_err());
+
+        fs::write(&mnemonic_file, "not a mnemonic").unwrap();
+        assert!(read_mnemonic_file(&mnemonic_file, DEFAULT_DERIVATION_PATH).is_err());
+    }
 }
@@ -0,0 +1,145 @@
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::error::{JayceError, Result};
+
+/// The lock file of the deployments, in the working directory where their report and state file
+/// are written by default.
+pub const DEPLOY_LOCK_PATH: &str = ".jayce.lock";
+
+#[derive(Serialize, Deserialize, Debug)]
+struct LockOwner {
+    pid: u32,
+    /// Unix timestamp of when the lock was acquired, in seconds.
+    started_at: u64,
+}
+
+/// Lock held for the duration of a deployment, so that concurrent runs in the same directory
+/// don't overwrite each other's report and state file, nor submit transactions with the same
+/// sequence numbers. It is released when dropped.
+#[derive(Debug)]
+pub struct DeployLock {
+    path: PathBuf,
+}
+
+impl DeployLock {
+    /// Acquires the lock at `path`. A lock left by a process that isn't running anymore is taken
+    /// over, as is any lock with `force`. A lock whose owner can't be read is considered held.
+    pub fn acquire(path: &Path, force: bool) -> Result<DeployLock> {
+        let owner = LockOwner {
+            pid: process::id(),
+            started_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+        };
+        // The owner is written to a temporary file linked into place, so that the lock is never
+        // seen without its owner.
+        let temp_path = path.with_extension(format!("lock.{}", owner.pid));
+        fs::write(&temp_path, serde_json::to_string(&owner)?)?;
+        let linked = fs::hard_link(&temp_path, path);
+        fs::remove_file(&temp_path)?;
+        match linked {
+            Ok(()) => {
+                return Ok(DeployLock {
+                    path: path.to_path_buf(),
+                })
+            }
+            Err(err) if err.kind() != ErrorKind::AlreadyExists => return Err(err.into()),
+            Err(_) => {}
+        }
+
+        let content = fs::read_to_string(path)?;
+        match serde_json::from_str::<LockOwner>(&content) {
+            Ok(previous) if !force && is_running(previous.pid) => Err(JayceError::Config(format!(
+                "Another deployment (pid {}, started at {}) holds the lock {}, wait for it to \
+                    finish or pass --force if it isn't running anymore",
+                previous.pid,
+                previous.started_at,
+                path.to_str().unwrap()
+            ))),
+            Err(_) if !force => Err(JayceError::Config(format!(
+                "The lock {} is held by an unknown owner, pass --force if no deployment is running",
+                path.to_str().unwrap()
+            ))),
+            _ => {
+                warn!("Taking over the lock {}", path.to_str().unwrap());
+                fs::remove_file(path)?;
+                DeployLock::acquire(path, false)
+            }
+        }
+    }
+}
+
+impl Drop for DeployLock {
+    fn drop(&mut self) {
+        // The lock may have been taken over with --force.
+        let owned = fs::read_to_string(&self.path)
+            .ok()
+            .and_then(|content| serde_json::from_str::<LockOwner>(&content).ok())
+            .is_some_and(|owner| owner.pid == process::id());
+        if owned {
+            let _ = fs::remove_file(&self.path);
+        }
+    }
+}
+
+#[cfg(unix)]
+fn is_running(pid: u32) -> bool {
+    process::Command::new("kill")
+        .args(["-0", &pid.to_string()])
+        .stderr(process::Stdio::null())
+        .status()
+        .is_ok_and(|status| status.success())
+}
+
+#[cfg(not(unix))]
+fn is_running(_pid: u32) -> bool {
+    true
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+
+    use crate::deploy_lock::DeployLock;
+
+    #[test]
+    fn test_deploy_lock() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("deploy.lock");
+
+        let lock = DeployLock::acquire(&path, false).unwrap();
+        assert!(DeployLock::acquire(&path, false)
+            .unwrap_err()
+            .to_string()
+            .contains("Another deployment"));
+        drop(lock);
+        assert!(!path.exists());
+
+        let lock = DeployLock::acquire(&path, false).unwrap();
+        let forced = DeployLock::acquire(&path, true).unwrap();
+        std::mem::forget(lock);
+        drop(forced);
+
+        // The lock of a process that isn't running is stale.
+        fs::write(&path, "{\"pid\": 999999999, \"started_at\": 0}").unwrap();
+        drop(DeployLock::acquire(&path, false).unwrap());
+        assert!(!path.exists());
+
+        // A lock whose owner can't be read is held, unless forced.
+        fs::write(&path, "").unwrap();
+        assert!(DeployLock::acquire(&path, false)
+            .unwrap_err()
+            .to_string()
+            .contains("unknown owner"));
+        drop(DeployLock::acquire(&path, true).unwrap());
+        assert!(!path.exists());
+    }
+}
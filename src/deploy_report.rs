@@ -0,0 +1,628 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use aptos::common::types::TransactionSummary;
+use aptos_sdk::crypto::ed25519::{Ed25519PublicKey, Ed25519Signature};
+use aptos_sdk::crypto::Signature;
+use aptos_sdk::move_types::account_address::AccountAddress;
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use strum_macros::Display;
+use tracing::info;
+
+use crate::deploy_config::{
+    AptosNetwork, CompilerOptions, DeployConfig, IncludedArtifacts, UpgradePolicy,
+};
+use crate::error::{JayceError, Result};
+use crate::move_package::{bytecode_modules_dir, package_metadata_path};
+use crate::redact::redact;
+use crate::report_writer::ReportFormat;
+use crate::signer::Signer;
+
+/// How the report of a deployment is combined with the report of a previous run.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, ValueEnum, Display)]
+#[serde(rename_all(serialize = "snake_case"))]
+#[strum(serialize_all = "snake_case")]
+pub enum ReportMode {
+    /// Replace the previous report.
+    Overwrite,
+    /// Replace the entries of the previous report having the same address names.
+    Merge,
+    /// Keep every entry of the previous report, preserving the history of the deployments.
+    Append,
+}
+
+/// The outcome of the deployment of a package.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Display)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum DeployStatus {
+    #[default]
+    Deployed,
+    /// The deployment of the package failed, and `--keep-going` let the other packages deploy.
+    Failed,
+    /// The package wasn't deployed because one of its dependencies wasn't, or because it was
+    /// already up to date.
+    Skipped,
+}
+
+/// SHA-256 checksums of the artifacts of a published package, hex encoded.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct PackageChecksums {
+    /// Checksum of the serialized package metadata (`package-metadata.bcs`).
+    pub metadata: String,
+    /// Checksums of the bytecode of the modules, keyed by module name.
+    pub modules: BTreeMap<String, String>,
+}
+
+impl PackageChecksums {
+    /// Computes the checksums of the last build of a package.
+    pub fn from_build(package_dir: &Path) -> Result<PackageChecksums> {
+        let mut modules = vec![];
+        for entry in fs::read_dir(bytecode_modules_dir(package_dir)?)? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|extension| extension == "mv") {
+                let module_name = path.file_stem().unwrap().to_str().unwrap().to_string();
+                modules.push((module_name, fs::read(&path)?));
+            }
+        }
+        Ok(PackageChecksums::from_artifacts(
+            &fs::read(package_metadata_path(package_dir)?)?,
+            modules,
+        ))
+    }
+
+    /// Computes the checksums of the serialized metadata of a package and of its modules, keyed
+    /// by module name.
+    pub fn from_artifacts(
+        metadata: &[u8],
+        modules: impl IntoIterator<Item = (String, Vec<u8>)>,
+    ) -> PackageChecksums {
+        PackageChecksums {
+            metadata: sha256_hex(metadata),
+            modules: modules
+                .into_iter()
+                .map(|(module_name, code)| (module_name, sha256_hex(&code)))
+                .collect(),
+        }
+    }
+}
+
+fn sha256_hex(bytes: &[u8]) -> String {
+    hex::encode(Sha256::digest(bytes))
+}
+
+const OCTAS_PER_APT: u64 = 100_000_000;
+
+/// The version of jayce, recorded in the reports of the packages it compiled.
+pub const JAYCE_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Formats an amount of Octas in APT.
+pub fn format_apt(octas: u64) -> String {
+    format!("{}.{:08}", octas / OCTAS_PER_APT, octas % OCTAS_PER_APT)
+}
+
+/// The gas used and the fee paid by transactions.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct GasSummary {
+    pub transactions: u64,
+    pub gas_used: u64,
+    /// The fee paid, in Octas.
+    pub fee: u64,
+}
+
+impl GasSummary {
+    fn add(&mut self, tx: &TransactionSummary) {
+        let gas_used = tx.gas_used.unwrap_or_default();
+        self.transactions += 1;
+        self.gas_used += gas_used;
+        self.fee += gas_used * tx.gas_unit_price.unwrap_or_default();
+    }
+
+    /// The average gas unit price, in Octas.
+    pub fn gas_unit_price(&self) -> u64 {
+        self.fee.checked_div(self.gas_used).unwrap_or_default()
+    }
+
+    /// The fee paid, in APT.
+    pub fn fee_apt(&self) -> String {
+        format_apt(self.fee)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct DeployReport {
+    pub account: AccountAddress,
+    /// The account paying the gas of the publish transactions, if it isn't the deployer.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fee_payer: Option<AccountAddress>,
+    pub network: AptosNetwork,
+    pub info: Vec<TxReport>,
+    /// The gas used and the fee paid by the run that wrote the report.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gas: Option<GasSummary>,
+    /// The git commit the packages were deployed from, in the repository of the first package.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub git_commit: Option<String>,
+    /// The git commit each repository of the packages was deployed from, by repository root.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub git_commits: BTreeMap<PathBuf, String>,
+    /// Signature of the rest of the report by the deployer.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<ReportSignature>,
+}
+
+/// Detached Ed25519 signature of a report, see [`DeployReport::sign`].
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ReportSignature {
+    pub public_key: Ed25519PublicKey,
+    pub signature: Ed25519Signature,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct TxReport {
+    pub module_path: PathBuf,
+    pub address_name: String,
+    /// The address of the package, `0x0` if it wasn't deployed.
+    pub deployed_at: AccountAddress,
+    /// The account that published the package, when it isn't the account of the report.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sender: Option<AccountAddress>,
+    pub tx_info: Vec<TransactionSummary>,
+    /// Transaction freezing the code of the object package.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub freeze_tx_info: Option<TransactionSummary>,
+    /// Transactions of the initializers called after publishing the package.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub init_tx_info: Vec<TransactionSummary>,
+    /// Transaction recording the deployment in the on-chain registry.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub registry_tx_info: Option<TransactionSummary>,
+    /// Sequence number of the multisig transaction proposing the publication of the package.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proposal_id: Option<u64>,
+    /// Payload of the multisig proposal, required to execute it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub payload_path: Option<PathBuf>,
+    /// Explorer page of the address the package was deployed at.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub explorer_url: Option<String>,
+    /// Explorer pages of the transactions of the package, see [`TxReport::transactions`].
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tx_explorer_urls: Vec<String>,
+    /// The upgrade policy the package was published with.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub upgrade_policy: Option<UpgradePolicy>,
+    /// The upgrade number of the package once published, 0 for its first publication.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub upgrade_number: Option<u64>,
+    /// The directory the published `package-metadata.bcs` and bytecode modules were copied to.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub artifacts_path: Option<PathBuf>,
+    /// Checksums of the published metadata and modules.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub checksums: Option<PackageChecksums>,
+    /// The compiler options the package was compiled with, to rebuild it with `jayce reproduce`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub compiler: Option<CompilerOptions>,
+    /// The artifacts the package was published with.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub included_artifacts: Option<IncludedArtifacts>,
+    /// The version of jayce the package was compiled by, whose compiler is used unless the
+    /// compiler options set its version.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub jayce_version: Option<String>,
+    #[serde(default)]
+    pub status: DeployStatus,
+    /// Why the package wasn't deployed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl TxReport {
+    /// Reports a package that wasn't deployed.
+    pub fn not_deployed(
+        module_path: &Path,
+        address_name: &str,
+        status: DeployStatus,
+        error: String,
+    ) -> TxReport {
+        TxReport {
+            module_path: module_path.to_path_buf(),
+            address_name: address_name.to_string(),
+            deployed_at: AccountAddress::ZERO,
+            sender: None,
+            tx_info: vec![],
+            freeze_tx_info: None,
+            init_tx_info: vec![],
+            registry_tx_info: None,
+            proposal_id: None,
+            payload_path: None,
+            explorer_url: None,
+            tx_explorer_urls: vec![],
+            upgrade_policy: None,
+            upgrade_number: None,
+            artifacts_path: None,
+            checksums: None,
+            compiler: None,
+            included_artifacts: None,
+            jayce_version: None,
+            status,
+            error: Some(redact(&error)),
+        }
+    }
+
+    /// Records the explorer pages of the deployed address and of the transactions.
+    pub fn set_explorer_urls(&mut self, config: &DeployConfig) {
+        self.explorer_url = config.explorer_url("account", &self.deployed_at.to_string());
+        self.tx_explorer_urls = self
+            .transactions()
+            .filter_map(|tx| config.explorer_url("txn", &tx.transaction_hash.to_string()))
+            .collect();
+    }
+
+    /// The transactions of the package: `tx_info`, `freeze_tx_info`, `init_tx_info` then
+    /// `registry_tx_info`.
+    pub fn transactions(&self) -> impl Iterator<Item = &TransactionSummary> {
+        self.tx_info
+            .iter()
+            .chain(&self.freeze_tx_info)
+            .chain(&self.init_tx_info)
+            .chain(&self.registry_tx_info)
+    }
+
+    pub fn gas_summary(&self) -> GasSummary {
+        let mut summary = GasSummary::default();
+        self.transactions().for_each(|tx| summary.add(tx));
+        summary
+    }
+
+    pub fn print_explorer_urls(&self) {
+        if let Some(explorer_url) = &self.explorer_url {
+            info!("{} deployed at {}", self.address_name, explorer_url);
+        }
+        for explorer_url in &self.tx_explorer_urls {
+            info!("Transaction: {}", explorer_url);
+        }
+    }
+}
+
+impl DeployReport {
+    pub fn from_path(path: &Path) -> Result<DeployReport> {
+        let content = fs::read_to_string(path)?;
+        let report: DeployReport = serde_json::from_str(&content)?;
+
+        Ok(report)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        self.save_as(path, &ReportFormat::Json)
+    }
+
+    pub fn save_as(&self, path: &Path, format: &ReportFormat) -> Result<()> {
+        fs::write(path, format.writer().write(self)?)?;
+        Ok(())
+    }
+
+    /// Signs the JSON of the report with the key of `signer`.
+    pub async fn sign<S: Signer>(&mut self, signer: &S) -> Result<()> {
+        self.signature = None;
+        let message = signing_message(serde_json::to_value(&*self)?)?;
+        self.signature = Some(ReportSignature {
+            public_key: signer.public_key().clone(),
+            signature: signer.sign_message(&message).await?,
+        });
+        Ok(())
+    }
+
+    /// Checks the signature of the JSON report at `path` against its content, as written, and
+    /// returns it.
+    pub fn verify_signature(path: &Path) -> Result<ReportSignature> {
+        let report: serde_json::Value = serde_json::from_str(&fs::read_to_string(path)?)?;
+        let signature: ReportSignature = report
+            .get("signature")
+            .map(|signature| serde_json::from_value(signature.clone()))
+            .transpose()?
+            .ok_or_else(|| {
+                JayceError::Verification(format!(
+                    "The report {} isn't signed",
+                    path.to_str().unwrap()
+                ))
+            })?;
+        signature
+            .signature
+            .verify_arbitrary_msg(&signing_message(report)?, &signature.public_key)
+            .map_err(|_| {
+                JayceError::Verification(format!(
+                    "The signature of the report {} doesn't match its content",
+                    path.to_str().unwrap()
+                ))
+            })?;
+        Ok(signature)
+    }
+
+    /// Combines the report of a previous run with this one according to `mode`.
+    pub fn merge(mut self, previous: DeployReport, mode: &ReportMode) -> Result<DeployReport> {
+        if *mode == ReportMode::Overwrite {
+            return Ok(self);
+        }
+        if previous.network != self.network {
+            return Err(JayceError::Config(format!(
+                "Cannot {} the deployment report of network {}",
+                mode, previous.network
+            )));
+        }
+        let mut info = previous.info;
+        if *mode == ReportMode::Merge {
+            // Packages that failed to deploy again keep their previous deployment.
+            info.retain(|previous| {
+                !self.info.iter().any(|tx_report| {
+                    tx_report.address_name == previous.address_name
+                        && tx_report.status == DeployStatus::Deployed
+                })
+            });
+        }
+        info.append(&mut self.info);
+        self.info = info;
+        self.git_commit = self.git_commit.or(previous.git_commit);
+        if self.git_commits.is_empty() {
+            self.git_commits = previous.git_commits;
+        }
+        Ok(self)
+    }
+
+    /// The gas used and the fee paid by the transactions of every package of the report.
+    pub fn gas_summary(&self) -> GasSummary {
+        let mut summary = GasSummary::default();
+        self.info
+            .iter()
+            .flat_map(TxReport::transactions)
+            .for_each(|tx| summary.add(tx));
+        summary
+    }
+
+    /// Renders the gas used and the fee paid by each package, then in total.
+    pub fn gas_summary_table(&self) -> String {
+        let mut table = format!(
+            "{:<20} {:>4} {:>12} {:>10} {:>14}\n",
+            "ADDRESS NAME", "TXS", "GAS USED", "GAS PRICE", "FEE (APT)"
+        );
+        let mut row = |address_name: &str, summary: &GasSummary| {
+            table.push_str(&format!(
+                "{:<20} {:>4} {:>12} {:>10} {:>14}\n",
+                address_name,
+                summary.transactions,
+                summary.gas_used,
+                summary.gas_unit_price(),
+                summary.fee_apt()
+            ))
+        };
+        for tx_report in &self.info {
+            row(&tx_report.address_name, &tx_report.gas_summary());
+        }
+        row("TOTAL", &self.gas_summary());
+        table
+    }
+
+    /// Returns the address each package of the report was deployed at, keyed by address name.
+    /// When an address name was deployed several times, the latest deployment is returned.
+    pub fn deployed_addresses(&self) -> BTreeMap<String, AccountAddress> {
+        self.info
+            .iter()
+            .filter(|tx_report| tx_report.status == DeployStatus::Deployed)
+            .map(|tx_report| (tx_report.address_name.clone(), tx_report.deployed_at))
+            .collect()
+    }
+}
+
+/// The bytes signed for a report: its JSON without the signature.
+fn signing_message(mut report: serde_json::Value) -> Result<Vec<u8>> {
+    if let Some(report) = report.as_object_mut() {
+        report.remove("signature");
+    }
+    Ok(serde_json::to_vec(&report)?)
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::BTreeMap;
+    use std::fs;
+    use std::path::PathBuf;
+
+    use aptos::common::types::TransactionSummary;
+    use aptos_sdk::crypto::HashValue;
+    use aptos_sdk::move_types::account_address::AccountAddress;
+
+    use crate::deploy_config::AptosNetwork;
+    use crate::deploy_report::{
+        DeployReport, DeployStatus, GasSummary, PackageChecksums, ReportMode, TxReport,
+    };
+    use crate::report_writer::ReportFormat;
+    use crate::signer::PrivateKeySigner;
+
+    fn report(packages: &[(&str, AccountAddress)]) -> DeployReport {
+        DeployReport {
+            account: AccountAddress::ONE,
+            fee_payer: None,
+            network: AptosNetwork::Testnet,
+            gas: None,
+            git_commit: None,
+            git_commits: BTreeMap::new(),
+            signature: None,
+            info: packages
+                .iter()
+                .map(|(address_name, deployed_at)| TxReport {
+                    module_path: PathBuf::from(address_name),
+                    address_name: address_name.to_string(),
+                    deployed_at: *deployed_at,
+                    sender: None,
+                    tx_info: vec![],
+                    freeze_tx_info: None,
+                    init_tx_info: vec![],
+                    registry_tx_info: None,
+                    proposal_id: None,
+                    payload_path: None,
+                    explorer_url: None,
+                    tx_explorer_urls: vec![],
+                    upgrade_policy: None,
+                    upgrade_number: None,
+                    artifacts_path: None,
+                    checksums: None,
+                    compiler: None,
+                    included_artifacts: None,
+                    jayce_version: None,
+                    status: DeployStatus::Deployed,
+                    error: None,
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_merge_reports() {
+        let previous = || {
+            report(&[
+                ("lib_addr", AccountAddress::ONE),
+                ("cpu_addr", AccountAddress::TWO),
+            ])
+        };
+        let current = || report(&[("cpu_addr", AccountAddress::THREE)]);
+        let address_names = |report: DeployReport| {
+            report
+                .info
+                .iter()
+                .map(|tx_report| tx_report.address_name.clone())
+                .collect::<Vec<_>>()
+        };
+
+        let merged = current().merge(previous(), &ReportMode::Merge).unwrap();
+        assert_eq!(
+            merged.deployed_addresses()["cpu_addr"],
+            AccountAddress::THREE
+        );
+        assert_eq!(address_names(merged), vec!["lib_addr", "cpu_addr"]);
+
+        let appended = current().merge(previous(), &ReportMode::Append).unwrap();
+        assert_eq!(
+            appended.deployed_addresses()["cpu_addr"],
+            AccountAddress::THREE
+        );
+        assert_eq!(
+            address_names(appended),
+            vec!["lib_addr", "cpu_addr", "cpu_addr"]
+        );
+
+        let overwritten = current().merge(previous(), &ReportMode::Overwrite).unwrap();
+        assert_eq!(address_names(overwritten), vec!["cpu_addr"]);
+    }
+
+    #[test]
+    fn test_gas_summary() {
+        let tx = |gas_used| TransactionSummary {
+            transaction_hash: HashValue::zero(),
+            gas_used: Some(gas_used),
+            gas_unit_price: Some(100),
+            pending: None,
+            sender: None,
+            sequence_number: None,
+            success: Some(true),
+            timestamp_us: None,
+            version: None,
+            vm_status: None,
+        };
+        let mut report = report(&[
+            ("lib_addr", AccountAddress::ONE),
+            ("cpu_addr", AccountAddress::TWO),
+        ]);
+        report.info[0].tx_info = vec![tx(1_000)];
+        report.info[1].tx_info = vec![tx(500_000), tx(1_000_000)];
+        report.info[1].freeze_tx_info = Some(tx(500));
+
+        assert_eq!(
+            report.gas_summary(),
+            GasSummary {
+                transactions: 4,
+                gas_used: 1_501_500,
+                fee: 150_150_000,
+            }
+        );
+        assert_eq!(report.gas_summary().fee_apt(), "1.50150000");
+        let table = report.gas_summary_table();
+        let lines = table.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 4);
+        assert!(lines[2].starts_with("cpu_addr"));
+        assert!(lines[3].starts_with("TOTAL"));
+        assert!(lines[3].ends_with(" 4      1501500        100     1.50150000"));
+    }
+
+    #[tokio::test]
+    async fn test_signed_report() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let report_path = temp_dir.path().join("signed_report.json");
+        let signer = PrivateKeySigner::from_private_key(&format!("0x{}", "11".repeat(32))).unwrap();
+        let mut signed = report(&[("lib_addr", AccountAddress::TWO)]);
+        signed.git_commit = Some("abc123".to_string());
+        signed.sign(&signer).await.unwrap();
+        signed.save_as(&report_path, &ReportFormat::Json).unwrap();
+        let signature = DeployReport::verify_signature(&report_path).unwrap();
+        assert_eq!(Some(signature), signed.signature);
+
+        let content = fs::read_to_string(&report_path).unwrap();
+        fs::write(&report_path, content.replace("abc123", "def456")).unwrap();
+        assert!(DeployReport::verify_signature(&report_path).is_err());
+
+        report(&[])
+            .save_as(&report_path, &ReportFormat::Json)
+            .unwrap();
+        assert!(DeployReport::verify_signature(&report_path).is_err());
+    }
+
+    #[test]
+    fn test_failed_packages_are_not_deployed() {
+        let mut current = report(&[("lib_addr", AccountAddress::THREE)]);
+        current.info.push(TxReport::not_deployed(
+            &PathBuf::from("cpu"),
+            "cpu_addr",
+            DeployStatus::Failed,
+            "out of gas".to_string(),
+        ));
+        assert!(!current.deployed_addresses().contains_key("cpu_addr"));
+
+        let previous = report(&[("cpu_addr", AccountAddress::TWO)]);
+        let merged = current.merge(previous, &ReportMode::Merge).unwrap();
+        assert_eq!(merged.deployed_addresses()["cpu_addr"], AccountAddress::TWO);
+        assert_eq!(
+            merged.deployed_addresses()["lib_addr"],
+            AccountAddress::THREE
+        );
+    }
+
+    #[test]
+    fn test_package_checksums() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let package_dir = temp_dir.path().join("checksums");
+        let build_dir = package_dir.join("build").join("checksums");
+        fs::create_dir_all(build_dir.join("bytecode_modules")).unwrap();
+        fs::write(
+            package_dir.join("Move.toml"),
+            "[package]\nname = \"checksums\"\n\n[addresses]\n",
+        )
+        .unwrap();
+        fs::write(build_dir.join("package-metadata.bcs"), b"").unwrap();
+        fs::write(build_dir.join("bytecode_modules").join("cpu.mv"), b"abc").unwrap();
+        fs::write(build_dir.join("bytecode_modules").join("cpu.mvsm"), b"map").unwrap();
+
+        let checksums = PackageChecksums::from_build(&package_dir).unwrap();
+        assert_eq!(
+            checksums.metadata,
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+        assert_eq!(checksums.modules.len(), 1);
+        assert_eq!(
+            checksums.modules["cpu"],
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+}
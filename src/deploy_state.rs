@@ -0,0 +1,88 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use aptos::common::types::TransactionSummary;
+use aptos_sdk::crypto::HashValue;
+use aptos_sdk::move_types::account_address::AccountAddress;
+use serde::{Deserialize, Serialize};
+
+use crate::deploy_config::AptosNetwork;
+use crate::deploy_report::{DeployStatus, TxReport};
+use crate::error::Result;
+
+/// Checkpoint of a deployment run, written after every successfully deployed package so that a
+/// failed run can be resumed.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct DeployState {
+    pub account: AccountAddress,
+    pub network: AptosNetwork,
+    pub packages: BTreeMap<String, PackageState>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PackageState {
+    pub deployed_at: AccountAddress,
+    pub tx_hashes: Vec<HashValue>,
+    /// The report of the package, once its deployment is complete. A resumed run reports it
+    /// again.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub report: Option<TxReport>,
+}
+
+impl PackageState {
+    /// The report of the package deployed by the run being resumed. State files without reports
+    /// only give the hashes of the transactions.
+    pub fn tx_report(&self, module_path: &Path, address_name: &str) -> TxReport {
+        if let Some(report) = &self.report {
+            return report.clone();
+        }
+        let mut report = TxReport::not_deployed(
+            module_path,
+            address_name,
+            DeployStatus::Deployed,
+            String::new(),
+        );
+        report.deployed_at = self.deployed_at;
+        report.tx_info = self
+            .tx_hashes
+            .iter()
+            .map(|hash| TransactionSummary {
+                transaction_hash: *hash,
+                gas_used: None,
+                gas_unit_price: None,
+                pending: None,
+                sender: None,
+                sequence_number: None,
+                success: Some(true),
+                timestamp_us: None,
+                version: None,
+                vm_status: None,
+            })
+            .collect();
+        report.error = None;
+        report
+    }
+}
+
+impl DeployState {
+    pub fn new(account: AccountAddress, network: AptosNetwork) -> Self {
+        DeployState {
+            account,
+            network,
+            packages: BTreeMap::new(),
+        }
+    }
+
+    pub fn from_path(path: &Path) -> Result<DeployState> {
+        let content = fs::read_to_string(path)?;
+        let state: DeployState = serde_json::from_str(&content)?;
+
+        Ok(state)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
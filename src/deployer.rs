@@ -0,0 +1,1078 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use aptos::common::types::{CliCommand, TransactionSummary};
+use aptos::move_tool::MoveTool;
+use aptos::Tool;
+use aptos_sdk::bcs;
+use aptos_sdk::move_types::account_address::AccountAddress;
+use aptos_sdk::move_types::identifier::Identifier;
+use aptos_sdk::move_types::language_storage::ModuleId;
+use aptos_sdk::rest_client::aptos_api_types::{AptosErrorCode, Transaction};
+use aptos_sdk::rest_client::error::RestError;
+use aptos_sdk::rest_client::Client;
+use aptos_sdk::transaction_builder::{aptos_stdlib, TransactionFactory};
+use aptos_sdk::types::chain_id::ChainId;
+use aptos_sdk::types::transaction::{
+    EntryFunction, RawTransaction, SignedTransaction, TransactionPayload,
+};
+use aptos_sdk::types::LocalAccount;
+use clap::Parser;
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::artifact_cache::ArtifactCache;
+use crate::bundle::Bundle;
+use crate::deploy_config::{
+    resolve_private_key, CompilerOptions, DeployConfig, DeployModuleType, UpgradePolicy,
+};
+use crate::deploy_report::{DeployReport, PackageChecksums};
+use crate::docker::run_aptos_in_docker;
+use crate::entry_function::resolve_function_id;
+use crate::error::{JayceError, Result};
+use crate::move_package::{
+    built_module_names, named_addresses_arg, package_dir_args, write_build_artifacts, PackageCopy,
+};
+use crate::package_registry::{find_up_to_date_package, upgrade_number, PackageMetadata};
+use crate::publish_backend::{
+    CompiledPackage, PendingPublish, PublishBackend, PublishOutcome, PublishRequest,
+};
+use crate::signer::{AnySigner, PrivateKeySigner, Signer};
+use crate::tasks::deploy_contracts::deploy_contracts_with_backend;
+use crate::utils::{
+    create_object_code_deployment_address, lookup_address, rest_client, wait_for_transaction,
+};
+
+/// The address of the chunked publish module on mainnet and testnet.
+const DEFAULT_LARGE_PACKAGES_MODULE_ADDRESS: &str =
+    "0x0e1ca3011bdd07246d4d16d909dbb2d6953a86c4735d5acf5865d962c630cce7";
+
+/// The size of the chunks of chunked publish in bytes when it isn't configured, the one of the
+/// aptos CLI.
+pub const DEFAULT_CHUNK_SIZE: usize = 55_000;
+
+/// A publish payload in the artifact cache, with the names of its modules to write them to the
+/// build directory.
+#[derive(Serialize, Deserialize)]
+struct CachedPayload {
+    /// The serialized metadata, hex encoded.
+    metadata: String,
+    /// The names and the hex encoded bytecode of the modules, in publishing order.
+    modules: Vec<(String, String)>,
+}
+
+impl CachedPayload {
+    /// Decodes the metadata and the modules of a cached payload, which is ignored if it is
+    /// invalid, e.g. written by another version of jayce.
+    fn decode(content: Vec<u8>) -> Option<(Vec<u8>, Vec<(String, Vec<u8>)>)> {
+        let cached: CachedPayload = serde_json::from_slice(&content).ok()?;
+        let modules = cached
+            .modules
+            .into_iter()
+            .map(|(name, code)| Some((name, hex::decode(code).ok()?)))
+            .collect::<Option<Vec<_>>>()?;
+        Some((hex::decode(cached.metadata).ok()?, modules))
+    }
+}
+
+#[derive(Deserialize)]
+struct PublishPayloadJson {
+    args: Vec<PublishPayloadArg>,
+}
+
+#[derive(Deserialize)]
+struct PublishPayloadArg {
+    value: serde_json::Value,
+}
+
+/// Deploys packages by submitting publish transactions through the Aptos REST API, with a
+/// [`RestBackend`].
+///
+/// Unlike [`crate::tasks::deploy_contracts::deploy_contracts`], the deployer doesn't fund the
+/// accounts, check their balance, write the deployment report nor the state file of `state_path`,
+/// which makes it usable as a library. The hooks, the address book and the artifacts directory of
+/// the configuration are still run and written. Initializers are not supported.
+pub struct Deployer {
+    config: DeployConfig,
+}
+
+impl Deployer {
+    pub fn new(config: DeployConfig) -> Self {
+        Deployer { config }
+    }
+
+    pub async fn run(&self) -> Result<DeployReport> {
+        if !self.config.initializers.is_empty() {
+            return Err(JayceError::Config(
+                "The deployer doesn't support initializers".to_string(),
+            ));
+        }
+        let backend = RestBackend::new(&self.config).await?;
+        let sender = match self.config.sender_address {
+            Some(sender_address) => sender_address,
+            None => lookup_address(&backend.client, backend.signer.address()).await?,
+        };
+        let mut report = deploy_contracts_with_backend(&self.config, &backend, sender).await?;
+        report.fee_payer = backend.fee_payer();
+        Ok(report)
+    }
+}
+
+/// Publishes packages by submitting transactions through the Aptos REST API, for the deployments
+/// the aptos CLI can't sign or build: sponsored, co-signed, signed by a `[signer]`, pipelined, or
+/// publishing precompiled packages.
+///
+/// The transactions are signed by the `[signer]` of the configuration when there is one,
+/// otherwise by the private key of the package or of the deployer, and co-signed by the
+/// `secondary_signers` of the package. With `pipeline`, the sequence numbers are only fetched once
+/// and tracked locally, so that the transactions of every package can be submitted before waiting
+/// for them. With a `bundle`, its packages are published as they were compiled.
+pub struct RestBackend {
+    client: Client,
+    signer: Arc<AnySigner>,
+    /// The signers of the packages published from their own account.
+    package_signers: BTreeMap<String, AnySigner>,
+    fee_payer: Option<LocalAccount>,
+    transaction_factory: TransactionFactory,
+    bundle: Option<Bundle>,
+    /// The next sequence number of the accounts, with `pipeline`.
+    sequence_numbers: Option<Mutex<BTreeMap<AccountAddress, u64>>>,
+    confirmation_timeout: Duration,
+    poll_interval: Duration,
+    /// The publications signed but not submitted yet, resubmitted as they are on retries.
+    signed: Mutex<BTreeMap<String, SignedPublication>>,
+}
+
+#[derive(Clone)]
+struct SignedPublication {
+    transactions: Vec<SignedTransaction>,
+    deployed_at: Option<AccountAddress>,
+}
+
+impl RestBackend {
+    pub async fn new(config: &DeployConfig) -> Result<RestBackend> {
+        let signer = match (&config.signer, &config.private_key) {
+            (Some(signer), _) => AnySigner::connect(signer).await?,
+            (None, Some(private_key)) => {
+                AnySigner::PrivateKey(PrivateKeySigner::from_private_key(private_key)?)
+            }
+            (None, None) => {
+                return Err(JayceError::Config(
+                    "A private key or a signer is required to deploy".to_string(),
+                ))
+            }
+        };
+        RestBackend::with_signer(config, Arc::new(signer)).await
+    }
+
+    /// Builds a backend signing the transactions of the deployer with `signer`, already connected,
+    /// instead of the signer of the configuration.
+    pub async fn with_signer(config: &DeployConfig, signer: Arc<AnySigner>) -> Result<RestBackend> {
+        let client = rest_client(&config.network, config.rest_url.clone())?;
+        let package_signers = config
+            .package_options
+            .iter()
+            .filter_map(|(address_name, options)| {
+                let private_key = options.private_key.as_ref()?;
+                Some(
+                    PrivateKeySigner::from_private_key(private_key)
+                        .map(|signer| (address_name.clone(), AnySigner::PrivateKey(signer))),
+                )
+            })
+            .collect::<Result<_>>()?;
+        let fee_payer = config
+            .fee_payer_private_key
+            .as_ref()
+            .map(|fee_payer_private_key| {
+                LocalAccount::from_private_key(fee_payer_private_key, 0).map_err(|err| {
+                    JayceError::Config(format!("Invalid fee payer private key: {}", err))
+                })
+            })
+            .transpose()?;
+        let chain_id = client.get_index().await?.into_inner().chain_id;
+        let mut transaction_factory = TransactionFactory::new(ChainId::new(chain_id));
+        if let Some(max_gas) = config.max_gas {
+            transaction_factory = transaction_factory.with_max_gas_amount(max_gas);
+        }
+        if let Some(gas_unit_price) = config.gas_unit_price {
+            transaction_factory = transaction_factory.with_gas_unit_price(gas_unit_price);
+        }
+        if let Some(expiration_secs) = config.expiration_secs {
+            transaction_factory =
+                transaction_factory.with_transaction_expiration_time(expiration_secs);
+        }
+        Ok(RestBackend {
+            client,
+            signer,
+            package_signers,
+            fee_payer,
+            transaction_factory,
+            bundle: config.bundle.as_deref().map(Bundle::open).transpose()?,
+            sequence_numbers: config.pipeline.then(Mutex::default),
+            confirmation_timeout: Duration::from_secs(config.confirmation_timeout_secs),
+            poll_interval: Duration::from_millis(config.poll_interval_ms),
+            signed: Mutex::default(),
+        })
+    }
+
+    /// The address of the fee payer paying the transactions, if there is one.
+    pub fn fee_payer(&self) -> Option<AccountAddress> {
+        self.fee_payer.as_ref().map(LocalAccount::address)
+    }
+
+    fn signer(&self, address_name: &str) -> &AnySigner {
+        self.package_signers
+            .get(address_name)
+            .unwrap_or(self.signer.as_ref())
+    }
+
+    /// Signs a transaction of `sender`, co-signed by `secondary_signers`.
+    async fn sign(
+        &self,
+        address_name: &str,
+        sender: AccountAddress,
+        payload: TransactionPayload,
+        sequence_number: u64,
+        secondary_signers: &[LocalAccount],
+    ) -> Result<SignedTransaction> {
+        self.signer(address_name)
+            .sign_transaction(
+                self.raw_transaction(sender, payload, sequence_number),
+                secondary_signers,
+                self.fee_payer.as_ref(),
+            )
+            .await
+    }
+
+    fn raw_transaction(
+        &self,
+        sender: AccountAddress,
+        payload: TransactionPayload,
+        sequence_number: u64,
+    ) -> RawTransaction {
+        self.transaction_factory
+            .payload(payload)
+            .sender(sender)
+            .sequence_number(sequence_number)
+            .build()
+    }
+
+    /// Submits signed transactions, returning them as pending, and takes their sequence numbers.
+    /// On a resubmission, the transactions the node already knows aren't submitted again.
+    async fn submit_transactions(
+        &self,
+        sender: AccountAddress,
+        transactions: &[SignedTransaction],
+        resubmission: bool,
+    ) -> Result<Vec<TransactionSummary>> {
+        let mut tx_info = vec![];
+        for transaction in transactions {
+            if !resubmission || !self.is_known(transaction).await? {
+                self.client.submit(transaction).await?;
+            }
+            tx_info.push(pending_summary(transaction));
+        }
+        if let (Some(sequence_numbers), Some(transaction)) =
+            (&self.sequence_numbers, transactions.last())
+        {
+            sequence_numbers
+                .lock()
+                .unwrap()
+                .insert(sender, transaction.sequence_number() + 1);
+        }
+        Ok(tx_info)
+    }
+
+    /// Signs the transactions publishing a package.
+    async fn sign_publication(
+        &self,
+        request: &PublishRequest<'_>,
+        package: &CompiledPackage,
+        chunked: bool,
+    ) -> Result<SignedPublication> {
+        let config = request.config;
+        let address_name = request.address_name;
+        let module_type = config.package_module_type(address_name);
+        let secondary_signers = secondary_signers(config, address_name)?;
+        let sequence_number = self.sequence_number(request.sender).await?;
+        let payloads = if chunked {
+            if !secondary_signers.is_empty()
+                || config
+                    .package_options
+                    .get(address_name)
+                    .is_some_and(|options| options.publish_function.is_some())
+            {
+                return Err(JayceError::Publish(format!(
+                    "{} requires chunked publish, which doesn't support secondary signers nor \
+                    publish functions",
+                    address_name
+                )));
+            }
+            let module_address = config.large_packages_module_address().unwrap_or_else(|| {
+                AccountAddress::from_hex_literal(DEFAULT_LARGE_PACKAGES_MODULE_ADDRESS).unwrap()
+            });
+            let chunk_size = config.chunk_size.unwrap_or(DEFAULT_CHUNK_SIZE);
+            let mut payloads =
+                chunked_publish_payloads(module_address, chunk_size, &module_type, package);
+            // The object is created by the last transaction, the package was compiled for a
+            // single one.
+            if module_type == DeployModuleType::Object && payloads.len() > 1 {
+                let deployed_at = self.publish_address(request, payloads.len() as u64).await?;
+                let (metadata, code) = self.payload(request, deployed_at).await?;
+                payloads = chunked_publish_payloads(
+                    module_address,
+                    chunk_size,
+                    &module_type,
+                    &CompiledPackage { metadata, code },
+                );
+            }
+            payloads
+        } else {
+            vec![self.publish_payload(request, &package.metadata, &package.code)?]
+        };
+
+        let deployed_at = match module_type {
+            DeployModuleType::Account => None,
+            DeployModuleType::Object => Some(create_object_code_deployment_address(
+                request.sender,
+                sequence_number + payloads.len() as u64 - 1,
+            )),
+        };
+        let mut transactions = vec![];
+        for (index, payload) in payloads.into_iter().enumerate() {
+            transactions.push(
+                self.sign(
+                    address_name,
+                    request.sender,
+                    payload,
+                    sequence_number + index as u64,
+                    &secondary_signers,
+                )
+                .await?,
+            );
+        }
+        Ok(SignedPublication {
+            transactions,
+            deployed_at,
+        })
+    }
+
+    /// Whether a transaction is pending or committed.
+    async fn is_known(&self, transaction: &SignedTransaction) -> Result<bool> {
+        match self
+            .client
+            .get_transaction_by_hash(transaction.committed_hash())
+            .await
+        {
+            Ok(_) => Ok(true),
+            Err(RestError::Api(err))
+                if err.error.error_code == AptosErrorCode::TransactionNotFound =>
+            {
+                Ok(false)
+            }
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Waits until transactions are committed, returning them.
+    async fn wait_transactions(
+        &self,
+        tx_info: &[TransactionSummary],
+    ) -> Result<Vec<TransactionSummary>> {
+        let mut committed = vec![];
+        for tx in tx_info {
+            let transaction = wait_for_transaction(
+                &self.client,
+                tx.transaction_hash,
+                self.confirmation_timeout,
+                self.poll_interval,
+            )
+            .await?;
+            committed.push(TransactionSummary::from(&transaction));
+        }
+        Ok(committed)
+    }
+
+    /// Returns the address a package is published at when its publication takes `transactions`
+    /// transactions: the sender address for account packages, the object created by the last
+    /// transaction for object packages.
+    async fn publish_address(
+        &self,
+        request: &PublishRequest<'_>,
+        transactions: u64,
+    ) -> Result<AccountAddress> {
+        Ok(
+            match request.config.package_module_type(request.address_name) {
+                DeployModuleType::Account => request.sender,
+                DeployModuleType::Object => create_object_code_deployment_address(
+                    request.sender,
+                    self.sequence_number(request.sender).await? + transactions - 1,
+                ),
+            },
+        )
+    }
+
+    /// Compiles a package published at `deployed_at`, or reads it from the bundle, returning its
+    /// metadata and modules.
+    async fn payload(
+        &self,
+        request: &PublishRequest<'_>,
+        deployed_at: AccountAddress,
+    ) -> Result<(Vec<u8>, Vec<Vec<u8>>)> {
+        let config = request.config;
+        let mut named_addresses =
+            config.package_addresses(request.address_name, request.deployed_addresses)?;
+        named_addresses.insert(request.address_name.to_string(), deployed_at);
+        if let Some((bundle, package)) = self
+            .bundle
+            .as_ref()
+            .and_then(|bundle| Some((bundle, bundle.package(request.address_name)?)))
+        {
+            package.check_named_addresses(&named_addresses)?;
+            return bundle.payload(package);
+        }
+        build_publish_payload(
+            request.package_dir,
+            request.address_name,
+            &named_addresses_arg(request.package_dir, request.address_name, &named_addresses)?,
+            &config.compiler_options(request.address_name),
+            config.upgrade_policy(request.address_name),
+            &config.included_artifacts(request.address_name).to_string(),
+            // A cached payload is written to the build directory, where the checksums and the
+            // artifacts of the published package are read.
+            !config.no_cache,
+        )
+        .await
+    }
+
+    /// Returns the checksums of the package just compiled or read by [`RestBackend::payload`].
+    fn checksums(&self, request: &PublishRequest<'_>) -> Result<PackageChecksums> {
+        match self
+            .bundle
+            .as_ref()
+            .and_then(|bundle| Some((bundle, bundle.package(request.address_name)?)))
+        {
+            Some((bundle, package)) => bundle.checksums(package),
+            None => PackageChecksums::from_build(request.package_dir),
+        }
+    }
+
+    /// Builds the payload publishing a package in a single transaction.
+    fn publish_payload(
+        &self,
+        request: &PublishRequest<'_>,
+        metadata: &[u8],
+        code: &[Vec<u8>],
+    ) -> Result<TransactionPayload> {
+        let config = request.config;
+        let publish_function = config
+            .package_options
+            .get(request.address_name)
+            .and_then(|options| options.publish_function.as_ref());
+        Ok(match publish_function {
+            Some(function_id) => publish_function_payload(
+                &resolve_function_id(
+                    function_id,
+                    &config.known_addresses(request.deployed_addresses),
+                ),
+                metadata,
+                code,
+            )?,
+            None => match config.package_module_type(request.address_name) {
+                DeployModuleType::Account => {
+                    aptos_stdlib::code_publish_package_txn(metadata.to_vec(), code.to_vec())
+                }
+                DeployModuleType::Object => {
+                    aptos_stdlib::object_code_deployment_publish(metadata.to_vec(), code.to_vec())
+                }
+            },
+        })
+    }
+}
+
+/// Resolves the secondary signers co-signing the publication of a package.
+fn secondary_signers(config: &DeployConfig, address_name: &str) -> Result<Vec<LocalAccount>> {
+    config
+        .package_options
+        .get(address_name)
+        .map(|options| options.secondary_signers.as_slice())
+        .unwrap_or_default()
+        .iter()
+        .map(|private_key| {
+            LocalAccount::from_private_key(&resolve_private_key(private_key)?, 0).map_err(|err| {
+                JayceError::Config(format!(
+                    "Invalid secondary signer of '{}': {}",
+                    address_name, err
+                ))
+            })
+        })
+        .collect()
+}
+
+/// Summarizes a transaction submitted but not committed yet.
+fn pending_summary(transaction: &SignedTransaction) -> TransactionSummary {
+    TransactionSummary {
+        transaction_hash: transaction.committed_hash(),
+        gas_used: None,
+        gas_unit_price: Some(transaction.gas_unit_price()),
+        pending: Some(true),
+        sender: Some(transaction.sender()),
+        sequence_number: Some(transaction.sequence_number()),
+        success: None,
+        timestamp_us: None,
+        version: None,
+        vm_status: None,
+    }
+}
+
+impl PublishBackend for RestBackend {
+    fn bundle(&self) -> Option<&Bundle> {
+        self.bundle.as_ref()
+    }
+
+    async fn sequence_number(&self, address: AccountAddress) -> Result<u64> {
+        let tracked = self
+            .sequence_numbers
+            .as_ref()
+            .and_then(|sequence_numbers| sequence_numbers.lock().unwrap().get(&address).copied());
+        if let Some(sequence_number) = tracked {
+            return Ok(sequence_number);
+        }
+        let sequence_number = self
+            .client
+            .get_account(address)
+            .await?
+            .into_inner()
+            .sequence_number;
+        if let Some(sequence_numbers) = &self.sequence_numbers {
+            sequence_numbers
+                .lock()
+                .unwrap()
+                .insert(address, sequence_number);
+        }
+        Ok(sequence_number)
+    }
+
+    async fn find_up_to_date_package(
+        &self,
+        address: AccountAddress,
+        name: &str,
+        code: &[Vec<u8>],
+    ) -> Result<Option<PackageMetadata>> {
+        find_up_to_date_package(&self.client, address, name, code).await
+    }
+
+    async fn upgrade_number(&self, address: AccountAddress, name: &str) -> Result<Option<u64>> {
+        upgrade_number(&self.client, address, name).await
+    }
+
+    /// Compiles the package at the address it is published at in a single transaction.
+    async fn compile(&self, request: &PublishRequest<'_>) -> Result<CompiledPackage> {
+        let deployed_at = self.publish_address(request, 1).await?;
+        let (metadata, code) = self.payload(request, deployed_at).await?;
+        Ok(CompiledPackage { metadata, code })
+    }
+
+    async fn simulate(&self, request: &PublishRequest<'_>) -> Result<Vec<TransactionSummary>> {
+        let deployed_at = self.publish_address(request, 1).await?;
+        let (metadata, code) = self.payload(request, deployed_at).await?;
+        let transaction = self.raw_transaction(
+            request.sender,
+            self.publish_payload(request, &metadata, &code)?,
+            self.sequence_number(request.sender).await?,
+        );
+        let simulation = self.signer(request.address_name).simulation_transaction(
+            transaction,
+            &secondary_signers(request.config, request.address_name)?,
+            self.fee_payer.as_ref(),
+        )?;
+        Ok(self
+            .client
+            .simulate(&simulation)
+            .await?
+            .into_inner()
+            .into_iter()
+            .map(|tx| TransactionSummary::from(&Transaction::UserTransaction(tx)))
+            .collect())
+    }
+
+    async fn submit(
+        &self,
+        request: &PublishRequest<'_>,
+        package: &CompiledPackage,
+        chunked: bool,
+    ) -> Result<PendingPublish> {
+        let signed = self
+            .signed
+            .lock()
+            .unwrap()
+            .get(request.address_name)
+            .cloned();
+        let resubmission = signed.is_some();
+        let signed = match signed {
+            Some(signed) => signed,
+            None => {
+                let signed = self.sign_publication(request, package, chunked).await?;
+                self.signed
+                    .lock()
+                    .unwrap()
+                    .insert(request.address_name.to_string(), signed.clone());
+                signed
+            }
+        };
+        let tx_info = self
+            .submit_transactions(request.sender, &signed.transactions, resubmission)
+            .await?;
+        self.signed.lock().unwrap().remove(request.address_name);
+        Ok(PendingPublish {
+            package_dir: request.package_dir.to_path_buf(),
+            address_name: request.address_name.to_string(),
+            tx_info,
+            deployed_at: signed.deployed_at,
+            checksums: Some(self.checksums(request)?),
+        })
+    }
+
+    /// Signed transactions are resubmitted as they are by `submit`, skipping the ones already
+    /// known by their hash.
+    async fn find_submitted(
+        &self,
+        _request: &PublishRequest<'_>,
+        _sequence_number: u64,
+        _chunked: bool,
+    ) -> Result<Option<PendingPublish>> {
+        Ok(None)
+    }
+
+    async fn wait(&self, pending: PendingPublish) -> Result<PublishOutcome> {
+        Ok(PublishOutcome {
+            tx_info: self.wait_transactions(&pending.tx_info).await?,
+            deployed_at: pending.deployed_at,
+            checksums: pending.checksums,
+        })
+    }
+
+    async fn freeze(
+        &self,
+        _config: &DeployConfig,
+        address_name: &str,
+        sender: AccountAddress,
+        code_object: AccountAddress,
+    ) -> Result<TransactionSummary> {
+        let transaction = self
+            .sign(
+                address_name,
+                sender,
+                aptos_stdlib::object_code_deployment_freeze_code_object(code_object),
+                self.sequence_number(sender).await?,
+                &[],
+            )
+            .await?;
+        let tx_info = self
+            .submit_transactions(sender, &[transaction], false)
+            .await?;
+        Ok(self.wait_transactions(&tx_info).await?.remove(0))
+    }
+}
+
+/// Splits the publication of a package into the transactions of chunked publish, as the aptos CLI
+/// does: the metadata and the modules are staged in chunks of `chunk_size` bytes by the
+/// `large_packages` module at `module_address`, whose last call publishes the staged package.
+fn chunked_publish_payloads(
+    module_address: AccountAddress,
+    chunk_size: usize,
+    module_type: &DeployModuleType,
+    package: &CompiledPackage,
+) -> Vec<TransactionPayload> {
+    let large_packages_call = |function: &str, metadata: &[u8], indices: &[u16], code: &[&[u8]]| {
+        TransactionPayload::EntryFunction(EntryFunction::new(
+            ModuleId::new(module_address, Identifier::new("large_packages").unwrap()),
+            Identifier::new(function).unwrap(),
+            vec![],
+            vec![
+                bcs::to_bytes(metadata).unwrap(),
+                bcs::to_bytes(indices).unwrap(),
+                bcs::to_bytes(code).unwrap(),
+            ],
+        ))
+    };
+
+    let mut metadata_chunks: Vec<&[u8]> = package.metadata.chunks(chunk_size).collect();
+    // The last metadata chunk is staged with the first modules.
+    let mut metadata_chunk = metadata_chunks.pop().unwrap_or_default();
+    let mut taken_size = metadata_chunk.len();
+    let mut payloads: Vec<TransactionPayload> = metadata_chunks
+        .into_iter()
+        .map(|chunk| large_packages_call("stage_code_chunk", chunk, &[], &[]))
+        .collect();
+    let mut code_indices = vec![];
+    let mut code_chunks = vec![];
+    for (index, module) in package.code.iter().enumerate() {
+        for chunk in module.chunks(chunk_size) {
+            if taken_size + chunk.len() > chunk_size {
+                payloads.push(large_packages_call(
+                    "stage_code_chunk",
+                    metadata_chunk,
+                    &code_indices,
+                    &code_chunks,
+                ));
+                metadata_chunk = &[];
+                code_indices.clear();
+                code_chunks.clear();
+                taken_size = 0;
+            }
+            code_indices.push(index as u16);
+            code_chunks.push(chunk);
+            taken_size += chunk.len();
+        }
+    }
+    payloads.push(large_packages_call(
+        match module_type {
+            DeployModuleType::Account => "stage_code_chunk_and_publish_to_account",
+            DeployModuleType::Object => "stage_code_chunk_and_publish_to_object",
+        },
+        metadata_chunk,
+        &code_indices,
+        &code_chunks,
+    ));
+    payloads
+}
+
+/// Compiles a package and returns its serialized metadata and modules, in publishing order. With
+/// `use_cache`, the payload is read from the artifact cache when the package didn't change, and
+/// written to the build directory of the package as if it was compiled. An `upgrade_policy`
+/// overrides the one of the `Move.toml`.
+pub(crate) async fn build_publish_payload(
+    package_dir: &Path,
+    address_name: &str,
+    named_addresses: &str,
+    compiler: &CompilerOptions,
+    upgrade_policy: Option<&UpgradePolicy>,
+    included_artifacts: &str,
+    use_cache: bool,
+) -> Result<(Vec<u8>, Vec<Vec<u8>>)> {
+    let cache = ArtifactCache::open().filter(|_| use_cache);
+    let key = cache.as_ref().and_then(|cache| {
+        cache.key(
+            package_dir,
+            &format!(
+                "publish-payload {} {} {} {}",
+                named_addresses,
+                compiler.cache_key(),
+                upgrade_policy.map_or("-".to_string(), UpgradePolicy::to_string),
+                included_artifacts
+            ),
+        )
+    });
+    if let (Some(cache), Some(key)) = (&cache, &key) {
+        if let Some((metadata, modules)) = cache.get(key).and_then(CachedPayload::decode) {
+            write_build_artifacts(package_dir, &metadata, &modules)?;
+            return Ok((
+                metadata,
+                modules.into_iter().map(|(_, code)| code).collect(),
+            ));
+        }
+    }
+
+    let json_output_file = std::env::temp_dir().join(format!(
+        "jayce-{}-{}-payload.json",
+        std::process::id(),
+        address_name
+    ));
+    write_publish_payload(
+        package_dir,
+        named_addresses,
+        compiler,
+        upgrade_policy,
+        included_artifacts,
+        &json_output_file,
+    )
+    .await?;
+    let content = fs::read(&json_output_file)?;
+    fs::remove_file(&json_output_file)?;
+    let payload: PublishPayloadJson = serde_json::from_slice(&content)?;
+    if payload.args.len() != 2 {
+        return Err(JayceError::Compile(format!(
+            "Unexpected publish payload for {}",
+            package_dir.to_str().unwrap()
+        )));
+    }
+    let metadata = decode_hex_value(&payload.args[0].value)?;
+    let code = payload.args[1]
+        .value
+        .as_array()
+        .ok_or_else(|| {
+            JayceError::Compile("Publish payload modules should be an array".to_string())
+        })?
+        .iter()
+        .map(decode_hex_value)
+        .collect::<Result<Vec<_>>>()?;
+    if let (Some(cache), Some(key)) = (&cache, &key) {
+        match built_module_names(package_dir, &code) {
+            Ok(names) => cache.put(
+                key,
+                &serde_json::to_vec(&CachedPayload {
+                    metadata: hex::encode(&metadata),
+                    modules: names
+                        .into_iter()
+                        .zip(code.iter().map(hex::encode))
+                        .collect(),
+                })?,
+            ),
+            Err(err) => warn!("Failed to cache the payload of {}: {}", address_name, err),
+        }
+    }
+    Ok((metadata, code))
+}
+
+/// Builds the payload calling the entry function `function_id` with the metadata and the code of a
+/// package.
+fn publish_function_payload(
+    function_id: &str,
+    metadata: &[u8],
+    code: &[Vec<u8>],
+) -> Result<TransactionPayload> {
+    let invalid = || JayceError::Config(format!("Invalid publish function {}", function_id));
+    let parts: Vec<&str> = function_id.split("::").collect();
+    let [address, module, function] = parts[..] else {
+        return Err(invalid());
+    };
+    Ok(TransactionPayload::EntryFunction(EntryFunction::new(
+        ModuleId::new(
+            AccountAddress::from_hex_literal(address).map_err(|_| invalid())?,
+            Identifier::new(module).map_err(|_| invalid())?,
+        ),
+        Identifier::new(function).map_err(|_| invalid())?,
+        vec![],
+        vec![
+            bcs::to_bytes(metadata).map_err(anyhow::Error::from)?,
+            bcs::to_bytes(code).map_err(anyhow::Error::from)?,
+        ],
+    )))
+}
+
+/// Returns the size of the publish transaction payload of a compiled package.
+pub(crate) fn publish_payload_size(metadata: &[u8], code: &[Vec<u8>]) -> Result<usize> {
+    bcs::serialized_size(&aptos_stdlib::code_publish_package_txn(
+        metadata.to_vec(),
+        code.to_vec(),
+    ))
+    .map_err(|err| JayceError::Compile(err.to_string()))
+}
+
+/// Compiles a package and writes the `code::publish_package_txn` entry function payload to
+/// `json_output_file`. An `upgrade_policy` overrides the one of the `Move.toml`.
+pub(crate) async fn write_publish_payload(
+    package_dir: &Path,
+    named_addresses: &str,
+    compiler: &CompilerOptions,
+    upgrade_policy: Option<&UpgradePolicy>,
+    included_artifacts: &str,
+    json_output_file: &Path,
+) -> Result<()> {
+    if let Some(image) = &compiler.docker_image {
+        let package_dir = fs::canonicalize(package_dir)?;
+        let (package_dir_args, copy) = package_dir_args(&package_dir, upgrade_policy)?;
+        let output_dir = match json_output_file.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent,
+            _ => Path::new("."),
+        };
+        let args = format!(
+            "move build-publish-payload {} --included-artifacts {} \
+            --json-output-file {} {} {}",
+            package_dir_args,
+            included_artifacts,
+            fs::canonicalize(output_dir)?
+                .join(json_output_file.file_name().unwrap())
+                .to_str()
+                .unwrap(),
+            named_addresses,
+            compiler.args()
+        );
+        let args: Vec<&str> = args.split_whitespace().collect();
+        // The copy is outside of the repository when the package is at its root.
+        let mut outputs = vec![output_dir];
+        outputs.extend(copy.as_ref().map(PackageCopy::dir));
+        return run_aptos_in_docker(image, &package_dir, &args, &outputs);
+    }
+    let (package_dir_args, _copy) = package_dir_args(package_dir, upgrade_policy)?;
+    let args = format!(
+        "aptos move build-publish-payload \
+            {} \
+            --included-artifacts {} \
+            --json-output-file {} \
+            {} \
+            {} \
+            ",
+        package_dir_args,
+        included_artifacts,
+        json_output_file.to_str().unwrap(),
+        named_addresses,
+        compiler.args()
+    );
+    let args: Vec<&str> = args.split_whitespace().collect();
+    let tool = Tool::try_parse_from(&args).map_err(|err| {
+        JayceError::Config(format!(
+            "Invalid arguments to build publish payload: {}",
+            err
+        ))
+    })?;
+    if let Tool::Move(MoveTool::BuildPublishPayload(cmd_executor)) = tool {
+        cmd_executor
+            .execute()
+            .await
+            .map_err(|err| JayceError::Compile(err.to_string()))?;
+        Ok(())
+    } else {
+        Err(JayceError::Config(format!(
+            "Wrong arguments to build publish payload: {:?}",
+            args
+        )))
+    }
+}
+
+fn decode_hex_value(value: &serde_json::Value) -> Result<Vec<u8>> {
+    let value = value.as_str().ok_or_else(|| {
+        JayceError::Compile("Publish payload argument should be a hex string".to_string())
+    })?;
+    hex::decode(value.trim_start_matches("0x")).map_err(|err| JayceError::Compile(err.to_string()))
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::BTreeMap;
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    use aptos_sdk::bcs;
+    use aptos_sdk::move_types::account_address::AccountAddress;
+    use aptos_sdk::rest_client::Client;
+    use aptos_sdk::transaction_builder::TransactionFactory;
+    use aptos_sdk::types::chain_id::ChainId;
+    use aptos_sdk::types::transaction::authenticator::TransactionAuthenticator;
+    use aptos_sdk::types::transaction::TransactionPayload;
+    use aptos_sdk::types::LocalAccount;
+    use url::Url;
+
+    use crate::deploy_config::DeployModuleType;
+    use crate::deployer::{chunked_publish_payloads, publish_function_payload, RestBackend};
+    use crate::publish_backend::CompiledPackage;
+    use crate::signer::{AnySigner, PrivateKeySigner, Signer};
+
+    #[test]
+    fn test_publish_function_payload() {
+        let code = vec![vec![1, 2], vec![3]];
+        let TransactionPayload::EntryFunction(entry_function) =
+            publish_function_payload("0xcafe::deployer::publish", &[4, 5], &code).unwrap()
+        else {
+            panic!("Expected an entry function payload");
+        };
+        assert_eq!(
+            entry_function.module().address(),
+            &AccountAddress::from_hex_literal("0xcafe").unwrap()
+        );
+        assert_eq!(entry_function.function().as_str(), "publish");
+        assert_eq!(
+            entry_function.args(),
+            &[
+                bcs::to_bytes(&vec![4u8, 5]).unwrap(),
+                bcs::to_bytes(&code).unwrap()
+            ]
+        );
+
+        assert!(publish_function_payload("deployer::publish", &[], &[]).is_err());
+    }
+
+    #[test]
+    fn test_chunked_publish_payloads() {
+        let package = CompiledPackage {
+            metadata: vec![7; 3],
+            code: vec![vec![0; 5], vec![1; 4]],
+        };
+        let calls = chunked_publish_payloads(
+            AccountAddress::from_hex_literal("0x7").unwrap(),
+            6,
+            &DeployModuleType::Object,
+            &package,
+        )
+        .into_iter()
+        .map(|payload| {
+            let TransactionPayload::EntryFunction(entry_function) = payload else {
+                panic!("Expected an entry function payload");
+            };
+            assert_eq!(entry_function.module().name().as_str(), "large_packages");
+            (
+                entry_function.function().to_string(),
+                entry_function.args().to_vec(),
+            )
+        })
+        .collect::<Vec<_>>();
+
+        // Each transaction stages at most 6 bytes, the last one publishes the package.
+        let args = |metadata: &[u8], indices: &[u16], code: &[&[u8]]| {
+            vec![
+                bcs::to_bytes(metadata).unwrap(),
+                bcs::to_bytes(indices).unwrap(),
+                bcs::to_bytes(code).unwrap(),
+            ]
+        };
+        assert_eq!(
+            calls,
+            [
+                ("stage_code_chunk".to_string(), args(&[7; 3], &[], &[])),
+                ("stage_code_chunk".to_string(), args(&[], &[0], &[&[0; 5]])),
+                (
+                    "stage_code_chunk_and_publish_to_object".to_string(),
+                    args(&[], &[1], &[&[1; 4]])
+                ),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_sign_with_secondary_signers() {
+        let account = |byte: &str| {
+            LocalAccount::from_private_key(&format!("0x{}", byte.repeat(32)), 0).unwrap()
+        };
+        let signer = PrivateKeySigner::from_private_key(&format!("0x{}", "11".repeat(32))).unwrap();
+        let sender = signer.address();
+        let fee_payer = account("33");
+        let fee_payer_address = fee_payer.address();
+        let backend = RestBackend {
+            client: Client::new(Url::parse("http://127.0.0.1:8080").unwrap()),
+            signer: Arc::new(AnySigner::PrivateKey(signer)),
+            package_signers: BTreeMap::new(),
+            fee_payer: Some(fee_payer),
+            transaction_factory: TransactionFactory::new(ChainId::test()),
+            bundle: None,
+            sequence_numbers: None,
+            confirmation_timeout: Duration::from_secs(1),
+            poll_interval: Duration::from_millis(1),
+            signed: Mutex::default(),
+        };
+        let secondary_signers = [account("22")];
+        let payload =
+            publish_function_payload("0xcafe::deployer::publish", &[4, 5], &[vec![1]]).unwrap();
+
+        let transaction = backend
+            .sign("lib_addr", sender, payload, 3, &secondary_signers)
+            .await
+            .unwrap();
+        assert!(transaction.verify_signature().is_ok());
+        assert_eq!(transaction.sender(), sender);
+        assert_eq!(transaction.sequence_number(), 3);
+        let TransactionAuthenticator::FeePayer {
+            secondary_signer_addresses,
+            fee_payer_address: paid_by,
+            ..
+        } = transaction.authenticator()
+        else {
+            panic!("Expected a fee payer transaction");
+        };
+        assert_eq!(secondary_signer_addresses, [secondary_signers[0].address()]);
+        assert_eq!(paid_by, fee_payer_address);
+    }
+}
@@ -0,0 +1,123 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use tracing::{info, warn};
+
+use crate::error::{JayceError, Result};
+use crate::git::repository_root;
+
+/// Runs an aptos CLI command compiling the package of `package_dir` inside a container of
+/// `image`, so that the bytecode doesn't depend on the compiler installed on the machine. `args`
+/// are the arguments of the aptos CLI, e.g. `move compile --package-dir <dir>`.
+///
+/// The repository of the package, or the package itself outside of a repository, and the
+/// directories of `outputs` are mounted at the same paths, so the paths of `args` and of the
+/// build directory are the same inside the container.
+pub fn run_aptos_in_docker(
+    image: &str,
+    package_dir: &Path,
+    args: &[&str],
+    outputs: &[&Path],
+) -> Result<()> {
+    if !image.contains("@sha256:") {
+        warn!(
+            "The image {} isn't pinned by digest, builds are only reproducible with the same image",
+            image
+        );
+    }
+    let package_dir = fs::canonicalize(package_dir)?;
+    let mut mounts = vec![repository_root(&package_dir).unwrap_or_else(|| package_dir.clone())];
+    for output in outputs {
+        fs::create_dir_all(output)?;
+        mounts.push(fs::canonicalize(output)?);
+    }
+    let docker_args = docker_args(image, &mounts, owner(&package_dir), args);
+    info!("Running `docker {}`...", docker_args.join(" "));
+    let output = Command::new("docker")
+        .args(&docker_args)
+        .stdout(Stdio::null())
+        .output()
+        .map_err(|err| JayceError::Compile(format!("Failed to run docker: {}", err)))?;
+    if !output.status.success() {
+        return Err(JayceError::Compile(format!(
+            "`aptos {}` failed in {}: {}",
+            args.join(" "),
+            image,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(())
+}
+
+/// The arguments of `docker run`. The container runs as `user`, the owner of the package, so the
+/// build directory it writes isn't owned by root.
+fn docker_args(
+    image: &str,
+    mounts: &[PathBuf],
+    user: Option<String>,
+    args: &[&str],
+) -> Vec<String> {
+    let mut docker_args = vec![
+        "run".to_string(),
+        "--rm".to_string(),
+        // Reaches the local network and the git dependencies.
+        "--network".to_string(),
+        "host".to_string(),
+        // The git dependencies are fetched to the home directory, which must be writable.
+        "--env".to_string(),
+        "HOME=/tmp".to_string(),
+        "--entrypoint".to_string(),
+        "aptos".to_string(),
+    ];
+    if let Some(user) = user {
+        docker_args.extend(["--user".to_string(), user]);
+    }
+    for mount in mounts {
+        let mount = mount.to_str().unwrap();
+        docker_args.extend(["--volume".to_string(), format!("{}:{}", mount, mount)]);
+    }
+    docker_args.extend([
+        "--workdir".to_string(),
+        mounts[0].to_str().unwrap().to_string(),
+        image.to_string(),
+    ]);
+    docker_args.extend(args.iter().map(|arg| arg.to_string()));
+    docker_args
+}
+
+#[cfg(unix)]
+fn owner(path: &Path) -> Option<String> {
+    use std::os::unix::fs::MetadataExt;
+
+    let metadata = fs::metadata(path).ok()?;
+    Some(format!("{}:{}", metadata.uid(), metadata.gid()))
+}
+
+#[cfg(not(unix))]
+fn owner(_path: &Path) -> Option<String> {
+    None
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::PathBuf;
+
+    use crate::docker::docker_args;
+
+    #[test]
+    fn test_docker_args() {
+        let args = docker_args(
+            "aptoslabs/tools@sha256:abc",
+            &[PathBuf::from("/repo"), PathBuf::from("/tmp/out")],
+            Some("1000:1000".to_string()),
+            &["move", "compile", "--package-dir", "/repo/contracts"],
+        );
+        assert_eq!(
+            args.join(" "),
+            "run --rm --network host --env HOME=/tmp --entrypoint aptos --user 1000:1000 \
+            --volume /repo:/repo --volume /tmp/out:/tmp/out --workdir /repo \
+            aptoslabs/tools@sha256:abc move compile --package-dir /repo/contracts"
+        );
+    }
+}
@@ -0,0 +1,259 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use aptos::common::types::{CliCommand, TransactionSummary};
+use aptos::move_tool::MoveTool;
+use aptos::Tool;
+use aptos_sdk::move_types::account_address::AccountAddress;
+use clap::Parser;
+use serde::{Deserialize, Serialize};
+
+use crate::deploy_config::CompilerOptions;
+use crate::error::{JayceError, Result};
+use crate::move_package::{bytecode_scripts_dir, compile_package, unassigned_named_addresses_arg};
+
+/// An entry function call, whose function id and address arguments may refer to address names,
+/// e.g. `verifier_addr::verifier::init` or `address:cpu_addr`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct EntryFunctionCall {
+    pub function_id: String,
+    #[serde(default)]
+    pub type_args: Vec<String>,
+    #[serde(default)]
+    pub args: Vec<String>,
+}
+
+/// A Move script of a package, run with the named addresses of the package and address arguments
+/// resolved from the address names.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ScriptCall {
+    pub package_dir: PathBuf,
+    /// The name of the script, e.g. `init` for `scripts/init.move`.
+    pub script: String,
+    #[serde(default)]
+    pub type_args: Vec<String>,
+    #[serde(default)]
+    pub args: Vec<String>,
+}
+
+/// Replaces the address name prefixing a function id or a type, e.g. `lib_addr::module::name`.
+pub fn resolve_function_id(
+    function_id: &str,
+    deployed_addresses: &BTreeMap<String, AccountAddress>,
+) -> String {
+    match function_id.split_once("::") {
+        Some((address, rest)) => match deployed_addresses.get(address) {
+            Some(hex_address) => format!("{}::{}", hex_address, rest),
+            None => function_id.to_string(),
+        },
+        None => function_id.to_string(),
+    }
+}
+
+/// Replaces the address names used as values of `address` and `vector<address>` arguments, which
+/// are written as `<type>:<value>`.
+pub fn resolve_arg(arg: &str, deployed_addresses: &BTreeMap<String, AccountAddress>) -> String {
+    let resolve = |value: &str| match deployed_addresses.get(value) {
+        Some(hex_address) => hex_address.to_string(),
+        None => value.to_string(),
+    };
+    match arg.split_once(':') {
+        Some(("address", value)) => format!("address:{}", resolve(value)),
+        Some(("vector<address>", values)) => format!(
+            "vector<address>:{}",
+            values.split(',').map(resolve).collect::<Vec<_>>().join(",")
+        ),
+        _ => arg.to_string(),
+    }
+}
+
+/// Submits an entry function call signed according to `signer_args`, e.g. `--profile <name>`.
+pub async fn run_entry_function(
+    call: &EntryFunctionCall,
+    deployed_addresses: &BTreeMap<String, AccountAddress>,
+    signer_args: &str,
+    gas_args: &str,
+    assume_yes: bool,
+) -> Result<TransactionSummary> {
+    let mut args = function_args("run", call, deployed_addresses);
+    args.extend(signer_args.split_whitespace().map(|arg| arg.to_string()));
+    args.extend(gas_args.split_whitespace().map(|arg| arg.to_string()));
+    if assume_yes {
+        args.push("--assume-yes".to_string());
+    }
+
+    let tool = Tool::try_parse_from(&args).map_err(|err| {
+        JayceError::Config(format!("Invalid arguments to run entry function: {}", err))
+    })?;
+    if let Tool::Move(MoveTool::Run(cmd_executor)) = tool {
+        cmd_executor
+            .execute()
+            .await
+            .map_err(|err| JayceError::Publish(err.to_string()))
+    } else {
+        Err(JayceError::Config(format!(
+            "Wrong arguments to run entry function: {:?}",
+            args
+        )))
+    }
+}
+
+/// Compiles the package of a script, then submits the script signed according to `signer_args`.
+pub async fn run_script(
+    call: &ScriptCall,
+    deployed_addresses: &BTreeMap<String, AccountAddress>,
+    signer_args: &str,
+    gas_args: &str,
+    assume_yes: bool,
+) -> Result<TransactionSummary> {
+    compile_package(
+        &call.package_dir,
+        &unassigned_named_addresses_arg(&call.package_dir, deployed_addresses)?,
+        &CompilerOptions::default(),
+    )
+    .await?;
+    let script_path = bytecode_scripts_dir(&call.package_dir)?.join(format!("{}.mv", call.script));
+    let mut args = vec![
+        "aptos".to_string(),
+        "move".to_string(),
+        "run-script".to_string(),
+        "--compiled-script-path".to_string(),
+        script_path.to_str().unwrap().to_string(),
+    ];
+    if !call.type_args.is_empty() {
+        args.push("--type-args".to_string());
+        args.extend(
+            call.type_args
+                .iter()
+                .map(|type_arg| resolve_function_id(type_arg, deployed_addresses)),
+        );
+    }
+    if !call.args.is_empty() {
+        args.push("--args".to_string());
+        args.extend(
+            call.args
+                .iter()
+                .map(|arg| resolve_arg(arg, deployed_addresses)),
+        );
+    }
+    args.extend(signer_args.split_whitespace().map(|arg| arg.to_string()));
+    args.extend(gas_args.split_whitespace().map(|arg| arg.to_string()));
+    if assume_yes {
+        args.push("--assume-yes".to_string());
+    }
+
+    let tool = Tool::try_parse_from(&args)
+        .map_err(|err| JayceError::Config(format!("Invalid arguments to run script: {}", err)))?;
+    if let Tool::Move(MoveTool::RunScript(cmd_executor)) = tool {
+        cmd_executor
+            .execute()
+            .await
+            .map_err(|err| JayceError::Publish(err.to_string()))
+    } else {
+        Err(JayceError::Config(format!(
+            "Wrong arguments to run script: {:?}",
+            args
+        )))
+    }
+}
+
+/// Executes a view function against the REST API at `rest_url`.
+pub async fn run_view_function(
+    call: &EntryFunctionCall,
+    deployed_addresses: &BTreeMap<String, AccountAddress>,
+    rest_url: &str,
+) -> Result<Vec<serde_json::Value>> {
+    let mut args = function_args("view", call, deployed_addresses);
+    args.push("--url".to_string());
+    args.push(rest_url.to_string());
+
+    let tool = Tool::try_parse_from(&args).map_err(|err| {
+        JayceError::Config(format!("Invalid arguments to run view function: {}", err))
+    })?;
+    if let Tool::Move(MoveTool::View(cmd_executor)) = tool {
+        Ok(cmd_executor.execute().await?)
+    } else {
+        Err(JayceError::Config(format!(
+            "Wrong arguments to run view function: {:?}",
+            args
+        )))
+    }
+}
+
+/// Builds the `aptos move <command>` arguments selecting the function and its arguments.
+fn function_args(
+    command: &str,
+    call: &EntryFunctionCall,
+    deployed_addresses: &BTreeMap<String, AccountAddress>,
+) -> Vec<String> {
+    let mut args = vec![
+        "aptos".to_string(),
+        "move".to_string(),
+        command.to_string(),
+        "--function-id".to_string(),
+        resolve_function_id(&call.function_id, deployed_addresses),
+    ];
+    if !call.type_args.is_empty() {
+        args.push("--type-args".to_string());
+        args.extend(
+            call.type_args
+                .iter()
+                .map(|type_arg| resolve_function_id(type_arg, deployed_addresses)),
+        );
+    }
+    if !call.args.is_empty() {
+        args.push("--args".to_string());
+        args.extend(
+            call.args
+                .iter()
+                .map(|arg| resolve_arg(arg, deployed_addresses)),
+        );
+    }
+    args
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::BTreeMap;
+
+    use aptos_sdk::move_types::account_address::AccountAddress;
+
+    use crate::entry_function::{resolve_arg, resolve_function_id};
+
+    #[test]
+    fn test_resolve_address_names() {
+        let deployed_addresses = BTreeMap::from([
+            (
+                "lib_addr".to_string(),
+                AccountAddress::from_hex_literal("0x1").unwrap(),
+            ),
+            (
+                "cpu_addr".to_string(),
+                AccountAddress::from_hex_literal("0x2").unwrap(),
+            ),
+        ]);
+
+        assert_eq!(
+            resolve_function_id("lib_addr::lib::init", &deployed_addresses),
+            format!(
+                "{}::lib::init",
+                AccountAddress::from_hex_literal("0x1").unwrap()
+            )
+        );
+        assert_eq!(
+            resolve_function_id("0x3::module::init", &deployed_addresses),
+            "0x3::module::init"
+        );
+        assert_eq!(
+            resolve_arg("address:cpu_addr", &deployed_addresses),
+            format!(
+                "address:{}",
+                AccountAddress::from_hex_literal("0x2").unwrap()
+            )
+        );
+        assert_eq!(
+            resolve_arg("u64:cpu_addr", &deployed_addresses),
+            "u64:cpu_addr"
+        );
+    }
+}
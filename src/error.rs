@@ -0,0 +1,124 @@
+use aptos::common::types::CliError;
+use aptos_sdk::rest_client::error::RestError;
+use thiserror::Error;
+
+use crate::retry::is_transient_message;
+
+pub type Result<T> = std::result::Result<T, JayceError>;
+
+/// Exit codes of `jayce`, by class of failure, so that CI pipelines can branch on them.
+pub mod exit_code {
+    /// Any failure not covered by a more specific code.
+    pub const FAILURE: i32 = 1;
+    /// Invalid arguments or configuration, unreadable packages or unresolved named addresses.
+    pub const CONFIG: i32 = 2;
+    /// A package failed to compile.
+    pub const COMPILE: i32 = 3;
+    /// The deployer can't pay for the deployment.
+    pub const INSUFFICIENT_BALANCE: i32 = 4;
+    /// The REST API or the faucet couldn't be reached or failed.
+    pub const NETWORK: i32 = 5;
+    /// A transaction was rejected or aborted.
+    pub const PUBLISH: i32 = 6;
+    /// The published modules or sources don't match the local ones.
+    pub const VERIFICATION: i32 = 7;
+    /// The deployment was interrupted with Ctrl-C, as a process interrupted by SIGINT.
+    pub const INTERRUPTED: i32 = 130;
+}
+
+#[derive(Debug, Error)]
+pub enum JayceError {
+    /// The deploy configuration is missing a field or contains an invalid value.
+    #[error("Config error: {0}")]
+    Config(String),
+    /// A `Move.toml` file could not be read or doesn't declare the expected addresses.
+    #[error("Package error: {0}")]
+    Package(String),
+    /// The packages can't be ordered, or a named address has no known deployed address.
+    #[error("Dependency error: {0}")]
+    Dependency(String),
+    #[error("Compile error: {0}")]
+    Compile(String),
+    #[error("Publish error: {0}")]
+    Publish(String),
+    /// The balance of the deployer doesn't cover the estimated cost of the deployment.
+    #[error("Insufficient balance: {0}")]
+    InsufficientBalance(String),
+    #[error("Faucet error: {0}")]
+    Faucet(String),
+    #[error("Verification error: {0}")]
+    Verification(String),
+    /// A deployment hook failed.
+    #[error("Hook error: {0}")]
+    Hook(String),
+    /// A remote signer failed or returned an invalid key or signature.
+    #[error("Signer error: {0}")]
+    Signer(String),
+    /// The deployment was interrupted by the user.
+    #[error("Interrupted")]
+    Interrupted,
+    #[error("Aptos CLI error: {0}")]
+    Cli(#[from] CliError),
+    #[error("REST error: {0}")]
+    Rest(#[from] RestError),
+    #[error("Prompt error: {0}")]
+    Prompt(#[from] dialoguer::Error),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+    #[error(transparent)]
+    Other(#[from] anyhow::Error),
+}
+
+impl JayceError {
+    /// Returns the exit code of the class of the error.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            JayceError::Config(_)
+            | JayceError::Package(_)
+            | JayceError::Dependency(_)
+            | JayceError::Prompt(_) => exit_code::CONFIG,
+            JayceError::Compile(_) | JayceError::Cli(CliError::MoveCompilationError(_)) => {
+                exit_code::COMPILE
+            }
+            JayceError::InsufficientBalance(_) => exit_code::INSUFFICIENT_BALANCE,
+            // Publishing goes through the aptos CLI, whose network failures end up as messages.
+            JayceError::Publish(message) if is_transient_message(message) => exit_code::NETWORK,
+            JayceError::Rest(_)
+            | JayceError::Faucet(_)
+            | JayceError::Cli(CliError::ApiError(_)) => exit_code::NETWORK,
+            JayceError::Publish(_) | JayceError::Cli(CliError::SimulationError(_)) => {
+                exit_code::PUBLISH
+            }
+            JayceError::Verification(_) => exit_code::VERIFICATION,
+            JayceError::Interrupted => exit_code::INTERRUPTED,
+            _ => exit_code::FAILURE,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::error::{exit_code, JayceError};
+
+    #[test]
+    fn test_exit_codes() {
+        assert_eq!(
+            JayceError::Config("missing network".to_string()).exit_code(),
+            exit_code::CONFIG
+        );
+        assert_eq!(
+            JayceError::Publish("Move abort: EPACKAGE_DEP_MISSING".to_string()).exit_code(),
+            exit_code::PUBLISH
+        );
+        assert_eq!(
+            JayceError::Publish("503 Service Unavailable".to_string()).exit_code(),
+            exit_code::NETWORK
+        );
+        assert_eq!(
+            JayceError::Hook("exit status: 1".to_string()).exit_code(),
+            exit_code::FAILURE
+        );
+    }
+}
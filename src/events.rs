@@ -0,0 +1,139 @@
+use std::fs::OpenOptions;
+use std::io::{self, Write};
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use aptos_sdk::crypto::HashValue;
+use aptos_sdk::move_types::account_address::AccountAddress;
+use serde::Serialize;
+use tracing::warn;
+
+use crate::error::Result;
+
+/// A lifecycle event of the deployment of a package.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum DeployEvent {
+    PackageStarted {
+        address_name: String,
+    },
+    Compiled {
+        address_name: String,
+    },
+    Submitted {
+        address_name: String,
+    },
+    Confirmed {
+        address_name: String,
+        deployed_at: AccountAddress,
+        tx_hashes: Vec<HashValue>,
+    },
+    Skipped {
+        address_name: String,
+    },
+    Failed {
+        address_name: String,
+        error: String,
+    },
+}
+
+#[derive(Serialize)]
+struct EventLine<'a> {
+    timestamp_ms: u128,
+    network: &'a str,
+    #[serde(flatten)]
+    event: &'a DeployEvent,
+}
+
+/// Writes the events of a deployment as JSON Lines, one object per event, so that CI can follow
+/// long deployments while they run.
+pub struct EventWriter {
+    network: String,
+    out: Mutex<Box<dyn Write + Send>>,
+}
+
+impl EventWriter {
+    /// Appends the events to `events_file`, or writes them to stdout with `stdout`. Returns `None`
+    /// when events are written to neither.
+    pub fn open(
+        events_file: Option<&Path>,
+        stdout: bool,
+        network: &str,
+    ) -> Result<Option<EventWriter>> {
+        let out: Box<dyn Write + Send> = match events_file {
+            Some(events_file) => Box::new(
+                OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(events_file)?,
+            ),
+            None if stdout => Box::new(io::stdout()),
+            None => return Ok(None),
+        };
+        Ok(Some(EventWriter {
+            network: network.to_string(),
+            out: Mutex::new(out),
+        }))
+    }
+
+    /// Writes an event. Failures are logged, since the events don't affect the deployment.
+    pub fn emit(&self, event: &DeployEvent) {
+        let line = EventLine {
+            timestamp_ms: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_millis(),
+            network: &self.network,
+            event,
+        };
+        let mut out = self.out.lock().unwrap();
+        if let Err(err) = serde_json::to_writer(&mut *out, &line)
+            .map_err(io::Error::from)
+            .and_then(|_| writeln!(out))
+            .and_then(|_| out.flush())
+        {
+            warn!("Failed to write deployment event: {}", err);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+
+    use aptos_sdk::move_types::account_address::AccountAddress;
+
+    use crate::events::{DeployEvent, EventWriter};
+
+    #[test]
+    fn test_write_events() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let events_file = temp_dir.path().join("events.jsonl");
+        let writer = EventWriter::open(Some(&events_file), false, "testnet")
+            .unwrap()
+            .unwrap();
+        writer.emit(&DeployEvent::PackageStarted {
+            address_name: "cpu_addr".to_string(),
+        });
+        writer.emit(&DeployEvent::Confirmed {
+            address_name: "cpu_addr".to_string(),
+            deployed_at: AccountAddress::ONE,
+            tx_hashes: vec![],
+        });
+
+        let content = fs::read_to_string(&events_file).unwrap();
+        let lines: Vec<serde_json::Value> = content
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["event"], "package_started");
+        assert_eq!(lines[0]["network"], "testnet");
+        assert_eq!(lines[1]["event"], "confirmed");
+        assert_eq!(lines[1]["address_name"], "cpu_addr");
+        assert!(lines[1]["timestamp_ms"].as_u64().is_some());
+
+        assert!(EventWriter::open(None, false, "testnet").unwrap().is_none());
+    }
+}
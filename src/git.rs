@@ -0,0 +1,364 @@
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::process::Command;
+
+use aptos_sdk::crypto::HashValue;
+use tracing::info;
+use url::Url;
+
+use crate::artifact_cache::cache_dir;
+use crate::error::{JayceError, Result};
+
+/// The prefix of the `modules_path` entries read from a git repository.
+const GIT_SOURCE_PREFIX: &str = "git:";
+
+/// A package of a git repository, from a `modules_path` entry of the form
+/// `git:<URL>?rev=<REVISION>&path=<PATH>`, e.g.
+/// `git:https://github.com/org/repo.git?rev=abc123&path=contracts/verifier`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GitSource {
+    pub url: String,
+    /// The commit the repository is checked out at. A branch or a tag is resolved to its commit
+    /// each time the repository is checked out.
+    pub rev: String,
+    /// The directory of the package in the repository, its root by default.
+    pub path: Option<PathBuf>,
+}
+
+impl GitSource {
+    /// Parses a `modules_path` entry, returning `None` if it isn't read from a git repository.
+    pub fn parse(entry: &Path) -> Result<Option<GitSource>> {
+        let Some(source) = entry
+            .to_str()
+            .and_then(|entry| entry.strip_prefix(GIT_SOURCE_PREFIX))
+        else {
+            return Ok(None);
+        };
+        let invalid = |reason: &str| {
+            JayceError::Config(format!(
+                "Invalid git source {}: {}",
+                entry.to_str().unwrap(),
+                reason
+            ))
+        };
+        let mut url = Url::parse(source).map_err(|err| invalid(&err.to_string()))?;
+        let mut rev = None;
+        let mut path = None;
+        for (key, value) in url.query_pairs() {
+            match key.as_ref() {
+                "rev" => rev = Some(value.to_string()),
+                "path" => path = Some(PathBuf::from(value.as_ref())),
+                _ => return Err(invalid(&format!("unknown parameter '{}'", key))),
+            }
+        }
+        let rev = rev.ok_or_else(|| invalid("the revision must be pinned with 'rev'"))?;
+        if rev.starts_with('-') {
+            return Err(invalid("'rev' can't start with '-'"));
+        }
+        if path.as_ref().is_some_and(|path| {
+            path.is_absolute()
+                || path
+                    .components()
+                    .any(|component| component == Component::ParentDir)
+        }) {
+            return Err(invalid("'path' must be relative to the repository"));
+        }
+        url.set_query(None);
+        Ok(Some(GitSource {
+            url: url.to_string(),
+            rev,
+            path,
+        }))
+    }
+
+    /// Clones the repository at the commit of the revision into `cache_dir`, unless it was already,
+    /// and returns the directory of the package. The checkouts are named after their commit, so a
+    /// branch or a tag is cloned again to resolve it, and a commit hash only once.
+    pub fn checkout(&self, cache_dir: &Path) -> Result<PathBuf> {
+        if self.rev.starts_with('-') {
+            return Err(JayceError::Config(format!(
+                "Invalid git revision {}",
+                self.rev
+            )));
+        }
+        let url_hash = HashValue::sha3_256_of(self.url.as_bytes()).to_hex();
+        let url_hash = &url_hash[..16];
+        let cached_dir = cache_dir.join(format!("{}-{}", url_hash, self.rev));
+        let checkout_dir = if is_commit_hash(&self.rev) && cached_dir.is_dir() {
+            cached_dir
+        } else {
+            self.clone_into(cache_dir, url_hash)?
+        };
+        let package_dir = match &self.path {
+            Some(path) => checkout_dir.join(path),
+            None => checkout_dir.clone(),
+        };
+        if !package_dir.is_dir() {
+            return Err(JayceError::Package(format!(
+                "{} has no directory {} at {}",
+                self.url,
+                self.path
+                    .as_deref()
+                    .unwrap_or(Path::new(""))
+                    .to_str()
+                    .unwrap(),
+                self.rev
+            )));
+        }
+        // The path is relative, but may still leave the checkout through a symbolic link.
+        if !fs::canonicalize(&package_dir)?.starts_with(fs::canonicalize(&checkout_dir)?) {
+            return Err(JayceError::Package(format!(
+                "The directory {} of {} is outside of the repository",
+                self.path.as_deref().unwrap().to_str().unwrap(),
+                self.url
+            )));
+        }
+        Ok(package_dir)
+    }
+
+    /// Clones the repository into `cache_dir` and checks out the commit of the revision, in a
+    /// directory named after the URL hash and the commit, returned.
+    fn clone_into(&self, cache_dir: &Path, url_hash: &str) -> Result<PathBuf> {
+        info!("Cloning {} at {}", self.url, self.rev);
+        fs::create_dir_all(cache_dir)?;
+        // Cloned next to the checkout, so that an interrupted clone isn't reused.
+        let clone_dir = cache_dir.join(format!("{}-{}.tmp", url_hash, self.rev.replace('/', "-")));
+        if clone_dir.exists() {
+            fs::remove_dir_all(&clone_dir)?;
+        }
+        git(
+            cache_dir,
+            &[
+                "clone",
+                "--quiet",
+                "--no-checkout",
+                "--",
+                &self.url,
+                clone_dir.to_str().unwrap(),
+            ],
+        )?;
+        // Only the default branch is checked out locally, the others are remote branches.
+        let commit = git(
+            &clone_dir,
+            &["rev-parse", "--verify", &format!("{}^{{commit}}", self.rev)],
+        )
+        .or_else(|_| {
+            git(
+                &clone_dir,
+                &[
+                    "rev-parse",
+                    "--verify",
+                    &format!("origin/{}^{{commit}}", self.rev),
+                ],
+            )
+        })?;
+        let checkout_dir = cache_dir.join(format!("{}-{}", url_hash, commit));
+        if checkout_dir.is_dir() {
+            fs::remove_dir_all(&clone_dir)?;
+        } else {
+            git(&clone_dir, &["checkout", "--quiet", "--detach", &commit])?;
+            fs::rename(&clone_dir, &checkout_dir)?;
+        }
+        Ok(checkout_dir)
+    }
+}
+
+/// Whether a revision is a full commit hash, rather than a branch, a tag or an abbreviated hash.
+fn is_commit_hash(rev: &str) -> bool {
+    rev.len() == 40 && rev.bytes().all(|byte| byte.is_ascii_hexdigit())
+}
+
+/// Returns the local directory of a `modules_path` entry, checking out the entries read from a
+/// git repository into the cache directory of jayce.
+pub fn resolve_git_source(entry: &Path) -> Result<PathBuf> {
+    let Some(source) = GitSource::parse(entry)? else {
+        return Ok(entry.to_path_buf());
+    };
+    let cache_dir = cache_dir()
+        .ok_or_else(|| JayceError::Config("No cache directory to clone into".to_string()))?;
+    source.checkout(&cache_dir.join("git"))
+}
+
+/// Whether a `modules_path` entry is read from a git repository.
+pub fn is_git_source(entry: &Path) -> bool {
+    entry
+        .to_str()
+        .is_some_and(|entry| entry.starts_with(GIT_SOURCE_PREFIX))
+}
+
+/// Returns the commit a revision of a remote repository points to: the revision itself when it
+/// is a commit hash, otherwise the commit of the branch or tag it names.
+pub fn remote_commit(url: &str, rev: &str) -> Result<String> {
+    if is_commit_hash(rev) {
+        return Ok(rev.to_lowercase());
+    }
+    git(&std::env::temp_dir(), &["ls-remote", "--", url, rev])?
+        .lines()
+        .next()
+        .and_then(|line| line.split_whitespace().next())
+        .map(str::to_string)
+        .ok_or_else(|| JayceError::Config(format!("{} has no revision {}", url, rev)))
+}
+
+/// Runs git in `dir` and returns its output.
+pub(crate) fn git(dir: &Path, args: &[&str]) -> Result<String> {
+    let output = Command::new("git").arg("-C").arg(dir).args(args).output()?;
+    if !output.status.success() {
+        return Err(JayceError::Config(format!(
+            "`git {}` failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Returns the commit checked out in the repository containing `dir`, if it is in one.
+pub fn head_commit(dir: &Path) -> Option<String> {
+    git(dir, &["rev-parse", "HEAD"]).ok()
+}
+
+/// Returns the root of the repository containing `dir`, if it is in one.
+pub fn repository_root(dir: &Path) -> Option<PathBuf> {
+    git(dir, &["rev-parse", "--show-toplevel"])
+        .ok()
+        .map(PathBuf::from)
+}
+
+/// Returns the files of the repository containing `dir` that changed since `commit`, including
+/// the uncommitted and untracked ones.
+pub fn changed_files(dir: &Path, commit: &str) -> Result<Vec<PathBuf>> {
+    let root = PathBuf::from(git(dir, &["rev-parse", "--show-toplevel"])?);
+    let changed = git(dir, &["diff", "--name-only", commit, "--"])?;
+    let untracked = git(&root, &["ls-files", "--others", "--exclude-standard"])?;
+    Ok(changed
+        .lines()
+        .chain(untracked.lines())
+        .filter(|line| !line.is_empty())
+        .map(|line| root.join(line))
+        .collect())
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+    use std::path::{Path, PathBuf};
+
+    use url::Url;
+
+    use crate::git::{changed_files, git, head_commit, GitSource};
+
+    fn commit_all(root: &Path) {
+        git(root, &["add", "-A"]).unwrap();
+        git(
+            root,
+            &[
+                "-c",
+                "user.name=jayce",
+                "-c",
+                "user.email=jayce@example.com",
+                "commit",
+                "-qm",
+                "init",
+            ],
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_changed_files() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let root = temp_dir.path();
+        fs::create_dir_all(root.join("cpu")).unwrap();
+        fs::create_dir_all(root.join("libs")).unwrap();
+        fs::write(root.join("cpu/cpu.move"), "module cpu {}").unwrap();
+        fs::write(root.join("libs/libs.move"), "module libs {}").unwrap();
+        git(root, &["init", "-q"]).unwrap();
+        commit_all(root);
+        let commit = head_commit(root).unwrap();
+
+        fs::write(root.join("cpu/cpu.move"), "module cpu { fun f() {} }").unwrap();
+        fs::write(root.join("cpu/new.move"), "module new {}").unwrap();
+        let root_path = root.canonicalize().unwrap();
+        let mut changed = changed_files(&root.join("libs"), &commit).unwrap();
+        changed.sort();
+
+        assert_eq!(
+            changed,
+            [
+                root_path.join("cpu/cpu.move"),
+                root_path.join("cpu/new.move")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_git_source() {
+        assert_eq!(
+            GitSource::parse(Path::new(
+                "git:https://github.com/org/repo.git?rev=abc123&path=contracts/verifier"
+            ))
+            .unwrap(),
+            Some(GitSource {
+                url: "https://github.com/org/repo.git".to_string(),
+                rev: "abc123".to_string(),
+                path: Some(PathBuf::from("contracts/verifier")),
+            })
+        );
+        assert_eq!(
+            GitSource::parse(Path::new("contracts/verifier")).unwrap(),
+            None
+        );
+        assert!(GitSource::parse(Path::new("git:https://github.com/org/repo.git")).is_err());
+        assert!(GitSource::parse(Path::new(
+            "git:https://github.com/org/repo.git?rev=a&path=../x"
+        ))
+        .is_err());
+        assert!(GitSource::parse(Path::new(
+            "git:https://github.com/org/repo.git?rev=--upload-pack=x"
+        ))
+        .is_err());
+    }
+
+    #[test]
+    fn test_checkout_git_source() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let root = temp_dir.path();
+        let repo = root.join("repo");
+        fs::create_dir_all(repo.join("contracts/verifier")).unwrap();
+        fs::write(repo.join("contracts/verifier/Move.toml"), "v1").unwrap();
+        git(&repo, &["init", "-q"]).unwrap();
+        commit_all(&repo);
+        let commit = head_commit(&repo).unwrap();
+        git(&repo, &["tag", "v1"]).unwrap();
+        fs::write(repo.join("contracts/verifier/Move.toml"), "v2").unwrap();
+        commit_all(&repo);
+
+        let source = GitSource {
+            url: Url::from_directory_path(&repo).unwrap().to_string(),
+            rev: commit.clone(),
+            path: Some(PathBuf::from("contracts/verifier")),
+        };
+        let package_dir = source.checkout(&root.join("cache")).unwrap();
+        assert_eq!(
+            fs::read_to_string(package_dir.join("Move.toml")).unwrap(),
+            "v1"
+        );
+        assert_eq!(head_commit(&package_dir), Some(commit));
+        // The checkout is reused, and named after the commit of a tag.
+        assert_eq!(source.checkout(&root.join("cache")).unwrap(), package_dir);
+        let tag_source = GitSource {
+            rev: "v1".to_string(),
+            ..source.clone()
+        };
+        assert_eq!(
+            tag_source.checkout(&root.join("cache")).unwrap(),
+            package_dir
+        );
+        let option_source = GitSource {
+            rev: "--upload-pack=x".to_string(),
+            ..source
+        };
+        assert!(option_source.checkout(&root.join("cache")).is_err());
+    }
+}
@@ -0,0 +1,176 @@
+use std::env;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use aptos_sdk::move_types::account_address::AccountAddress;
+
+use crate::deploy_report::{DeployReport, DeployStatus};
+use crate::error::Result;
+use crate::tui::{print_stderr, print_stdout};
+
+/// Set when stdout is reserved for the events of `--json-events`.
+static STDOUT_RESERVED: AtomicBool = AtomicBool::new(false);
+
+/// Whether jayce runs in a GitHub Actions workflow.
+pub fn detected() -> bool {
+    env::var("GITHUB_ACTIONS").is_ok_and(|value| value == "true")
+}
+
+/// Writes the workflow commands to stderr from now on, stdout being reserved for the events of
+/// `--json-events`. The runner reads the commands from both.
+pub fn reserve_stdout() {
+    STDOUT_RESERVED.store(true, Ordering::SeqCst);
+}
+
+/// Prints a workflow command, e.g. an [`annotation`].
+pub fn print_command(command: &str) {
+    if STDOUT_RESERVED.load(Ordering::SeqCst) {
+        print_stderr(command);
+    } else {
+        print_stdout(command);
+    }
+}
+
+/// Formats a workflow command annotating the run, e.g. `::error title=...::message`.
+pub fn annotation(level: &str, title: &str, message: &str) -> String {
+    format!(
+        "::{} title={}::{}",
+        level,
+        escape(title).replace(':', "%3A").replace(',', "%2C"),
+        escape(message)
+    )
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+/// Annotates the workflow run with the outcome of every package of the report, appends a table of
+/// the deployed packages to the step summary and exports their addresses as step outputs, keyed
+/// by address name.
+pub fn write_github_actions_output(report: &DeployReport) -> Result<()> {
+    for tx_report in &report.info {
+        match tx_report.status {
+            DeployStatus::Deployed => print_command(&annotation(
+                "notice",
+                &format!("Deployed {}", tx_report.address_name),
+                &format!(
+                    "{} deployed on {} at {}",
+                    tx_report.address_name, report.network, tx_report.deployed_at
+                ),
+            )),
+            // Packages already up to date are skipped at their address.
+            DeployStatus::Skipped if tx_report.deployed_at != AccountAddress::ZERO => {
+                print_command(&annotation(
+                    "notice",
+                    &format!("Skipped {}", tx_report.address_name),
+                    &format!(
+                        "{} is already up to date on {} at {}",
+                        tx_report.address_name, report.network, tx_report.deployed_at
+                    ),
+                ))
+            }
+            DeployStatus::Failed | DeployStatus::Skipped => print_command(&annotation(
+                "error",
+                &format!("Failed to deploy {}", tx_report.address_name),
+                tx_report.error.as_deref().unwrap_or_default(),
+            )),
+        }
+    }
+    if let Ok(summary_path) = env::var("GITHUB_STEP_SUMMARY") {
+        append(Path::new(&summary_path), &step_summary(report))?;
+    }
+    if let Ok(output_path) = env::var("GITHUB_OUTPUT") {
+        let outputs: String = report
+            .info
+            .iter()
+            .filter(|tx_report| tx_report.status == DeployStatus::Deployed)
+            .map(|tx_report| format!("{}={}\n", tx_report.address_name, tx_report.deployed_at))
+            .collect();
+        append(Path::new(&output_path), &outputs)?;
+    }
+    Ok(())
+}
+
+/// Renders the Markdown table of the step summary.
+fn step_summary(report: &DeployReport) -> String {
+    let mut summary = format!(
+        "### Deployment to {}\n\n\
+        | Address name | Address | Status | Gas used |\n\
+        |--------------|---------|--------|----------|\n",
+        report.network
+    );
+    for tx_report in &report.info {
+        summary.push_str(&format!(
+            "| {} | {} | {} | {} |\n",
+            tx_report.address_name,
+            match &tx_report.explorer_url {
+                Some(explorer_url) => format!("[{}]({})", tx_report.deployed_at, explorer_url),
+                None => tx_report.deployed_at.to_string(),
+            },
+            tx_report.status,
+            tx_report.gas_summary().gas_used
+        ));
+    }
+    summary.push('\n');
+    summary
+}
+
+fn append(path: &Path, content: &str) -> Result<()> {
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?
+        .write_all(content.as_bytes())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::BTreeMap;
+    use std::path::PathBuf;
+
+    use aptos_sdk::move_types::account_address::AccountAddress;
+
+    use crate::deploy_config::AptosNetwork;
+    use crate::deploy_report::{DeployReport, DeployStatus, TxReport};
+    use crate::github_actions::{annotation, step_summary};
+
+    #[test]
+    fn test_annotation() {
+        assert_eq!(
+            annotation("error", "Failed: cpu, lib", "out of gas\n50%"),
+            "::error title=Failed%3A cpu%2C lib::out of gas%0A50%25"
+        );
+    }
+
+    #[test]
+    fn test_step_summary() {
+        let report = DeployReport {
+            account: AccountAddress::ONE,
+            fee_payer: None,
+            network: AptosNetwork::Testnet,
+            gas: None,
+            git_commit: None,
+            git_commits: BTreeMap::new(),
+            signature: None,
+            info: vec![TxReport::not_deployed(
+                &PathBuf::from("cpu"),
+                "cpu_addr",
+                DeployStatus::Failed,
+                "out of gas".to_string(),
+            )],
+        };
+        let summary = step_summary(&report);
+        assert!(summary.starts_with("### Deployment to testnet\n"));
+        assert!(summary.contains(&format!(
+            "| cpu_addr | {} | failed | 0 |",
+            AccountAddress::ZERO
+        )));
+    }
+}
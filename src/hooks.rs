@@ -0,0 +1,145 @@
+use std::collections::BTreeMap;
+use std::process::Command;
+
+use aptos_sdk::move_types::account_address::AccountAddress;
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+use crate::deploy_config::DeployConfig;
+use crate::entry_function::{run_script, ScriptCall};
+use crate::error::{JayceError, Result};
+use crate::tasks::deploy_contracts::{gas_args, signer_args};
+
+/// A command run before or after a deployment: either a shell command, or a Move script.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum Hook {
+    Command(String),
+    Script(ScriptCall),
+}
+
+/// Hooks run before compiling and after publishing, either around the whole deployment or
+/// around a single package.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct Hooks {
+    pub pre_deploy: Vec<Hook>,
+    pub post_deploy: Vec<Hook>,
+}
+
+impl Hooks {
+    /// The number of transactions sent by the script hooks.
+    pub fn script_count(&self) -> u64 {
+        self.pre_deploy
+            .iter()
+            .chain(&self.post_deploy)
+            .filter(|hook| matches!(hook, Hook::Script(_)))
+            .count() as u64
+    }
+}
+
+/// The package a hook is run for.
+pub struct HookPackage<'a> {
+    pub address_name: &'a str,
+    /// The address of the package, once it is published.
+    pub deployed_at: Option<AccountAddress>,
+}
+
+/// Runs hooks in order, stopping at the first failure. Shell commands get the deployed addresses
+/// as `JAYCE_ADDRESS_<ADDRESS NAME>` environment variables, along with `JAYCE_NETWORK`, and
+/// `JAYCE_ADDRESS_NAME` and `JAYCE_DEPLOYED_AT` for package hooks.
+pub async fn run_hooks(
+    hooks: &[Hook],
+    config: &DeployConfig,
+    deployed_addresses: &BTreeMap<String, AccountAddress>,
+    package: Option<&HookPackage<'_>>,
+) -> Result<()> {
+    for hook in hooks {
+        match hook {
+            Hook::Command(command) => {
+                info!("Running hook `{}`...", command);
+                let mut process = Command::new("sh");
+                process
+                    .arg("-c")
+                    .arg(command)
+                    .envs(hook_env(config, deployed_addresses, package));
+                let status = process.status()?;
+                if !status.success() {
+                    return Err(JayceError::Hook(format!(
+                        "`{}` exited with {}",
+                        command, status
+                    )));
+                }
+            }
+            Hook::Script(call) => {
+                info!(
+                    "Running script {} of {}...",
+                    call.script,
+                    call.package_dir.to_str().unwrap()
+                );
+                let tx_info = run_script(
+                    call,
+                    &config.known_addresses(deployed_addresses),
+                    &signer_args(config)?,
+                    &gas_args(config),
+                    config.yes,
+                )
+                .await
+                .map_err(|err| JayceError::Hook(format!("script {}: {}", call.script, err)))?;
+                info!(
+                    "Script {} executed: {}",
+                    call.script, tx_info.transaction_hash
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+fn hook_env(
+    config: &DeployConfig,
+    deployed_addresses: &BTreeMap<String, AccountAddress>,
+    package: Option<&HookPackage<'_>>,
+) -> Vec<(String, String)> {
+    let mut env = vec![("JAYCE_NETWORK".to_string(), config.network_name())];
+    env.extend(deployed_addresses.iter().map(|(address_name, address)| {
+        (
+            format!("JAYCE_ADDRESS_{}", address_name.to_uppercase()),
+            address.to_string(),
+        )
+    }));
+    if let Some(package) = package {
+        env.push((
+            "JAYCE_ADDRESS_NAME".to_string(),
+            package.address_name.to_string(),
+        ));
+        if let Some(deployed_at) = package.deployed_at {
+            env.push(("JAYCE_DEPLOYED_AT".to_string(), deployed_at.to_string()));
+        }
+    }
+    env
+}
+
+#[cfg(test)]
+mod test {
+    use crate::hooks::{Hook, Hooks};
+
+    #[test]
+    fn test_deserialize_hooks() {
+        let hooks: Hooks = toml::from_str(
+            r#"
+            pre_deploy = ["make bindings"]
+            post_deploy = [
+                "curl -X POST https://example.com/deployed",
+                { package_dir = "scripts", script = "init", args = ["address:cpu_addr"] },
+            ]
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            hooks.pre_deploy,
+            vec![Hook::Command("make bindings".to_string())]
+        );
+        assert!(matches!(&hooks.post_deploy[1], Hook::Script(call) if call.script == "init"));
+    }
+}
@@ -0,0 +1,287 @@
+use std::fs;
+use std::path::PathBuf;
+
+use aptos_sdk::crypto::ed25519::{Ed25519PublicKey, Ed25519Signature};
+use aptos_sdk::crypto::{Signature, ValidCryptoMaterialStringExt};
+use reqwest::{Certificate, Identity};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use url::{Host, Url};
+
+use crate::error::{JayceError, Result};
+use crate::kms::{send, signature_from_bytes, string_field};
+use crate::signer::Signer;
+
+/// The connection to a signing service over HTTP, from a `[signer]` table of kind `http`.
+///
+/// The service answers `GET <url>/public_key` with `{"public_key": "0x..."}` and
+/// `POST <url>/sign` of `{"message": "0x..."}` with `{"signature": "0x..."}`, the message being
+/// the BCS signing message of a transaction. The service is reached over `https`, or over `http`
+/// only on the loopback interface, since the bearer token would be sent in clear.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct HttpSignerConfig {
+    pub url: String,
+    /// Sent in the `Authorization: Bearer` header.
+    pub bearer_token: Option<String>,
+    /// PEM certificate of the client, authenticating it to the service with mutual TLS.
+    pub client_cert: Option<PathBuf>,
+    /// PEM PKCS #8 private key of `client_cert`.
+    pub client_key: Option<PathBuf>,
+    /// PEM certificate trusted in addition to the system roots, e.g. of an internal CA.
+    pub ca_cert: Option<PathBuf>,
+}
+
+/// Signs with a key held by a signing service over HTTP.
+pub struct HttpSigner {
+    url: String,
+    bearer_token: Option<String>,
+    public_key: Ed25519PublicKey,
+    client: reqwest::Client,
+}
+
+impl HttpSigner {
+    pub async fn connect(config: &HttpSignerConfig) -> Result<HttpSigner> {
+        check_url(&config.url)?;
+        let mut builder = reqwest::Client::builder();
+        match (&config.client_cert, &config.client_key) {
+            (Some(client_cert), Some(client_key)) => {
+                let identity =
+                    Identity::from_pkcs8_pem(&fs::read(client_cert)?, &fs::read(client_key)?)
+                        .map_err(|err| {
+                            JayceError::Config(format!("Invalid client certificate: {}", err))
+                        })?;
+                builder = builder.identity(identity);
+            }
+            (None, None) => {}
+            _ => {
+                return Err(JayceError::Config(
+                    "The client certificate and key of the signer must be set together".to_string(),
+                ))
+            }
+        }
+        if let Some(ca_cert) = &config.ca_cert {
+            let certificate = Certificate::from_pem(&fs::read(ca_cert)?)
+                .map_err(|err| JayceError::Config(format!("Invalid CA certificate: {}", err)))?;
+            builder = builder.add_root_certificate(certificate);
+        }
+        let client = builder
+            .build()
+            .map_err(|err| JayceError::Signer(err.to_string()))?;
+        let url = config.url.trim_end_matches('/').to_string();
+        let response = send(
+            "Signing service",
+            authorize(
+                client.get(format!("{}/public_key", url)),
+                &config.bearer_token,
+            ),
+        )
+        .await?;
+        let public_key = Ed25519PublicKey::from_encoded_string(string_field(
+            "Signing service",
+            &response,
+            "/public_key",
+        )?)
+        .map_err(|err| JayceError::Signer(format!("Invalid public key: {}", err)))?;
+        Ok(HttpSigner {
+            url,
+            bearer_token: config.bearer_token.clone(),
+            public_key,
+            client,
+        })
+    }
+}
+
+/// Checks that the signing service is reached over `https`, unless it is on the loopback
+/// interface.
+fn check_url(url: &str) -> Result<()> {
+    let parsed = Url::parse(url)
+        .map_err(|err| JayceError::Config(format!("Invalid signer url {}: {}", url, err)))?;
+    let loopback = match parsed.host() {
+        Some(Host::Domain(domain)) => domain == "localhost",
+        Some(Host::Ipv4(ip)) => ip.is_loopback(),
+        Some(Host::Ipv6(ip)) => ip.is_loopback(),
+        None => false,
+    };
+    match parsed.scheme() {
+        "https" => Ok(()),
+        "http" if loopback => Ok(()),
+        _ => Err(JayceError::Config(format!(
+            "The signer url {} must use https, unless the service is on the loopback interface",
+            url
+        ))),
+    }
+}
+
+fn authorize(
+    request: reqwest::RequestBuilder,
+    bearer_token: &Option<String>,
+) -> reqwest::RequestBuilder {
+    match bearer_token {
+        Some(bearer_token) => request.bearer_auth(bearer_token),
+        None => request,
+    }
+}
+
+impl Signer for HttpSigner {
+    fn public_key(&self) -> &Ed25519PublicKey {
+        &self.public_key
+    }
+
+    async fn sign_message(&self, message: &[u8]) -> Result<Ed25519Signature> {
+        let response = send(
+            "Signing service",
+            authorize(
+                self.client.post(format!("{}/sign", self.url)),
+                &self.bearer_token,
+            )
+            .json(&json!({ "message": format!("0x{}", hex::encode(message)) })),
+        )
+        .await?;
+        let signature = string_field("Signing service", &response, "/signature")?;
+        let signature = signature_from_bytes(
+            &hex::decode(signature.trim_start_matches("0x"))
+                .map_err(|err| JayceError::Signer(format!("Invalid signature: {}", err)))?,
+        )?;
+        // A signature of another key or message would only fail once submitted.
+        signature
+            .verify_arbitrary_msg(message, &self.public_key)
+            .map_err(|_| {
+                JayceError::Signer(
+                    "The signing service returned a signature that doesn't match its public key"
+                        .to_string(),
+                )
+            })?;
+        Ok(signature)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use aptos_sdk::crypto::ed25519::{Ed25519PrivateKey, Ed25519PublicKey};
+    use aptos_sdk::crypto::{Signature, SigningKey, Uniform, ValidCryptoMaterialStringExt};
+    use rand::rngs::OsRng;
+    use serde_json::{json, Value};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    use crate::http_signer::{check_url, HttpSigner, HttpSignerConfig};
+    use crate::signer::Signer;
+
+    /// Serves `public_key` then one signature of `private_key`, checking the bearer token.
+    async fn serve_signer(
+        listener: TcpListener,
+        public_key: Ed25519PublicKey,
+        private_key: Ed25519PrivateKey,
+    ) {
+        for _ in 0..2 {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut request = vec![];
+            let mut buffer = [0u8; 4096];
+            let (head, body) = loop {
+                let read = stream.read(&mut buffer).await.unwrap();
+                request.extend_from_slice(&buffer[..read]);
+                let text = String::from_utf8_lossy(&request).to_string();
+                if let Some((head, body)) = text.split_once("\r\n\r\n") {
+                    let length = head
+                        .lines()
+                        .find_map(|line| {
+                            line.to_lowercase()
+                                .strip_prefix("content-length: ")
+                                .map(str::to_string)
+                        })
+                        .map_or(0, |length| length.parse().unwrap());
+                    if body.len() >= length {
+                        break (head.to_string(), body.to_string());
+                    }
+                }
+            };
+            assert!(head.to_lowercase().contains("authorization: bearer secret"));
+            let response = if head.starts_with("GET /public_key") {
+                json!({ "public_key": public_key.to_encoded_string().unwrap() })
+            } else {
+                let body: Value = serde_json::from_str(&body).unwrap();
+                let message =
+                    hex::decode(body["message"].as_str().unwrap().trim_start_matches("0x"))
+                        .unwrap();
+                let signature = private_key.sign_arbitrary_message(&message);
+                json!({ "signature": signature.to_encoded_string().unwrap() })
+            }
+            .to_string();
+            stream
+                .write_all(
+                    format!(
+                        "HTTP/1.1 200 OK\r\n\
+                        content-type: application/json\r\n\
+                        content-length: {}\r\n\
+                        connection: close\r\n\r\n{}",
+                        response.len(),
+                        response
+                    )
+                    .as_bytes(),
+                )
+                .await
+                .unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_http_signer() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let private_key = Ed25519PrivateKey::generate_for_testing();
+        let public_key = Ed25519PublicKey::from(&private_key);
+        let server = tokio::spawn(serve_signer(listener, public_key.clone(), private_key));
+
+        let signer = HttpSigner::connect(&HttpSignerConfig {
+            url,
+            bearer_token: Some("secret".to_string()),
+            client_cert: None,
+            client_key: None,
+            ca_cert: None,
+        })
+        .await
+        .unwrap();
+        assert_eq!(signer.public_key(), &public_key);
+        let signature = signer.sign_message(b"message").await.unwrap();
+        assert!(signature
+            .verify_arbitrary_msg(b"message", &public_key)
+            .is_ok());
+        server.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_http_signer_mismatched_signature() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let public_key = Ed25519PublicKey::from(&Ed25519PrivateKey::generate(&mut OsRng));
+        let server = tokio::spawn(serve_signer(
+            listener,
+            public_key,
+            Ed25519PrivateKey::generate(&mut OsRng),
+        ));
+
+        let signer = HttpSigner::connect(&HttpSignerConfig {
+            url,
+            bearer_token: Some("secret".to_string()),
+            client_cert: None,
+            client_key: None,
+            ca_cert: None,
+        })
+        .await
+        .unwrap();
+        assert!(signer.sign_message(b"message").await.is_err());
+        server.await.unwrap();
+    }
+
+    #[test]
+    fn test_check_url() {
+        assert!(check_url("https://signer.example.com").is_ok());
+        assert!(check_url("http://127.0.0.1:8080").is_ok());
+        assert!(check_url("http://[::1]:8080").is_ok());
+        assert!(check_url("http://localhost:8080/").is_ok());
+        assert!(check_url("http://signer.example.com").is_err());
+        assert!(check_url("http://10.0.0.1").is_err());
+        assert!(check_url("ftp://localhost").is_err());
+        assert!(check_url("signer").is_err());
+    }
+}
@@ -0,0 +1,243 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use aptos_sdk::bcs;
+use aptos_sdk::crypto::ed25519::{Ed25519PrivateKey, Ed25519PublicKey, Ed25519Signature};
+use aptos_sdk::crypto::{Uniform, ValidCryptoMaterialStringExt};
+use aptos_sdk::move_types::account_address::AccountAddress;
+use aptos_sdk::types::keyless::{Groth16Proof, Pepper, ZeroKnowledgeSig, ZKP};
+use aptos_sdk::types::transaction::authenticator::EphemeralSignature;
+use aptos_sdk::types::transaction::{RawTransaction, SignedTransaction};
+use aptos_sdk::types::{EphemeralKeyPair, KeylessAccount, LocalAccount};
+use rand::rngs::OsRng;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::error::{JayceError, Result};
+use crate::kms::{send, string_field};
+use crate::redact::register_secret;
+use crate::signer::Signer;
+use crate::tasks::account::write_private_key_file;
+
+/// The `max_exp_horizon_secs` of the keyless configuration of the Aptos networks: how long after
+/// the JWT was issued the ephemeral key can be used at most.
+const EXP_HORIZON_SECS: u64 = 10_000_000;
+
+const BLINDER_LENGTH: usize = 31;
+
+/// A keyless account, derived from the identity of a JWT of an OIDC provider, from a `[signer]`
+/// table of kind `keyless`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct KeylessConfig {
+    /// The JWT, whose `nonce` claim must be the nonce of the ephemeral key.
+    pub jwt: String,
+    /// The ephemeral key written by `jayce key ephemeral`.
+    pub ephemeral_key_path: PathBuf,
+    /// The claim of the JWT identifying the account.
+    #[serde(default = "default_uid_key")]
+    pub uid_key: String,
+    /// The pepper service of the network, e.g.
+    /// `https://api.devnet.aptoslabs.com/keyless/pepper/v0/fetch`.
+    pub pepper_url: String,
+    /// The prover service of the network, e.g.
+    /// `https://api.devnet.aptoslabs.com/keyless/prover/v0/prove`.
+    pub prover_url: String,
+}
+
+fn default_uid_key() -> String {
+    "sub".to_string()
+}
+
+/// The ephemeral key signing the transactions of a keyless account until it expires.
+#[derive(Serialize, Deserialize)]
+struct EphemeralKeyFile {
+    private_key: String,
+    /// Unix timestamp the key expires at, in seconds.
+    expiry_date_secs: u64,
+    /// Hex-encoded blinder of the nonce.
+    blinder: String,
+}
+
+#[derive(Deserialize)]
+struct ProverResponse {
+    proof: Groth16Proof,
+    training_wheels_signature: EphemeralSignature,
+}
+
+/// Generates an ephemeral key valid for `expiry_secs` and writes it to `path`. Returns the nonce
+/// to request the JWT of the keyless account with.
+pub fn generate_ephemeral_key(path: &Path, expiry_secs: u64) -> Result<String> {
+    let private_key = Ed25519PrivateKey::generate(&mut OsRng);
+    let blinder: Vec<u8> = (0..BLINDER_LENGTH).map(|_| OsRng.gen()).collect();
+    let file = EphemeralKeyFile {
+        private_key: private_key.to_encoded_string()?,
+        expiry_date_secs: now_secs() + expiry_secs,
+        blinder: hex::encode(&blinder),
+    };
+    let key_pair = ephemeral_key_pair(private_key, file.expiry_date_secs, blinder)?;
+    write_private_key_file(path, &serde_json::to_string_pretty(&file)?)?;
+    Ok(key_pair.nonce().to_string())
+}
+
+/// Signs the transactions of a keyless account with its ephemeral key and the zero-knowledge proof
+/// of its JWT.
+pub struct KeylessSigner {
+    account: LocalAccount,
+    ephemeral_public_key: Ed25519PublicKey,
+}
+
+impl KeylessSigner {
+    /// Fetches the pepper of the account and the proof of the JWT from the keyless services.
+    pub async fn connect(config: &KeylessConfig) -> Result<KeylessSigner> {
+        register_secret(&config.jwt);
+        let file: EphemeralKeyFile =
+            serde_json::from_str(&fs::read_to_string(&config.ephemeral_key_path)?)?;
+        register_secret(&file.private_key);
+        if file.expiry_date_secs <= now_secs() {
+            return Err(JayceError::Signer(format!(
+                "The ephemeral key {} expired, generate another one with `jayce key ephemeral`",
+                config.ephemeral_key_path.to_str().unwrap()
+            )));
+        }
+        let private_key = Ed25519PrivateKey::from_encoded_string(&file.private_key)
+            .map_err(|err| JayceError::Signer(format!("Invalid ephemeral key: {}", err)))?;
+        let ephemeral_public_key = Ed25519PublicKey::from(&private_key);
+        let blinder = hex::decode(&file.blinder)
+            .map_err(|err| JayceError::Signer(format!("Invalid ephemeral key blinder: {}", err)))?;
+        let key_pair = ephemeral_key_pair(private_key, file.expiry_date_secs, blinder)?;
+
+        let client = reqwest::Client::new();
+        let mut request = json!({
+            "jwt_b64": config.jwt,
+            "epk": hex::encode(bcs::to_bytes(key_pair.public_key()).map_err(anyhow::Error::from)?),
+            "exp_date_secs": file.expiry_date_secs,
+            "epk_blinder": file.blinder,
+            "uid_key": config.uid_key,
+        });
+        let response = send(
+            "The pepper service",
+            client.post(&config.pepper_url).json(&request),
+        )
+        .await?;
+        let pepper = string_field("The pepper service", &response, "/pepper")?.to_string();
+        let pepper_bytes: [u8; BLINDER_LENGTH] = hex::decode(pepper.trim_start_matches("0x"))
+            .ok()
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or_else(|| JayceError::Signer(format!("Invalid pepper: {}", pepper)))?;
+
+        request["pepper"] = json!(pepper);
+        request["exp_horizon_secs"] = json!(EXP_HORIZON_SECS);
+        let response = send(
+            "The prover service",
+            client.post(&config.prover_url).json(&request),
+        )
+        .await?;
+        let response: ProverResponse = serde_json::from_value(response).map_err(|err| {
+            JayceError::Signer(format!("The prover service returned no proof: {}", err))
+        })?;
+        let proof = ZeroKnowledgeSig {
+            proof: ZKP::Groth16(response.proof),
+            exp_horizon_secs: EXP_HORIZON_SECS,
+            extra_field: None,
+            override_aud_val: None,
+            training_wheels_signature: Some(response.training_wheels_signature),
+        };
+
+        let account = KeylessAccount::new_from_jwt(
+            &config.jwt,
+            key_pair,
+            Some(&config.uid_key),
+            Pepper::new(pepper_bytes),
+            proof,
+        )
+        .map_err(|err| JayceError::Signer(format!("Invalid JWT: {}", err)))?;
+        let address = account.authentication_key().account_address();
+        Ok(KeylessSigner {
+            account: LocalAccount::new_keyless(address, account, 0),
+            ephemeral_public_key,
+        })
+    }
+}
+
+impl Signer for KeylessSigner {
+    /// The public key of the ephemeral key, which isn't the key of the account.
+    fn public_key(&self) -> &Ed25519PublicKey {
+        &self.ephemeral_public_key
+    }
+
+    async fn sign_message(&self, _message: &[u8]) -> Result<Ed25519Signature> {
+        Err(JayceError::Signer(
+            "A keyless account only signs transactions".to_string(),
+        ))
+    }
+
+    fn address(&self) -> AccountAddress {
+        self.account.address()
+    }
+
+    async fn sign_transaction(
+        &self,
+        raw_transaction: RawTransaction,
+        secondary_signers: &[LocalAccount],
+        fee_payer: Option<&LocalAccount>,
+    ) -> Result<SignedTransaction> {
+        if fee_payer.is_some() || !secondary_signers.is_empty() {
+            return Err(JayceError::Signer(
+                "Keyless accounts don't support fee payers nor secondary signers".to_string(),
+            ));
+        }
+        Ok(self.account.sign_transaction(raw_transaction))
+    }
+
+    fn simulation_transaction(
+        &self,
+        _raw_transaction: RawTransaction,
+        _secondary_signers: &[LocalAccount],
+        _fee_payer: Option<&LocalAccount>,
+    ) -> Result<SignedTransaction> {
+        Err(JayceError::Signer(
+            "Transactions of keyless accounts can't be simulated, unset simulate_first and \
+            max_fee"
+                .to_string(),
+        ))
+    }
+}
+
+fn ephemeral_key_pair(
+    private_key: Ed25519PrivateKey,
+    expiry_date_secs: u64,
+    blinder: Vec<u8>,
+) -> Result<EphemeralKeyPair> {
+    EphemeralKeyPair::new(private_key, expiry_date_secs, blinder)
+        .map_err(|err| JayceError::Signer(format!("Invalid ephemeral key: {}", err)))
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+
+    use crate::keyless::{generate_ephemeral_key, EphemeralKeyFile};
+
+    #[test]
+    fn test_generate_ephemeral_key() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("ephemeral_key.json");
+
+        let nonce = generate_ephemeral_key(&path, 3600).unwrap();
+        assert!(!nonce.is_empty());
+        let file: EphemeralKeyFile =
+            serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(hex::decode(file.blinder).unwrap().len(), 31);
+        // The key isn't overwritten.
+        assert!(generate_ephemeral_key(&path, 3600).is_err());
+    }
+}
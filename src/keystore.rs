@@ -0,0 +1,197 @@
+use std::env;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+
+use aes_gcm::aead::rand_core::RngCore;
+use aes_gcm::aead::{Aead, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Nonce};
+use aptos_sdk::move_types::account_address::AccountAddress;
+use aptos_sdk::types::LocalAccount;
+use dialoguer::Password;
+use serde::{Deserialize, Serialize};
+
+use crate::color::prompt_theme;
+use crate::error::{JayceError, Result};
+
+/// Environment variable holding the keystore passphrase, used instead of prompting for it.
+pub const KEYSTORE_PASSPHRASE_ENV: &str = "JAYCE_KEYSTORE_PASSPHRASE";
+
+const KEYSTORE_VERSION: u32 = 1;
+const SCRYPT_LOG_N: u8 = 15;
+const SCRYPT_R: u32 = 8;
+const SCRYPT_P: u32 = 1;
+
+/// A private key encrypted with AES-256-GCM, using a key derived from a passphrase with scrypt.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Keystore {
+    pub version: u32,
+    pub address: AccountAddress,
+    pub kdf: KdfParams,
+    pub nonce: String,
+    pub ciphertext: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct KdfParams {
+    pub log_n: u8,
+    pub r: u32,
+    pub p: u32,
+    pub salt: String,
+}
+
+impl Keystore {
+    pub fn encrypt(private_key: &str, passphrase: &str) -> Result<Keystore> {
+        Self::encrypt_with_log_n(private_key, passphrase, SCRYPT_LOG_N)
+    }
+
+    fn encrypt_with_log_n(private_key: &str, passphrase: &str, log_n: u8) -> Result<Keystore> {
+        let address = LocalAccount::from_private_key(private_key, 0)
+            .map_err(|err| JayceError::Config(format!("Invalid private key: {}", err)))?
+            .address();
+        let mut salt = [0u8; 32];
+        OsRng.fill_bytes(&mut salt);
+        let mut nonce = [0u8; 12];
+        OsRng.fill_bytes(&mut nonce);
+        let kdf = KdfParams {
+            log_n,
+            r: SCRYPT_R,
+            p: SCRYPT_P,
+            salt: hex::encode(salt),
+        };
+
+        let ciphertext = cipher(&kdf, passphrase)?
+            .encrypt(Nonce::from_slice(&nonce), private_key.as_bytes())
+            .map_err(|_| JayceError::Config("Failed to encrypt the private key".to_string()))?;
+        Ok(Keystore {
+            version: KEYSTORE_VERSION,
+            address,
+            kdf,
+            nonce: hex::encode(nonce),
+            ciphertext: hex::encode(ciphertext),
+        })
+    }
+
+    pub fn decrypt(&self, passphrase: &str) -> Result<String> {
+        let nonce = decode_hex(&self.nonce)?;
+        // `Nonce::from_slice` panics on a nonce of another length.
+        if nonce.len() != 12 {
+            return Err(JayceError::Config("The keystore is corrupted".to_string()));
+        }
+        let ciphertext = decode_hex(&self.ciphertext)?;
+        let private_key = cipher(&self.kdf, passphrase)?
+            .decrypt(Nonce::from_slice(&nonce), ciphertext.as_slice())
+            .map_err(|_| {
+                JayceError::Config("Failed to decrypt the keystore, wrong passphrase?".to_string())
+            })?;
+        String::from_utf8(private_key)
+            .map_err(|_| JayceError::Config("The keystore is corrupted".to_string()))
+    }
+
+    pub fn from_path(path: &Path) -> Result<Keystore> {
+        let content = fs::read_to_string(path)?;
+        let keystore: Keystore = serde_json::from_str(&content)?;
+
+        Ok(keystore)
+    }
+
+    /// Writes the keystore with `0600` permissions, restricting an existing file to them too.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let mut options = OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        let mut file = options.open(path)?;
+        #[cfg(unix)]
+        file.set_permissions(std::os::unix::fs::PermissionsExt::from_mode(0o600))?;
+        file.write_all(serde_json::to_string_pretty(self)?.as_bytes())?;
+        file.sync_all()?;
+        Ok(())
+    }
+}
+
+/// Reads and decrypts a keystore file, taking the passphrase from `JAYCE_KEYSTORE_PASSPHRASE` or
+/// prompting for it.
+pub fn read_keystore(path: &Path) -> Result<String> {
+    let keystore = Keystore::from_path(path)?;
+    let passphrase = match env::var(KEYSTORE_PASSPHRASE_ENV) {
+        Ok(passphrase) => passphrase,
+        Err(_) => Password::with_theme(prompt_theme().as_ref())
+            .with_prompt(format!("Passphrase of {}", path.to_str().unwrap()))
+            .interact()?,
+    };
+    keystore.decrypt(&passphrase)
+}
+
+fn cipher(kdf: &KdfParams, passphrase: &str) -> Result<Aes256Gcm> {
+    let params = scrypt::Params::new(kdf.log_n, kdf.r, kdf.p, 32)
+        .map_err(|err| JayceError::Config(format!("Invalid keystore parameters: {}", err)))?;
+    let mut key = [0u8; 32];
+    scrypt::scrypt(
+        passphrase.as_bytes(),
+        &decode_hex(&kdf.salt)?,
+        &params,
+        &mut key,
+    )
+    .map_err(|err| JayceError::Config(format!("Failed to derive the keystore key: {}", err)))?;
+    Aes256Gcm::new_from_slice(&key)
+        .map_err(|err| JayceError::Config(format!("Invalid keystore key: {}", err)))
+}
+
+fn decode_hex(value: &str) -> Result<Vec<u8>> {
+    hex::decode(value)
+        .map_err(|err| JayceError::Config(format!("The keystore is corrupted: {}", err)))
+}
+
+#[cfg(test)]
+mod test {
+    use aptos_sdk::crypto::ValidCryptoMaterialStringExt;
+    use aptos_sdk::types::LocalAccount;
+    use rand::rngs::OsRng;
+
+    use crate::keystore::Keystore;
+
+    #[test]
+    fn test_keystore_roundtrip() {
+        let account = LocalAccount::generate(&mut OsRng);
+        let private_key = account.private_key().to_encoded_string().unwrap();
+
+        let keystore = Keystore::encrypt_with_log_n(&private_key, "passphrase", 10).unwrap();
+        assert_eq!(keystore.address, account.address());
+        assert_eq!(keystore.decrypt("passphrase").unwrap(), private_key);
+        assert!(keystore.decrypt("wrong passphrase").is_err());
+    }
+
+    #[test]
+    fn test_keystore_bad_nonce() {
+        let account = LocalAccount::generate(&mut OsRng);
+        let private_key = account.private_key().to_encoded_string().unwrap();
+
+        let mut keystore = Keystore::encrypt_with_log_n(&private_key, "passphrase", 10).unwrap();
+        keystore.nonce = hex::encode([0u8; 8]);
+        assert!(keystore.decrypt("passphrase").is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_keystore_permissions() {
+        use std::fs;
+        use std::os::unix::fs::PermissionsExt;
+
+        let account = LocalAccount::generate(&mut OsRng);
+        let private_key = account.private_key().to_encoded_string().unwrap();
+        let keystore = Keystore::encrypt_with_log_n(&private_key, "passphrase", 10).unwrap();
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("keystore.json");
+        fs::write(&path, "").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o644)).unwrap();
+
+        keystore.save(&path).unwrap();
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+        assert_eq!(
+            Keystore::from_path(&path).unwrap().address,
+            account.address()
+        );
+    }
+}
@@ -0,0 +1,546 @@
+use std::env;
+use std::process::Command;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use aptos_sdk::crypto::ed25519::{Ed25519PublicKey, Ed25519Signature};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+
+use crate::error::{JayceError, Result};
+use crate::signer::Signer;
+
+/// DER prefix of the SubjectPublicKeyInfo of an Ed25519 public key, followed by the 32 bytes of
+/// the key.
+const ED25519_SPKI_PREFIX: [u8; 12] = [
+    0x30, 0x2a, 0x30, 0x05, 0x06, 0x03, 0x2b, 0x65, 0x70, 0x03, 0x21, 0x00,
+];
+
+/// Signs with an AWS KMS key, through the KMS JSON API signed with AWS Signature Version 4.
+pub struct AwsKmsSigner {
+    key_id: String,
+    kms: AwsKmsClient,
+    public_key: Ed25519PublicKey,
+}
+
+struct AwsKmsClient {
+    region: String,
+    credentials: AwsCredentials,
+    client: reqwest::Client,
+}
+
+struct AwsCredentials {
+    access_key_id: String,
+    secret_access_key: String,
+    session_token: Option<String>,
+}
+
+impl AwsCredentials {
+    fn from_env() -> Result<AwsCredentials> {
+        let var = |name: &str| {
+            env::var(name).map_err(|_| {
+                JayceError::Config(format!("{} is required to sign with AWS KMS", name))
+            })
+        };
+        Ok(AwsCredentials {
+            access_key_id: var("AWS_ACCESS_KEY_ID")?,
+            secret_access_key: var("AWS_SECRET_ACCESS_KEY")?,
+            session_token: env::var("AWS_SESSION_TOKEN").ok(),
+        })
+    }
+}
+
+impl AwsKmsSigner {
+    pub async fn connect(key_id: &str, region: Option<String>) -> Result<AwsKmsSigner> {
+        let region = region
+            .or_else(|| env::var("AWS_REGION").ok())
+            .or_else(|| env::var("AWS_DEFAULT_REGION").ok())
+            .ok_or_else(|| {
+                JayceError::Config("The region of the AWS KMS key is not set".to_string())
+            })?;
+        let kms = AwsKmsClient {
+            region,
+            credentials: AwsCredentials::from_env()?,
+            client: reqwest::Client::new(),
+        };
+        let response = kms.call("GetPublicKey", json!({ "KeyId": key_id })).await?;
+        Ok(AwsKmsSigner {
+            key_id: key_id.to_string(),
+            public_key: public_key_from_der(&decode_base64(
+                "AWS KMS",
+                string_field("AWS KMS", &response, "/PublicKey")?,
+            )?)?,
+            kms,
+        })
+    }
+}
+
+impl AwsKmsClient {
+    /// Calls an action of the KMS JSON API.
+    async fn call(&self, action: &str, body: Value) -> Result<Value> {
+        let host = format!("kms.{}.amazonaws.com", self.region);
+        let body = body.to_string();
+        let now = Utc::now();
+        let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+        let date = now.format("%Y%m%d").to_string();
+        let target = format!("TrentService.{}", action);
+
+        let mut headers = vec![
+            ("content-type", "application/x-amz-json-1.1".to_string()),
+            ("host", host.clone()),
+            ("x-amz-date", amz_date.clone()),
+            ("x-amz-target", target),
+        ];
+        if let Some(session_token) = &self.credentials.session_token {
+            headers.push(("x-amz-security-token", session_token.clone()));
+        }
+        let authorization = sigv4_authorization(
+            &self.credentials,
+            &self.region,
+            "kms",
+            &date,
+            &amz_date,
+            &headers,
+            &body,
+        );
+        let mut request = self
+            .client
+            .post(format!("https://{}/", host))
+            .header("authorization", authorization)
+            .body(body);
+        for (name, value) in headers.iter().filter(|(name, _)| *name != "host") {
+            request = request.header(*name, value);
+        }
+        send("AWS KMS", request).await
+    }
+}
+
+impl Signer for AwsKmsSigner {
+    fn public_key(&self) -> &Ed25519PublicKey {
+        &self.public_key
+    }
+
+    async fn sign_message(&self, message: &[u8]) -> Result<Ed25519Signature> {
+        let response = self
+            .kms
+            .call(
+                "Sign",
+                json!({
+                    "KeyId": self.key_id,
+                    "Message": BASE64.encode(message),
+                    "MessageType": "RAW",
+                    "SigningAlgorithm": "ED25519_SHA_512",
+                }),
+            )
+            .await?;
+        signature_from_bytes(&decode_base64(
+            "AWS KMS",
+            string_field("AWS KMS", &response, "/Signature")?,
+        )?)
+    }
+}
+
+/// Builds the `Authorization` header of a request signed with AWS Signature Version 4. `headers`
+/// holds every signed header, with lowercase names.
+fn sigv4_authorization(
+    credentials: &AwsCredentials,
+    region: &str,
+    service: &str,
+    date: &str,
+    amz_date: &str,
+    headers: &[(&str, String)],
+    body: &str,
+) -> String {
+    let mut headers = headers.to_vec();
+    headers.sort_by_key(|(name, _)| *name);
+    let canonical_headers: String = headers
+        .iter()
+        .map(|(name, value)| format!("{}:{}\n", name, value.trim()))
+        .collect();
+    let signed_headers = headers
+        .iter()
+        .map(|(name, _)| *name)
+        .collect::<Vec<_>>()
+        .join(";");
+    let canonical_request = format!(
+        "POST\n/\n\n{}\n{}\n{}",
+        canonical_headers,
+        signed_headers,
+        hex::encode(Sha256::digest(body.as_bytes()))
+    );
+    let scope = format!("{}/{}/{}/aws4_request", date, region, service);
+    let string_to_sign = format!(
+        "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+        amz_date,
+        scope,
+        hex::encode(Sha256::digest(canonical_request.as_bytes()))
+    );
+    let signing_key = sigv4_signing_key(&credentials.secret_access_key, date, region, service);
+    format!(
+        "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+        credentials.access_key_id,
+        scope,
+        signed_headers,
+        hex::encode(hmac_sha256(&signing_key, string_to_sign.as_bytes()))
+    )
+}
+
+fn sigv4_signing_key(secret_access_key: &str, date: &str, region: &str, service: &str) -> Vec<u8> {
+    let key = hmac_sha256(
+        format!("AWS4{}", secret_access_key).as_bytes(),
+        date.as_bytes(),
+    );
+    let key = hmac_sha256(&key, region.as_bytes());
+    let key = hmac_sha256(&key, service.as_bytes());
+    hmac_sha256(&key, b"aws4_request")
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any size");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// Signs with a GCP Cloud KMS key version, through the Cloud KMS REST API.
+pub struct GcpKmsSigner {
+    key_version: String,
+    /// The access token and when it was fetched.
+    access_token: Mutex<(String, Instant)>,
+    public_key: Ed25519PublicKey,
+    client: reqwest::Client,
+}
+
+const GCP_KMS_URL: &str = "https://cloudkms.googleapis.com/v1";
+
+/// How long an access token is used before it is fetched again. The tokens of gcloud expire after
+/// an hour, deployments may last longer.
+const GCP_ACCESS_TOKEN_LIFETIME: Duration = Duration::from_secs(45 * 60);
+
+impl GcpKmsSigner {
+    pub async fn connect(key_version: &str) -> Result<GcpKmsSigner> {
+        let access_token = gcp_access_token()?;
+        let client = reqwest::Client::new();
+        let response = send(
+            "GCP KMS",
+            client
+                .get(format!("{}/{}/publicKey", GCP_KMS_URL, key_version))
+                .bearer_auth(&access_token),
+        )
+        .await?;
+        let pem = string_field("GCP KMS", &response, "/pem")?;
+        let der = pem
+            .lines()
+            .filter(|line| !line.starts_with("-----"))
+            .collect::<String>();
+        Ok(GcpKmsSigner {
+            key_version: key_version.to_string(),
+            access_token: Mutex::new((access_token, Instant::now())),
+            public_key: public_key_from_der(&decode_base64("GCP KMS", &der)?)?,
+            client,
+        })
+    }
+
+    /// Returns the access token, fetched again once it may have expired.
+    fn access_token(&self) -> Result<String> {
+        let mut access_token = self.access_token.lock().unwrap();
+        if access_token.1.elapsed() >= GCP_ACCESS_TOKEN_LIFETIME {
+            *access_token = (gcp_access_token()?, Instant::now());
+        }
+        Ok(access_token.0.clone())
+    }
+}
+
+/// Returns the access token of the `GOOGLE_OAUTH_ACCESS_TOKEN` environment variable, otherwise
+/// the one of the account `gcloud` is logged in with.
+fn gcp_access_token() -> Result<String> {
+    if let Ok(access_token) = env::var("GOOGLE_OAUTH_ACCESS_TOKEN") {
+        return Ok(access_token);
+    }
+    let output = Command::new("gcloud")
+        .args(["auth", "print-access-token"])
+        .output()
+        .map_err(|err| {
+            JayceError::Config(format!(
+                "GOOGLE_OAUTH_ACCESS_TOKEN is not set and gcloud can't be run: {}",
+                err
+            ))
+        })?;
+    if !output.status.success() {
+        return Err(JayceError::Config(format!(
+            "Failed to get a GCP access token: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+impl Signer for GcpKmsSigner {
+    fn public_key(&self) -> &Ed25519PublicKey {
+        &self.public_key
+    }
+
+    async fn sign_message(&self, message: &[u8]) -> Result<Ed25519Signature> {
+        let access_token = self.access_token()?;
+        let response = send(
+            "GCP KMS",
+            self.client
+                .post(format!(
+                    "{}/{}:asymmetricSign",
+                    GCP_KMS_URL, self.key_version
+                ))
+                .bearer_auth(&access_token)
+                .json(&json!({ "data": BASE64.encode(message) })),
+        )
+        .await?;
+        signature_from_bytes(&decode_base64(
+            "GCP KMS",
+            string_field("GCP KMS", &response, "/signature")?,
+        )?)
+    }
+}
+
+/// Signs with a key of the transit secrets engine of HashiCorp Vault.
+pub struct VaultSigner {
+    key_name: String,
+    vault: VaultClient,
+    public_key: Ed25519PublicKey,
+}
+
+struct VaultClient {
+    address: String,
+    token: String,
+    namespace: Option<String>,
+    mount: String,
+    client: reqwest::Client,
+}
+
+impl VaultSigner {
+    pub async fn connect(
+        key_name: &str,
+        address: Option<String>,
+        mount: &str,
+    ) -> Result<VaultSigner> {
+        let address = address
+            .or_else(|| env::var("VAULT_ADDR").ok())
+            .ok_or_else(|| JayceError::Config("The address of Vault is not set".to_string()))?;
+        let vault = VaultClient {
+            address: address.trim_end_matches('/').to_string(),
+            token: env::var("VAULT_TOKEN").map_err(|_| {
+                JayceError::Config("VAULT_TOKEN is required to sign with Vault".to_string())
+            })?,
+            namespace: env::var("VAULT_NAMESPACE").ok(),
+            mount: mount.to_string(),
+            client: reqwest::Client::new(),
+        };
+        let response = send(
+            "Vault",
+            vault.request(reqwest::Method::GET, &format!("keys/{}", key_name)),
+        )
+        .await?;
+        let latest_version = response
+            .pointer("/data/latest_version")
+            .and_then(Value::as_u64)
+            .ok_or_else(|| JayceError::Signer("Vault returned no key version".to_string()))?;
+        let public_key = decode_base64(
+            "Vault",
+            string_field(
+                "Vault",
+                &response,
+                &format!("/data/keys/{}/public_key", latest_version),
+            )?,
+        )?;
+        Ok(VaultSigner {
+            key_name: key_name.to_string(),
+            public_key: Ed25519PublicKey::try_from(public_key.as_slice())
+                .map_err(|err| JayceError::Signer(format!("Invalid Vault public key: {}", err)))?,
+            vault,
+        })
+    }
+}
+
+impl VaultClient {
+    fn request(&self, method: reqwest::Method, path: &str) -> reqwest::RequestBuilder {
+        let request = self
+            .client
+            .request(
+                method,
+                format!("{}/v1/{}/{}", self.address, self.mount, path),
+            )
+            .header("X-Vault-Token", &self.token);
+        match &self.namespace {
+            Some(namespace) => request.header("X-Vault-Namespace", namespace),
+            None => request,
+        }
+    }
+}
+
+impl Signer for VaultSigner {
+    fn public_key(&self) -> &Ed25519PublicKey {
+        &self.public_key
+    }
+
+    async fn sign_message(&self, message: &[u8]) -> Result<Ed25519Signature> {
+        let response = send(
+            "Vault",
+            self.vault
+                .request(reqwest::Method::POST, &format!("sign/{}", self.key_name))
+                .json(&json!({ "input": BASE64.encode(message) })),
+        )
+        .await?;
+        signature_from_bytes(&decode_vault_signature(string_field(
+            "Vault",
+            &response,
+            "/data/signature",
+        )?)?)
+    }
+}
+
+/// Decodes a signature of the transit secrets engine, e.g. `vault:v1:<base64>`.
+fn decode_vault_signature(signature: &str) -> Result<Vec<u8>> {
+    let encoded = signature
+        .strip_prefix("vault:")
+        .and_then(|signature| signature.split_once(':'))
+        .map(|(_, encoded)| encoded)
+        .ok_or_else(|| JayceError::Signer(format!("Unexpected Vault signature '{}'", signature)))?;
+    decode_base64("Vault", encoded)
+}
+
+/// Sends a request to a signing service and returns its JSON response.
+pub(crate) async fn send(service: &str, request: reqwest::RequestBuilder) -> Result<Value> {
+    let response = request
+        .send()
+        .await
+        .map_err(|err| JayceError::Signer(format!("{} request failed: {}", service, err)))?;
+    let status = response.status();
+    let body = response
+        .text()
+        .await
+        .map_err(|err| JayceError::Signer(format!("{} request failed: {}", service, err)))?;
+    if !status.is_success() {
+        return Err(JayceError::Signer(format!(
+            "{} returned {}: {}",
+            service, status, body
+        )));
+    }
+    Ok(serde_json::from_str(&body)?)
+}
+
+pub(crate) fn string_field<'a>(
+    service: &str,
+    response: &'a Value,
+    pointer: &str,
+) -> Result<&'a str> {
+    response
+        .pointer(pointer)
+        .and_then(Value::as_str)
+        .ok_or_else(|| {
+            JayceError::Signer(format!(
+                "{} returned no {}",
+                service,
+                pointer.trim_start_matches('/')
+            ))
+        })
+}
+
+fn decode_base64(service: &str, encoded: &str) -> Result<Vec<u8>> {
+    BASE64
+        .decode(encoded)
+        .map_err(|err| JayceError::Signer(format!("{} returned invalid base64: {}", service, err)))
+}
+
+/// Reads an Ed25519 public key from its DER-encoded SubjectPublicKeyInfo.
+fn public_key_from_der(der: &[u8]) -> Result<Ed25519PublicKey> {
+    der.strip_prefix(&ED25519_SPKI_PREFIX)
+        .and_then(|key| Ed25519PublicKey::try_from(key).ok())
+        .ok_or_else(|| JayceError::Signer("The signing key is not an Ed25519 key".to_string()))
+}
+
+pub(crate) fn signature_from_bytes(bytes: &[u8]) -> Result<Ed25519Signature> {
+    Ed25519Signature::try_from(bytes)
+        .map_err(|err| JayceError::Signer(format!("Invalid signature: {}", err)))
+}
+
+#[cfg(test)]
+mod test {
+    use aptos_sdk::crypto::ed25519::{Ed25519PrivateKey, Ed25519PublicKey};
+    use aptos_sdk::crypto::{Uniform, ValidCryptoMaterial};
+
+    use crate::kms::{
+        decode_vault_signature, public_key_from_der, sigv4_authorization, sigv4_signing_key,
+        AwsCredentials, ED25519_SPKI_PREFIX,
+    };
+
+    #[test]
+    fn test_sigv4_signing_key() {
+        // The example of the AWS Signature Version 4 documentation.
+        assert_eq!(
+            hex::encode(sigv4_signing_key(
+                "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY",
+                "20150830",
+                "us-east-1",
+                "iam"
+            )),
+            "c4afb1cc5771d871763a393e44b703571b55cc28424d1a5e86da6ed3c154a4b9"
+        );
+    }
+
+    #[test]
+    fn test_sigv4_authorization() {
+        let mut credentials = AwsCredentials {
+            access_key_id: "AKIDEXAMPLE".to_string(),
+            secret_access_key: "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY".to_string(),
+            session_token: None,
+        };
+        let mut headers = vec![
+            ("content-type", "application/x-amz-json-1.1".to_string()),
+            ("host", "kms.us-east-1.amazonaws.com".to_string()),
+            ("x-amz-date", "20150830T123600Z".to_string()),
+            ("x-amz-target", "TrentService.GetPublicKey".to_string()),
+        ];
+        let authorization = |credentials: &AwsCredentials, headers: &[(&str, String)]| {
+            sigv4_authorization(
+                credentials,
+                "us-east-1",
+                "kms",
+                "20150830",
+                "20150830T123600Z",
+                headers,
+                r#"{"KeyId":"alias/jayce"}"#,
+            )
+        };
+        // The headers of the same request signed by botocore.
+        assert_eq!(
+            authorization(&credentials, &headers),
+            "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20150830/us-east-1/kms/aws4_request, \
+             SignedHeaders=content-type;host;x-amz-date;x-amz-target, \
+             Signature=9fe07d4d695c74564dbf7464423f040ce75abb9bdd397f3700550e9778b0e7f6"
+        );
+        credentials.session_token = Some("session-token".to_string());
+        headers.push(("x-amz-security-token", "session-token".to_string()));
+        assert_eq!(
+            authorization(&credentials, &headers),
+            "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20150830/us-east-1/kms/aws4_request, \
+             SignedHeaders=content-type;host;x-amz-date;x-amz-security-token;x-amz-target, \
+             Signature=71d9a47998614ce3c4b9c7893fb38a4d7fd8f2ced3523d6f5e66977eaeeb8770"
+        );
+    }
+
+    #[test]
+    fn test_public_key_from_der() {
+        let private_key = Ed25519PrivateKey::generate_for_testing();
+        let public_key = Ed25519PublicKey::from(&private_key);
+        let der = [ED25519_SPKI_PREFIX.as_slice(), &public_key.to_bytes()].concat();
+        assert_eq!(public_key_from_der(&der).unwrap(), public_key);
+        assert!(public_key_from_der(&der[1..]).is_err());
+    }
+
+    #[test]
+    fn test_decode_vault_signature() {
+        assert_eq!(decode_vault_signature("vault:v1:AQID").unwrap(), [1, 2, 3]);
+        assert!(decode_vault_signature("AQID").is_err());
+    }
+}
@@ -1,3 +1,38 @@
+pub mod address_book;
+pub mod artifact_cache;
+pub mod bundle;
+pub mod color;
+pub mod completions;
 pub mod deploy_config;
+pub mod deploy_lock;
+pub mod deploy_report;
+pub mod deploy_state;
+pub mod deployer;
+pub mod docker;
+pub mod entry_function;
+pub mod error;
+pub mod events;
+pub mod git;
+pub mod github_actions;
+pub mod hooks;
+pub mod http_signer;
+pub mod keyless;
+pub mod keystore;
+pub mod kms;
+pub mod logging;
+pub mod move_package;
+pub mod package_registry;
+pub mod progress;
+pub mod publish_backend;
+pub mod redact;
+pub mod registry;
+pub mod report_writer;
+pub mod retry;
+pub mod signer;
 pub mod tasks;
+pub mod telemetry;
+pub mod testing;
+pub mod tui;
 pub mod utils;
+pub mod vm_status;
+pub mod webhooks;
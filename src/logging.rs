@@ -0,0 +1,60 @@
+use std::io;
+
+use clap::ValueEnum;
+use strum_macros::Display;
+use tracing::level_filters::LevelFilter;
+use tracing_subscriber::fmt::writer::BoxMakeWriter;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::Layer;
+
+use crate::color::colors_enabled;
+use crate::redact::RedactingWriter;
+use crate::telemetry::Telemetry;
+use crate::tui::DashboardWriter;
+
+#[derive(Clone, Debug, PartialEq, ValueEnum, Display)]
+#[strum(serialize_all = "snake_case")]
+pub enum LogFormat {
+    Text,
+    Json,
+}
+
+/// Installs the global logger writing to stderr, with secrets masked. The default level is `info`,
+/// each `verbose` increment lowers it by one level and `quiet` only keeps errors. With `telemetry`,
+/// the spans are also exported to its collector. With `dashboard`, the logs are shown by the
+/// dashboard while it is running.
+pub fn init_logging(
+    verbose: u8,
+    quiet: bool,
+    format: &LogFormat,
+    telemetry: Option<&Telemetry>,
+    dashboard: bool,
+) {
+    let level = if quiet {
+        LevelFilter::ERROR
+    } else {
+        match verbose {
+            0 => LevelFilter::INFO,
+            1 => LevelFilter::DEBUG,
+            _ => LevelFilter::TRACE,
+        }
+    };
+    let writer = if dashboard {
+        BoxMakeWriter::new(|| RedactingWriter(DashboardWriter))
+    } else {
+        BoxMakeWriter::new(|| RedactingWriter(io::stderr()))
+    };
+    let fmt_layer = tracing_subscriber::fmt::layer()
+        .with_writer(writer)
+        .with_ansi(!dashboard && colors_enabled())
+        .with_target(false);
+    let fmt_layer = match format {
+        LogFormat::Text => fmt_layer.boxed(),
+        LogFormat::Json => fmt_layer.json().boxed(),
+    };
+    tracing_subscriber::registry()
+        .with(fmt_layer.with_filter(level))
+        .with(telemetry.map(|telemetry| telemetry.layer()))
+        .init();
+}
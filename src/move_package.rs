@@ -0,0 +1,725 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use aptos::common::types::CliCommand;
+use aptos::move_tool::MoveTool;
+use aptos::Tool;
+use aptos_sdk::crypto::HashValue;
+use aptos_sdk::move_types::account_address::AccountAddress;
+use clap::Parser;
+use config::{Config, File, FileFormat};
+use serde::Deserialize;
+use tracing::warn;
+
+use crate::deploy_config::{CompilerOptions, DeployModuleType, PackageOptions, UpgradePolicy};
+use crate::docker::run_aptos_in_docker;
+use crate::error::{JayceError, Result};
+use crate::git::resolve_git_source;
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct MoveTomlFile {
+    pub package: MovePackageInfo,
+    pub addresses: HashMap<String, String>,
+    #[serde(default)]
+    pub dependencies: HashMap<String, MoveDependency>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct MovePackageInfo {
+    pub name: String,
+    pub upgrade_policy: Option<UpgradePolicy>,
+}
+
+/// A copy of a package whose `Move.toml` sets another upgrade policy, which the aptos CLI reads
+/// when building the package metadata, so that the `Move.toml` of the package is left untouched.
+/// The copy is a sibling of the package, so that its local dependencies resolve alike, and is
+/// removed when dropped.
+pub struct PackageCopy {
+    dir: PathBuf,
+}
+
+impl PackageCopy {
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+}
+
+impl Drop for PackageCopy {
+    fn drop(&mut self) {
+        if let Err(err) = fs::remove_dir_all(&self.dir) {
+            warn!("Failed to remove {}: {}", self.dir.to_str().unwrap(), err);
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct MoveDependency {
+    pub local: Option<PathBuf>,
+    /// The URL of the repository of a git dependency.
+    pub git: Option<String>,
+    /// The branch, tag or commit of a git dependency.
+    pub rev: Option<String>,
+}
+
+impl MoveTomlFile {
+    pub fn from_package_dir(package_dir: &Path) -> Result<MoveTomlFile> {
+        let move_toml: MoveTomlFile = Config::builder()
+            .add_source(File::new(
+                package_dir.join("Move.toml").to_str().unwrap(),
+                FileFormat::Toml,
+            ))
+            .build()
+            .and_then(|content| content.try_deserialize())
+            .map_err(|err| {
+                JayceError::Package(format!(
+                    "Failed to read {}/Move.toml: {}",
+                    package_dir.to_str().unwrap(),
+                    err
+                ))
+            })?;
+
+        Ok(move_toml)
+    }
+}
+
+/// Returns the directory containing the compiled bytecode modules of a package.
+pub fn bytecode_modules_dir(package_dir: &Path) -> Result<PathBuf> {
+    let move_toml = MoveTomlFile::from_package_dir(package_dir)?;
+    Ok(package_dir
+        .join("build")
+        .join(move_toml.package.name)
+        .join("bytecode_modules"))
+}
+
+/// Returns the path of the serialized metadata of the last build of a package.
+pub fn package_metadata_path(package_dir: &Path) -> Result<PathBuf> {
+    let move_toml = MoveTomlFile::from_package_dir(package_dir)?;
+    Ok(package_dir
+        .join("build")
+        .join(move_toml.package.name)
+        .join("package-metadata.bcs"))
+}
+
+/// Returns the names of the modules of `code`, in order, from the last build of a package, which
+/// must hold them.
+pub fn built_module_names(package_dir: &Path, code: &[Vec<u8>]) -> Result<Vec<String>> {
+    let modules_dir = bytecode_modules_dir(package_dir)?;
+    let mut built_modules = HashMap::new();
+    for entry in fs::read_dir(&modules_dir)? {
+        let path = entry?.path();
+        if path.extension().is_some_and(|extension| extension == "mv") {
+            let name = path.file_stem().unwrap().to_str().unwrap().to_string();
+            built_modules.insert(fs::read(&path)?, name);
+        }
+    }
+    code.iter()
+        .map(|module| {
+            built_modules.get(module).cloned().ok_or_else(|| {
+                JayceError::Package(format!(
+                    "A module of the payload of {} is missing from {}",
+                    package_dir.to_str().unwrap(),
+                    modules_dir.to_str().unwrap()
+                ))
+            })
+        })
+        .collect()
+}
+
+/// Writes the metadata and the modules of a package to its build directory, replacing the ones of
+/// its last build, as if it was just compiled.
+pub fn write_build_artifacts(
+    package_dir: &Path,
+    metadata: &[u8],
+    modules: &[(String, Vec<u8>)],
+) -> Result<()> {
+    let modules_dir = bytecode_modules_dir(package_dir)?;
+    if modules_dir.exists() {
+        fs::remove_dir_all(&modules_dir)?;
+    }
+    fs::create_dir_all(&modules_dir)?;
+    for (name, code) in modules {
+        fs::write(modules_dir.join(format!("{}.mv", name)), code)?;
+    }
+    fs::write(package_metadata_path(package_dir)?, metadata)?;
+    Ok(())
+}
+
+/// Copies the `package-metadata.bcs` and the bytecode modules of the last build of a package to
+/// `destination`.
+pub fn copy_package_artifacts(package_dir: &Path, destination: &Path) -> Result<()> {
+    fs::create_dir_all(destination)?;
+    fs::copy(
+        package_metadata_path(package_dir)?,
+        destination.join("package-metadata.bcs"),
+    )?;
+    for entry in fs::read_dir(bytecode_modules_dir(package_dir)?)? {
+        let path = entry?.path();
+        if path.extension().is_some_and(|extension| extension == "mv") {
+            fs::copy(&path, destination.join(path.file_name().unwrap()))?;
+        }
+    }
+    Ok(())
+}
+
+/// Copies a package next to it, with `policy` as the upgrade policy of the `Move.toml` of the
+/// copy. The build directory isn't copied.
+pub fn copy_with_upgrade_policy(package_dir: &Path, policy: &UpgradePolicy) -> Result<PackageCopy> {
+    let package_dir = fs::canonicalize(package_dir)?;
+    let path = package_dir.join("Move.toml");
+    let mut manifest: toml::Table = fs::read_to_string(&path)?
+        .parse()
+        .map_err(anyhow::Error::from)?;
+    manifest
+        .get_mut("package")
+        .and_then(|package| package.as_table_mut())
+        .ok_or_else(|| {
+            JayceError::Package(format!(
+                "Missing [package] section in {}",
+                path.to_str().unwrap()
+            ))
+        })?
+        .insert(
+            "upgrade_policy".to_string(),
+            toml::Value::String(policy.to_string()),
+        );
+    let copy = PackageCopy {
+        dir: package_dir.with_file_name(format!(
+            ".{}-jayce-{}",
+            package_dir.file_name().unwrap().to_str().unwrap(),
+            std::process::id()
+        )),
+    };
+    if copy.dir.exists() {
+        fs::remove_dir_all(&copy.dir)?;
+    }
+    copy_dir(&package_dir, &copy.dir, &["build"])?;
+    fs::write(
+        copy.dir.join("Move.toml"),
+        toml::to_string(&manifest).map_err(anyhow::Error::from)?,
+    )?;
+    Ok(copy)
+}
+
+fn copy_dir(from: &Path, to: &Path, skipped: &[&str]) -> Result<()> {
+    fs::create_dir_all(to)?;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        let name = entry.file_name();
+        if skipped.iter().any(|skipped| name == *skipped) {
+            continue;
+        }
+        let path = entry.path();
+        if path.is_dir() {
+            copy_dir(&path, &to.join(&name), &[])?;
+        } else {
+            fs::copy(&path, to.join(&name))?;
+        }
+    }
+    Ok(())
+}
+
+/// Returns the `--package-dir` option of the aptos CLI building a package, built from a copy
+/// with `upgrade_policy` when it is set, into the build directory of the package either way. The
+/// returned copy must be kept until the package is built.
+pub fn package_dir_args(
+    package_dir: &Path,
+    upgrade_policy: Option<&UpgradePolicy>,
+) -> Result<(String, Option<PackageCopy>)> {
+    match upgrade_policy {
+        Some(upgrade_policy) => {
+            let copy = copy_with_upgrade_policy(package_dir, upgrade_policy)?;
+            let args = format!(
+                "--package-dir {} --output-dir {}",
+                copy.dir.to_str().unwrap(),
+                fs::canonicalize(package_dir)?.to_str().unwrap()
+            );
+            Ok((args, Some(copy)))
+        }
+        None => Ok((
+            format!("--package-dir {}", package_dir.to_str().unwrap()),
+            None,
+        )),
+    }
+}
+
+/// Returns the upgrade policy a package was published with if it isn't the one of its `Move.toml`.
+/// The `Move.toml` is part of the metadata, so it is only overridden in that case.
+pub fn upgrade_policy_override<'a>(
+    package_dir: &Path,
+    upgrade_policy: Option<&'a UpgradePolicy>,
+) -> Result<Option<&'a UpgradePolicy>> {
+    let Some(upgrade_policy) = upgrade_policy else {
+        return Ok(None);
+    };
+    let manifest_upgrade_policy = MoveTomlFile::from_package_dir(package_dir)?
+        .package
+        .upgrade_policy
+        .unwrap_or(UpgradePolicy::Compatible);
+    Ok(Some(upgrade_policy).filter(|upgrade_policy| **upgrade_policy != manifest_upgrade_policy))
+}
+
+/// Returns the directory containing the compiled scripts of a package.
+pub fn bytecode_scripts_dir(package_dir: &Path) -> Result<PathBuf> {
+    let move_toml = MoveTomlFile::from_package_dir(package_dir)?;
+    Ok(package_dir
+        .join("build")
+        .join(move_toml.package.name)
+        .join("bytecode_scripts"))
+}
+
+/// Compiles a package with the given `--named-addresses` argument and compiler options.
+pub async fn compile_package(
+    package_dir: &Path,
+    named_addresses: &str,
+    compiler: &CompilerOptions,
+) -> Result<()> {
+    if let Some(image) = &compiler.docker_image {
+        let package_dir = fs::canonicalize(package_dir)?;
+        let args = format!(
+            "move compile --package-dir {} {} {}",
+            package_dir.to_str().unwrap(),
+            named_addresses,
+            compiler.args()
+        );
+        let args: Vec<&str> = args.split_whitespace().collect();
+        return run_aptos_in_docker(image, &package_dir, &args, &[]);
+    }
+    let args = format!(
+        "aptos move compile \
+            --package-dir {} \
+            {} \
+            {} \
+            ",
+        package_dir.to_str().unwrap(),
+        named_addresses,
+        compiler.args()
+    );
+    let args: Vec<&str> = args.split_whitespace().collect();
+    let tool = Tool::try_parse_from(&args).map_err(|err| {
+        JayceError::Config(format!("Invalid arguments to compile package: {}", err))
+    })?;
+    if let Tool::Move(MoveTool::Compile(cmd_executor)) = tool {
+        cmd_executor
+            .execute()
+            .await
+            .map_err(|err| JayceError::Compile(err.to_string()))?;
+        Ok(())
+    } else {
+        Err(JayceError::Config(format!(
+            "Wrong arguments to compile package: {:?}",
+            args
+        )))
+    }
+}
+
+pub fn get_named_addresses(
+    package_dir: &Path,
+    address_name: &str,
+    module_type: DeployModuleType,
+) -> Result<HashMap<String, String>> {
+    let mut named_addresses = MoveTomlFile::from_package_dir(package_dir)?.addresses;
+    if !named_addresses.contains_key(address_name) {
+        return Err(JayceError::Package(format!(
+            "Address name {} not found in {}/Move.toml",
+            address_name,
+            package_dir.to_str().unwrap()
+        )));
+    }
+    if module_type == DeployModuleType::Object {
+        named_addresses.remove(address_name);
+    }
+    Ok(named_addresses)
+}
+
+/// Builds the `--named-addresses` argument for a package whose named addresses are all deployed.
+pub fn named_addresses_arg(
+    package_dir: &Path,
+    address_name: &str,
+    deployed_addresses: &BTreeMap<String, AccountAddress>,
+) -> Result<String> {
+    get_named_addresses(package_dir, address_name, DeployModuleType::Account)?
+        .keys()
+        .map(|named_address| {
+            deployed_addresses
+                .get(named_address)
+                .map(|hex_address| format!("{}={}", named_address, hex_address))
+                .ok_or_else(|| {
+                    JayceError::Dependency(format!(
+                        "Address of '{}' not found, required by '{}'",
+                        named_address, address_name
+                    ))
+                })
+        })
+        .collect::<Result<Vec<_>>>()
+        .map(|named_addresses| {
+            if named_addresses.is_empty() {
+                "".to_string()
+            } else {
+                format!("--named-addresses {}", named_addresses.join(","))
+            }
+        })
+}
+
+/// A placeholder for a named address that isn't deployed yet, derived from its name so that every
+/// package refers to it with the same address.
+pub fn placeholder_address(named_address: &str) -> AccountAddress {
+    AccountAddress::from_bytes(HashValue::sha3_256_of(named_address.as_bytes()).to_vec()).unwrap()
+}
+
+/// Builds the `--named-addresses` argument assigning the unassigned (`_`) addresses of a package
+/// from the deployed addresses.
+pub fn unassigned_named_addresses_arg(
+    package_dir: &Path,
+    deployed_addresses: &BTreeMap<String, AccountAddress>,
+) -> Result<String> {
+    let mut addresses: Vec<(String, String)> = MoveTomlFile::from_package_dir(package_dir)?
+        .addresses
+        .into_iter()
+        .collect();
+    addresses.sort();
+    let named_addresses = addresses
+        .into_iter()
+        .filter(|(_, value)| value == "_")
+        .map(|(named_address, _)| {
+            deployed_addresses
+                .get(&named_address)
+                .map(|hex_address| format!("{}={}", named_address, hex_address))
+                .ok_or_else(|| {
+                    JayceError::Dependency(format!(
+                        "Address of '{}' not found, required by {}",
+                        named_address,
+                        package_dir.to_str().unwrap()
+                    ))
+                })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    if named_addresses.is_empty() {
+        Ok("".to_string())
+    } else {
+        Ok(format!("--named-addresses {}", named_addresses.join(",")))
+    }
+}
+
+/// Finds the Move packages under `root`, skipping hidden and `build` directories.
+pub fn find_packages(root: &Path) -> Result<Vec<PathBuf>> {
+    let mut packages = vec![];
+    if root.join("Move.toml").is_file() {
+        packages.push(root.to_path_buf());
+    }
+    let mut entries = fs::read_dir(root)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<std::io::Result<Vec<_>>>()?;
+    entries.sort();
+    for path in entries {
+        let name = path.file_name().unwrap().to_str().unwrap_or_default();
+        if path.is_dir() && !name.starts_with('.') && name != "build" {
+            packages.extend(find_packages(&path)?);
+        }
+    }
+    Ok(packages)
+}
+
+/// Expands the entries of `modules_path` that are glob patterns (e.g. `contracts/*`) or directories
+/// without a `Move.toml` into the Move packages they contain. Returns whether an entry was
+/// expanded.
+pub fn expand_modules_path(modules_path: &[PathBuf]) -> Result<(Vec<PathBuf>, bool)> {
+    let mut packages = vec![];
+    let mut expanded = false;
+    for entry in modules_path {
+        let entry = &resolve_git_source(entry)?;
+        let pattern = entry.to_str().unwrap();
+        if pattern.contains(['*', '?', '[']) {
+            expanded = true;
+            let paths = glob::glob(pattern)
+                .map_err(|err| JayceError::Config(format!("Invalid pattern {}: {}", pattern, err)))?
+                .collect::<std::result::Result<Vec<_>, _>>()
+                .map_err(anyhow::Error::from)?;
+            for path in paths.into_iter().filter(|path| path.is_dir()) {
+                packages.extend(find_packages(&path)?);
+            }
+        } else if entry.is_dir() && !entry.join("Move.toml").is_file() {
+            expanded = true;
+            packages.extend(find_packages(entry)?);
+        } else {
+            packages.push(entry.clone());
+        }
+    }
+    Ok((packages, expanded))
+}
+
+/// Infers the address name of each package from the unassigned (`_`) addresses of its
+/// `Move.toml`. A package with a single unassigned address is deployed at it, and that address is
+/// then removed from the candidates of the other packages, until every package has one.
+pub fn infer_address_names(packages: &[PathBuf]) -> Result<Vec<String>> {
+    let mut candidates = packages
+        .iter()
+        .map(|package_dir| {
+            let mut addresses: Vec<String> = MoveTomlFile::from_package_dir(package_dir)?
+                .addresses
+                .into_iter()
+                .filter(|(_, value)| value == "_")
+                .map(|(name, _)| name)
+                .collect();
+            addresses.sort();
+            Ok(addresses)
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let mut names: Vec<Option<String>> = vec![None; packages.len()];
+    while let Some(package) = (0..packages.len())
+        .find(|&package| names[package].is_none() && candidates[package].len() == 1)
+    {
+        let name = candidates[package][0].clone();
+        for (other, other_candidates) in candidates.iter_mut().enumerate() {
+            if names[other].is_none() {
+                other_candidates.retain(|candidate| *candidate != name);
+            }
+        }
+        names[package] = Some(name);
+    }
+    names
+        .into_iter()
+        .zip(packages)
+        .zip(&candidates)
+        .map(|((name, package_dir), candidates)| {
+            name.ok_or_else(|| {
+                JayceError::Package(format!(
+                    "Can't infer the address name of {} (candidates: [{}]), set addresses_name",
+                    package_dir.to_str().unwrap(),
+                    candidates.join(", ")
+                ))
+            })
+        })
+        .collect()
+}
+
+/// Orders the packages so that every package is deployed after the packages it depends on.
+///
+/// A package depends on another one if its `Move.toml` declares the other package's address name,
+/// one of its overrides refers to it, or it refers to the other package as a local dependency.
+/// Packages without dependencies between them keep their original order.
+pub fn sort_packages(
+    modules_path: &[PathBuf],
+    addresses_name: &[String],
+    package_options: &BTreeMap<String, PackageOptions>,
+) -> Result<Vec<(PathBuf, String)>> {
+    let canonical_paths: Vec<Option<PathBuf>> = modules_path
+        .iter()
+        .map(|package_dir| fs::canonicalize(package_dir).ok())
+        .collect();
+    let mut dependencies = Vec::with_capacity(modules_path.len());
+    for (package, package_dir) in modules_path.iter().enumerate() {
+        let move_toml = MoveTomlFile::from_package_dir(package_dir)?;
+        let overrides = package_options
+            .get(&addresses_name[package])
+            .map(|options| &options.overrides);
+        let local_dependencies: Vec<PathBuf> = move_toml
+            .dependencies
+            .values()
+            .filter_map(|dependency| dependency.local.as_ref())
+            .filter_map(|local| fs::canonicalize(package_dir.join(local)).ok())
+            .collect();
+        let package_dependencies: Vec<usize> = (0..modules_path.len())
+            .filter(|&other| modules_path[other] != *package_dir)
+            .filter(|&other| {
+                move_toml.addresses.contains_key(&addresses_name[other])
+                    || overrides.is_some_and(|overrides| {
+                        overrides
+                            .values()
+                            .any(|value| *value == addresses_name[other])
+                    })
+                    || canonical_paths[other]
+                        .as_ref()
+                        .is_some_and(|path| local_dependencies.contains(path))
+            })
+            .collect();
+        dependencies.push(package_dependencies);
+    }
+
+    let mut sorted: Vec<usize> = Vec::with_capacity(modules_path.len());
+    while sorted.len() < modules_path.len() {
+        let next = (0..modules_path.len()).find(|package| {
+            !sorted.contains(package)
+                && dependencies[*package]
+                    .iter()
+                    .all(|dependency| sorted.contains(dependency))
+        });
+        match next {
+            Some(package) => sorted.push(package),
+            None => {
+                let cycle: Vec<&str> = (0..modules_path.len())
+                    .filter(|package| !sorted.contains(package))
+                    .map(|package| addresses_name[package].as_str())
+                    .collect();
+                return Err(JayceError::Dependency(format!(
+                    "Dependency cycle detected between packages: {}",
+                    cycle.join(", ")
+                )));
+            }
+        }
+    }
+
+    Ok(sorted
+        .into_iter()
+        .map(|package| {
+            (
+                modules_path[package].clone(),
+                addresses_name[package].clone(),
+            )
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::BTreeMap;
+    use std::fs;
+    use std::path::{Path, PathBuf};
+
+    use crate::deploy_config::UpgradePolicy;
+    use crate::move_package::{
+        copy_with_upgrade_policy, expand_modules_path, find_packages, infer_address_names,
+        package_dir_args, sort_packages, MoveTomlFile,
+    };
+
+    fn write_package(root: &Path, name: &str, addresses: &[&str]) -> PathBuf {
+        let package_dir = root.join(name);
+        fs::create_dir_all(&package_dir).unwrap();
+        let addresses = addresses
+            .iter()
+            .map(|address| format!("{} = \"_\"\n", address))
+            .collect::<String>();
+        fs::write(
+            package_dir.join("Move.toml"),
+            format!(
+                "[package]\nname = \"{}\"\n\n[addresses]\n{}",
+                name, addresses
+            ),
+        )
+        .unwrap();
+        package_dir
+    }
+
+    #[test]
+    fn test_sort_packages() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let root = temp_dir.path();
+        let libs = write_package(root, "libs", &["lib_addr"]);
+        let cpu = write_package(root, "cpu", &["lib_addr", "cpu_addr"]);
+        let verifier = write_package(root, "verifier", &["lib_addr", "cpu_addr", "verifier_addr"]);
+
+        let sorted = sort_packages(
+            &[verifier, cpu, libs],
+            &[
+                "verifier_addr".to_string(),
+                "cpu_addr".to_string(),
+                "lib_addr".to_string(),
+            ],
+            &BTreeMap::new(),
+        )
+        .unwrap();
+        let sorted: Vec<&str> = sorted.iter().map(|(_, name)| name.as_str()).collect();
+        assert_eq!(sorted, vec!["lib_addr", "cpu_addr", "verifier_addr"]);
+    }
+
+    #[test]
+    fn test_find_packages() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let root = temp_dir.path();
+        let libs = write_package(root, "libs", &["lib_addr"]);
+        let cpu = write_package(&root.join("nested"), "cpu", &["cpu_addr"]);
+        write_package(
+            &root.join("libs").join("build"),
+            "dependency",
+            &["lib_addr"],
+        );
+
+        assert_eq!(find_packages(root).unwrap(), vec![libs, cpu]);
+    }
+
+    #[test]
+    fn test_expand_modules_path() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let root = temp_dir.path();
+        let libs = write_package(&root.join("navori"), "libs", &["lib_addr"]);
+        let cpu = write_package(&root.join("navori"), "cpu", &["lib_addr", "cpu_addr"]);
+        let verifier = write_package(root, "verifier", &["lib_addr", "cpu_addr", "verifier_addr"]);
+
+        let (packages, expanded) =
+            expand_modules_path(&[root.join("navori/*"), verifier.clone()]).unwrap();
+        assert!(expanded);
+        assert_eq!(packages, vec![cpu.clone(), libs.clone(), verifier.clone()]);
+        assert_eq!(
+            expand_modules_path(&[root.join("navori")]).unwrap(),
+            (vec![cpu, libs], true)
+        );
+        assert_eq!(
+            infer_address_names(&packages).unwrap(),
+            vec!["cpu_addr", "lib_addr", "verifier_addr"]
+        );
+
+        let other = write_package(root, "other", &["lib_addr"]);
+        assert!(infer_address_names(&[packages[1].clone(), other]).is_err());
+    }
+
+    #[test]
+    fn test_copy_with_upgrade_policy() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let root = temp_dir.path();
+        let libs = write_package(root, "libs", &["lib_addr"]);
+        fs::create_dir_all(libs.join("sources")).unwrap();
+        fs::write(libs.join("sources/lib.move"), "module lib_addr::lib {}").unwrap();
+        fs::create_dir_all(libs.join("build/Libs")).unwrap();
+        let original = fs::read_to_string(libs.join("Move.toml")).unwrap();
+
+        let copy = copy_with_upgrade_policy(&libs, &UpgradePolicy::Immutable).unwrap();
+        assert_eq!(
+            copy.dir().parent().unwrap(),
+            fs::canonicalize(root).unwrap()
+        );
+        let move_toml = MoveTomlFile::from_package_dir(copy.dir()).unwrap();
+        assert_eq!(
+            move_toml.package.upgrade_policy,
+            Some(UpgradePolicy::Immutable)
+        );
+        assert!(move_toml.addresses.contains_key("lib_addr"));
+        assert!(copy.dir().join("sources/lib.move").exists());
+        assert!(!copy.dir().join("build").exists());
+        assert_eq!(
+            fs::read_to_string(libs.join("Move.toml")).unwrap(),
+            original
+        );
+        let copy_dir = copy.dir().to_path_buf();
+        drop(copy);
+        assert!(!copy_dir.exists());
+
+        let (args, copy) = package_dir_args(&libs, None).unwrap();
+        assert_eq!(args, format!("--package-dir {}", libs.to_str().unwrap()));
+        assert!(copy.is_none());
+        let (args, copy) = package_dir_args(&libs, Some(&UpgradePolicy::Compatible)).unwrap();
+        assert_eq!(
+            args,
+            format!(
+                "--package-dir {} --output-dir {}",
+                copy.unwrap().dir().to_str().unwrap(),
+                fs::canonicalize(&libs).unwrap().to_str().unwrap()
+            )
+        );
+    }
+
+    #[test]
+    fn test_sort_packages_cycle() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let root = temp_dir.path();
+        let a = write_package(root, "a", &["a_addr", "b_addr"]);
+        let b = write_package(root, "b", &["a_addr", "b_addr"]);
+
+        let err = sort_packages(
+            &[a, b],
+            &["a_addr".to_string(), "b_addr".to_string()],
+            &BTreeMap::new(),
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("Dependency cycle detected"));
+    }
+}
@@ -0,0 +1,112 @@
+use std::collections::HashSet;
+
+use aptos_sdk::move_types::account_address::AccountAddress;
+use aptos_sdk::rest_client::Client;
+use serde::{Deserialize, Deserializer};
+
+use crate::error::Result;
+
+/// The `0x1::code::PackageRegistry` resource, listing the packages published at an address.
+#[derive(Deserialize, Debug, Clone)]
+pub struct PackageRegistry {
+    pub packages: Vec<PackageMetadata>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct PackageMetadata {
+    pub name: String,
+    #[serde(deserialize_with = "deserialize_u64")]
+    pub upgrade_number: u64,
+    pub upgrade_policy: UpgradePolicy,
+    /// Digest of the sources of the package, computed by the compiler.
+    pub source_digest: String,
+    pub modules: Vec<ModuleMetadata>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct UpgradePolicy {
+    pub policy: u8,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct ModuleMetadata {
+    pub name: String,
+}
+
+/// The REST API serializes `u64` values as strings.
+fn deserialize_u64<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> std::result::Result<u64, D::Error> {
+    String::deserialize(deserializer)?
+        .parse()
+        .map_err(serde::de::Error::custom)
+}
+
+impl PackageRegistry {
+    /// Reads the package registry of an address, which is empty if no package is published there.
+    pub async fn fetch(client: &Client, address: AccountAddress) -> Result<PackageRegistry> {
+        let resource = client
+            .get_account_resource(address, "0x1::code::PackageRegistry")
+            .await?
+            .into_inner();
+        match resource {
+            Some(resource) => Ok(serde_json::from_value(resource.data)?),
+            None => Ok(PackageRegistry { packages: vec![] }),
+        }
+    }
+
+    pub fn package(&self, name: &str) -> Option<&PackageMetadata> {
+        self.packages.iter().find(|package| package.name == name)
+    }
+}
+
+/// Returns the upgrade number of the package named `name` published at `address`, if there is one.
+pub async fn upgrade_number(
+    client: &Client,
+    address: AccountAddress,
+    name: &str,
+) -> Result<Option<u64>> {
+    Ok(PackageRegistry::fetch(client, address)
+        .await?
+        .package(name)
+        .map(|package| package.upgrade_number))
+}
+
+/// Returns the metadata of the package published at `address` if it has the given name and
+/// exactly the modules of `code`, i.e. publishing `code` again wouldn't change anything.
+pub async fn find_up_to_date_package(
+    client: &Client,
+    address: AccountAddress,
+    name: &str,
+    code: &[Vec<u8>],
+) -> Result<Option<PackageMetadata>> {
+    let registry = PackageRegistry::fetch(client, address).await?;
+    let Some(package) = registry.package(name) else {
+        return Ok(None);
+    };
+    if matches_published_modules(client, address, package, code).await? {
+        Ok(Some(package.clone()))
+    } else {
+        Ok(None)
+    }
+}
+
+/// Checks that the modules of a package published at `address` are exactly the ones of `code`.
+pub async fn matches_published_modules(
+    client: &Client,
+    address: AccountAddress,
+    package: &PackageMetadata,
+    code: &[Vec<u8>],
+) -> Result<bool> {
+    if package.modules.len() != code.len() {
+        return Ok(false);
+    }
+    let published: HashSet<Vec<u8>> = client
+        .get_account_modules(address)
+        .await?
+        .into_inner()
+        .into_iter()
+        .map(|module| module.bytecode.inner().to_vec())
+        .collect();
+    Ok(code.iter().all(|bytecode| published.contains(bytecode)))
+}
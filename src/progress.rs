@@ -0,0 +1,196 @@
+use std::cell::Cell;
+use std::io::{self, IsTerminal};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use aptos_sdk::crypto::HashValue;
+use aptos_sdk::move_types::account_address::AccountAddress;
+use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+use opentelemetry::KeyValue;
+use strum_macros::Display;
+use tracing::info;
+
+use crate::color::colors_enabled;
+use crate::events::{DeployEvent, EventWriter};
+use crate::telemetry::metrics;
+use crate::tui::dashboard;
+
+/// The phases a package goes through during a deployment.
+#[derive(Clone, Copy, Debug, PartialEq, Display)]
+#[strum(serialize_all = "lowercase")]
+pub enum Phase {
+    Compiling,
+    Simulating,
+    Submitting,
+    Freezing,
+    Verifying,
+    Initializing,
+    Confirmed,
+    Simulated,
+    Skipped,
+    Failed,
+}
+
+/// Shows the phase of each package of a deployment, in the dashboard when it is running, as
+/// progress bars when stderr is a terminal and as log events otherwise.
+pub struct DeployProgress {
+    multi_progress: Option<MultiProgress>,
+    events: Option<Arc<EventWriter>>,
+}
+
+pub struct PackageProgress {
+    address_name: String,
+    bar: Option<ProgressBar>,
+    started_at: Instant,
+    /// The current phase and when it started, measuring the duration of the phases.
+    phase: Cell<Option<(Phase, Instant)>>,
+    finished: bool,
+    events: Option<Arc<EventWriter>>,
+}
+
+impl DeployProgress {
+    pub fn new() -> Self {
+        DeployProgress {
+            multi_progress: (io::stderr().is_terminal() && dashboard().is_none())
+                .then(MultiProgress::new),
+            events: None,
+        }
+    }
+
+    /// Also writes the lifecycle events of the packages with `events`.
+    pub fn with_events(mut self, events: Option<EventWriter>) -> Self {
+        self.events = events.map(Arc::new);
+        self
+    }
+
+    pub fn emit(&self, event: DeployEvent) {
+        if let Some(events) = &self.events {
+            events.emit(&event);
+        }
+    }
+
+    pub fn package(&self, address_name: &str) -> PackageProgress {
+        let bar = self.multi_progress.as_ref().map(|multi_progress| {
+            let bar = multi_progress.add(ProgressBar::new_spinner());
+            let style =
+                ProgressStyle::with_template("{spinner} {prefix:<24} {msg:<14} [{elapsed}]")
+                    .unwrap();
+            // The default spinner isn't ASCII.
+            bar.set_style(if colors_enabled() {
+                style
+            } else {
+                style.tick_chars("|/-\\ ")
+            });
+            bar.set_prefix(address_name.to_string());
+            bar.enable_steady_tick(Duration::from_millis(100));
+            bar
+        });
+        self.emit(DeployEvent::PackageStarted {
+            address_name: address_name.to_string(),
+        });
+        PackageProgress {
+            address_name: address_name.to_string(),
+            bar,
+            started_at: Instant::now(),
+            phase: Cell::new(None),
+            finished: false,
+            events: self.events.clone(),
+        }
+    }
+}
+
+impl Default for DeployProgress {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PackageProgress {
+    pub fn set_phase(&self, phase: Phase) {
+        self.record_phase(phase);
+        self.emit_phase(phase);
+        self.show_phase(phase);
+    }
+
+    pub fn finish(mut self, phase: Phase) {
+        self.finished = true;
+        self.record_phase(phase);
+        self.emit_phase(phase);
+        match &self.bar {
+            Some(bar) => bar.finish_with_message(phase.to_string()),
+            None => self.show_phase(phase),
+        }
+    }
+
+    /// Finishes a package that was published at `deployed_at` by the transactions `tx_hashes`.
+    pub fn confirm(self, deployed_at: AccountAddress, tx_hashes: Vec<HashValue>) {
+        self.emit(DeployEvent::Confirmed {
+            address_name: self.address_name.clone(),
+            deployed_at,
+            tx_hashes,
+        });
+        self.finish(Phase::Confirmed);
+    }
+
+    /// Records the duration of the phase ending with the start of `phase`.
+    fn record_phase(&self, phase: Phase) {
+        if let Some((previous, started_at)) = self.phase.replace(Some((phase, Instant::now()))) {
+            metrics().phase_duration.record(
+                started_at.elapsed().as_secs_f64(),
+                &[KeyValue::new("phase", previous.to_string())],
+            );
+        }
+    }
+
+    fn show_phase(&self, phase: Phase) {
+        if let Some(dashboard) = dashboard() {
+            dashboard.set_phase(&self.address_name, phase);
+            return;
+        }
+        match &self.bar {
+            Some(bar) => bar.set_message(phase.to_string()),
+            None => info!(
+                address_name = %self.address_name,
+                elapsed_secs = self.started_at.elapsed().as_secs_f64(),
+                "{}",
+                phase
+            ),
+        }
+    }
+
+    /// Writes the events marking the start of a phase, the other events carry more details and are
+    /// written by the deployment itself.
+    fn emit_phase(&self, phase: Phase) {
+        let address_name = self.address_name.clone();
+        match phase {
+            Phase::Submitting => {
+                self.emit(DeployEvent::Compiled {
+                    address_name: address_name.clone(),
+                });
+                self.emit(DeployEvent::Submitted { address_name });
+            }
+            Phase::Skipped => self.emit(DeployEvent::Skipped { address_name }),
+            _ => {}
+        }
+    }
+
+    fn emit(&self, event: DeployEvent) {
+        if let Some(events) = &self.events {
+            events.emit(&event);
+        }
+    }
+}
+
+impl Drop for PackageProgress {
+    /// A package dropped before being finished failed, e.g. when an error is returned.
+    fn drop(&mut self) {
+        if self.finished {
+            return;
+        }
+        match &self.bar {
+            Some(bar) => bar.abandon_with_message(Phase::Failed.to_string()),
+            None => self.show_phase(Phase::Failed),
+        }
+        self.record_phase(Phase::Failed);
+    }
+}
@@ -0,0 +1,533 @@
+use std::collections::{BTreeMap, VecDeque};
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use aptos::common::types::TransactionSummary;
+use aptos_sdk::crypto::HashValue;
+use aptos_sdk::move_types::account_address::AccountAddress;
+use aptos_sdk::rest_client::aptos_api_types::{Transaction, TransactionPayload};
+use aptos_sdk::rest_client::Client;
+
+use crate::bundle::Bundle;
+use crate::deploy_config::{DeployConfig, DeployModuleType};
+use crate::deploy_report::PackageChecksums;
+use crate::deployer::build_publish_payload;
+use crate::error::{JayceError, Result};
+use crate::package_registry::{find_up_to_date_package, upgrade_number, PackageMetadata};
+use crate::tasks::deploy_contracts::{
+    chunked_publish_args, freeze_code_object, publish_args, run_deploy_command,
+};
+use crate::utils::{create_object_code_deployment_address, rest_client};
+
+/// The publication of a package.
+pub struct PublishRequest<'a> {
+    pub config: &'a DeployConfig,
+    pub package_dir: &'a Path,
+    pub address_name: &'a str,
+    pub sender: AccountAddress,
+    /// The `--named-addresses` option publishing the package.
+    pub named_addresses: &'a str,
+    /// The `--named-addresses` option compiling the package. The address of an object package
+    /// is only known once published, it is compiled at the sender address.
+    pub payload_named_addresses: &'a str,
+    /// The addresses of the packages deployed so far.
+    pub deployed_addresses: &'a BTreeMap<String, AccountAddress>,
+}
+
+/// A compiled package, as it is published.
+pub struct CompiledPackage {
+    pub metadata: Vec<u8>,
+    /// The modules, in publishing order.
+    pub code: Vec<Vec<u8>>,
+}
+
+/// The transactions of a publication, submitted but maybe not committed yet.
+pub struct PendingPublish {
+    pub package_dir: PathBuf,
+    pub address_name: String,
+    pub tx_info: Vec<TransactionSummary>,
+    /// The object created by the publication of an object package.
+    pub deployed_at: Option<AccountAddress>,
+    /// The checksums of the published package, when the backend can tell them.
+    pub checksums: Option<PackageChecksums>,
+}
+
+/// A committed publication.
+pub struct PublishOutcome {
+    pub tx_info: Vec<TransactionSummary>,
+    /// The object created by the publication of an object package.
+    pub deployed_at: Option<AccountAddress>,
+    /// The checksums of the published package, when the backend can tell them.
+    pub checksums: Option<PackageChecksums>,
+}
+
+/// Everything a deployment asks the compiler and the network for, so that the deployment logic
+/// can run against [`MockBackend`] instead of a network.
+///
+/// Source verification and initializers are optional steps of a deployment that still go through
+/// the aptos CLI.
+pub trait PublishBackend: Sync {
+    /// The bundle the packages are published from, whose sources may be gone.
+    fn bundle(&self) -> Option<&Bundle> {
+        None
+    }
+
+    /// Returns the sequence number of an account.
+    fn sequence_number(&self, address: AccountAddress) -> impl Future<Output = Result<u64>> + Send;
+
+    /// Returns the package named `name` published at `address` if its modules are exactly `code`.
+    fn find_up_to_date_package(
+        &self,
+        address: AccountAddress,
+        name: &str,
+        code: &[Vec<u8>],
+    ) -> impl Future<Output = Result<Option<PackageMetadata>>> + Send;
+
+    /// Returns the upgrade number of the package named `name` published at `address`.
+    fn upgrade_number(
+        &self,
+        address: AccountAddress,
+        name: &str,
+    ) -> impl Future<Output = Result<Option<u64>>> + Send;
+
+    /// Compiles a package into the payload it is published with.
+    fn compile(
+        &self,
+        request: &PublishRequest,
+    ) -> impl Future<Output = Result<CompiledPackage>> + Send;
+
+    /// Simulates the publication of a package, returning the transactions it would send.
+    fn simulate(
+        &self,
+        request: &PublishRequest,
+    ) -> impl Future<Output = Result<Vec<TransactionSummary>>> + Send;
+
+    /// Submits the transactions publishing `package`, with chunked publish when `chunked`.
+    fn submit(
+        &self,
+        request: &PublishRequest,
+        package: &CompiledPackage,
+        chunked: bool,
+    ) -> impl Future<Output = Result<PendingPublish>> + Send;
+
+    /// Returns the publication of a package whose submission from the sequence number
+    /// `sequence_number` of its sender failed, if it reached the network anyway. A submission is
+    /// only retried when this returns `None`, since publishing isn't idempotent.
+    fn find_submitted(
+        &self,
+        request: &PublishRequest,
+        sequence_number: u64,
+        chunked: bool,
+    ) -> impl Future<Output = Result<Option<PendingPublish>>> + Send;
+
+    /// Waits until the transactions of a publication are committed.
+    fn wait(&self, pending: PendingPublish) -> impl Future<Output = Result<PublishOutcome>> + Send;
+
+    /// Freezes the code of the object package `address_name` published by `sender` at
+    /// `code_object`, so that it can't be upgraded anymore.
+    fn freeze(
+        &self,
+        config: &DeployConfig,
+        address_name: &str,
+        sender: AccountAddress,
+        code_object: AccountAddress,
+    ) -> impl Future<Output = Result<TransactionSummary>> + Send;
+}
+
+/// Publishes packages with the aptos CLI.
+pub struct AptosBackend {
+    client: Client,
+}
+
+impl AptosBackend {
+    pub fn new(config: &DeployConfig) -> Result<AptosBackend> {
+        Ok(AptosBackend {
+            client: rest_client(&config.network, config.rest_url.clone())?,
+        })
+    }
+}
+
+impl PublishBackend for AptosBackend {
+    async fn sequence_number(&self, address: AccountAddress) -> Result<u64> {
+        Ok(self
+            .client
+            .get_account(address)
+            .await?
+            .into_inner()
+            .sequence_number)
+    }
+
+    async fn find_up_to_date_package(
+        &self,
+        address: AccountAddress,
+        name: &str,
+        code: &[Vec<u8>],
+    ) -> Result<Option<PackageMetadata>> {
+        find_up_to_date_package(&self.client, address, name, code).await
+    }
+
+    async fn upgrade_number(&self, address: AccountAddress, name: &str) -> Result<Option<u64>> {
+        upgrade_number(&self.client, address, name).await
+    }
+
+    async fn compile(&self, request: &PublishRequest<'_>) -> Result<CompiledPackage> {
+        let (metadata, code) = build_publish_payload(
+            request.package_dir,
+            request.address_name,
+            request.payload_named_addresses,
+            &request.config.compiler_options(request.address_name),
+            request.config.upgrade_policy(request.address_name),
+            &request
+                .config
+                .included_artifacts(request.address_name)
+                .to_string(),
+            !request.config.no_cache,
+        )
+        .await?;
+        Ok(CompiledPackage { metadata, code })
+    }
+
+    async fn simulate(&self, request: &PublishRequest<'_>) -> Result<Vec<TransactionSummary>> {
+        let (args, _copy) = publish_args(
+            request.config,
+            request.package_dir,
+            request.address_name,
+            request.named_addresses,
+        )?;
+        let mut args: Vec<&str> = args.split_whitespace().collect();
+        args.push("--local");
+        args.push("--assume-yes");
+        let (tx_info, _) = run_deploy_command(&args)
+            .await
+            .map_err(|err| JayceError::Publish(err.to_string()))?;
+        Ok(tx_info)
+    }
+
+    /// The aptos CLI compiles the package again.
+    async fn submit(
+        &self,
+        request: &PublishRequest<'_>,
+        _package: &CompiledPackage,
+        chunked: bool,
+    ) -> Result<PendingPublish> {
+        let (args, _copy) = publish_args(
+            request.config,
+            request.package_dir,
+            request.address_name,
+            request.named_addresses,
+        )?;
+        let chunked_publish_args = chunked_publish_args(request.config);
+        let mut args: Vec<&str> = args.split_whitespace().collect();
+        if request.config.yes {
+            args.push("--assume-yes");
+        }
+        if chunked {
+            args.extend(chunked_publish_args.split_whitespace());
+        }
+        let (tx_info, deployed_at) = run_deploy_command(&args)
+            .await
+            .map_err(|err| JayceError::Publish(err.to_string()))?;
+        Ok(PendingPublish {
+            package_dir: request.package_dir.to_path_buf(),
+            address_name: request.address_name.to_string(),
+            tx_info,
+            deployed_at,
+            // The publish command compiled the package again, leaving the published artifacts in
+            // the build directory.
+            checksums: Some(PackageChecksums::from_build(request.package_dir)?),
+        })
+    }
+
+    /// The aptos CLI signs with the sequence number of the account, which moves once a
+    /// transaction is committed. The publication is only recovered when it is the transaction
+    /// committed at `sequence_number`, chunked publications can't be.
+    async fn find_submitted(
+        &self,
+        request: &PublishRequest<'_>,
+        sequence_number: u64,
+        chunked: bool,
+    ) -> Result<Option<PendingPublish>> {
+        if self.sequence_number(request.sender).await? == sequence_number {
+            return Ok(None);
+        }
+        let transaction = self
+            .client
+            .get_account_transactions(request.sender, Some(sequence_number), Some(1))
+            .await?
+            .into_inner()
+            .pop();
+        let module_type = request.config.package_module_type(request.address_name);
+        match transaction {
+            Some(transaction)
+                if !chunked && transaction.success() && is_publication(&transaction) =>
+            {
+                Ok(Some(PendingPublish {
+                    package_dir: request.package_dir.to_path_buf(),
+                    address_name: request.address_name.to_string(),
+                    tx_info: vec![TransactionSummary::from(&transaction)],
+                    deployed_at: match module_type {
+                        DeployModuleType::Account => None,
+                        DeployModuleType::Object => Some(create_object_code_deployment_address(
+                            request.sender,
+                            sequence_number,
+                        )),
+                    },
+                    checksums: Some(PackageChecksums::from_build(request.package_dir)?),
+                }))
+            }
+            _ => Err(JayceError::Publish(format!(
+                "The publication of {} failed, but {} sent transactions from sequence number {} \
+                meanwhile: check them before deploying again",
+                request.address_name, request.sender, sequence_number
+            ))),
+        }
+    }
+
+    /// The aptos CLI only returns once the transactions are committed.
+    async fn wait(&self, pending: PendingPublish) -> Result<PublishOutcome> {
+        Ok(PublishOutcome {
+            tx_info: pending.tx_info,
+            deployed_at: pending.deployed_at,
+            checksums: pending.checksums,
+        })
+    }
+
+    async fn freeze(
+        &self,
+        config: &DeployConfig,
+        address_name: &str,
+        _sender: AccountAddress,
+        code_object: AccountAddress,
+    ) -> Result<TransactionSummary> {
+        freeze_code_object(config, address_name, code_object).await
+    }
+}
+
+/// Whether a transaction publishes a package, as the aptos CLI does.
+fn is_publication(transaction: &Transaction) -> bool {
+    let Transaction::UserTransaction(transaction) = transaction else {
+        return false;
+    };
+    let TransactionPayload::EntryFunctionPayload(payload) = &transaction.request.payload else {
+        return false;
+    };
+    [
+        "0x1::code::publish_package_txn",
+        "0x1::object_code_deployment::publish",
+    ]
+    .contains(&payload.function.to_string().as_str())
+}
+
+/// A backend keeping the accounts in memory, publishing every package without compiling it.
+/// It records the calls it receives, e.g. `compile cpu_addr`, and can fail the submissions of a
+/// package to test how failures are handled:
+///
+/// ```
+/// use jayce::publish_backend::MockBackend;
+///
+/// let backend = MockBackend::new().fail_submit("cpu_addr", &["503 Service Unavailable"]);
+/// assert!(backend.calls().is_empty());
+/// ```
+#[derive(Default)]
+pub struct MockBackend {
+    calls: Mutex<Vec<String>>,
+    sequence_numbers: Mutex<BTreeMap<AccountAddress, u64>>,
+    submit_failures: Mutex<BTreeMap<String, VecDeque<String>>>,
+    simulate_failures: Mutex<BTreeMap<String, String>>,
+}
+
+/// Gas used by each transaction of [`MockBackend`].
+pub const MOCK_GAS_USED: u64 = 1_000;
+/// Gas unit price of the transactions of [`MockBackend`].
+pub const MOCK_GAS_UNIT_PRICE: u64 = 100;
+
+impl MockBackend {
+    pub fn new() -> MockBackend {
+        MockBackend::default()
+    }
+
+    /// Makes the next submissions of a package fail, one per error.
+    pub fn fail_submit(self, address_name: &str, errors: &[&str]) -> MockBackend {
+        self.submit_failures
+            .lock()
+            .unwrap()
+            .entry(address_name.to_string())
+            .or_default()
+            .extend(errors.iter().map(|error| error.to_string()));
+        self
+    }
+
+    /// Makes the simulations of a package abort with a VM status.
+    pub fn fail_simulate(self, address_name: &str, vm_status: &str) -> MockBackend {
+        self.simulate_failures
+            .lock()
+            .unwrap()
+            .insert(address_name.to_string(), vm_status.to_string());
+        self
+    }
+
+    /// Returns the calls received by the backend, in order.
+    pub fn calls(&self) -> Vec<String> {
+        self.calls.lock().unwrap().clone()
+    }
+
+    /// Takes `count` sequence numbers of `sender`, returning the first one.
+    fn next_sequence_numbers(&self, sender: AccountAddress, count: u64) -> u64 {
+        let mut sequence_numbers = self.sequence_numbers.lock().unwrap();
+        let sequence_number = sequence_numbers.entry(sender).or_default();
+        *sequence_number += count;
+        *sequence_number - count
+    }
+
+    fn record(&self, call: &str, address_name: &str) {
+        self.calls
+            .lock()
+            .unwrap()
+            .push(format!("{} {}", call, address_name));
+    }
+}
+
+/// Builds `count` successful transactions of `sender`, from the sequence number `sequence_number`.
+fn mock_transactions(
+    sender: AccountAddress,
+    sequence_number: u64,
+    count: u64,
+) -> Vec<TransactionSummary> {
+    (sequence_number..sequence_number + count)
+        .map(|sequence_number| TransactionSummary {
+            transaction_hash: HashValue::sha3_256_of(
+                format!("{} {}", sender, sequence_number).as_bytes(),
+            ),
+            gas_used: Some(MOCK_GAS_USED),
+            gas_unit_price: Some(MOCK_GAS_UNIT_PRICE),
+            pending: None,
+            sender: Some(sender),
+            sequence_number: Some(sequence_number),
+            success: Some(true),
+            timestamp_us: None,
+            version: None,
+            vm_status: Some("Executed successfully".to_string()),
+        })
+        .collect()
+}
+
+impl PublishBackend for MockBackend {
+    async fn sequence_number(&self, address: AccountAddress) -> Result<u64> {
+        Ok(self
+            .sequence_numbers
+            .lock()
+            .unwrap()
+            .get(&address)
+            .copied()
+            .unwrap_or_default())
+    }
+
+    /// Nothing is published before the mock starts.
+    async fn find_up_to_date_package(
+        &self,
+        _address: AccountAddress,
+        _name: &str,
+        _code: &[Vec<u8>],
+    ) -> Result<Option<PackageMetadata>> {
+        Ok(None)
+    }
+
+    /// Upgrade numbers aren't tracked.
+    async fn upgrade_number(&self, _address: AccountAddress, _name: &str) -> Result<Option<u64>> {
+        Ok(None)
+    }
+
+    async fn compile(&self, request: &PublishRequest<'_>) -> Result<CompiledPackage> {
+        self.record("compile", request.address_name);
+        Ok(CompiledPackage {
+            metadata: request.address_name.as_bytes().to_vec(),
+            code: vec![request.payload_named_addresses.as_bytes().to_vec()],
+        })
+    }
+
+    async fn simulate(&self, request: &PublishRequest<'_>) -> Result<Vec<TransactionSummary>> {
+        self.record("simulate", request.address_name);
+        let sequence_number = self.sequence_number(request.sender).await?;
+        let mut tx_info = mock_transactions(request.sender, sequence_number, 1);
+        if let Some(vm_status) = self
+            .simulate_failures
+            .lock()
+            .unwrap()
+            .get(request.address_name)
+        {
+            tx_info[0].success = Some(false);
+            tx_info[0].vm_status = Some(vm_status.clone());
+        }
+        Ok(tx_info)
+    }
+
+    async fn submit(
+        &self,
+        request: &PublishRequest<'_>,
+        _package: &CompiledPackage,
+        chunked: bool,
+    ) -> Result<PendingPublish> {
+        self.record("submit", request.address_name);
+        if let Some(error) = self
+            .submit_failures
+            .lock()
+            .unwrap()
+            .get_mut(request.address_name)
+            .and_then(VecDeque::pop_front)
+        {
+            return Err(JayceError::Publish(error));
+        }
+        // Chunked publish stages the package in a first transaction and publishes it in a
+        // second one.
+        let count = if chunked { 2 } else { 1 };
+        let sequence_number = self.next_sequence_numbers(request.sender, count);
+        let tx_info = mock_transactions(request.sender, sequence_number, count);
+        let deployed_at = match request.config.package_module_type(request.address_name) {
+            DeployModuleType::Account => None,
+            DeployModuleType::Object => Some(create_object_code_deployment_address(
+                request.sender,
+                sequence_number + count - 1,
+            )),
+        };
+        Ok(PendingPublish {
+            package_dir: request.package_dir.to_path_buf(),
+            address_name: request.address_name.to_string(),
+            tx_info,
+            deployed_at,
+            checksums: None,
+        })
+    }
+
+    /// The submissions of the mock fail before reaching the network.
+    async fn find_submitted(
+        &self,
+        request: &PublishRequest<'_>,
+        _sequence_number: u64,
+        _chunked: bool,
+    ) -> Result<Option<PendingPublish>> {
+        self.record("find_submitted", request.address_name);
+        Ok(None)
+    }
+
+    async fn wait(&self, pending: PendingPublish) -> Result<PublishOutcome> {
+        self.record("wait", &pending.address_name);
+        Ok(PublishOutcome {
+            tx_info: pending.tx_info,
+            deployed_at: pending.deployed_at,
+            checksums: pending.checksums,
+        })
+    }
+
+    async fn freeze(
+        &self,
+        _config: &DeployConfig,
+        address_name: &str,
+        sender: AccountAddress,
+        _code_object: AccountAddress,
+    ) -> Result<TransactionSummary> {
+        self.record("freeze", address_name);
+        let sequence_number = self.next_sequence_numbers(sender, 1);
+        Ok(mock_transactions(sender, sequence_number, 1).remove(0))
+    }
+}
@@ -0,0 +1,148 @@
+use std::io::{self, Write};
+use std::panic;
+use std::sync::{OnceLock, RwLock};
+
+const MASK: &str = "[REDACTED]";
+
+/// Prefixes of the values that are masked even when they weren't registered: AIP-80 private keys,
+/// the private key argument of the aptos CLI, also in debug-formatted argument lists, and private
+/// keys of configuration files.
+const SECRET_PREFIXES: [&str; 5] = [
+    "ed25519-priv-",
+    "--private-key ",
+    "--private-key=",
+    "--private-key\", \"",
+    "private_key = \"",
+];
+
+fn secrets() -> &'static RwLock<Vec<String>> {
+    static SECRETS: OnceLock<RwLock<Vec<String>>> = OnceLock::new();
+    SECRETS.get_or_init(Default::default)
+}
+
+/// Registers a secret, e.g. a private key, to mask in the logs and the error messages.
+pub fn register_secret(secret: &str) {
+    let secret = secret.trim();
+    let secret = secret.strip_prefix("ed25519-priv-").unwrap_or(secret);
+    let secret = secret.strip_prefix("0x").unwrap_or(secret);
+    // Short values would mask unrelated parts of the messages.
+    if secret.len() < 16 {
+        return;
+    }
+    let mut secrets = secrets().write().unwrap();
+    if !secrets.iter().any(|registered| registered == secret) {
+        secrets.push(secret.to_string());
+    }
+}
+
+/// Masks the registered secrets and anything that looks like a private key in a message.
+pub fn redact(message: &str) -> String {
+    let mut redacted = message.to_string();
+    for secret in secrets().read().unwrap().iter() {
+        redacted = redacted.replace(secret.as_str(), MASK);
+    }
+    for prefix in SECRET_PREFIXES {
+        redacted = mask_values(&redacted, prefix);
+    }
+    redacted
+}
+
+/// Masks the value following each occurrence of `prefix`, up to the next whitespace, quote or
+/// comma.
+fn mask_values(message: &str, prefix: &str) -> String {
+    let mut redacted = String::with_capacity(message.len());
+    let mut rest = message;
+    while let Some(index) = rest.find(prefix) {
+        let (before, after) = rest.split_at(index + prefix.len());
+        redacted.push_str(before);
+        let end = after
+            .find(|c: char| c.is_whitespace() || c == '"' || c == '\'' || c == ',')
+            .unwrap_or(after.len());
+        if end > 0 {
+            redacted.push_str(MASK);
+        }
+        rest = &after[end..];
+    }
+    redacted.push_str(rest);
+    redacted
+}
+
+/// A writer masking secrets in everything written to it, used for the logs.
+pub struct RedactingWriter<W>(pub W);
+
+impl<W: Write> Write for RedactingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0
+            .write_all(redact(&String::from_utf8_lossy(buf)).as_bytes())?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.flush()
+    }
+}
+
+/// Wraps the panic hook with one masking secrets in the panic messages. Panics without secrets are
+/// left to the previous hook, the others are printed masked instead.
+pub fn install_panic_hook() {
+    let previous = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        let message = info.to_string();
+        let redacted = redact(&message);
+        if redacted == message {
+            previous(info);
+        } else {
+            eprintln!("{}", redacted);
+        }
+    }));
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Write;
+
+    use crate::redact::{redact, register_secret, RedactingWriter};
+
+    #[test]
+    fn test_redact() {
+        let private_key = format!("0x{}", "a1".repeat(32));
+        register_secret(&private_key);
+        assert_eq!(
+            redact(&format!("Invalid private key: {}", private_key)),
+            "Invalid private key: 0x[REDACTED]"
+        );
+        assert_eq!(
+            redact(&format!(
+                "`aptos init --private-key 0x{} --network devnet` failed",
+                "b2".repeat(32)
+            )),
+            "`aptos init --private-key [REDACTED] --network devnet` failed"
+        );
+        assert_eq!(
+            redact(&format!("key: ed25519-priv-0x{}", "c3".repeat(32))),
+            "key: ed25519-priv-[REDACTED]"
+        );
+        assert_eq!(
+            redact("private_key = \"0xabc\"\nnetwork = \"devnet\""),
+            "private_key = \"[REDACTED]\"\nnetwork = \"devnet\""
+        );
+        assert_eq!(
+            redact(&format!(
+                "{:?}",
+                ["move", "publish", "--private-key", "d4".repeat(32).as_str()]
+            )),
+            "[\"move\", \"publish\", \"--private-key\", \"[REDACTED]\"]"
+        );
+        assert_eq!(
+            redact("--private-key-file key.txt"),
+            "--private-key-file key.txt"
+        );
+
+        let mut output = vec![];
+        write!(RedactingWriter(&mut output), "signing with {}", private_key).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "signing with 0x[REDACTED]"
+        );
+    }
+}
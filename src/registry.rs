@@ -0,0 +1,83 @@
+use std::fs;
+use std::path::Path;
+
+use aptos_sdk::move_types::account_address::AccountAddress;
+use serde::{Deserialize, Serialize};
+
+use crate::entry_function::EntryFunctionCall;
+use crate::error::Result;
+use crate::package_registry::PackageMetadata;
+
+/// The name of the registry package, in its Move.toml.
+pub const REGISTRY_PACKAGE_NAME: &str = "JayceRegistry";
+
+const REGISTRY_MOVE_TOML: &str = include_str!("../move/registry/Move.toml");
+const REGISTRY_SOURCE: &str = include_str!("../move/registry/sources/registry.move");
+
+/// The on-chain registry recording every deployment, from the `[registry]` table.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct RegistryConfig {
+    /// The address the registry package is published at. By default, it is published at the
+    /// deployer's account if it isn't there yet.
+    pub address: Option<AccountAddress>,
+}
+
+/// Writes the registry package to `dir`, to be published.
+pub fn write_registry_package(dir: &Path) -> Result<()> {
+    fs::create_dir_all(dir.join("sources"))?;
+    fs::write(dir.join("Move.toml"), REGISTRY_MOVE_TOML)?;
+    fs::write(dir.join("sources").join("registry.move"), REGISTRY_SOURCE)?;
+    Ok(())
+}
+
+/// The call recording the deployment of a package, as published at `package_address`.
+pub fn record_call(
+    registry: AccountAddress,
+    package: &PackageMetadata,
+    package_address: AccountAddress,
+) -> EntryFunctionCall {
+    EntryFunctionCall {
+        function_id: format!("{}::registry::record", registry),
+        type_args: vec![],
+        args: vec![
+            format!("string:{}", package.name),
+            format!("u64:{}", package.upgrade_number),
+            format!("address:{}", package_address),
+            format!("string:{}", package.source_digest),
+        ],
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use aptos_sdk::move_types::account_address::AccountAddress;
+
+    use crate::package_registry::{PackageMetadata, UpgradePolicy};
+    use crate::registry::record_call;
+
+    #[test]
+    fn test_record_call() {
+        let package = PackageMetadata {
+            name: "Verifier".to_string(),
+            upgrade_number: 2,
+            upgrade_policy: UpgradePolicy { policy: 1 },
+            source_digest: "ABCD".to_string(),
+            modules: vec![],
+        };
+
+        let call = record_call(AccountAddress::ONE, &package, AccountAddress::TWO);
+        assert_eq!(
+            call.function_id,
+            format!("{}::registry::record", AccountAddress::ONE)
+        );
+        assert_eq!(
+            call.args,
+            vec![
+                "string:Verifier".to_string(),
+                "u64:2".to_string(),
+                format!("address:{}", AccountAddress::TWO),
+                "string:ABCD".to_string(),
+            ]
+        );
+    }
+}
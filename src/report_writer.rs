@@ -0,0 +1,210 @@
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use strum_macros::Display;
+
+use crate::deploy_report::{DeployReport, DeployStatus, TxReport};
+use crate::error::Result;
+
+/// The format the deployment report is written in. Only JSON reports can be read back by the
+/// commands taking a deployment report.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, ValueEnum, Display)]
+#[serde(rename_all(serialize = "snake_case"))]
+#[strum(serialize_all = "snake_case")]
+pub enum ReportFormat {
+    Json,
+    Yaml,
+    Toml,
+    Markdown,
+}
+
+/// Renders a deployment report to the content of the report file.
+pub trait ReportWriter {
+    fn write(&self, report: &DeployReport) -> Result<String>;
+}
+
+pub struct JsonReportWriter;
+
+pub struct YamlReportWriter;
+
+pub struct TomlReportWriter;
+
+/// Writes a human-readable Markdown table of the deployed packages, with explorer links.
+pub struct MarkdownReportWriter;
+
+impl ReportFormat {
+    pub fn writer(&self) -> Box<dyn ReportWriter> {
+        match self {
+            ReportFormat::Json => Box::new(JsonReportWriter),
+            ReportFormat::Yaml => Box::new(YamlReportWriter),
+            ReportFormat::Toml => Box::new(TomlReportWriter),
+            ReportFormat::Markdown => Box::new(MarkdownReportWriter),
+        }
+    }
+}
+
+impl ReportWriter for JsonReportWriter {
+    fn write(&self, report: &DeployReport) -> Result<String> {
+        Ok(serde_json::to_string_pretty(report)?)
+    }
+}
+
+impl ReportWriter for YamlReportWriter {
+    fn write(&self, report: &DeployReport) -> Result<String> {
+        Ok(serde_yaml::to_string(report).map_err(anyhow::Error::from)?)
+    }
+}
+
+impl ReportWriter for TomlReportWriter {
+    fn write(&self, report: &DeployReport) -> Result<String> {
+        Ok(toml::to_string_pretty(report).map_err(anyhow::Error::from)?)
+    }
+}
+
+impl ReportWriter for MarkdownReportWriter {
+    fn write(&self, report: &DeployReport) -> Result<String> {
+        let link = |kind: &str, id: String| match report.network.explorer_url(kind, &id) {
+            Some(url) => format!("[{}]({})", id, url),
+            None => id,
+        };
+        let mut content = format!(
+            "# Deployment report\n\n- Network: {}\n- Account: {}\n",
+            report.network,
+            link("account", report.account.to_string())
+        );
+        if let Some(fee_payer) = report.fee_payer {
+            content.push_str(&format!(
+                "- Fee payer: {}\n",
+                link("account", fee_payer.to_string())
+            ));
+        }
+        if let Some(gas) = &report.gas {
+            content.push_str(&format!(
+                "- Gas used: {} in {} transactions, {} APT\n",
+                gas.gas_used,
+                gas.transactions,
+                gas.fee_apt()
+            ));
+        }
+        // Packages published by other accounts than the one of the report get their own section.
+        let mut senders = vec![report.account];
+        for sender in report.info.iter().filter_map(|tx_report| tx_report.sender) {
+            if !senders.contains(&sender) {
+                senders.push(sender);
+            }
+        }
+        for sender in &senders {
+            if senders.len() > 1 {
+                content.push_str(&format!(
+                    "\n## Published by {}\n",
+                    link("account", sender.to_string())
+                ));
+            }
+            write_table(
+                &mut content,
+                report
+                    .info
+                    .iter()
+                    .filter(|tx_report| tx_report.sender.unwrap_or(report.account) == *sender),
+                &link,
+            );
+        }
+        Ok(content)
+    }
+}
+
+/// Writes the table of the packages of a Markdown report.
+fn write_table<'a>(
+    content: &mut String,
+    tx_reports: impl Iterator<Item = &'a TxReport>,
+    link: &impl Fn(&str, String) -> String,
+) {
+    content
+        .push_str("\n| Address name | Deployed at | Package | Upgrade policy | Transactions |\n");
+    content.push_str("| --- | --- | --- | --- | --- |\n");
+    for tx_report in tx_reports {
+        let transactions = tx_report
+            .transactions()
+            .map(|tx| link("txn", tx.transaction_hash.to_string()))
+            .collect::<Vec<_>>()
+            .join(", ");
+        content.push_str(&format!(
+            "| {} | {} | {} | {} | {} |\n",
+            tx_report.address_name,
+            match (&tx_report.status, &tx_report.explorer_url) {
+                (DeployStatus::Deployed, Some(explorer_url)) => {
+                    format!("[{}]({})", tx_report.deployed_at, explorer_url)
+                }
+                (DeployStatus::Deployed, None) => {
+                    link("account", tx_report.deployed_at.to_string())
+                }
+                (status, _) => format!(
+                    "{}: {}",
+                    status,
+                    tx_report.error.as_deref().unwrap_or_default()
+                ),
+            },
+            tx_report.module_path.to_str().unwrap(),
+            tx_report
+                .upgrade_policy
+                .as_ref()
+                .map(|policy| policy.to_string())
+                .unwrap_or_default(),
+            transactions
+        ));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::BTreeMap;
+    use std::path::PathBuf;
+
+    use aptos_sdk::move_types::account_address::AccountAddress;
+
+    use crate::deploy_config::{AptosNetwork, UpgradePolicy};
+    use crate::deploy_report::{DeployReport, DeployStatus, TxReport};
+    use crate::report_writer::ReportFormat;
+
+    #[test]
+    fn test_markdown_report() {
+        let report = DeployReport {
+            account: AccountAddress::ONE,
+            fee_payer: None,
+            network: AptosNetwork::Testnet,
+            gas: None,
+            git_commit: None,
+            git_commits: BTreeMap::new(),
+            signature: None,
+            info: vec![TxReport {
+                module_path: PathBuf::from("libs"),
+                address_name: "lib_addr".to_string(),
+                deployed_at: AccountAddress::TWO,
+                sender: None,
+                tx_info: vec![],
+                freeze_tx_info: None,
+                init_tx_info: vec![],
+                registry_tx_info: None,
+                proposal_id: None,
+                payload_path: None,
+                explorer_url: None,
+                tx_explorer_urls: vec![],
+                upgrade_policy: Some(UpgradePolicy::Immutable),
+                upgrade_number: None,
+                artifacts_path: None,
+                checksums: None,
+                compiler: None,
+                included_artifacts: None,
+                jayce_version: None,
+                status: DeployStatus::Deployed,
+                error: None,
+            }],
+        };
+
+        let content = ReportFormat::Markdown.writer().write(&report).unwrap();
+        assert!(content.contains(&format!(
+            "| lib_addr | [{0}](https://explorer.aptoslabs.com/account/{0}?network=testnet) | libs \
+            | immutable |  |",
+            AccountAddress::TWO
+        )));
+    }
+}
@@ -0,0 +1,207 @@
+use std::fmt::Display;
+use std::future::Future;
+use std::time::Duration;
+
+use aptos::common::types::CliError;
+use aptos_sdk::rest_client::error::RestError;
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::error::JayceError;
+use crate::telemetry::metrics;
+
+/// How transient failures of the REST API and the faucet are retried.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct RetryPolicy {
+    /// Maximum number of attempts, including the first one.
+    pub max_attempts: u32,
+    /// Delay before the first retry, doubled after each retry.
+    pub initial_backoff_ms: u64,
+    pub max_backoff_ms: u64,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: 4,
+            initial_backoff_ms: 1_000,
+            max_backoff_ms: 30_000,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Returns the delay before the retry following the attempt `attempt`, starting from 1.
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        Duration::from_millis(
+            self.initial_backoff_ms
+                .saturating_mul(1 << (attempt - 1).min(16))
+                .min(self.max_backoff_ms),
+        )
+    }
+
+    /// Runs `operation` until it succeeds, fails with an error that isn't transient, or the
+    /// attempts are exhausted. The operation must be safe to repeat, e.g. a read or a simulation.
+    pub async fn run<T, E, F, Fut>(&self, mut operation: F) -> std::result::Result<T, E>
+    where
+        E: Display + Transient,
+        F: FnMut() -> Fut,
+        Fut: Future<Output = std::result::Result<T, E>>,
+    {
+        let mut attempt = 1;
+        loop {
+            match operation().await {
+                Err(err) if attempt < self.max_attempts && err.is_transient() => {
+                    let backoff = self.backoff(attempt);
+                    warn!(
+                        "Attempt {} failed: {}, retrying in {:?}...",
+                        attempt, err, backoff
+                    );
+                    metrics().retries.add(1, &[]);
+                    tokio::time::sleep(backoff).await;
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+/// An error that may be transient: rate limiting, server errors, timeouts and connection
+/// failures.
+pub trait Transient {
+    fn is_transient(&self) -> bool;
+}
+
+/// Whether an HTTP status code reports rate limiting or an unavailable server.
+pub fn is_transient_status(status_code: u16) -> bool {
+    matches!(status_code, 429 | 502 | 503 | 504)
+}
+
+/// Whether an error message reports a transient failure, for the errors that are only known by
+/// their message, e.g. the ones of the aptos CLI. Status codes are matched with their reason
+/// phrase, a bare number could be part of an address or a version.
+pub fn is_transient_message(message: &str) -> bool {
+    let message = message.to_lowercase();
+    [
+        "too many requests",
+        "rate limit",
+        "bad gateway",
+        "service unavailable",
+        "gateway timeout",
+        "timed out",
+        "connection reset",
+        "connection refused",
+        "error sending request",
+    ]
+    .iter()
+    .any(|pattern| message.contains(pattern))
+}
+
+impl Transient for RestError {
+    fn is_transient(&self) -> bool {
+        match self {
+            RestError::Api(err) => is_transient_status(err.status_code.as_u16()),
+            RestError::Http(status_code, err) => {
+                is_transient_status(status_code.as_u16()) || err.is_timeout() || err.is_connect()
+            }
+            RestError::Timeout(_) => true,
+            RestError::Unknown(err) => err.is_transient(),
+            _ => false,
+        }
+    }
+}
+
+impl Transient for anyhow::Error {
+    fn is_transient(&self) -> bool {
+        if let Some(err) = self.downcast_ref::<RestError>() {
+            return err.is_transient();
+        }
+        if let Some(err) = self.downcast_ref::<reqwest::Error>() {
+            return err.is_timeout()
+                || err.is_connect()
+                || err
+                    .status()
+                    .is_some_and(|status| is_transient_status(status.as_u16()));
+        }
+        is_transient_message(&self.to_string())
+    }
+}
+
+impl Transient for JayceError {
+    fn is_transient(&self) -> bool {
+        match self {
+            JayceError::Rest(err) => err.is_transient(),
+            JayceError::Other(err) => err.is_transient(),
+            // The aptos CLI and the faucet only report their network failures as messages.
+            JayceError::Publish(message)
+            | JayceError::Faucet(message)
+            | JayceError::Cli(CliError::ApiError(message)) => is_transient_message(message),
+            _ => false,
+        }
+    }
+}
+
+impl Transient for String {
+    fn is_transient(&self) -> bool {
+        is_transient_message(self)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use crate::retry::{is_transient_message, is_transient_status, RetryPolicy};
+
+    #[test]
+    fn test_retry_policy() {
+        let policy = RetryPolicy {
+            max_attempts: 5,
+            initial_backoff_ms: 100,
+            max_backoff_ms: 300,
+        };
+        assert_eq!(policy.backoff(1), Duration::from_millis(100));
+        assert_eq!(policy.backoff(2), Duration::from_millis(200));
+        assert_eq!(policy.backoff(3), Duration::from_millis(300));
+
+        assert!(is_transient_message("API error: 429 Too Many Requests"));
+        assert!(is_transient_message("error sending request for url"));
+        assert!(!is_transient_message("Move abort: EPACKAGE_DEP_MISSING"));
+        // Status codes are only matched with their reason phrase.
+        assert!(!is_transient_message(
+            "Transaction 0x5030 failed at version 429"
+        ));
+        assert!(is_transient_status(503));
+        assert!(!is_transient_status(400));
+    }
+
+    #[tokio::test]
+    async fn test_retry_transient_errors() {
+        let policy = RetryPolicy {
+            max_attempts: 3,
+            initial_backoff_ms: 1,
+            max_backoff_ms: 1,
+        };
+        let mut attempts = 0;
+        let result: Result<(), String> = policy
+            .run(|| {
+                attempts += 1;
+                async { Err("503 Service Unavailable".to_string()) }
+            })
+            .await;
+        assert!(result.is_err());
+        assert_eq!(attempts, 3);
+
+        let mut attempts = 0;
+        let result: Result<(), String> = policy
+            .run(|| {
+                attempts += 1;
+                async { Err("invalid argument".to_string()) }
+            })
+            .await;
+        assert!(result.is_err());
+        assert_eq!(attempts, 1);
+    }
+}
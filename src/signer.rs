@@ -0,0 +1,394 @@
+use std::future::Future;
+
+use aptos_sdk::crypto::ed25519::{Ed25519PrivateKey, Ed25519PublicKey, Ed25519Signature};
+use aptos_sdk::crypto::{signing_message, SigningKey, ValidCryptoMaterialStringExt};
+use aptos_sdk::move_types::account_address::AccountAddress;
+use aptos_sdk::types::transaction::authenticator::{AccountAuthenticator, AuthenticationKey};
+use aptos_sdk::types::transaction::{RawTransaction, RawTransactionWithData, SignedTransaction};
+use aptos_sdk::types::LocalAccount;
+use serde::{Deserialize, Serialize};
+
+use crate::error::{JayceError, Result};
+use crate::http_signer::{HttpSigner, HttpSignerConfig};
+use crate::keyless::{KeylessConfig, KeylessSigner};
+use crate::kms::{AwsKmsSigner, GcpKmsSigner, VaultSigner};
+
+/// Signs the transactions of the deployer, with an Ed25519 key which may never leave the service
+/// holding it unless the signer builds its own transactions.
+pub trait Signer: Sync {
+    fn public_key(&self) -> &Ed25519PublicKey;
+
+    /// Signs the signing message of a transaction, i.e. its BCS bytes prefixed with the hash of
+    /// its type.
+    fn sign_message(&self, message: &[u8])
+        -> impl Future<Output = Result<Ed25519Signature>> + Send;
+
+    /// The address of the account of the key.
+    fn address(&self) -> AccountAddress {
+        AuthenticationKey::ed25519(self.public_key()).account_address()
+    }
+
+    /// Signs a transaction, co-signed by `secondary_signers` and paid by `fee_payer` when there
+    /// are some.
+    fn sign_transaction(
+        &self,
+        raw_transaction: RawTransaction,
+        secondary_signers: &[LocalAccount],
+        fee_payer: Option<&LocalAccount>,
+    ) -> impl Future<Output = Result<SignedTransaction>> + Send {
+        async move {
+            let public_key = self.public_key().clone();
+            let secondary_addresses: Vec<AccountAddress> = secondary_signers
+                .iter()
+                .map(LocalAccount::address)
+                .collect();
+            let message = match fee_payer {
+                None if secondary_signers.is_empty() => signing_message(&raw_transaction),
+                None => signing_message(&RawTransactionWithData::new_multi_agent(
+                    raw_transaction.clone(),
+                    secondary_addresses.clone(),
+                )),
+                Some(fee_payer) => signing_message(&RawTransactionWithData::new_fee_payer(
+                    raw_transaction.clone(),
+                    secondary_addresses.clone(),
+                    fee_payer.address(),
+                )),
+            }
+            .map_err(anyhow::Error::from)?;
+            let signature = self.sign_message(&message).await?;
+            let cosign = |account: &LocalAccount| {
+                AccountAuthenticator::ed25519(
+                    account.public_key().clone(),
+                    account.private_key().sign_arbitrary_message(&message),
+                )
+            };
+            Ok(match fee_payer {
+                None if secondary_signers.is_empty() => {
+                    SignedTransaction::new(raw_transaction, public_key, signature)
+                }
+                None => SignedTransaction::new_multi_agent(
+                    raw_transaction,
+                    AccountAuthenticator::ed25519(public_key, signature),
+                    secondary_addresses,
+                    secondary_signers.iter().map(cosign).collect(),
+                ),
+                Some(fee_payer) => SignedTransaction::new_fee_payer(
+                    raw_transaction,
+                    AccountAuthenticator::ed25519(public_key, signature),
+                    secondary_addresses,
+                    secondary_signers.iter().map(cosign).collect(),
+                    fee_payer.address(),
+                    cosign(fee_payer),
+                ),
+            })
+        }
+    }
+
+    /// Builds a transaction with invalid signatures, as the simulation API requires.
+    fn simulation_transaction(
+        &self,
+        raw_transaction: RawTransaction,
+        secondary_signers: &[LocalAccount],
+        fee_payer: Option<&LocalAccount>,
+    ) -> Result<SignedTransaction> {
+        let public_key = self.public_key().clone();
+        let signature = Ed25519Signature::dummy_signature();
+        let dummy = |account: &LocalAccount| {
+            AccountAuthenticator::ed25519(account.public_key().clone(), signature.clone())
+        };
+        let secondary_addresses = secondary_signers
+            .iter()
+            .map(LocalAccount::address)
+            .collect();
+        Ok(match fee_payer {
+            None if secondary_signers.is_empty() => {
+                SignedTransaction::new(raw_transaction, public_key, signature)
+            }
+            None => SignedTransaction::new_multi_agent(
+                raw_transaction,
+                AccountAuthenticator::ed25519(public_key, signature.clone()),
+                secondary_addresses,
+                secondary_signers.iter().map(dummy).collect(),
+            ),
+            Some(fee_payer) => SignedTransaction::new_fee_payer(
+                raw_transaction,
+                AccountAuthenticator::ed25519(public_key, signature.clone()),
+                secondary_addresses,
+                secondary_signers.iter().map(dummy).collect(),
+                fee_payer.address(),
+                dummy(fee_payer),
+            ),
+        })
+    }
+}
+
+/// Signs with a private key held by jayce.
+pub struct PrivateKeySigner {
+    private_key: Ed25519PrivateKey,
+    public_key: Ed25519PublicKey,
+}
+
+impl PrivateKeySigner {
+    pub fn from_private_key(private_key: &str) -> Result<PrivateKeySigner> {
+        let private_key = Ed25519PrivateKey::from_encoded_string(private_key)
+            .map_err(|err| JayceError::Config(format!("Invalid private key: {}", err)))?;
+        Ok(PrivateKeySigner {
+            public_key: Ed25519PublicKey::from(&private_key),
+            private_key,
+        })
+    }
+}
+
+impl Signer for PrivateKeySigner {
+    fn public_key(&self) -> &Ed25519PublicKey {
+        &self.public_key
+    }
+
+    async fn sign_message(&self, message: &[u8]) -> Result<Ed25519Signature> {
+        Ok(self.private_key.sign_arbitrary_message(message))
+    }
+}
+
+/// An external service signing the transactions of the deployer, from the `[signer]` table.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SignerConfig {
+    /// An AWS KMS key of the `ECC_NIST_EDWARDS25519` spec, used with the credentials of the
+    /// `AWS_ACCESS_KEY_ID`, `AWS_SECRET_ACCESS_KEY` and `AWS_SESSION_TOKEN` environment variables.
+    AwsKms {
+        key_id: String,
+        /// Defaults to the `AWS_REGION` environment variable.
+        region: Option<String>,
+    },
+    /// A GCP Cloud KMS key version of the `EC_SIGN_ED25519` algorithm, e.g.
+    /// `projects/p/locations/global/keyRings/r/cryptoKeys/k/cryptoKeyVersions/1`, used with the
+    /// token of the `GOOGLE_OAUTH_ACCESS_TOKEN` environment variable, or of `gcloud`.
+    GcpKms { key_version: String },
+    /// A HashiCorp Vault transit key of the `ed25519` type, used with the token of the
+    /// `VAULT_TOKEN` environment variable.
+    Vault {
+        key_name: String,
+        /// Defaults to the `VAULT_ADDR` environment variable.
+        address: Option<String>,
+        /// Path of the transit secrets engine.
+        #[serde(default = "default_transit_mount")]
+        mount: String,
+    },
+    /// A signing service over HTTP.
+    Http(HttpSignerConfig),
+    /// A keyless account of an OIDC identity.
+    Keyless(KeylessConfig),
+}
+
+fn default_transit_mount() -> String {
+    "transit".to_string()
+}
+
+/// One of the signers, selected by the configuration.
+pub enum AnySigner {
+    PrivateKey(PrivateKeySigner),
+    AwsKms(AwsKmsSigner),
+    GcpKms(GcpKmsSigner),
+    Vault(VaultSigner),
+    Http(HttpSigner),
+    Keyless(KeylessSigner),
+}
+
+impl AnySigner {
+    /// Connects to the service of a signer and fetches its public key.
+    pub async fn connect(config: &SignerConfig) -> Result<AnySigner> {
+        Ok(match config {
+            SignerConfig::AwsKms { key_id, region } => {
+                AnySigner::AwsKms(AwsKmsSigner::connect(key_id, region.clone()).await?)
+            }
+            SignerConfig::GcpKms { key_version } => {
+                AnySigner::GcpKms(GcpKmsSigner::connect(key_version).await?)
+            }
+            SignerConfig::Vault {
+                key_name,
+                address,
+                mount,
+            } => AnySigner::Vault(VaultSigner::connect(key_name, address.clone(), mount).await?),
+            SignerConfig::Http(config) => AnySigner::Http(HttpSigner::connect(config).await?),
+            SignerConfig::Keyless(config) => {
+                AnySigner::Keyless(KeylessSigner::connect(config).await?)
+            }
+        })
+    }
+}
+
+impl Signer for AnySigner {
+    fn public_key(&self) -> &Ed25519PublicKey {
+        match self {
+            AnySigner::PrivateKey(signer) => signer.public_key(),
+            AnySigner::AwsKms(signer) => signer.public_key(),
+            AnySigner::GcpKms(signer) => signer.public_key(),
+            AnySigner::Vault(signer) => signer.public_key(),
+            AnySigner::Http(signer) => signer.public_key(),
+            AnySigner::Keyless(signer) => signer.public_key(),
+        }
+    }
+
+    async fn sign_message(&self, message: &[u8]) -> Result<Ed25519Signature> {
+        match self {
+            AnySigner::PrivateKey(signer) => signer.sign_message(message).await,
+            AnySigner::AwsKms(signer) => signer.sign_message(message).await,
+            AnySigner::GcpKms(signer) => signer.sign_message(message).await,
+            AnySigner::Vault(signer) => signer.sign_message(message).await,
+            AnySigner::Http(signer) => signer.sign_message(message).await,
+            AnySigner::Keyless(signer) => signer.sign_message(message).await,
+        }
+    }
+
+    fn address(&self) -> AccountAddress {
+        match self {
+            AnySigner::PrivateKey(signer) => signer.address(),
+            AnySigner::AwsKms(signer) => signer.address(),
+            AnySigner::GcpKms(signer) => signer.address(),
+            AnySigner::Vault(signer) => signer.address(),
+            AnySigner::Http(signer) => signer.address(),
+            AnySigner::Keyless(signer) => signer.address(),
+        }
+    }
+
+    async fn sign_transaction(
+        &self,
+        raw_transaction: RawTransaction,
+        secondary_signers: &[LocalAccount],
+        fee_payer: Option<&LocalAccount>,
+    ) -> Result<SignedTransaction> {
+        match self {
+            AnySigner::PrivateKey(signer) => {
+                signer
+                    .sign_transaction(raw_transaction, secondary_signers, fee_payer)
+                    .await
+            }
+            AnySigner::AwsKms(signer) => {
+                signer
+                    .sign_transaction(raw_transaction, secondary_signers, fee_payer)
+                    .await
+            }
+            AnySigner::GcpKms(signer) => {
+                signer
+                    .sign_transaction(raw_transaction, secondary_signers, fee_payer)
+                    .await
+            }
+            AnySigner::Vault(signer) => {
+                signer
+                    .sign_transaction(raw_transaction, secondary_signers, fee_payer)
+                    .await
+            }
+            AnySigner::Http(signer) => {
+                signer
+                    .sign_transaction(raw_transaction, secondary_signers, fee_payer)
+                    .await
+            }
+            AnySigner::Keyless(signer) => {
+                signer
+                    .sign_transaction(raw_transaction, secondary_signers, fee_payer)
+                    .await
+            }
+        }
+    }
+
+    fn simulation_transaction(
+        &self,
+        raw_transaction: RawTransaction,
+        secondary_signers: &[LocalAccount],
+        fee_payer: Option<&LocalAccount>,
+    ) -> Result<SignedTransaction> {
+        match self {
+            AnySigner::PrivateKey(signer) => {
+                signer.simulation_transaction(raw_transaction, secondary_signers, fee_payer)
+            }
+            AnySigner::AwsKms(signer) => {
+                signer.simulation_transaction(raw_transaction, secondary_signers, fee_payer)
+            }
+            AnySigner::GcpKms(signer) => {
+                signer.simulation_transaction(raw_transaction, secondary_signers, fee_payer)
+            }
+            AnySigner::Vault(signer) => {
+                signer.simulation_transaction(raw_transaction, secondary_signers, fee_payer)
+            }
+            AnySigner::Http(signer) => {
+                signer.simulation_transaction(raw_transaction, secondary_signers, fee_payer)
+            }
+            AnySigner::Keyless(signer) => {
+                signer.simulation_transaction(raw_transaction, secondary_signers, fee_payer)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use aptos_sdk::crypto::Signature;
+    use aptos_sdk::move_types::account_address::AccountAddress;
+    use aptos_sdk::transaction_builder::{aptos_stdlib, TransactionFactory};
+    use aptos_sdk::types::chain_id::ChainId;
+    use aptos_sdk::types::transaction::RawTransaction;
+    use aptos_sdk::types::LocalAccount;
+
+    use crate::signer::{PrivateKeySigner, Signer};
+
+    fn account(byte: &str) -> LocalAccount {
+        LocalAccount::from_private_key(&format!("0x{}", byte.repeat(32)), 0).unwrap()
+    }
+
+    fn raw_transaction(sender: AccountAddress) -> RawTransaction {
+        TransactionFactory::new(ChainId::test())
+            .payload(aptos_stdlib::aptos_account_transfer(AccountAddress::ONE, 1))
+            .sender(sender)
+            .sequence_number(0)
+            .build()
+    }
+
+    #[tokio::test]
+    async fn test_private_key_signer() {
+        let private_key = format!("0x{}", "11".repeat(32));
+        let signer = PrivateKeySigner::from_private_key(&private_key).unwrap();
+        assert_eq!(
+            signer.address(),
+            LocalAccount::from_private_key(&private_key, 0)
+                .unwrap()
+                .address()
+        );
+        let signature = signer.sign_message(b"message").await.unwrap();
+        assert!(signature
+            .verify_arbitrary_msg(b"message", signer.public_key())
+            .is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_sign_transaction() {
+        let signer = PrivateKeySigner::from_private_key(&format!("0x{}", "11".repeat(32))).unwrap();
+        let secondary_signers = [account("22"), account("33")];
+        let fee_payer = account("44");
+        for (secondary_signers, fee_payer) in [
+            (&[][..], None),
+            (&secondary_signers[..], None),
+            (&[][..], Some(&fee_payer)),
+            (&secondary_signers[..], Some(&fee_payer)),
+        ] {
+            let transaction = signer
+                .sign_transaction(
+                    raw_transaction(signer.address()),
+                    secondary_signers,
+                    fee_payer,
+                )
+                .await
+                .unwrap();
+            assert!(transaction.verify_signature().is_ok());
+            assert_eq!(transaction.sender(), signer.address());
+        }
+        // The simulation transactions aren't signed.
+        let transaction = signer
+            .simulation_transaction(
+                raw_transaction(signer.address()),
+                &secondary_signers,
+                Some(&fee_payer),
+            )
+            .unwrap();
+        assert!(transaction.verify_signature().is_err());
+    }
+}
@@ -0,0 +1,70 @@
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::Path;
+
+use aptos_sdk::crypto::ValidCryptoMaterialStringExt;
+use aptos_sdk::types::LocalAccount;
+use rand::rngs::OsRng;
+use tracing::info;
+
+use crate::deploy_config::AptosNetwork;
+use crate::error::{JayceError, Result};
+use crate::retry::RetryPolicy;
+use crate::utils::{fund_account, DEFAULT_FAUCET_AMOUNT};
+
+/// Generates an account and writes its private key to `key_path`, readable only by the current
+/// user. The account is funded from the faucet of the network when `fund` is set. Only the address
+/// is printed, so that the key never shows up in terminal logs.
+pub async fn generate_account(
+    key_path: &Path,
+    fund: bool,
+    network: &AptosNetwork,
+    faucet_urls: &[String],
+    rest_url: Option<String>,
+    retry: &RetryPolicy,
+) -> Result<()> {
+    let account = LocalAccount::generate(&mut OsRng);
+    write_private_key_file(key_path, &account.private_key().to_encoded_string()?)?;
+    info!(
+        "Saved the private key of account {} to {}",
+        account.address(),
+        key_path.to_str().unwrap()
+    );
+    if fund {
+        fund_account(
+            network,
+            faucet_urls,
+            rest_url,
+            account.address(),
+            DEFAULT_FAUCET_AMOUNT,
+            retry,
+        )
+        .await?;
+        info!(
+            "Funded account {} with {} Octas",
+            account.address(),
+            DEFAULT_FAUCET_AMOUNT
+        );
+    }
+    println!("{}", account.address());
+    Ok(())
+}
+
+/// Writes a private key to a new file with `0600` permissions, refusing to overwrite an existing
+/// key.
+pub(crate) fn write_private_key_file(path: &Path, private_key: &str) -> Result<()> {
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    let mut file = options.open(path).map_err(|err| {
+        JayceError::Config(format!(
+            "Failed to create key file {}: {}, pass it with --private-key-file or remove it",
+            path.to_str().unwrap(),
+            err
+        ))
+    })?;
+    writeln!(file, "{}", private_key)?;
+    file.sync_all()?;
+    Ok(())
+}
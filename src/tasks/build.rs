@@ -0,0 +1,229 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use tracing::{error, info};
+
+use crate::artifact_cache::ArtifactCache;
+use crate::bundle::{
+    is_archive, write_bundle, write_bundle_package, BundleManifest, BundlePackage, MANIFEST_FILE,
+};
+use crate::deploy_config::{IncludedArtifacts, PartialDeployConfig, UpgradePolicy};
+use crate::deployer::build_publish_payload;
+use crate::error::{JayceError, Result};
+use crate::move_package::{
+    compile_package, placeholder_address, sort_packages, unassigned_named_addresses_arg,
+    MoveTomlFile,
+};
+
+/// Compiles every package of the configuration without publishing anything. Named addresses are
+/// resolved as a deployment would, from the fixed named addresses, the deployed addresses and the
+/// overrides of the packages, and the addresses that aren't deployed yet are replaced with
+/// placeholders. Packages that didn't change since they were last compiled successfully are
+/// skipped, unless `no_cache` is set.
+///
+/// With `artifacts_dir`, the packages are compiled with their upgrade policy and written to a
+/// bundle that `jayce deploy --bundle` publishes without compiling them again: a directory, or an
+/// archive when it ends with `.tar.gz`, `.tgz`, `.tar` or `.zip`. The addresses of every package
+/// must then be known, since they are part of its bytecode.
+pub async fn build(
+    mut partial: PartialDeployConfig,
+    no_cache: bool,
+    artifacts_dir: Option<&Path>,
+) -> Result<()> {
+    partial.expand_modules_path()?;
+    let modules_path = partial
+        .modules_path
+        .ok_or_else(|| JayceError::Config("Missing argument 'modules-path'".to_string()))?;
+    let addresses_name = partial.addresses_name.unwrap_or_default();
+    if modules_path.len() != addresses_name.len() {
+        return Err(JayceError::Config(format!(
+            "'addresses_name' has {} entries, but 'modules_path' has {}",
+            addresses_name.len(),
+            modules_path.len()
+        )));
+    }
+    let mut deployed_addresses = partial.named_addresses.unwrap_or_default();
+    deployed_addresses.extend(partial.deployed_addresses.unwrap_or_default());
+    let package_options = partial.package_options.unwrap_or_default();
+    let compiler = partial.compiler.unwrap_or_default();
+    // The artifacts of the packages without `included_artifacts`, as a deployment would publish.
+    let included_artifacts = if partial.publish_code == Some(true) {
+        IncludedArtifacts::All
+    } else if partial.verify_source == Some(true) {
+        IncludedArtifacts::Sparse
+    } else {
+        IncludedArtifacts::None
+    };
+
+    let cache = ArtifactCache::open().filter(|_| !no_cache && artifacts_dir.is_none());
+    let bundle_dir = artifacts_dir.map(bundle_dir).transpose()?;
+    let mut manifest = BundleManifest::default();
+    let mut failed = vec![];
+    for (package_dir, address_name) in
+        sort_packages(&modules_path, &addresses_name, &package_options)?
+    {
+        info!(
+            "Compiling package {} with address name {}...",
+            package_dir.to_str().unwrap(),
+            address_name
+        );
+        let mut named_addresses = match package_options.get(&address_name) {
+            Some(options) => options.named_addresses(&address_name, &deployed_addresses)?,
+            None => deployed_addresses.clone(),
+        };
+        let move_toml = MoveTomlFile::from_package_dir(&package_dir)?;
+        if bundle_dir.is_some() {
+            let mut unknown: Vec<&String> = move_toml
+                .addresses
+                .iter()
+                .filter(|(name, value)| *value == "_" && !named_addresses.contains_key(*name))
+                .map(|(name, _)| name)
+                .collect();
+            if !unknown.is_empty() {
+                unknown.sort();
+                return Err(JayceError::Config(format!(
+                    "Package '{}' can't be bundled, the address of {} is unknown: set it in \
+                    'named_addresses' or with --deployed-addresses",
+                    address_name,
+                    unknown
+                        .iter()
+                        .map(|name| format!("'{}'", name))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                )));
+            }
+        }
+        for named_address in move_toml.addresses.keys() {
+            named_addresses
+                .entry(named_address.clone())
+                .or_insert_with(|| placeholder_address(named_address));
+        }
+        let named_addresses_arg = unassigned_named_addresses_arg(&package_dir, &named_addresses)?;
+        let options = package_options.get(&address_name);
+        let package_compiler = match options {
+            Some(options) => options.compiler.or(&compiler),
+            None => compiler.clone(),
+        };
+        if let Some(bundle_dir) = &bundle_dir {
+            let upgrade_policy = options
+                .and_then(|options| options.upgrade_policy.clone())
+                .or(move_toml.package.upgrade_policy)
+                .unwrap_or(UpgradePolicy::Compatible);
+            let mut package = BundlePackage {
+                address_name: address_name.clone(),
+                name: move_toml.package.name,
+                module_path: package_dir.clone(),
+                upgrade_policy,
+                named_addresses: named_addresses
+                    .iter()
+                    .filter(|(name, _)| {
+                        move_toml
+                            .addresses
+                            .get(*name)
+                            .is_some_and(|value| value == "_")
+                    })
+                    .map(|(name, address)| (name.clone(), *address))
+                    .collect(),
+                modules: vec![],
+                compiler: package_compiler,
+                included_artifacts: options
+                    .and_then(|options| options.included_artifacts.clone())
+                    .unwrap_or(included_artifacts.clone()),
+                checksums: None,
+            };
+            match build_publish_payload(
+                &package_dir,
+                &address_name,
+                &named_addresses_arg,
+                &package.compiler,
+                options.and_then(|options| options.upgrade_policy.as_ref()),
+                &package.included_artifacts.to_string(),
+                false,
+            )
+            .await
+            {
+                Ok((metadata, code)) => {
+                    write_bundle_package(bundle_dir, &mut package, &metadata, &code)?;
+                    manifest.packages.push(package);
+                }
+                Err(err) => {
+                    error!("Failed to compile {}: {}", address_name, err);
+                    failed.push(address_name);
+                }
+            }
+            continue;
+        }
+        let named_addresses = named_addresses_arg;
+        let key = cache.as_ref().and_then(|cache| {
+            cache.key(
+                &package_dir,
+                &format!(
+                    "compile {} {}",
+                    named_addresses,
+                    package_compiler.cache_key()
+                ),
+            )
+        });
+        if let (Some(cache), Some(key)) = (&cache, &key) {
+            if cache.get(key).is_some() {
+                info!("Package {} is up to date", address_name);
+                continue;
+            }
+        }
+        match compile_package(&package_dir, &named_addresses, &package_compiler).await {
+            Ok(()) => {
+                if let (Some(cache), Some(key)) = (&cache, &key) {
+                    cache.put(key, &[]);
+                }
+            }
+            Err(err) => {
+                error!("Failed to compile {}: {}", address_name, err);
+                failed.push(address_name);
+            }
+        }
+    }
+
+    if !failed.is_empty() {
+        return Err(JayceError::Compile(format!(
+            "{} package(s) failed to compile: {}",
+            failed.len(),
+            failed.join(", ")
+        )));
+    }
+    if let (Some(bundle_dir), Some(artifacts_dir)) = (&bundle_dir, artifacts_dir) {
+        write_bundle(bundle_dir, &manifest, artifacts_dir)?;
+        info!(
+            "Wrote the bundle of {} package(s) to {}",
+            manifest.packages.len(),
+            artifacts_dir.to_str().unwrap()
+        );
+        return Ok(());
+    }
+    info!("Compiled {} package(s)", modules_path.len());
+    Ok(())
+}
+
+/// The directory the bundle is written to: `artifacts_dir`, or a temporary directory packed into
+/// it when it is an archive. A previous bundle is replaced, but not a directory holding other
+/// files.
+fn bundle_dir(artifacts_dir: &Path) -> Result<PathBuf> {
+    let dir = if is_archive(artifacts_dir) {
+        std::env::temp_dir().join(format!("jayce-bundle-build-{}", std::process::id()))
+    } else {
+        if artifacts_dir.is_dir()
+            && fs::read_dir(artifacts_dir)?.next().is_some()
+            && !artifacts_dir.join(MANIFEST_FILE).exists()
+        {
+            return Err(JayceError::Config(format!(
+                "{} isn't empty nor a bundle",
+                artifacts_dir.to_str().unwrap()
+            )));
+        }
+        artifacts_dir.to_path_buf()
+    };
+    if dir.exists() {
+        fs::remove_dir_all(&dir)?;
+    }
+    fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
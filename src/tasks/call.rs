@@ -0,0 +1,46 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use aptos_sdk::move_types::account_address::AccountAddress;
+
+use crate::deploy_config::AptosNetwork;
+use crate::deploy_report::DeployReport;
+use crate::entry_function::{run_entry_function, EntryFunctionCall};
+use crate::error::Result;
+use crate::tasks::deploy_contracts::private_key_signer_args;
+
+/// Loads the deployed addresses recorded in the report, if it exists, overridden by
+/// `deployed_addresses`. Also returns the network of the report.
+pub(crate) fn load_deployed_addresses(
+    report_path: &Path,
+    deployed_addresses: BTreeMap<String, AccountAddress>,
+) -> Result<(BTreeMap<String, AccountAddress>, Option<AptosNetwork>)> {
+    if !report_path.exists() {
+        return Ok((deployed_addresses, None));
+    }
+    let report = DeployReport::from_path(report_path)?;
+    let mut addresses = report.deployed_addresses();
+    addresses.extend(deployed_addresses);
+    Ok((addresses, Some(report.network)))
+}
+
+/// Calls an entry function, resolving the address names from the deployment report.
+pub async fn call(
+    call: &EntryFunctionCall,
+    private_key: &str,
+    network: Option<AptosNetwork>,
+    rest_url: Option<String>,
+    report_path: &Path,
+    deployed_addresses: BTreeMap<String, AccountAddress>,
+    yes: bool,
+) -> Result<()> {
+    let (deployed_addresses, report_network) =
+        load_deployed_addresses(report_path, deployed_addresses)?;
+    let network = network.or(report_network).unwrap_or(AptosNetwork::Devnet);
+
+    let signer_args = private_key_signer_args(&network, private_key, rest_url)?;
+    let result = run_entry_function(call, &deployed_addresses, &signer_args, "", yes).await?;
+
+    println!("{}", serde_json::to_string_pretty(&result)?);
+    Ok(())
+}
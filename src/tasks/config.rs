@@ -0,0 +1,280 @@
+use std::fs;
+use std::path::Path;
+
+use serde_json::Value;
+use strum_macros::Display;
+use toml_edit::{DocumentMut, Item, Table};
+
+use crate::deploy_config::{ConfigLayers, DeployConfig};
+use crate::error::{JayceError, Result};
+use crate::redact::redact;
+
+/// The fields holding secrets, masked even when they aren't private keys. The URLs of the
+/// webhooks embed their tokens.
+const SECRET_FIELDS: [&str; 6] = [
+    "private_key",
+    "fee_payer_private_key",
+    "secondary_signers",
+    "bearer_token",
+    "jwt",
+    "webhooks",
+];
+
+/// Where the value of a field of the effective configuration comes from.
+#[derive(Clone, Copy, Debug, PartialEq, Display)]
+pub enum ConfigSource {
+    #[strum(serialize = "flag")]
+    Flag,
+    #[strum(serialize = "config file")]
+    ConfigFile,
+    #[strum(serialize = "environment variable")]
+    Environment,
+    #[strum(serialize = "aptos CLI profile")]
+    Profile,
+    #[strum(serialize = "default")]
+    Default,
+}
+
+/// Renders the effective configuration of a deployment, one field per line annotated with where
+/// its value comes from: the layer of highest precedence setting it, the aptos CLI profile, or a
+/// default. Secrets are masked.
+pub fn show_config(config: &DeployConfig, layers: &ConfigLayers) -> Result<String> {
+    let Value::Object(fields) = serde_json::to_value(config)? else {
+        unreachable!("The configuration is a struct");
+    };
+    let flags = serde_json::to_value(&layers.flags)?;
+    let environment = serde_json::to_value(&layers.environment)?;
+    let config_file = serde_json::to_value(&layers.config_file)?;
+    let layers = [
+        (ConfigSource::Flag, &flags),
+        (ConfigSource::Environment, &environment),
+        (ConfigSource::ConfigFile, &config_file),
+    ];
+    let mut content = String::new();
+    for (field, mut value) in fields {
+        mask_secrets(&mut value);
+        let source = config_source(&field, &layers, config.profile.is_some());
+        content.push_str(&format!("{} = {}  # {}\n", field, value, source));
+    }
+    Ok(redact(&content))
+}
+
+/// The source of a field, from the layers of highest precedence first.
+fn config_source(field: &str, layers: &[(ConfigSource, &Value)], profile: bool) -> ConfigSource {
+    for (source, layer) in layers {
+        if layer_keys(field).iter().any(|key| !layer[key].is_null()) {
+            return *source;
+        }
+    }
+    if profile
+        && [
+            "private_key",
+            "sender_address",
+            "network",
+            "rest_url",
+            "faucet_url",
+        ]
+        .contains(&field)
+    {
+        return ConfigSource::Profile;
+    }
+    ConfigSource::Default
+}
+
+/// The keys of a layer setting a field.
+fn layer_keys(field: &str) -> Vec<&str> {
+    match field {
+        "network" => vec!["network", "networks"],
+        "modules_path" | "addresses_name" => {
+            vec!["modules_path", "addresses_name", "packages", "workspace"]
+        }
+        _ => vec![field],
+    }
+}
+
+/// Reads the value of a dotted key of a TOML configuration file, e.g.
+/// `package_options.verifier_addr.freeze`. Strings are returned without their quotes.
+pub fn get_config_value(config_path: &Path, key: &str) -> Result<String> {
+    let document = read_document(config_path)?;
+    let mut item = document.as_item();
+    for part in key.split('.') {
+        item = item
+            .get(part)
+            .filter(|item| !item.is_none())
+            .ok_or_else(|| JayceError::Config(format!("Key '{}' not found", key)))?;
+    }
+    Ok(match item.as_str() {
+        Some(value) => value.to_string(),
+        None => item.to_string().trim().to_string(),
+    })
+}
+
+/// Sets a dotted key of a TOML configuration file, keeping its comments and the order of its
+/// keys. The value is parsed as TOML, e.g. `true` or `["a", "b"]`, and taken as a string when it
+/// isn't valid TOML or is an address.
+pub fn set_config_value(config_path: &Path, key: &str, value: &str) -> Result<()> {
+    let mut document = read_document(config_path)?;
+    let mut new_value = match value.parse::<toml_edit::Value>() {
+        Ok(parsed) if !value.starts_with("0x") => parsed,
+        _ => value.into(),
+    };
+    let mut item = document.as_item_mut();
+    for part in key.split('.') {
+        if item.is_none() {
+            let mut table = Table::new();
+            table.set_implicit(true);
+            *item = Item::Table(table);
+        }
+        if !item.is_table_like() {
+            return Err(JayceError::Config(format!(
+                "Can't set '{}', '{}' isn't a table",
+                key, part
+            )));
+        }
+        item = &mut item[part];
+    }
+    // Keeps the comment following the previous value.
+    if let Some(previous) = item.as_value() {
+        *new_value.decor_mut() = previous.decor().clone();
+    }
+    *item = Item::Value(new_value);
+    fs::write(config_path, document.to_string())?;
+    Ok(())
+}
+
+fn read_document(config_path: &Path) -> Result<DocumentMut> {
+    if config_path
+        .extension()
+        .is_some_and(|extension| extension != "toml")
+    {
+        return Err(JayceError::Config(
+            "Only TOML configuration files can be read and edited".to_string(),
+        ));
+    }
+    fs::read_to_string(config_path)?
+        .parse::<DocumentMut>()
+        .map_err(|err| {
+            JayceError::Config(format!(
+                "Invalid configuration file {}: {}",
+                config_path.to_str().unwrap(),
+                err
+            ))
+        })
+}
+
+fn mask_secrets(value: &mut Value) {
+    match value {
+        Value::Object(fields) => {
+            for (key, value) in fields.iter_mut() {
+                if SECRET_FIELDS.contains(&key.as_str()) && !value.is_null() {
+                    *value = Value::String("[REDACTED]".to_string());
+                } else {
+                    mask_secrets(value);
+                }
+            }
+        }
+        Value::Array(values) => values.iter_mut().for_each(mask_secrets),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+
+    use serde_json::json;
+
+    use crate::tasks::config::{
+        config_source, get_config_value, mask_secrets, set_config_value, ConfigSource,
+    };
+
+    #[test]
+    fn test_config_source() {
+        let flags = json!({"max_gas": 1000, "workspace": "move", "networks": null});
+        let environment = json!({"private_key": "0xabc"});
+        let config_file = json!({"gas_unit_price": 100, "networks": ["testnet"], "max_gas": 10});
+        let layers = [
+            (ConfigSource::Flag, &flags),
+            (ConfigSource::Environment, &environment),
+            (ConfigSource::ConfigFile, &config_file),
+        ];
+
+        assert_eq!(config_source("max_gas", &layers, false), ConfigSource::Flag);
+        assert_eq!(
+            config_source("addresses_name", &layers, false),
+            ConfigSource::Flag
+        );
+        assert_eq!(
+            config_source("private_key", &layers, true),
+            ConfigSource::Environment
+        );
+        assert_eq!(
+            config_source("gas_unit_price", &layers, false),
+            ConfigSource::ConfigFile
+        );
+        assert_eq!(
+            config_source("network", &layers, false),
+            ConfigSource::ConfigFile
+        );
+        assert_eq!(
+            config_source("rest_url", &layers, true),
+            ConfigSource::Profile
+        );
+        assert_eq!(
+            config_source("max_fee", &layers, false),
+            ConfigSource::Default
+        );
+    }
+
+    #[test]
+    fn test_set_config_value() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("config_set.toml");
+        fs::write(
+            &path,
+            "# Deployment\nnetwork = \"devnet\" # or testnet\nmax_gas = 1000\n\n\
+            [package_options.lib_addr]\nfreeze = false\n",
+        )
+        .unwrap();
+
+        set_config_value(&path, "network", "testnet").unwrap();
+        set_config_value(&path, "max_gas", "2000").unwrap();
+        set_config_value(&path, "package_options.lib_addr.freeze", "true").unwrap();
+        set_config_value(&path, "named_addresses.std_addr", "0x1").unwrap();
+        let content = fs::read_to_string(&path).unwrap();
+        assert!(content.starts_with(
+            "# Deployment\nnetwork = \"testnet\" # or testnet\nmax_gas = 2000\n\n\
+            [package_options.lib_addr]\nfreeze = true\n"
+        ));
+        assert!(content.contains("[named_addresses]\nstd_addr = \"0x1\"\n"));
+        assert_eq!(get_config_value(&path, "network").unwrap(), "testnet");
+        assert_eq!(
+            get_config_value(&path, "package_options.lib_addr.freeze").unwrap(),
+            "true"
+        );
+        assert!(get_config_value(&path, "rest_url").is_err());
+        assert!(set_config_value(&path, "network.name", "testnet").is_err());
+    }
+
+    #[test]
+    fn test_mask_secrets() {
+        let mut value = json!({
+            "private_key": "0xabc",
+            "package_options": {"lib_addr": {"secondary_signers": ["0xdef"], "freeze": true}},
+            "fee_payer_private_key": null,
+            "webhooks": [{"url": "https://hooks.slack.com/services/T/B/X", "format": "slack"}],
+        });
+        mask_secrets(&mut value);
+        assert_eq!(
+            value,
+            json!({
+                "private_key": "[REDACTED]",
+                "package_options": {
+                    "lib_addr": {"secondary_signers": "[REDACTED]", "freeze": true}
+                },
+                "fee_payer_private_key": null,
+                "webhooks": "[REDACTED]",
+            })
+        );
+    }
+}
@@ -1,9 +1,10 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet};
+use std::env;
+use std::fs;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use std::{fs, panic};
+use std::time::{SystemTime, UNIX_EPOCH};
 
-use anyhow::{anyhow, ensure};
 use aptos::common::types::{CliCommand, CliError, TransactionSummary};
 use aptos::move_tool::MoveTool;
 use aptos::Tool;
@@ -11,43 +12,326 @@ use aptos_sdk::crypto::ValidCryptoMaterialStringExt;
 use aptos_sdk::move_types::account_address::AccountAddress;
 use aptos_sdk::types::LocalAccount;
 use clap::Parser;
-use config::{Config, File, FileFormat};
-use dialoguer::theme::ColorfulTheme;
-use dialoguer::Confirm;
-use serde::{Deserialize, Serialize};
+use dialoguer::{Confirm, Input};
+use opentelemetry::KeyValue;
+use rand::rngs::OsRng;
 use tokio::sync::Mutex;
+use tracing::{error, info, instrument, warn};
 
-use crate::deploy_config::{AptosNetwork, DeployConfig, DeployModuleType};
-use crate::utils::{generate_account_and_faucet, DEFAULT_FAUCET_AMOUNT};
+use crate::address_book::{record_address, AddressBook};
+use crate::bundle::Bundle;
+use crate::color::prompt_theme;
+use crate::deploy_config::{
+    AptosNetwork, CompilerOptions, DeployConfig, DeployModuleType, IncludedArtifacts,
+    PartialDeployConfig, UpgradePolicy,
+};
+use crate::deploy_lock::{DeployLock, DEPLOY_LOCK_PATH};
+use crate::deploy_report::{DeployReport, DeployStatus, ReportMode, TxReport, JAYCE_VERSION};
+use crate::deploy_state::{DeployState, PackageState};
+use crate::deployer::{publish_payload_size, RestBackend};
+use crate::entry_function::{run_entry_function, EntryFunctionCall};
+use crate::error::{JayceError, Result};
+use crate::events::{DeployEvent, EventWriter};
+use crate::git::{changed_files, head_commit, repository_root};
+use crate::github_actions::{reserve_stdout, write_github_actions_output};
+use crate::hooks::{run_hooks, HookPackage};
+use crate::move_package::{
+    copy_package_artifacts, get_named_addresses, named_addresses_arg, package_dir_args,
+    sort_packages, MoveTomlFile, PackageCopy,
+};
+use crate::package_registry::PackageRegistry;
+use crate::progress::{DeployProgress, PackageProgress, Phase};
+use crate::publish_backend::{
+    AptosBackend, PendingPublish, PublishBackend, PublishOutcome, PublishRequest,
+};
+use crate::redact::{redact, register_secret};
+use crate::registry::{record_call, write_registry_package, RegistryConfig, REGISTRY_PACKAGE_NAME};
+use crate::report_writer::ReportFormat;
+use crate::signer::{AnySigner, PrivateKeySigner, Signer};
+use crate::tasks::account::write_private_key_file;
+use crate::tasks::multisig::propose_deployment;
+use crate::tasks::plan::{
+    estimate_deployment_cost, plan_deployment, predict_object_addresses, transactions_fee,
+};
+use crate::tasks::verify::verify_package_source;
+use crate::telemetry::metrics;
+use crate::tui::{dashboard, interrupted, print_stderr, print_stdout};
+use crate::utils::{
+    check_chain_id, create_object_code_deployment_address, fund_account, fund_account_if_missing,
+    get_balance, lookup_address, resolve_rest_url, rest_client, DEFAULT_FAUCET_AMOUNT,
+};
+use crate::vm_status::describe_vm_status;
+use crate::webhooks::{notify_webhooks, WebhookEvent};
 
-const DEPLOYER_PROFILE: &str = "jayce_deployer";
+/// Maximum size of a package published in a single transaction, larger packages are published
+/// with chunked publish.
+pub const MAX_PUBLISH_PACKAGE_SIZE: usize = 60_000;
 
-#[derive(Deserialize, Debug, Clone)]
-pub struct MoveTomlFile {
-    pub addresses: HashMap<String, String>,
-}
-
-#[derive(Serialize, Deserialize)]
-struct DeployReport {
-    account: AccountAddress,
-    network: AptosNetwork,
-    info: Vec<TxReport>,
-}
-
-#[derive(Serialize, Deserialize)]
-struct TxReport {
-    module_path: PathBuf,
-    address_name: String,
-    deployed_at: AccountAddress,
-    tx_info: Vec<TransactionSummary>,
+/// Deploys the packages to every network, one after the other. Each network gets its own report
+/// and state file, and a failure on a network doesn't stop the deployment to the next ones.
+pub async fn deploy_contracts_to_networks(
+    partial: PartialDeployConfig,
+    networks: &[AptosNetwork],
+) -> Result<()> {
+    if partial.rest_url.is_some() || partial.faucet_url.is_some() || partial.faucet_urls.is_some() {
+        return Err(JayceError::Config(
+            "The REST and faucet URLs of a multi-network deployment must be set per network, in \
+            environments named after the networks"
+                .to_string(),
+        ));
+    }
+    if networks.contains(&AptosNetwork::Custom) {
+        return Err(JayceError::Config(
+            "Custom networks can't be part of a multi-network deployment".to_string(),
+        ));
+    }
+    let mut failed = vec![];
+    for network in networks {
+        info!("Deploying to {}...", network);
+        let result = async {
+            let config = DeployConfig::try_from(partial.for_network(network)?)?;
+            deploy_contracts(config).await
+        }
+        .await;
+        match result {
+            Err(JayceError::Interrupted) => return Err(JayceError::Interrupted),
+            Err(err) => {
+                error!("Deployment to {} failed: {}", network, err);
+                failed.push(network.to_string());
+            }
+            Ok(()) => {}
+        }
+    }
+    if !failed.is_empty() {
+        return Err(JayceError::Publish(format!(
+            "Deployment failed on {} of {} networks: {}",
+            failed.len(),
+            networks.len(),
+            failed.join(", ")
+        )));
+    }
+    Ok(())
 }
 
-pub async fn deploy_contracts(mut config: DeployConfig) -> anyhow::Result<()> {
-    let report_info: Arc<Mutex<Vec<TxReport>>> = Arc::new(Mutex::new(vec![]));
-    let sender_addr = match &config.private_key {
-        None => {
+pub async fn deploy_contracts(mut config: DeployConfig) -> Result<()> {
+    let _lock = DeployLock::acquire(Path::new(DEPLOY_LOCK_PATH), config.force)?;
+    if config.json_events {
+        reserve_stdout();
+    }
+    if config.dry_run && config.private_key.is_none() {
+        return Err(JayceError::Config(
+            "A private key is required for a dry run".to_string(),
+        ));
+    }
+    if config.signer.is_some()
+        && (config.private_key.is_some()
+            || config.profile.is_some()
+            || config.multisig_address.is_some()
+            || config
+                .package_options
+                .values()
+                .any(|options| options.private_key.is_some() || options.profile.is_some()))
+    {
+        return Err(JayceError::Config(
+            "A signer can't be combined with private keys, profiles or multisig deployments"
+                .to_string(),
+        ));
+    }
+    if config.multisig_address.is_some()
+        && (config.private_key.is_none()
+            || config.addresses_name.iter().any(|address_name| {
+                config.package_module_type(address_name) != DeployModuleType::Account
+            }))
+    {
+        return Err(JayceError::Config(
+            "Multisig deployments require a private key and the 'account' module type".to_string(),
+        ));
+    }
+    if (config.multisig_address.is_some() || config.fee_payer_private_key.is_some())
+        && config
+            .package_options
+            .values()
+            .any(|options| options.private_key.is_some() || options.profile.is_some())
+    {
+        return Err(JayceError::Config(
+            "Multisig and sponsored deployments don't support per-package accounts".to_string(),
+        ));
+    }
+    if config.pipeline
+        && (config.multisig_address.is_some()
+            || !config.initializers.is_empty()
+            || config
+                .package_options
+                .values()
+                .any(|options| options.private_key.is_some() || options.profile.is_some()))
+    {
+        return Err(JayceError::Config(
+            "Pipelined deployments don't support multisig, initializers or per-package accounts"
+                .to_string(),
+        ));
+    }
+    if config.bundle.is_some()
+        && (config.dry_run
+            || config.multisig_address.is_some()
+            || config.changed_only
+            || config.verify_source
+            || config.registry.is_some()
+            || !config.initializers.is_empty())
+    {
+        return Err(JayceError::Config(
+            "Bundles don't support dry runs, multisig, changed-only, verify-source, registry or \
+            initializers"
+                .to_string(),
+        ));
+    }
+    // The bundle is extracted until the deployment is over.
+    let bundle = config.bundle.as_deref().map(Bundle::open).transpose()?;
+    if let Some(bundle) = &bundle {
+        config.modules_path = bundle
+            .packages()
+            .iter()
+            .map(|package| package.module_path.clone())
+            .collect();
+        config.addresses_name = bundle
+            .packages()
+            .iter()
+            .map(|package| package.address_name.clone())
+            .collect();
+        config.bundle = Some(bundle.dir().to_path_buf());
+    }
+    // Packages built in a container are published through the REST API, since the aptos CLI would
+    // compile them again. Multisig proposals already publish the payload built in the container.
+    let docker_build =
+        config.builds_in_docker() && config.multisig_address.is_none() && !config.dry_run;
+    // Initializers are run by the aptos CLI, which can't sign with a signer nor be sponsored.
+    if (config.signer.is_some() || config.fee_payer_private_key.is_some())
+        && !config.initializers.is_empty()
+    {
+        return Err(JayceError::Config(
+            "Signer and sponsored deployments don't support initializers".to_string(),
+        ));
+    }
+    // Script hooks are run by the aptos CLI too, signed by the private key of the deployer.
+    if config.signer.is_some()
+        && config
+            .package_hooks
+            .values()
+            .chain([&config.hooks])
+            .any(|hooks| hooks.script_count() > 0)
+    {
+        return Err(JayceError::Config(
+            "Signer deployments don't support script hooks".to_string(),
+        ));
+    }
+    if config.multisig_address.is_some() && config.fee_payer_private_key.is_some() {
+        return Err(JayceError::Config(
+            "Multisig deployments don't support fee payers".to_string(),
+        ));
+    }
+    let co_signed = config
+        .package_options
+        .values()
+        .any(|options| !options.secondary_signers.is_empty());
+    if co_signed && config.multisig_address.is_some() {
+        return Err(JayceError::Config(
+            "Multisig deployments don't support secondary signers".to_string(),
+        ));
+    }
+    if config.registry.is_some()
+        && (config.multisig_address.is_some()
+            || config.fee_payer_private_key.is_some()
+            || config.signer.is_some()
+            || config.pipeline
+            || co_signed
+            || docker_build)
+    {
+        return Err(JayceError::Config(
+            "The deployment registry doesn't support multisig, sponsored, signer, co-signed, \
+            pipelined or Docker deployments"
+                .to_string(),
+        ));
+    }
+    if config.sign_report && config.output_format != ReportFormat::Json {
+        return Err(JayceError::Config(
+            "Signed reports require the JSON report format".to_string(),
+        ));
+    }
+    if config.report_mode != ReportMode::Overwrite && config.output_format != ReportFormat::Json {
+        return Err(JayceError::Config(format!(
+            "The '{}' report mode requires the JSON report format",
+            config.report_mode
+        )));
+    }
+    if let (Some(profile), None) = (&config.profile, &config.private_key) {
+        return Err(JayceError::Config(format!(
+            "Aptos CLI profile '{}' has no private key",
+            profile
+        )));
+    }
+    for (address_name, options) in &config.package_options {
+        if let (Some(profile), None) = (&options.profile, &options.private_key) {
+            return Err(JayceError::Config(format!(
+                "Aptos CLI profile '{}' of '{}' has no private key",
+                profile, address_name
+            )));
+        }
+    }
+    confirm_mainnet(&config)?;
+    if let Some(chain_id) = config.expected_chain_id() {
+        check_chain_id(&config.network, config.rest_url.clone(), chain_id).await?;
+    }
+    if let Some(from_report) = &config.from_report {
+        let report = DeployReport::from_path(from_report)?;
+        if report.network != config.network {
+            return Err(JayceError::Config(format!(
+                "The deployment report {} was created for network {}",
+                from_report.to_str().unwrap(),
+                report.network
+            )));
+        }
+        for (address_name, address) in report.deployed_addresses() {
+            config
+                .deployed_addresses
+                .entry(address_name)
+                .or_insert(address);
+        }
+    }
+    if let Some(address_book) = &config.address_book {
+        // Packages being deployed are not taken from the address book, they would be skipped.
+        for (address_name, address) in
+            AddressBook::from_path(address_book)?.addresses(&config.network_name())
+        {
+            if !config.addresses_name.contains(&address_name) {
+                config
+                    .deployed_addresses
+                    .entry(address_name)
+                    .or_insert(address);
+            }
+        }
+    }
+    if config.changed_only {
+        skip_unchanged_packages(&mut config)?;
+    }
+    if let Some(artifacts_dir) = &config.artifacts_dir {
+        let started_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        config.artifacts_dir =
+            Some(artifacts_dir.join(format!("{}-{}", config.network_name(), started_at)));
+    }
+    // The signer of the `[signer]` table, only connected to once.
+    let mut signer = None;
+    let sender_addr = match (&config.signer, &config.private_key) {
+        (Some(signer_config), _) => {
+            let connected = Arc::new(AnySigner::connect(signer_config).await?);
+            let address = connected.address();
+            signer = Some(connected);
+            resolve_sender(&mut config, address).await?
+        }
+        (None, None) => {
             if !config.yes
-                && !Confirm::with_theme(&ColorfulTheme::default())
+                && !Confirm::with_theme(prompt_theme().as_ref())
                     .with_prompt("No private key provided, do you want to generate one?")
                     .default(false)
                     .show_default(true)
@@ -56,349 +340,2331 @@ pub async fn deploy_contracts(mut config: DeployConfig) -> anyhow::Result<()> {
             {
                 return Ok(());
             }
-            let account = generate_account_and_faucet(
+            // The key is saved before funding the account, so that the funds can't be lost.
+            let account = LocalAccount::generate(&mut OsRng);
+            let private_key = account.private_key().to_encoded_string()?;
+            let address = account.address();
+            register_secret(&private_key);
+            write_private_key_file(&config.generated_key_path, &private_key)?;
+            fund_account(
                 &config.network,
-                config.faucet_url.clone(),
+                &config.faucets(),
                 config.rest_url.clone(),
+                address,
+                DEFAULT_FAUCET_AMOUNT,
+                &config.retry,
             )
             .await?;
-            let private_key = account.private_key().to_encoded_string()?;
-            let address = account.address();
-            println!(
-                "Generated account with address: {}, balance: {} Octas",
-                address, DEFAULT_FAUCET_AMOUNT
+            info!(
+                "Generated account with address: {}, balance: {} Octas, private key saved to {}",
+                address,
+                DEFAULT_FAUCET_AMOUNT,
+                config.generated_key_path.to_str().unwrap()
             );
-            println!("Your private key is: {}", private_key);
+            if config.print_key {
+                let line = format!("Your private key is: {}", private_key);
+                // Stdout is reserved for the events.
+                if config.json_events {
+                    print_stderr(&line);
+                } else {
+                    print_stdout(&line);
+                }
+            }
             config.private_key = Some(private_key);
             address
         }
-        Some(private_key) => LocalAccount::from_private_key(private_key, 0)?.address(),
+        (None, Some(private_key)) => {
+            let address = LocalAccount::from_private_key(private_key, 0)
+                .map_err(|err| JayceError::Config(format!("Invalid private key: {}", err)))?
+                .address();
+            resolve_sender(&mut config, address).await?
+        }
+    };
+
+    let state = if config.resume && config.state_path.exists() {
+        let state = DeployState::from_path(&config.state_path)?;
+        if state.network != config.network {
+            return Err(JayceError::Config(format!(
+                "Cannot resume: the state file was created for network {}",
+                state.network
+            )));
+        }
+        if state.account != sender_addr {
+            return Err(JayceError::Config(format!(
+                "Cannot resume: the state file was created by account {}, use its private key",
+                state.account
+            )));
+        }
+        info!(
+            "Resuming deployment from {}...",
+            config.state_path.to_str().unwrap()
+        );
+        state
+    } else {
+        DeployState::new(sender_addr, config.network.clone())
     };
 
-    create_profile(&config).await?;
+    if config.dry_run {
+        return plan_deployment(&config, sender_addr).await;
+    }
+    if (config.fee_payer_private_key.is_some()
+        || config.signer.is_some()
+        || config.pipeline
+        || co_signed
+        || bundle.is_some()
+        || docker_build)
+        && config.multisig_address.is_none()
+    {
+        // Sponsored, co-signed and transactions signed by a key management service can't be
+        // built by the aptos CLI, which also waits for every transaction, nor can it publish
+        // precompiled packages: publish through the REST API.
+        let backend = match &signer {
+            Some(signer) => RestBackend::with_signer(&config, Arc::clone(signer)).await?,
+            None => RestBackend::new(&config).await?,
+        };
+        deploy_with_backend(config, backend, state, sender_addr, signer).await
+    } else {
+        let backend = AptosBackend::new(&config)?;
+        deploy_with_backend(config, backend, state, sender_addr, signer).await
+    }
+}
+
+/// Funds the accounts of the deployment and checks their balance, then deploys the packages
+/// through `backend` and writes the report, signed by `signer` when it is the one of the
+/// `[signer]` table.
+async fn deploy_with_backend<B: PublishBackend + Send + 'static>(
+    mut config: DeployConfig,
+    backend: B,
+    mut state: DeployState,
+    sender_addr: AccountAddress,
+    signer: Option<Arc<AnySigner>>,
+) -> Result<()> {
+    run_pre_deploy_hooks(&config, &state).await?;
+    if config.profile.is_none() {
+        fund_account_if_missing(
+            &config.network,
+            &config.faucets(),
+            config.rest_url.clone(),
+            sender_addr,
+            &config.retry,
+        )
+        .await?;
+    }
+    for (address_name, options) in &config.package_options {
+        if options.private_key.is_some() && options.profile.is_none() {
+            fund_account_if_missing(
+                &config.network,
+                &config.faucets(),
+                config.rest_url.clone(),
+                package_sender(&config, address_name, sender_addr)?,
+                &config.retry,
+            )
+            .await?;
+        }
+    }
+    let fee_payer = config
+        .fee_payer_private_key
+        .as_ref()
+        .map(|fee_payer_private_key| {
+            LocalAccount::from_private_key(fee_payer_private_key, 0)
+                .map(|fee_payer| fee_payer.address())
+                .map_err(|err| {
+                    JayceError::Config(format!("Invalid fee payer private key: {}", err))
+                })
+        })
+        .transpose()?;
+    if !config.skip_balance_check && config.multisig_address.is_none() {
+        check_balance(&config, &backend, sender_addr, fee_payer).await?;
+    }
+    if config.registry.is_some() {
+        let address = publish_registry(&config, sender_addr).await?;
+        config.registry = Some(RegistryConfig {
+            address: Some(address),
+        });
+    }
 
+    notify_webhooks(&config.webhooks, &started_event(&config, sender_addr)).await;
+    let report_info: Arc<Mutex<Vec<TxReport>>> = Arc::new(Mutex::new(vec![]));
     let config = Arc::new(config);
     let report_info_clone = Arc::clone(&report_info);
     let config_clone = Arc::clone(&config);
-    let result = tokio::spawn(async move {
+    let mut deployment = tokio::spawn(async move {
         let mut report_info = report_info_clone.lock().await;
-        run_core(&config_clone, &mut report_info, sender_addr).await
-    })
-    .await;
+        match config_clone.multisig_address {
+            Some(multisig_address) => {
+                propose_deployment(&config_clone, multisig_address, &mut report_info).await
+            }
+            None => {
+                run_core(
+                    &config_clone,
+                    &backend,
+                    &mut report_info,
+                    &mut state,
+                    Some(&config_clone.state_path),
+                    sender_addr,
+                )
+                .await
+            }
+        }
+    });
+    let result = tokio::select! {
+        result = &mut deployment => {
+            result.map_err(|err| JayceError::from(anyhow::Error::from(err)))
+        }
+        () = interrupted() => {
+            warn!("Interrupted, aborting the deployment...");
+            deployment.abort();
+            let _ = deployment.await;
+            Err(JayceError::Interrupted)
+        }
+    };
 
-    fs::write(
-        &config.output_json,
-        serde_json::to_string_pretty(&DeployReport {
+    save_report(
+        &config,
+        DeployReport {
             account: sender_addr,
+            fee_payer,
             network: config.network.clone(),
             info: std::mem::take(&mut *report_info.lock().await),
-        })?,
-    )?;
-    remove_profile()?;
-    match result {
-        Ok(result) => result,
-        Err(err) => Err(err.into()),
+            gas: None,
+            git_commit: None,
+            git_commits: BTreeMap::new(),
+            signature: None,
+        },
+        signer.as_deref(),
+    )
+    .await?;
+    let result = result.and_then(|result| result);
+    match &result {
+        Ok(()) => {
+            if config.state_path.exists() {
+                fs::remove_file(&config.state_path)?;
+            }
+        }
+        Err(err) => notify_webhooks(&config.webhooks, &failed_event(&config, err)).await,
     }
+    result
 }
 
-async fn run_core(
+/// Deploys the packages through `backend` and returns the report, without funding the deployer,
+/// checking its balance nor writing a state file to resume from. With a
+/// [`MockBackend`](crate::publish_backend::MockBackend), it checks a configuration without a
+/// network.
+pub async fn deploy_contracts_with_backend<B: PublishBackend>(
     config: &DeployConfig,
-    report_info: &mut Vec<TxReport>,
+    backend: &B,
     sender_addr: AccountAddress,
-) -> anyhow::Result<()> {
-    let mut deployed_addresses = config.deployed_addresses.clone();
-    for (package_dir, address_name) in config.modules_path.iter().zip(&config.addresses_name) {
-        if deployed_addresses.contains_key(address_name) {
-            println!(
-                "Address name {} already deployed, skipping...",
-                address_name
-            );
-            continue;
-        }
-        println!(
-            "Deploying package {} with address name {}...",
-            package_dir.to_str().unwrap(),
-            address_name
-        );
-        let named_addresses =
-            get_named_addresses(package_dir, address_name, config.module_type.clone())?;
-        let named_addresses = named_addresses
-            .keys()
-            .map(|named_address| {
-                let mut hex_address = deployed_addresses.get(named_address);
-                if hex_address.is_none() {
-                    if named_address == address_name {
-                        hex_address = Some(&sender_addr);
-                    } else {
-                        panic!(
-                            "{}",
-                            format!(
-                                "'{}' should be deployed before '{}'",
-                                named_address, address_name
-                            )
-                        );
-                    }
-                }
-                format!("{}={}", named_address, hex_address.unwrap())
-            })
-            .reduce(|acc, cur| format!("{},{}", acc, cur))
-            .map(|named_addresses| format!("--named-addresses {}", named_addresses))
-            .unwrap_or("".to_string());
-
-        let args = format!(
-            "aptos move {} \
-                    --package-dir {} \
-                    --included-artifacts {} \
-                    --profile {} \
-                    {} \
-                    {} \
-                    ",
-            match config.module_type {
-                DeployModuleType::Object => "create-object-and-publish-package",
-                DeployModuleType::Account => "publish",
-            },
-            package_dir.to_str().unwrap(),
-            if config.publish_code { "all" } else { "none" },
-            DEPLOYER_PROFILE,
-            match config.module_type {
-                DeployModuleType::Account => "".to_string(),
-                DeployModuleType::Object => format!("--address-name {}", address_name),
-            },
-            named_addresses
-        );
-        let mut args: Vec<&str> = args.split_whitespace().collect();
+) -> Result<DeployReport> {
+    let mut report_info = vec![];
+    let mut state = DeployState::new(sender_addr, config.network.clone());
+    run_pre_deploy_hooks(config, &state).await?;
+    run_core(
+        config,
+        backend,
+        &mut report_info,
+        &mut state,
+        None,
+        sender_addr,
+    )
+    .await?;
+    Ok(DeployReport {
+        account: sender_addr,
+        fee_payer: None,
+        network: config.network.clone(),
+        info: report_info,
+        gas: None,
+        git_commit: None,
+        git_commits: BTreeMap::new(),
+        signature: None,
+    })
+}
 
-        if config.yes {
-            args.push("--assume-yes");
-        }
+fn started_event(config: &DeployConfig, sender_addr: AccountAddress) -> WebhookEvent {
+    WebhookEvent::DeploymentStarted {
+        network: config.network_name(),
+        account: sender_addr,
+        packages: config.addresses_name.clone(),
+    }
+}
 
-        let (tx_info, deployed_at) = match run_deploy_command(&args).await {
-            Ok(x) => x,
-            Err(err) => {
-                match err {
-                    CliError::PackageSizeExceeded(err1, err0) => {
-                        println!(
-                            "The package is larger than {} bytes ({} bytes)!",
-                            err1, err0
-                        );
-                        match config.network {
-                            AptosNetwork::Mainnet | AptosNetwork::Testnet => {
-                                if !config.yes && !Confirm::with_theme(&ColorfulTheme::default())
-                                    .with_prompt("Do you want to publish packages using chunked publish?")
-                                    .default(false)
-                                    .show_default(true)
-                                    .wait_for_newline(true)
-                                    .interact()? {
-                                    return Err(err.into());
-                                } else {
-                                    args.push("--chunked-publish");
-                                    run_deploy_command(&args).await?
-                                }
-                            }
-                            _ => {
-                                return Err(anyhow!(
-                                    "{} is not supported for chunked publish",
-                                    config.network
-                                ));
-                            }
-                        }
-                    }
-                    _ => {
-                        return Err(err.into());
-                    }
-                }
-            }
-        };
+fn deployed_event(config: &DeployConfig, tx_report: &TxReport) -> WebhookEvent {
+    WebhookEvent::PackageDeployed {
+        network: config.network_name(),
+        address_name: tx_report.address_name.clone(),
+        deployed_at: tx_report.deployed_at,
+        explorer_url: tx_report.explorer_url.clone(),
+    }
+}
 
-        let deployed_at = match config.module_type {
-            DeployModuleType::Account => sender_addr,
-            DeployModuleType::Object => deployed_at.unwrap(),
-        };
-        deployed_addresses.insert(address_name.clone(), deployed_at);
-        report_info.push(TxReport {
-            module_path: package_dir.clone(),
-            address_name: address_name.clone(),
-            deployed_at,
-            tx_info,
-        });
+fn failed_event(config: &DeployConfig, err: &JayceError) -> WebhookEvent {
+    WebhookEvent::DeploymentFailed {
+        network: config.network_name(),
+        error: redact(&err.to_string()),
     }
-    Ok(())
 }
 
-async fn create_profile(config: &DeployConfig) -> anyhow::Result<()> {
-    let private_key = config
-        .private_key
-        .clone()
-        .expect("Private key not found, this should not happen");
-    let rest_url = match config.rest_url.clone() {
-        None => config.network.rest_url().expect("Failed to get rest url"),
-        Some(rest_url) => rest_url,
-    };
-    let faucet_url = match config.faucet_url.clone() {
-        None => config
-            .network
-            .faucet_url()
-            .expect("Failed to get faucet url"),
-        Some(faucet_url) => faucet_url,
+/// Returns the account of the deployer whose key has the address `derived`: the configured sender
+/// address, or the account the key was rotated into. The sender address is set to it when it
+/// differs from `derived`, so that the transactions are sent from it.
+async fn resolve_sender(
+    config: &mut DeployConfig,
+    derived: AccountAddress,
+) -> Result<AccountAddress> {
+    let address = match config.sender_address {
+        Some(address) => address,
+        None => {
+            lookup_address(
+                &rest_client(&config.network, config.rest_url.clone())?,
+                derived,
+            )
+            .await?
+        }
     };
+    if address != derived {
+        info!(
+            "The key of {} was rotated, deploying from account {}",
+            derived, address
+        );
+        config.sender_address = Some(address);
+    }
+    Ok(address)
+}
 
-    let command = format!(
-        "aptos init \
-        --network {} \
-        --profile {} \
-        --private-key {} \
-        --rest-url {} \
-        --faucet-url {} \
-        {}",
-        config.network,
-        DEPLOYER_PROFILE,
-        private_key,
-        rest_url,
-        faucet_url,
-        if config.yes { "--assume-yes" } else { "" }
-    );
-    let command: Vec<&str> = command.split_whitespace().collect();
-    let tool = Tool::try_parse_from(&command).expect("Failed to parse arguments");
-    if let Tool::Init(cmd_executor) = tool {
-        Ok(cmd_executor.execute().await?)
-    } else {
-        Err(anyhow!(format!(
-            "Wrong arguments to deploy contracts: {:?}",
-            command
-        )))
+/// Guards against deploying to mainnet by accident, e.g. with a command copied from a testnet
+/// deployment: unless `--allow-mainnet` is set, the network name must be typed at a prompt, which
+/// `--yes` can't answer.
+fn confirm_mainnet(config: &DeployConfig) -> Result<()> {
+    if config.network != AptosNetwork::Mainnet || config.allow_mainnet || config.dry_run {
+        return Ok(());
+    }
+    if config.yes {
+        return Err(JayceError::Config(
+            "Deploying to mainnet with --yes requires --allow-mainnet".to_string(),
+        ));
     }
+    let network: String = Input::with_theme(prompt_theme().as_ref())
+        .with_prompt("You are deploying to mainnet, type the network name to continue")
+        .interact_text()?;
+    if network.trim() != AptosNetwork::Mainnet.to_string() {
+        return Err(JayceError::Config(
+            "Mainnet deployment not confirmed".to_string(),
+        ));
+    }
+    Ok(())
 }
 
-fn remove_profile() -> anyhow::Result<()> {
-    let mut config_yaml: serde_yaml::Value = Config::builder()
-        .add_source(File::new(".aptos/config.yaml", FileFormat::Yaml))
-        .build()?
-        .try_deserialize()?;
-    let profiles = config_yaml["profiles"].as_mapping_mut().unwrap();
-    if profiles.len() == 1 {
-        if profiles.contains_key(DEPLOYER_PROFILE) {
-            fs::remove_dir_all(".aptos")?;
-        }
-    } else if profiles.remove(DEPLOYER_PROFILE).is_some() {
-        fs::write(".aptos/config.yaml", serde_yaml::to_string(&config_yaml)?)?;
+/// Checks that the balance of every account publishing packages covers its part of the estimated
+/// cost of the deployment, offering to fund the accounts from the faucet on networks other than
+/// mainnet. The fee payer of a sponsored deployment pays the whole cost.
+async fn check_balance<B: PublishBackend>(
+    config: &DeployConfig,
+    backend: &B,
+    sender_addr: AccountAddress,
+    fee_payer: Option<AccountAddress>,
+) -> Result<()> {
+    info!("Estimating the cost of the deployment...");
+    let costs = estimate_deployment_cost(config, backend, sender_addr).await?;
+    if let Some(fee_payer) = fee_payer {
+        return check_sender_balance(config, fee_payer, costs.values().sum()).await;
+    }
+    for (sender_addr, estimated_cost) in costs {
+        check_sender_balance(config, sender_addr, estimated_cost).await?;
     }
     Ok(())
 }
 
-async fn run_deploy_command(
-    args: &Vec<&str>,
-) -> anyhow::Result<(Vec<TransactionSummary>, Option<AccountAddress>), CliError> {
-    let tool = Tool::try_parse_from(args).expect("Failed to parse arguments");
+async fn check_sender_balance(
+    config: &DeployConfig,
+    sender_addr: AccountAddress,
+    estimated_cost: u64,
+) -> Result<()> {
+    let rest_url = resolve_rest_url(&config.network, config.rest_url.clone())?;
+    let balance = get_balance(&rest_url, sender_addr).await?;
+    info!(
+        "Estimated cost: {} Octas, balance of {}: {} Octas",
+        estimated_cost, sender_addr, balance
+    );
+    if balance >= estimated_cost {
+        return Ok(());
+    }
 
-    if let Tool::Move(MoveTool::CreateObjectAndPublishPackage(cmd_executor)) = tool {
-        let (tx_info, object_addr) = cmd_executor.execute().await?;
-        Ok((tx_info, Some(object_addr)))
-    } else if let Tool::Move(MoveTool::Publish(cmd_executor)) = tool {
-        let tx_info = cmd_executor.execute().await?;
-        Ok((tx_info, None))
+    let faucet_urls = config.faucets();
+    if config.network != AptosNetwork::Mainnet
+        && !faucet_urls.is_empty()
+        && (config.yes
+            || Confirm::with_theme(prompt_theme().as_ref())
+                .with_prompt(
+                    "Insufficient balance, do you want to fund the account from the faucet?",
+                )
+                .default(true)
+                .interact()?)
+    {
+        return fund_account(
+            &config.network,
+            &faucet_urls,
+            Some(rest_url),
+            sender_addr,
+            (estimated_cost - balance).max(DEFAULT_FAUCET_AMOUNT),
+            &config.retry,
+        )
+        .await;
+    }
+    Err(JayceError::InsufficientBalance(format!(
+        "account {} has {} Octas, but the deployment is estimated at {} Octas",
+        sender_addr, balance, estimated_cost
+    )))
+}
+
+/// Writes the deployment report, combined with the existing one according to the report mode.
+/// It is signed by `signer`, otherwise by the private key of the deployer, with `sign_report`.
+async fn save_report(
+    config: &DeployConfig,
+    mut report: DeployReport,
+    signer: Option<&AnySigner>,
+) -> Result<()> {
+    let gas = report.gas_summary();
+    if gas.transactions > 0 {
+        info!("{}", report.gas_summary_table());
+    }
+    report.gas = Some(gas);
+    // A package that failed to deploy keeps the previous commit, so that the next deployment with
+    // `changed_only` retries it.
+    if report
+        .info
+        .iter()
+        .all(|tx_report| tx_report.status == DeployStatus::Deployed)
+    {
+        report.git_commit = config.modules_path.first().and_then(|dir| head_commit(dir));
+        report.git_commits = config
+            .modules_path
+            .iter()
+            .filter_map(|dir| Some((repository_root(dir)?, head_commit(dir)?)))
+            .collect();
+    }
+    if config.github_actions {
+        write_github_actions_output(&report)?;
+    }
+    let mut report = if config.report_mode != ReportMode::Overwrite && config.output_json.exists() {
+        report.merge(
+            DeployReport::from_path(&config.output_json)?,
+            &config.report_mode,
+        )?
     } else {
-        Err(CliError::UnexpectedError(format!(
-            "Wrong arguments to deploy contracts: {:?}",
-            args
-        )))
+        report
+    };
+    if config.sign_report {
+        match (signer, &config.private_key) {
+            (Some(signer), _) => report.sign(signer).await?,
+            (None, Some(private_key)) => {
+                report
+                    .sign(&PrivateKeySigner::from_private_key(private_key)?)
+                    .await?
+            }
+            (None, None) => {
+                return Err(JayceError::Config(
+                    "Signing the report requires the key of the deployer".to_string(),
+                ))
+            }
+        }
     }
+    report.save_as(&config.output_json, &config.output_format)
 }
 
-fn get_named_addresses(
-    package_dir: &Path,
-    address_name: &String,
-    module_type: DeployModuleType,
-) -> anyhow::Result<HashMap<String, String>> {
-    let move_toml: MoveTomlFile = Config::builder()
-        .add_source(File::new(
-            package_dir.join("Move.toml").to_str().unwrap(),
-            FileFormat::Toml,
+/// Adds the packages of the previous report that didn't change since the commit it was deployed
+/// from, and don't depend on a package that changed, to the deployed addresses, so that they are
+/// skipped. The report is then merged with the previous one to keep them.
+fn skip_unchanged_packages(config: &mut DeployConfig) -> Result<()> {
+    if config.output_format != ReportFormat::Json || !config.output_json.exists() {
+        return Err(JayceError::Config(format!(
+            "--changed-only requires the JSON report of a previous deployment at {}",
+            config.output_json.to_str().unwrap()
+        )));
+    }
+    let report = DeployReport::from_path(&config.output_json)?;
+    if report.network != config.network {
+        return Err(JayceError::Config(format!(
+            "The deployment report {} was created for network {}",
+            config.output_json.to_str().unwrap(),
+            report.network
+        )));
+    }
+    let commit = report.git_commit.clone().ok_or_else(|| {
+        JayceError::Config(format!(
+            "The deployment report {} doesn't record a git commit, deploy every package once \
+            without --changed-only",
+            config.output_json.to_str().unwrap()
         ))
-        .build()?
-        .try_deserialize()?;
-    let mut named_addresses = move_toml.addresses;
-    ensure!(
-        named_addresses.contains_key(address_name),
-        format!(
-            "Address name {} not found in {}/Move.toml",
-            address_name,
-            package_dir.to_str().unwrap()
-        )
-    );
-    if module_type == DeployModuleType::Object {
-        named_addresses.remove(address_name);
+    })?;
+    let Some(first_package) = config.modules_path.first() else {
+        return Ok(());
+    };
+    // The packages may be in several repositories, each compared with the commit it was deployed
+    // from. Older reports only record the commit of the repository of the first package.
+    let first_root = repository_root(first_package);
+    let mut package_roots = BTreeMap::new();
+    let mut repository_changes = BTreeMap::new();
+    for package_dir in &config.modules_path {
+        let root = repository_root(package_dir).ok_or_else(|| {
+            JayceError::Config(format!(
+                "--changed-only requires the packages to be in git repositories, {} isn't",
+                package_dir.to_str().unwrap()
+            ))
+        })?;
+        package_roots.insert(package_dir.canonicalize()?, root.clone());
+        if repository_changes.contains_key(&root) {
+            continue;
+        }
+        let files = match report
+            .git_commits
+            .get(&root)
+            .or((Some(&root) == first_root.as_ref()).then_some(&commit))
+        {
+            Some(commit) => Some(changed_files(package_dir, commit)?),
+            None => {
+                info!(
+                    "No commit of {} is recorded, its packages are deployed again",
+                    root.to_str().unwrap()
+                );
+                None
+            }
+        };
+        repository_changes.insert(root, files);
     }
-    Ok(named_addresses)
-}
 
-#[cfg(test)]
-mod test {
-    use std::collections::BTreeMap;
-    use std::path::PathBuf;
+    let mut changed = BTreeSet::new();
+    for (package_dir, address_name) in sort_packages(
+        &config.modules_path,
+        &config.addresses_name,
+        &config.package_options,
+    )? {
+        let package_dir = package_dir.canonicalize()?;
+        let named_addresses = get_named_addresses(
+            &package_dir,
+            &address_name,
+            config.package_module_type(&address_name),
+        )?;
+        let overrides = config
+            .package_options
+            .get(&address_name)
+            .map(|options| &options.overrides);
+        let package_changed = match &repository_changes[&package_roots[&package_dir]] {
+            Some(files) => files.iter().any(|file| file.starts_with(&package_dir)),
+            // The commit the repository was deployed from is unknown.
+            None => true,
+        };
+        if package_changed
+            || changed.iter().any(|dependency: &String| {
+                named_addresses.contains_key(dependency)
+                    || overrides.is_some_and(|overrides| {
+                        overrides.values().any(|value| value == dependency)
+                    })
+            })
+        {
+            changed.insert(address_name);
+        }
+    }
+
+    let deployed_addresses = report.deployed_addresses();
+    for address_name in &config.addresses_name {
+        if changed.contains(address_name) {
+            info!("{} changed since {}", address_name, commit);
+        } else if let Some(address) = deployed_addresses.get(address_name) {
+            info!(
+                "{} didn't change since {}, skipping it",
+                address_name, commit
+            );
+            config
+                .deployed_addresses
+                .entry(address_name.clone())
+                .or_insert(*address);
+        }
+    }
+    if config.report_mode == ReportMode::Overwrite {
+        config.report_mode = ReportMode::Merge;
+    }
+    Ok(())
+}
 
-    use anyhow::anyhow;
-    use aptos::common::types::CliCommand;
-    use aptos::node::NodeTool;
-    use aptos::Tool;
-    use clap::Parser;
-    use tokio::sync::oneshot;
+/// The addresses of the packages deployed before this run, configured or recorded by the resumed
+/// run.
+fn known_addresses(config: &DeployConfig, state: &DeployState) -> BTreeMap<String, AccountAddress> {
+    let mut deployed_addresses = config.deployed_addresses.clone();
+    for (address_name, package_state) in &state.packages {
+        deployed_addresses
+            .entry(address_name.clone())
+            .or_insert(package_state.deployed_at);
+    }
+    deployed_addresses
+}
 
-    use crate::deploy_config::{AptosNetwork, DeployConfig, DeployModuleType};
-    use crate::tasks::deploy_contracts::deploy_contracts;
+/// Runs the pre-deploy hooks of the deployment, then those of the packages left to deploy. They run
+/// before anything is compiled, the cost estimate included, so that they can generate sources.
+async fn run_pre_deploy_hooks(config: &DeployConfig, state: &DeployState) -> Result<()> {
+    let deployed_addresses = known_addresses(config, state);
+    run_hooks(&config.hooks.pre_deploy, config, &deployed_addresses, None).await?;
+    for address_name in &config.addresses_name {
+        if deployed_addresses.contains_key(address_name) {
+            continue;
+        }
+        if let Some(hooks) = config.package_hooks.get(address_name) {
+            let package = HookPackage {
+                address_name,
+                deployed_at: None,
+            };
+            run_hooks(
+                &hooks.pre_deploy,
+                config,
+                &deployed_addresses,
+                Some(&package),
+            )
+            .await?;
+        }
+    }
+    Ok(())
+}
 
-    #[tokio::test]
-    async fn test_deploy_contracts() {
-        let (tx, rx) = oneshot::channel();
-        let run_localnet_task = tokio::spawn(async move {
-            tokio::select! {
-                _ = rx => {
-                    println!("Localnet task finished");
-                }
-                _ = run_localnet() => {
-                    println!("Localnet task finished");
+#[instrument(skip_all, fields(network = %config.network_name()))]
+async fn run_core<B: PublishBackend>(
+    config: &DeployConfig,
+    backend: &B,
+    report_info: &mut Vec<TxReport>,
+    state: &mut DeployState,
+    state_path: Option<&Path>,
+    sender_addr: AccountAddress,
+) -> Result<()> {
+    let mut deployed_addresses = known_addresses(config, state);
+    let packages = deployment_order(config, backend.bundle())?;
+    let _network_status = match dashboard() {
+        Some(dashboard) => {
+            dashboard.set_packages(
+                packages
+                    .iter()
+                    .map(|(_, address_name)| address_name.clone()),
+            );
+            Some(dashboard.watch_network(
+                config.network_name(),
+                rest_client(&config.network, config.rest_url.clone())?,
+            ))
+        }
+        None => None,
+    };
+    if packages.iter().any(|(_, address_name)| {
+        config.package_module_type(address_name) == DeployModuleType::Object
+    }) {
+        let mut sequence_numbers =
+            BTreeMap::from([(sender_addr, backend.sequence_number(sender_addr).await?)]);
+        for (_, address_name) in &packages {
+            let sender = package_sender(config, address_name, sender_addr)?;
+            if !sequence_numbers.contains_key(&sender) {
+                sequence_numbers.insert(sender, backend.sequence_number(sender).await?);
+            }
+        }
+        for (address_name, predicted_address) in predict_object_addresses(
+            config,
+            &packages,
+            &deployed_addresses,
+            sender_addr,
+            sequence_numbers,
+        )? {
+            info!("{} will be deployed at {}", address_name, predicted_address);
+        }
+    }
+    let progress = DeployProgress::new().with_events(EventWriter::open(
+        config.events_file.as_deref(),
+        config.json_events,
+        &config.network_name(),
+    )?);
+    let mut failed = vec![];
+    let mut skipped = vec![];
+    // With `pipeline`, the packages are only confirmed once every publication is submitted.
+    let mut submitted = vec![];
+    let mut result = Ok(());
+    for (package_dir, address_name) in &packages {
+        let package_progress = progress.package(address_name);
+        if deployed_addresses.contains_key(address_name) {
+            if let Some(package_state) = state.packages.get(address_name) {
+                let tx_report = package_state.tx_report(package_dir, address_name);
+                match &tx_report.error {
+                    Some(error) => warn!(
+                        "{} was {}, the resumed run doesn't retry them",
+                        address_name, error
+                    ),
+                    None => info!("{} was deployed by the resumed run", address_name),
                 }
+                report_info.push(tx_report);
             }
-        });
-        tokio::time::sleep(tokio::time::Duration::from_secs(30)).await;
+            package_progress.finish(Phase::Skipped);
+            continue;
+        }
+        let named_addresses =
+            match package_named_addresses(config, backend.bundle(), package_dir, address_name) {
+                Ok(named_addresses) => named_addresses,
+                Err(err) => {
+                    result = Err(err);
+                    break;
+                }
+            };
+        let overrides = config
+            .package_options
+            .get(address_name)
+            .map(|options| &options.overrides);
+        if let Some(dependency) = failed.iter().chain(&skipped).find(|dependency| {
+            named_addresses.contains(*dependency)
+                || overrides
+                    .is_some_and(|overrides| overrides.values().any(|value| value == *dependency))
+        }) {
+            package_progress.finish(Phase::Skipped);
+            report_info.push(TxReport::not_deployed(
+                package_dir,
+                address_name,
+                DeployStatus::Skipped,
+                format!("dependency {} was not deployed", dependency),
+            ));
+            skipped.push(address_name.clone());
+            continue;
+        }
 
-        let config = DeployConfig {
-            module_type: DeployModuleType::Object,
-            private_key: None,
-            network: AptosNetwork::Local,
-            modules_path: vec![
-                PathBuf::from("examples/contracts/navori/libs"),
-                PathBuf::from("examples/contracts/navori/cpu-2"),
-                PathBuf::from("examples/contracts/navori/cpu"),
-                PathBuf::from("examples/contracts/navori/verifier"),
-            ],
-            addresses_name: vec![
-                "lib_addr".to_string(),
-                "cpu_2_addr".to_string(),
-                "cpu_addr".to_string(),
-                "verifier_addr".to_string(),
-            ],
-            yes: true,
-            output_json: PathBuf::from("test.json"),
-            deployed_addresses: BTreeMap::new(),
-            rest_url: Some("http://localhost:8080".to_string()),
-            faucet_url: Some("http://localhost:8081".to_string()),
-            publish_code: false,
+        let published = match publish_package(
+            config,
+            backend,
+            package_dir,
+            address_name,
+            &mut deployed_addresses,
+            sender_addr,
+            package_progress,
+        )
+        .await
+        {
+            Ok(Publication::Submitted(package)) if config.pipeline => {
+                submitted.push(package);
+                continue;
+            }
+            Ok(Publication::Submitted(package)) => {
+                confirm_package(
+                    config,
+                    backend,
+                    package,
+                    &deployed_addresses,
+                    state,
+                    state_path,
+                    sender_addr,
+                )
+                .await
+            }
+            Ok(Publication::UpToDate(tx_report)) => {
+                report_info.push(tx_report);
+                continue;
+            }
+            Err(err) => Err(err),
         };
-        deploy_contracts(config).await.unwrap();
+        if let Err(err) = record_result(
+            config,
+            &progress,
+            report_info,
+            &mut failed,
+            package_dir,
+            address_name,
+            published,
+        ) {
+            result = Err(err);
+            break;
+        }
+    }
+    // The packages submitted before a failure are published all the same, so they are confirmed
+    // and reported before failing with it.
+    for package in submitted {
+        let (package_dir, address_name) =
+            (package.package_dir.clone(), package.address_name.clone());
+        let confirmed = confirm_package(
+            config,
+            backend,
+            package,
+            &deployed_addresses,
+            state,
+            state_path,
+            sender_addr,
+        )
+        .await;
+        let recorded = record_result(
+            config,
+            &progress,
+            report_info,
+            &mut failed,
+            &package_dir,
+            &address_name,
+            confirmed,
+        );
+        result = result.and(recorded);
+    }
+    result?;
 
-        tx.send(()).unwrap();
-        run_localnet_task.await.unwrap();
+    if !failed.is_empty() {
+        return Err(JayceError::Publish(format!(
+            "{} package(s) failed to deploy ({}), {} skipped ({})",
+            failed.len(),
+            failed.join(", "),
+            skipped.len(),
+            skipped.join(", ")
+        )));
     }
+    run_hooks(&config.hooks.post_deploy, config, &deployed_addresses, None).await
+}
 
-    async fn run_localnet() -> anyhow::Result<()> {
-        let args = "aptos node run-localnet --performance";
-        let args: Vec<_> = args.split_whitespace().collect();
-        let tool = Tool::try_parse_from(args)?;
-        if let Tool::Node(NodeTool::RunLocalnet(cmd_executor)) = tool {
-            cmd_executor.execute().await?;
-        } else {
-            return Err(anyhow!("Wrong arguments to run localnet"));
+/// Records the deployment of a package in the report, failing with its error unless
+/// `keep_going`.
+fn record_result(
+    config: &DeployConfig,
+    progress: &DeployProgress,
+    report_info: &mut Vec<TxReport>,
+    failed: &mut Vec<String>,
+    package_dir: &Path,
+    address_name: &str,
+    result: Result<TxReport>,
+) -> Result<()> {
+    let err = match result {
+        // A package whose post-publish steps failed is deployed, but fails the deployment.
+        Ok(tx_report) => match tx_report.error.clone() {
+            Some(error) => {
+                report_info.push(tx_report);
+                JayceError::Publish(format!("{} was {}", address_name, error))
+            }
+            None => {
+                report_info.push(tx_report);
+                return Ok(());
+            }
+        },
+        Err(err) => {
+            report_info.push(TxReport::not_deployed(
+                package_dir,
+                address_name,
+                DeployStatus::Failed,
+                err.to_string(),
+            ));
+            err
         }
-        Ok(())
+    };
+    progress.emit(DeployEvent::Failed {
+        address_name: address_name.to_string(),
+        error: redact(&err.to_string()),
+    });
+    if !config.keep_going {
+        return Err(err);
+    }
+    error!("Failed to deploy {}: {}", address_name, err);
+    failed.push(address_name.to_string());
+    Ok(())
+}
+
+/// Simulates the publication of a package, failing with the reason the simulation failed, or if
+/// its fee exceeds `max_fee`.
+async fn simulate_publish<B: PublishBackend>(
+    config: &DeployConfig,
+    backend: &B,
+    request: &PublishRequest<'_>,
+    chunked: bool,
+) -> Result<()> {
+    // The chunks of a package are staged by the previous ones, they can't be simulated before
+    // those are committed.
+    if chunked {
+        if config.max_fee.is_some() || config.simulate_first == Some(true) {
+            return Err(JayceError::Publish(format!(
+                "{} requires chunked publish, which can't be simulated nor checked against \
+                max_fee: drop max_fee and simulate_first to publish it without simulation",
+                request.address_name
+            )));
+        }
+        warn!(
+            "Chunked publish can't be simulated, {} is published without simulation",
+            request.address_name
+        );
+        return Ok(());
+    }
+    let tx_info = config
+        .retry
+        .run(|| backend.simulate(request))
+        .await
+        .map_err(|err| simulation_failed(request.address_name, &err.to_string()))?;
+    if let Some(tx) = tx_info.iter().find(|tx| tx.success == Some(false)) {
+        return Err(simulation_failed(
+            request.address_name,
+            tx.vm_status.as_deref().unwrap_or_default(),
+        ));
+    }
+    match config.max_fee {
+        Some(max_fee) => confirm_fee(
+            config,
+            request.address_name,
+            transactions_fee(&tx_info),
+            max_fee,
+        ),
+        None => Ok(()),
+    }
+}
+
+pub(crate) fn simulation_failed(address_name: &str, vm_status: &str) -> JayceError {
+    JayceError::Publish(format!(
+        "The simulation of {} failed: {}",
+        address_name,
+        describe_vm_status(vm_status)
+    ))
+}
+
+/// Fails if the estimated fee of a package exceeds `max_fee`, unless the user confirms it.
+pub(crate) fn confirm_fee(
+    config: &DeployConfig,
+    address_name: &str,
+    fee: u64,
+    max_fee: u64,
+) -> Result<()> {
+    if fee <= max_fee {
+        return Ok(());
+    }
+    let message = format!(
+        "The estimated fee of {} ({} Octas) exceeds --max-fee ({} Octas)",
+        address_name, fee, max_fee
+    );
+    if !config.yes
+        && Confirm::with_theme(prompt_theme().as_ref())
+            .with_prompt(format!("{}, publish it anyway?", message))
+            .default(false)
+            .interact()?
+    {
+        return Ok(());
+    }
+    Err(JayceError::Publish(message))
+}
+
+/// A package whose publication was submitted, waiting to be confirmed.
+struct SubmittedPackage {
+    package_dir: PathBuf,
+    address_name: String,
+    /// The account publishing the package.
+    sender: AccountAddress,
+    deployed_at: AccountAddress,
+    package: PreparedPackage,
+    pending: PendingPublish,
+    progress: PackageProgress,
+}
+
+/// What [`publish_package`] did with a package.
+enum Publication {
+    Submitted(SubmittedPackage),
+    /// The package was already up to date, it is reported as skipped.
+    UpToDate(TxReport),
+}
+
+/// Compiles a package and submits its publication, unless it is already up to date.
+#[allow(clippy::too_many_arguments)]
+#[instrument(skip_all, fields(address_name = %address_name))]
+async fn publish_package<B: PublishBackend>(
+    config: &DeployConfig,
+    backend: &B,
+    package_dir: &Path,
+    address_name: &str,
+    deployed_addresses: &mut BTreeMap<String, AccountAddress>,
+    deployer_addr: AccountAddress,
+    package_progress: PackageProgress,
+) -> Result<Publication> {
+    let sender_addr = package_sender(config, address_name, deployer_addr)?;
+    package_progress.set_phase(Phase::Compiling);
+    let package = prepare_package(
+        config,
+        backend.bundle(),
+        package_dir,
+        address_name,
+        deployed_addresses,
+        sender_addr,
+    )?;
+    let request = PublishRequest {
+        config,
+        package_dir,
+        address_name,
+        sender: sender_addr,
+        named_addresses: &package.named_addresses,
+        payload_named_addresses: &package.payload_named_addresses,
+        deployed_addresses,
+    };
+    let compiled = backend.compile(&request).await?;
+    // Account packages are published at the sender address, so the payload is the one that
+    // would be published and can be compared with the package already published there.
+    if config.package_module_type(address_name) == DeployModuleType::Account {
+        if let Some(published) = backend
+            .find_up_to_date_package(sender_addr, &package.name, &compiled.code)
+            .await?
+            .filter(|published| published.upgrade_policy.policy == package.upgrade_policy.code())
+        {
+            info!(
+                "Package {} is already up to date (upgrade number {})",
+                package.name, published.upgrade_number
+            );
+            deployed_addresses.insert(address_name.to_string(), sender_addr);
+            package_progress.finish(Phase::Skipped);
+            let mut tx_report = TxReport::not_deployed(
+                package_dir,
+                address_name,
+                DeployStatus::Skipped,
+                "already up to date".to_string(),
+            );
+            tx_report.deployed_at = sender_addr;
+            tx_report.sender = (sender_addr != deployer_addr).then_some(sender_addr);
+            tx_report.upgrade_number = Some(published.upgrade_number);
+            tx_report.set_explorer_urls(config);
+            return Ok(Publication::UpToDate(tx_report));
+        }
+    }
+    let payload_size = publish_payload_size(&compiled.metadata, &compiled.code)?;
+    let chunked = payload_size > MAX_PUBLISH_PACKAGE_SIZE && !config.override_size_check;
+    if payload_size > MAX_PUBLISH_PACKAGE_SIZE && config.override_size_check {
+        warn!(
+            "The package is larger than {} bytes ({} bytes), publishing it in a single \
+            transaction because of --override-size-check",
+            MAX_PUBLISH_PACKAGE_SIZE, payload_size
+        );
+    }
+    if chunked {
+        if config.no_chunked {
+            return Err(JayceError::Publish(format!(
+                "The package is larger than {} bytes ({} bytes) and chunked publish is disabled",
+                MAX_PUBLISH_PACKAGE_SIZE, payload_size
+            )));
+        }
+        if !config.chunked_publish_supported() {
+            return Err(JayceError::Publish(format!(
+                "The package is larger than {} bytes ({} bytes) and {} is not supported for \
+                chunked publish",
+                MAX_PUBLISH_PACKAGE_SIZE, payload_size, config.network
+            )));
+        }
+        info!(
+            "The package is larger than {} bytes ({} bytes), using chunked publish...",
+            MAX_PUBLISH_PACKAGE_SIZE, payload_size
+        );
+    }
+    if config.simulates_first() {
+        package_progress.set_phase(Phase::Simulating);
+        simulate_publish(config, backend, &request, chunked).await?;
+    }
+
+    package_progress.set_phase(Phase::Submitting);
+    let sequence_number = backend.sequence_number(sender_addr).await?;
+    // A failed submission may have reached the network, it is only submitted again when it
+    // didn't.
+    let mut attempted = false;
+    let pending = config
+        .retry
+        .run(|| {
+            let retried = std::mem::replace(&mut attempted, true);
+            let (request, compiled) = (&request, &compiled);
+            async move {
+                if retried {
+                    if let Some(pending) = backend
+                        .find_submitted(request, sequence_number, chunked)
+                        .await?
+                    {
+                        return Ok(pending);
+                    }
+                }
+                backend.submit(request, compiled, chunked).await
+            }
+        })
+        .await?;
+    let deployed_at = match config.package_module_type(address_name) {
+        DeployModuleType::Account => sender_addr,
+        DeployModuleType::Object => {
+            let deployed_at = pending.deployed_at.unwrap();
+            // Chunked publish creates the object in its last transaction.
+            let predicted_address = create_object_code_deployment_address(
+                sender_addr,
+                sequence_number + pending.tx_info.len() as u64 - 1,
+            );
+            if deployed_at != predicted_address {
+                return Err(JayceError::Publish(format!(
+                    "{} was deployed at {}, but it was predicted at {}",
+                    address_name, deployed_at, predicted_address
+                )));
+            }
+            deployed_at
+        }
+    };
+    deployed_addresses.insert(address_name.to_string(), deployed_at);
+    Ok(Publication::Submitted(SubmittedPackage {
+        package_dir: package_dir.to_path_buf(),
+        address_name: address_name.to_string(),
+        sender: sender_addr,
+        deployed_at,
+        package,
+        pending,
+        progress: package_progress,
+    }))
+}
+
+/// Waits for the publication of a package, then freezes it, verifies its sources, runs its
+/// initializers and post-deploy hooks, and records it.
+#[instrument(skip_all, fields(address_name = %submitted.address_name))]
+async fn confirm_package<B: PublishBackend>(
+    config: &DeployConfig,
+    backend: &B,
+    submitted: SubmittedPackage,
+    deployed_addresses: &BTreeMap<String, AccountAddress>,
+    state: &mut DeployState,
+    state_path: Option<&Path>,
+    deployer_addr: AccountAddress,
+) -> Result<TxReport> {
+    let SubmittedPackage {
+        package_dir,
+        address_name,
+        sender: sender_addr,
+        deployed_at,
+        package,
+        pending,
+        progress: package_progress,
+    } = submitted;
+    let address_name = address_name.as_str();
+    let PublishOutcome {
+        tx_info, checksums, ..
+    } = backend.wait(pending).await?;
+    // The package is published: it is recorded before anything else can fail, and the failures
+    // of the next steps are reported with it.
+    let mut tx_report = TxReport {
+        module_path: package_dir.clone(),
+        address_name: address_name.to_string(),
+        deployed_at,
+        sender: (sender_addr != deployer_addr).then_some(sender_addr),
+        tx_info,
+        freeze_tx_info: None,
+        init_tx_info: vec![],
+        registry_tx_info: None,
+        proposal_id: None,
+        payload_path: None,
+        explorer_url: None,
+        tx_explorer_urls: vec![],
+        upgrade_policy: Some(package.upgrade_policy.clone()),
+        upgrade_number: None,
+        artifacts_path: None,
+        checksums,
+        compiler: Some(package.compiler.clone()),
+        included_artifacts: Some(package.included_artifacts.clone()),
+        jayce_version: Some(JAYCE_VERSION.to_string()),
+        status: DeployStatus::Deployed,
+        error: None,
+    };
+    tx_report.set_explorer_urls(config);
+    save_package_state(state, state_path, &tx_report)?;
+    if let Some(address_book) = &config.address_book {
+        record_address(
+            address_book,
+            &config.network_name(),
+            address_name,
+            deployed_at,
+        )?;
+    }
+
+    if let Err(err) = post_publish(
+        config,
+        backend,
+        &package,
+        deployed_addresses,
+        sender_addr,
+        &package_progress,
+        &mut tx_report,
+    )
+    .await
+    {
+        tx_report.error = Some(redact(&format!(
+            "published, but its post-publish steps failed: {}",
+            err
+        )));
+    }
+    tx_report.upgrade_number = backend
+        .upgrade_number(deployed_at, &package.name)
+        .await
+        .unwrap_or_else(|err| {
+            warn!(
+                "Failed to read the upgrade number of {}: {}",
+                package.name, err
+            );
+            None
+        });
+    tx_report.set_explorer_urls(config);
+    save_package_state(state, state_path, &tx_report)?;
+    for tx in tx_report.transactions() {
+        metrics().gas_used.add(
+            tx.gas_used.unwrap_or_default(),
+            &[KeyValue::new("network", config.network_name())],
+        );
+        if let Some(dashboard) = dashboard() {
+            dashboard.add_gas(
+                tx.gas_used.unwrap_or_default(),
+                tx.gas_unit_price.unwrap_or_default(),
+            );
+        }
+    }
+    notify_webhooks(&config.webhooks, &deployed_event(config, &tx_report)).await;
+    package_progress.confirm(
+        deployed_at,
+        tx_report
+            .transactions()
+            .map(|tx| tx.transaction_hash)
+            .collect(),
+    );
+    tx_report.print_explorer_urls();
+    Ok(tx_report)
+}
+
+/// Checkpoints a published package in the state, with its report so far, and writes it to
+/// `state_path` when the run can be resumed.
+fn save_package_state(
+    state: &mut DeployState,
+    state_path: Option<&Path>,
+    tx_report: &TxReport,
+) -> Result<()> {
+    state.packages.insert(
+        tx_report.address_name.clone(),
+        PackageState {
+            deployed_at: tx_report.deployed_at,
+            tx_hashes: tx_report
+                .tx_info
+                .iter()
+                .map(|tx| tx.transaction_hash)
+                .collect(),
+            report: Some(tx_report.clone()),
+        },
+    );
+    match state_path {
+        Some(state_path) => state.save(state_path),
+        None => Ok(()),
+    }
+}
+
+/// Runs the steps following the publication of a package: saving its artifacts, freezing it,
+/// verifying its sources, its initializers, the registry and its post-deploy hooks. Their
+/// transactions are added to `tx_report`.
+async fn post_publish<B: PublishBackend>(
+    config: &DeployConfig,
+    backend: &B,
+    package: &PreparedPackage,
+    deployed_addresses: &BTreeMap<String, AccountAddress>,
+    sender_addr: AccountAddress,
+    package_progress: &PackageProgress,
+    tx_report: &mut TxReport,
+) -> Result<()> {
+    let package_dir = tx_report.module_path.clone();
+    let address_name = tx_report.address_name.clone();
+    let address_name = address_name.as_str();
+    let deployed_at = tx_report.deployed_at;
+    tx_report.artifacts_path =
+        save_artifacts(config, backend.bundle(), &package_dir, address_name)?;
+    if config.package_module_type(address_name) == DeployModuleType::Object
+        && config
+            .package_options
+            .get(address_name)
+            .is_some_and(|options| options.freeze)
+    {
+        package_progress.set_phase(Phase::Freezing);
+        tx_report.freeze_tx_info = Some(
+            backend
+                .freeze(config, address_name, sender_addr, deployed_at)
+                .await?,
+        );
+        info!("Froze the code of {} at {}", address_name, deployed_at);
+    }
+    if config.verify_source {
+        package_progress.set_phase(Phase::Verifying);
+        verify_package_source(
+            &package_dir,
+            deployed_at,
+            &named_addresses_arg(
+                &package_dir,
+                address_name,
+                &config.package_addresses(address_name, deployed_addresses)?,
+            )?,
+            &config.compiler_options(address_name),
+            config.upgrade_policy(address_name),
+            &resolve_rest_url(&config.network, config.rest_url.clone())?,
+        )
+        .await?;
+        info!("Verified the sources of {}", address_name);
+    }
+    for call in config.initializers.get(address_name).into_iter().flatten() {
+        package_progress.set_phase(Phase::Initializing);
+        tx_report.init_tx_info.push(
+            run_entry_function(
+                call,
+                &config.known_addresses(deployed_addresses),
+                &package_signer_args(config, address_name)?,
+                &gas_args(config),
+                config.yes,
+            )
+            .await?,
+        );
+    }
+    tx_report.registry_tx_info = match config
+        .registry
+        .as_ref()
+        .and_then(|registry| registry.address)
+    {
+        Some(registry) => {
+            Some(record_deployment(config, registry, &package.name, deployed_at).await?)
+        }
+        None => None,
+    };
+    if let Some(hooks) = config.package_hooks.get(address_name) {
+        let hook_package = HookPackage {
+            address_name,
+            deployed_at: Some(deployed_at),
+        };
+        run_hooks(
+            &hooks.post_deploy,
+            config,
+            deployed_addresses,
+            Some(&hook_package),
+        )
+        .await?;
+    }
+    Ok(())
+}
+
+/// Copies the published artifacts of a package to the artifacts directory of the run, returning
+/// the directory they were copied to. The artifacts of a package of `bundle` are copied from it.
+pub(crate) fn save_artifacts(
+    config: &DeployConfig,
+    bundle: Option<&Bundle>,
+    package_dir: &Path,
+    address_name: &str,
+) -> Result<Option<PathBuf>> {
+    let Some(artifacts_dir) = &config.artifacts_dir else {
+        return Ok(None);
+    };
+    let artifacts_path = artifacts_dir.join(address_name);
+    match bundle.and_then(|bundle| Some((bundle, bundle.package(address_name)?))) {
+        Some((bundle, package)) => bundle.copy_artifacts(package, &artifacts_path)?,
+        None => copy_package_artifacts(package_dir, &artifacts_path)?,
+    }
+    Ok(Some(artifacts_path))
+}
+
+/// What publishing a package needs besides its payload, read from its `Move.toml` and options,
+/// or from the bundle it was compiled into.
+pub(crate) struct PreparedPackage {
+    /// The name of the package in its `Move.toml`.
+    pub name: String,
+    pub upgrade_policy: UpgradePolicy,
+    /// The `--named-addresses` option publishing the package, empty for a bundled package.
+    pub named_addresses: String,
+    /// The `--named-addresses` option compiling the package, empty for a bundled package.
+    pub payload_named_addresses: String,
+    pub compiler: CompilerOptions,
+    pub included_artifacts: IncludedArtifacts,
+}
+
+/// Prepares the publication of a package. The packages of `bundle` are already compiled, they
+/// must have been compiled with the upgrade policy of their options.
+pub(crate) fn prepare_package(
+    config: &DeployConfig,
+    bundle: Option<&Bundle>,
+    package_dir: &Path,
+    address_name: &str,
+    deployed_addresses: &BTreeMap<String, AccountAddress>,
+    sender_addr: AccountAddress,
+) -> Result<PreparedPackage> {
+    if let Some(package) = bundle.and_then(|bundle| bundle.package(address_name)) {
+        if let Some(upgrade_policy) = config
+            .package_options
+            .get(address_name)
+            .and_then(|options| options.upgrade_policy.as_ref())
+            .filter(|upgrade_policy| **upgrade_policy != package.upgrade_policy)
+        {
+            return Err(JayceError::Package(format!(
+                "Package '{}' of the bundle was compiled with the '{}' upgrade policy, but its \
+                options set '{}'",
+                address_name, package.upgrade_policy, upgrade_policy
+            )));
+        }
+        return Ok(PreparedPackage {
+            name: package.name.clone(),
+            upgrade_policy: package.upgrade_policy.clone(),
+            named_addresses: String::new(),
+            payload_named_addresses: String::new(),
+            compiler: package.compiler.clone(),
+            included_artifacts: package.included_artifacts.clone(),
+        });
+    }
+    Ok(PreparedPackage {
+        name: MoveTomlFile::from_package_dir(package_dir)?.package.name,
+        upgrade_policy: resolve_upgrade_policy(config, package_dir, address_name)?,
+        named_addresses: resolve_named_addresses(
+            config,
+            package_dir,
+            address_name,
+            deployed_addresses,
+            sender_addr,
+        )?,
+        payload_named_addresses: payload_named_addresses(
+            config,
+            package_dir,
+            address_name,
+            deployed_addresses,
+            sender_addr,
+        )?,
+        compiler: config.compiler_options(address_name),
+        included_artifacts: config.included_artifacts(address_name),
+    })
+}
+
+/// Returns the packages to deploy, in deployment order. The packages of `bundle` are already in
+/// order, and their sources may be gone.
+pub(crate) fn deployment_order(
+    config: &DeployConfig,
+    bundle: Option<&Bundle>,
+) -> Result<Vec<(PathBuf, String)>> {
+    match bundle {
+        Some(bundle) => Ok(bundle
+            .packages()
+            .iter()
+            .map(|package| (package.module_path.clone(), package.address_name.clone()))
+            .collect()),
+        None => sort_packages(
+            &config.modules_path,
+            &config.addresses_name,
+            &config.package_options,
+        ),
+    }
+}
+
+/// Returns the named addresses a package refers to, from its `Move.toml` or its bundle.
+fn package_named_addresses(
+    config: &DeployConfig,
+    bundle: Option<&Bundle>,
+    package_dir: &Path,
+    address_name: &str,
+) -> Result<BTreeSet<String>> {
+    match bundle.and_then(|bundle| bundle.package(address_name)) {
+        Some(package) => Ok(package.named_addresses.keys().cloned().collect()),
+        None => Ok(get_named_addresses(
+            package_dir,
+            address_name,
+            config.package_module_type(address_name),
+        )?
+        .into_keys()
+        .collect()),
+    }
+}
+
+/// Builds the `--named-addresses` argument of a package, resolving the address names of its
+/// dependencies from the already deployed packages.
+pub(crate) fn resolve_named_addresses(
+    config: &DeployConfig,
+    package_dir: &Path,
+    address_name: &str,
+    deployed_addresses: &BTreeMap<String, AccountAddress>,
+    sender_addr: AccountAddress,
+) -> Result<String> {
+    let named_addresses = get_named_addresses(
+        package_dir,
+        address_name,
+        config.package_module_type(address_name),
+    )?;
+    let deployed_addresses = config.package_addresses(address_name, deployed_addresses)?;
+    Ok(named_addresses
+        .keys()
+        .map(|named_address| {
+            let mut hex_address = deployed_addresses.get(named_address);
+            if hex_address.is_none() {
+                if named_address == address_name {
+                    hex_address = Some(&sender_addr);
+                } else {
+                    return Err(JayceError::Dependency(format!(
+                        "'{}' should be deployed before '{}'",
+                        named_address, address_name
+                    )));
+                }
+            }
+            Ok(format!("{}={}", named_address, hex_address.unwrap()))
+        })
+        .collect::<Result<Vec<_>>>()?
+        .into_iter()
+        .reduce(|acc, cur| format!("{},{}", acc, cur))
+        .map(|named_addresses| format!("--named-addresses {}", named_addresses))
+        .unwrap_or("".to_string()))
+}
+
+/// Builds the `--named-addresses` argument compiling a package. The address of an object package
+/// is only known when publishing it, any address gives the same payload size.
+pub(crate) fn payload_named_addresses(
+    config: &DeployConfig,
+    package_dir: &Path,
+    address_name: &str,
+    deployed_addresses: &BTreeMap<String, AccountAddress>,
+    sender_addr: AccountAddress,
+) -> Result<String> {
+    let mut payload_addresses = config.package_addresses(address_name, deployed_addresses)?;
+    payload_addresses.insert(address_name.to_string(), sender_addr);
+    named_addresses_arg(package_dir, address_name, &payload_addresses)
+}
+
+/// Returns the upgrade policy of a package: the one of its options, otherwise the one of its
+/// `Move.toml`.
+pub(crate) fn resolve_upgrade_policy(
+    config: &DeployConfig,
+    package_dir: &Path,
+    address_name: &str,
+) -> Result<UpgradePolicy> {
+    match config.upgrade_policy(address_name) {
+        Some(upgrade_policy) => Ok(upgrade_policy.clone()),
+        None => Ok(MoveTomlFile::from_package_dir(package_dir)?
+            .package
+            .upgrade_policy
+            .unwrap_or(UpgradePolicy::Compatible)),
+    }
+}
+
+/// Publishes the registry package at the configured address, or at the deployer's account if
+/// there is none, unless it is already there. Returns the address of the registry.
+async fn publish_registry(
+    config: &DeployConfig,
+    sender_addr: AccountAddress,
+) -> Result<AccountAddress> {
+    let configured = config
+        .registry
+        .as_ref()
+        .and_then(|registry| registry.address);
+    let address = configured.unwrap_or(sender_addr);
+    let client = rest_client(&config.network, config.rest_url.clone())?;
+    if let Some(registry) = PackageRegistry::fetch(&client, address)
+        .await?
+        .package(REGISTRY_PACKAGE_NAME)
+    {
+        // An upgradable registry could be changed to rewrite the recorded history.
+        if registry.upgrade_policy.policy != UpgradePolicy::Immutable.code() {
+            warn!(
+                "The deployment registry at {} isn't immutable, its history can be rewritten by \
+                upgrading it",
+                address
+            );
+        }
+        return Ok(address);
+    }
+    if configured.is_some_and(|configured| configured != sender_addr) {
+        return Err(JayceError::Config(format!(
+            "The deployment registry isn't published at {}",
+            address
+        )));
+    }
+
+    info!("Publishing the deployment registry at {}", address);
+    let package_dir = env::temp_dir().join(format!("jayce-registry-{}", address));
+    write_registry_package(&package_dir)?;
+    let args = format!(
+        "aptos move publish --package-dir {} --named-addresses jayce_registry={} {} {} \
+        --assume-yes",
+        package_dir.to_str().unwrap(),
+        address,
+        signer_args(config)?,
+        gas_args(config)
+    );
+    run_deploy_command(&args.split_whitespace().collect())
+        .await
+        .map_err(|err| {
+            JayceError::Publish(format!(
+                "Failed to publish the deployment registry: {}",
+                err
+            ))
+        })?;
+    Ok(address)
+}
+
+/// Records the deployment of a package in the registry.
+async fn record_deployment(
+    config: &DeployConfig,
+    registry: AccountAddress,
+    package_name: &str,
+    deployed_at: AccountAddress,
+) -> Result<TransactionSummary> {
+    let client = rest_client(&config.network, config.rest_url.clone())?;
+    let package = PackageRegistry::fetch(&client, deployed_at)
+        .await?
+        .package(package_name)
+        .cloned()
+        .ok_or_else(|| {
+            JayceError::Publish(format!(
+                "Package {} not found at {} after publishing it",
+                package_name, deployed_at
+            ))
+        })?;
+    run_entry_function(
+        &record_call(registry, &package, deployed_at),
+        &BTreeMap::new(),
+        &signer_args(config)?,
+        &gas_args(config),
+        config.yes,
+    )
+    .await
+}
+
+/// Freezes the code of an object package, so that it can't be upgraded anymore.
+pub(crate) async fn freeze_code_object(
+    config: &DeployConfig,
+    address_name: &str,
+    code_object: AccountAddress,
+) -> Result<TransactionSummary> {
+    let call = EntryFunctionCall {
+        function_id: "0x1::object_code_deployment::freeze_code_object".to_string(),
+        type_args: vec![],
+        args: vec![format!("address:{}", code_object)],
+    };
+    run_entry_function(
+        &call,
+        &BTreeMap::new(),
+        &package_signer_args(config, address_name)?,
+        &gas_args(config),
+        config.yes,
+    )
+    .await
+}
+
+/// Builds the aptos CLI command publishing a package, and the copy of the package it builds when
+/// its options set its upgrade policy, which must be kept until the command has run.
+pub(crate) fn publish_args(
+    config: &DeployConfig,
+    package_dir: &Path,
+    address_name: &str,
+    named_addresses: &str,
+) -> Result<(String, Option<PackageCopy>)> {
+    let (package_dir_args, copy) =
+        package_dir_args(package_dir, config.upgrade_policy(address_name))?;
+    let args = format!(
+        "aptos move {} \
+                {} \
+                --included-artifacts {} \
+                {} \
+                {} \
+                {} \
+                {} \
+                {} \
+                {} \
+                ",
+        match config.package_module_type(address_name) {
+            DeployModuleType::Object => "create-object-and-publish-package",
+            DeployModuleType::Account => "publish",
+        },
+        package_dir_args,
+        config.included_artifacts(address_name),
+        package_signer_args(config, address_name)?,
+        match config.package_module_type(address_name) {
+            DeployModuleType::Account => "".to_string(),
+            DeployModuleType::Object => format!("--address-name {}", address_name),
+        },
+        named_addresses,
+        config.compiler_args(address_name),
+        gas_args(config),
+        if config.override_size_check {
+            "--override-size-check"
+        } else {
+            ""
+        }
+    );
+    Ok((args, copy))
+}
+
+/// Builds the options publishing a package with chunked publish.
+pub(crate) fn chunked_publish_args(config: &DeployConfig) -> String {
+    let mut args = "--chunked-publish".to_string();
+    if let Some(address) = config.large_packages_module_address() {
+        args.push_str(&format!(" --large-packages-module-address {}", address));
+    }
+    if let Some(chunk_size) = config.chunk_size {
+        args.push_str(&format!(" --chunk-size {}", chunk_size));
+    }
+    args
+}
+
+/// Builds the gas options shared by every transaction of the deployment.
+pub(crate) fn gas_args(config: &DeployConfig) -> String {
+    [
+        ("--max-gas", config.max_gas),
+        ("--gas-unit-price", config.gas_unit_price),
+        ("--expiration-secs", config.expiration_secs),
+    ]
+    .iter()
+    .filter_map(|(flag, value)| value.map(|value| format!("{} {}", flag, value)))
+    .collect::<Vec<_>>()
+    .join(" ")
+}
+
+/// Builds the aptos CLI options signing the transactions of the deployment: the configured
+/// profile if there is one, otherwise the private key and the REST URL of the network.
+pub(crate) fn signer_args(config: &DeployConfig) -> Result<String> {
+    match &config.profile {
+        Some(profile) => Ok(format!("--profile {}", profile)),
+        None => {
+            let private_key = config.private_key.as_deref().ok_or_else(|| {
+                JayceError::Config(
+                    "A private key or profile is required to sign with the aptos CLI".to_string(),
+                )
+            })?;
+            let args =
+                private_key_signer_args(&config.network, private_key, config.rest_url.clone())?;
+            match config.sender_address {
+                Some(sender_address) => Ok(format!("{} --sender-account {}", args, sender_address)),
+                None => Ok(args),
+            }
+        }
+    }
+}
+
+/// Builds the aptos CLI options signing the transactions of a package: the profile or private key
+/// of its options if it has one, otherwise the ones of the deployer.
+pub(crate) fn package_signer_args(config: &DeployConfig, address_name: &str) -> Result<String> {
+    let options = config.package_options.get(address_name);
+    match options.map(|options| (&options.profile, &options.private_key)) {
+        Some((Some(profile), _)) => Ok(format!("--profile {}", profile)),
+        Some((None, Some(private_key))) => {
+            private_key_signer_args(&config.network, private_key, config.rest_url.clone())
+        }
+        _ => signer_args(config),
+    }
+}
+
+/// Returns the account publishing a package: the one of the private key of its options if it has
+/// one, otherwise the deployer.
+pub(crate) fn package_sender(
+    config: &DeployConfig,
+    address_name: &str,
+    deployer_addr: AccountAddress,
+) -> Result<AccountAddress> {
+    let private_key = config
+        .package_options
+        .get(address_name)
+        .and_then(|options| options.private_key.as_deref());
+    match private_key {
+        Some(private_key) => Ok(LocalAccount::from_private_key(private_key, 0)
+            .map_err(|err| {
+                JayceError::Config(format!(
+                    "Invalid private key of '{}': {}",
+                    address_name, err
+                ))
+            })?
+            .address()),
+        None => Ok(deployer_addr),
+    }
+}
+
+/// Builds the aptos CLI options signing transactions with a private key, without going through
+/// an aptos CLI profile so that the `.aptos` directory is never touched.
+pub(crate) fn private_key_signer_args(
+    network: &AptosNetwork,
+    private_key: &str,
+    rest_url: Option<String>,
+) -> Result<String> {
+    Ok(format!(
+        "--private-key {} --url {}",
+        private_key,
+        resolve_rest_url(network, rest_url)?
+    ))
+}
+
+pub(crate) async fn run_deploy_command(
+    args: &Vec<&str>,
+) -> anyhow::Result<(Vec<TransactionSummary>, Option<AccountAddress>), CliError> {
+    let tool = Tool::try_parse_from(args).map_err(|err| {
+        CliError::CommandArgumentError(format!("Invalid arguments to deploy contracts: {}", err))
+    })?;
+
+    if let Tool::Move(MoveTool::CreateObjectAndPublishPackage(cmd_executor)) = tool {
+        let (tx_info, object_addr) = cmd_executor.execute().await?;
+        Ok((tx_info, Some(object_addr)))
+    } else if let Tool::Move(MoveTool::Publish(cmd_executor)) = tool {
+        let tx_info = cmd_executor.execute().await?;
+        Ok((tx_info, None))
+    } else {
+        Err(CliError::UnexpectedError(format!(
+            "Wrong arguments to deploy contracts: {:?}",
+            args
+        )))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::BTreeMap;
+    use std::fs;
+    use std::path::{Path, PathBuf};
+
+    use aptos_sdk::crypto::HashValue;
+    use aptos_sdk::move_types::account_address::AccountAddress;
+    use aptos_sdk::types::LocalAccount;
+
+    use crate::deploy_config::{
+        AptosNetwork, CompilerOptions, DeployConfig, DeployModuleType, PackageOptions,
+        DEFAULT_CONFIRMATION_TIMEOUT_SECS, DEFAULT_POLL_INTERVAL_MS,
+    };
+    use crate::deploy_report::{DeployStatus, ReportMode, TxReport};
+    use crate::deploy_state::{DeployState, PackageState};
+    use crate::error::Result;
+    use crate::hooks::Hooks;
+    use crate::publish_backend::{MockBackend, MOCK_GAS_UNIT_PRICE, MOCK_GAS_USED};
+    use crate::report_writer::ReportFormat;
+    use crate::retry::RetryPolicy;
+    use crate::tasks::deploy_contracts::{
+        chunked_publish_args, confirm_mainnet, deploy_contracts, deploy_contracts_with_backend,
+        run_core, signer_args,
+    };
+    use crate::testing::LocalnetFixture;
+    use crate::utils::{create_object_code_deployment_address, fund_account};
+
+    fn test_config(modules_path: Vec<PathBuf>, addresses_name: &[&str]) -> DeployConfig {
+        DeployConfig {
+            module_type: DeployModuleType::Object,
+            private_key: None,
+            network: AptosNetwork::Local,
+            modules_path,
+            addresses_name: addresses_name.iter().map(|name| name.to_string()).collect(),
+            yes: true,
+            allow_mainnet: false,
+            output_json: PathBuf::from("test.json"),
+            output_format: ReportFormat::Json,
+            report_mode: ReportMode::Overwrite,
+            deployed_addresses: BTreeMap::new(),
+            named_addresses: BTreeMap::new(),
+            rest_url: None,
+            faucet_url: None,
+            faucet_urls: vec![],
+            publish_code: false,
+            resume: false,
+            force: false,
+            state_path: PathBuf::from(".jayce-state.json"),
+            generated_key_path: PathBuf::from(".jayce-generated-key"),
+            print_key: false,
+            dry_run: false,
+            skip_balance_check: false,
+            multisig_address: None,
+            fee_payer_private_key: None,
+            max_gas: None,
+            gas_unit_price: None,
+            expiration_secs: None,
+            max_fee: None,
+            simulate_first: None,
+            confirmation_timeout_secs: DEFAULT_CONFIRMATION_TIMEOUT_SECS,
+            poll_interval_ms: DEFAULT_POLL_INTERVAL_MS,
+            initializers: BTreeMap::new(),
+            hooks: Hooks::default(),
+            package_hooks: BTreeMap::new(),
+            package_options: BTreeMap::new(),
+            compiler: CompilerOptions::default(),
+            profile: None,
+            sender_address: None,
+            custom_network: None,
+            retry: RetryPolicy::default(),
+            no_chunked: false,
+            large_packages_module_address: None,
+            override_size_check: false,
+            chunk_size: None,
+            address_book: None,
+            keep_going: false,
+            no_cache: false,
+            changed_only: false,
+            sign_report: false,
+            pipeline: false,
+            artifacts_dir: None,
+            bundle: None,
+            verify_source: false,
+            events_file: None,
+            json_events: false,
+            webhooks: vec![],
+            registry: None,
+            github_actions: false,
+            from_report: None,
+            signer: None,
+            chunked_publish: None,
+        }
+    }
+
+    fn write_package(root: &Path, name: &str, addresses: &[&str]) -> PathBuf {
+        let package_dir = root.join(name);
+        fs::create_dir_all(&package_dir).unwrap();
+        let addresses = addresses
+            .iter()
+            .map(|address| format!("{} = \"_\"\n", address))
+            .collect::<String>();
+        fs::write(
+            package_dir.join("Move.toml"),
+            format!(
+                "[package]\nname = \"{}\"\n\n[addresses]\n{}",
+                name, addresses
+            ),
+        )
+        .unwrap();
+        package_dir
+    }
+
+    /// Runs the core of a deployment of `packages`, given as the address name of each package
+    /// with the named addresses of its `Move.toml`, written to a temporary directory. `configure`
+    /// overrides the test configuration given that directory, and the run resumes from `state`.
+    async fn run_core_with_mock(
+        packages: &[(&str, &[&str])],
+        configure: impl FnOnce(&Path, DeployConfig) -> DeployConfig,
+        backend: &MockBackend,
+        mut state: DeployState,
+    ) -> (Vec<TxReport>, DeployState, Result<()>) {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let root = temp_dir.path();
+        let modules_path = packages
+            .iter()
+            .map(|(address_name, addresses)| write_package(root, address_name, addresses))
+            .collect();
+        let address_names: Vec<&str> = packages
+            .iter()
+            .map(|(address_name, _)| *address_name)
+            .collect();
+        let config = configure(
+            root,
+            DeployConfig {
+                state_path: root.join("state.json"),
+                ..test_config(modules_path, &address_names)
+            },
+        );
+        let mut report_info = vec![];
+        let result = run_core(
+            &config,
+            backend,
+            &mut report_info,
+            &mut state,
+            Some(&config.state_path),
+            AccountAddress::ONE,
+        )
+        .await;
+        (report_info, state, result)
+    }
+
+    /// A library and a package depending on it.
+    const LIB_AND_CPU: &[(&str, &[&str])] = &[
+        ("cpu_addr", &["lib_addr", "cpu_addr"]),
+        ("lib_addr", &["lib_addr"]),
+    ];
+
+    fn new_state() -> DeployState {
+        DeployState::new(AccountAddress::ONE, AptosNetwork::Local)
+    }
+
+    fn statuses(report_info: &[TxReport]) -> Vec<(&str, AccountAddress, DeployStatus)> {
+        report_info
+            .iter()
+            .map(|tx_report| {
+                (
+                    tx_report.address_name.as_str(),
+                    tx_report.deployed_at,
+                    tx_report.status.clone(),
+                )
+            })
+            .collect()
+    }
+
+    fn no_retry_backoff(config: DeployConfig) -> DeployConfig {
+        DeployConfig {
+            retry: RetryPolicy {
+                max_attempts: 2,
+                initial_backoff_ms: 0,
+                max_backoff_ms: 0,
+            },
+            ..config
+        }
+    }
+
+    #[tokio::test]
+    async fn test_run_core_retries_transient_errors() {
+        let backend = MockBackend::new().fail_submit("lib_addr", &["503 Service Unavailable"]);
+        let (report_info, _, result) = run_core_with_mock(
+            LIB_AND_CPU,
+            |_, config| no_retry_backoff(config),
+            &backend,
+            new_state(),
+        )
+        .await;
+        assert!(result.is_ok());
+        assert_eq!(
+            backend.calls(),
+            [
+                "compile lib_addr",
+                "submit lib_addr",
+                "find_submitted lib_addr",
+                "submit lib_addr",
+                "wait lib_addr",
+                "compile cpu_addr",
+                "submit cpu_addr",
+                "wait cpu_addr",
+            ]
+        );
+        assert_eq!(
+            statuses(&report_info),
+            [
+                (
+                    "lib_addr",
+                    create_object_code_deployment_address(AccountAddress::ONE, 0),
+                    DeployStatus::Deployed
+                ),
+                (
+                    "cpu_addr",
+                    create_object_code_deployment_address(AccountAddress::ONE, 1),
+                    DeployStatus::Deployed
+                ),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_run_core_skips_dependents_of_failed_packages() {
+        let backend = MockBackend::new().fail_submit("lib_addr", &["Move abort"]);
+        let (report_info, _, result) = run_core_with_mock(
+            LIB_AND_CPU,
+            |_, config| DeployConfig {
+                keep_going: true,
+                ..no_retry_backoff(config)
+            },
+            &backend,
+            new_state(),
+        )
+        .await;
+        assert!(result.is_err());
+        assert_eq!(backend.calls(), ["compile lib_addr", "submit lib_addr"]);
+        assert_eq!(
+            statuses(&report_info),
+            [
+                ("lib_addr", AccountAddress::ZERO, DeployStatus::Failed),
+                ("cpu_addr", AccountAddress::ZERO, DeployStatus::Skipped),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_run_core_per_package_senders() {
+        let lib_private_key = format!("0x{}", "11".repeat(32));
+        let lib_sender = LocalAccount::from_private_key(&lib_private_key, 0)
+            .unwrap()
+            .address();
+        let (report_info, _, result) = run_core_with_mock(
+            LIB_AND_CPU,
+            |_, config| DeployConfig {
+                package_options: BTreeMap::from([(
+                    "lib_addr".to_string(),
+                    PackageOptions {
+                        private_key: Some(lib_private_key),
+                        ..Default::default()
+                    },
+                )]),
+                ..config
+            },
+            &MockBackend::new(),
+            new_state(),
+        )
+        .await;
+        result.unwrap();
+
+        // Each account publishes its first package with its first transaction.
+        assert_eq!(
+            report_info
+                .iter()
+                .map(|tx_report| (tx_report.deployed_at, tx_report.sender))
+                .collect::<Vec<_>>(),
+            [
+                (
+                    create_object_code_deployment_address(lib_sender, 0),
+                    Some(lib_sender)
+                ),
+                (
+                    create_object_code_deployment_address(AccountAddress::ONE, 0),
+                    None
+                ),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_run_core_max_fee() {
+        let backend = MockBackend::new();
+        let (report_info, _, result) = run_core_with_mock(
+            &[("lib_addr", &["lib_addr"])],
+            |_, config| DeployConfig {
+                max_fee: Some(MOCK_GAS_USED * MOCK_GAS_UNIT_PRICE - 1),
+                ..config
+            },
+            &backend,
+            new_state(),
+        )
+        .await;
+
+        assert!(result.is_err());
+        assert_eq!(backend.calls(), ["compile lib_addr", "simulate lib_addr"]);
+        assert_eq!(report_info[0].status, DeployStatus::Failed);
+    }
+
+    #[tokio::test]
+    async fn test_run_core_reports_resumed_packages() {
+        let backend = MockBackend::new();
+        let mut state = new_state();
+        state.packages.insert(
+            "lib_addr".to_string(),
+            PackageState {
+                deployed_at: AccountAddress::TWO,
+                tx_hashes: vec![HashValue::zero()],
+                report: None,
+            },
+        );
+        let (report_info, state, result) =
+            run_core_with_mock(LIB_AND_CPU, |_, config| config, &backend, state).await;
+        result.unwrap();
+
+        assert_eq!(
+            backend.calls(),
+            ["compile cpu_addr", "submit cpu_addr", "wait cpu_addr"]
+        );
+        assert_eq!(report_info[0].address_name, "lib_addr");
+        assert_eq!(report_info[0].deployed_at, AccountAddress::TWO);
+        assert_eq!(
+            report_info[0].tx_info[0].transaction_hash,
+            HashValue::zero()
+        );
+        assert_eq!(report_info[1].address_name, "cpu_addr");
+        // The resumed run records the reports, for the next resumption.
+        assert!(state.packages["cpu_addr"].report.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_run_core_post_publish_failure() {
+        let (report_info, state, result) = run_core_with_mock(
+            &[("lib_addr", &["lib_addr"])],
+            |root, config| DeployConfig {
+                // The mock doesn't compile the package, so there are no artifacts to save.
+                artifacts_dir: Some(root.join("artifacts")),
+                ..config
+            },
+            &MockBackend::new(),
+            new_state(),
+        )
+        .await;
+
+        assert!(result.is_err());
+        let deployed_at = create_object_code_deployment_address(AccountAddress::ONE, 0);
+        assert_eq!(report_info[0].status, DeployStatus::Deployed);
+        assert_eq!(report_info[0].deployed_at, deployed_at);
+        assert!(report_info[0]
+            .error
+            .as_ref()
+            .unwrap()
+            .contains("post-publish steps failed"));
+        // The published package is recorded for --resume.
+        assert_eq!(state.packages["lib_addr"].deployed_at, deployed_at);
+    }
+
+    #[tokio::test]
+    async fn test_run_core_pipeline() {
+        let backend = MockBackend::new().fail_submit("c_addr", &["Move abort"]);
+        let (report_info, state, result) = run_core_with_mock(
+            &[
+                ("a_addr", &["a_addr"]),
+                ("b_addr", &["b_addr"]),
+                ("c_addr", &["c_addr"]),
+            ],
+            |_, config| DeployConfig {
+                pipeline: true,
+                ..config
+            },
+            &backend,
+            new_state(),
+        )
+        .await;
+
+        // The packages are submitted back-to-back, and the ones submitted before the failure are
+        // still confirmed and reported.
+        assert!(result.is_err());
+        assert_eq!(
+            backend.calls(),
+            [
+                "compile a_addr",
+                "submit a_addr",
+                "compile b_addr",
+                "submit b_addr",
+                "compile c_addr",
+                "submit c_addr",
+                "wait a_addr",
+                "wait b_addr",
+            ]
+        );
+        assert_eq!(
+            report_info
+                .iter()
+                .map(|tx_report| (tx_report.address_name.as_str(), tx_report.deployed_at))
+                .collect::<Vec<_>>(),
+            [
+                ("c_addr", AccountAddress::ZERO),
+                (
+                    "a_addr",
+                    create_object_code_deployment_address(AccountAddress::ONE, 0)
+                ),
+                (
+                    "b_addr",
+                    create_object_code_deployment_address(AccountAddress::ONE, 1)
+                ),
+            ]
+        );
+        assert_eq!(report_info[0].status, DeployStatus::Failed);
+        assert_eq!(state.packages.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_run_core_simulate_first() {
+        let backend = MockBackend::new().fail_simulate(
+            "lib_addr",
+            "Move abort in 0x1::code: EUPGRADE_IMMUTABLE(0x50003): Cannot upgrade an immutable \
+            package",
+        );
+        let (report_info, _, result) = run_core_with_mock(
+            &[("lib_addr", &["lib_addr"])],
+            |_, config| DeployConfig {
+                simulate_first: Some(true),
+                ..config
+            },
+            &backend,
+            new_state(),
+        )
+        .await;
+
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("module 0x1::code with code 0x50003"));
+        assert_eq!(backend.calls(), ["compile lib_addr", "simulate lib_addr"]);
+        assert_eq!(report_info[0].status, DeployStatus::Failed);
+    }
+
+    #[test]
+    fn test_confirm_mainnet() {
+        let config = DeployConfig {
+            network: AptosNetwork::Mainnet,
+            ..test_config(vec![], &[])
+        };
+        assert!(confirm_mainnet(&config)
+            .unwrap_err()
+            .to_string()
+            .contains("--allow-mainnet"));
+        assert!(confirm_mainnet(&DeployConfig {
+            allow_mainnet: true,
+            ..config
+        })
+        .is_ok());
+        assert!(confirm_mainnet(&test_config(vec![], &[])).is_ok());
+    }
+
+    #[test]
+    fn test_signer_args_sender_address() {
+        let config = DeployConfig {
+            private_key: Some(format!("0x{}", "11".repeat(32))),
+            sender_address: Some(AccountAddress::TWO),
+            ..test_config(vec![], &[])
+        };
+        assert!(signer_args(&config)
+            .unwrap()
+            .ends_with(&format!("--sender-account {}", AccountAddress::TWO)));
+        // Deployments signed by a signer have no private key.
+        assert!(signer_args(&test_config(vec![], &[])).is_err());
+    }
+
+    #[test]
+    fn test_chunked_publish_args() {
+        let config = DeployConfig {
+            network: AptosNetwork::Testnet,
+            ..test_config(vec![], &[])
+        };
+        assert_eq!(chunked_publish_args(&config), "--chunked-publish");
+        let config = DeployConfig {
+            chunk_size: Some(58_000),
+            ..test_config(vec![], &[])
+        };
+        assert_eq!(
+            chunked_publish_args(&config),
+            format!(
+                "--chunked-publish --large-packages-module-address {} --chunk-size 58000",
+                AccountAddress::from_hex_literal("0x7").unwrap()
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn test_deploy_contracts_with_backend_writes_no_state() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let root = temp_dir.path();
+        let libs = write_package(root, "libs", &["lib_addr"]);
+        let config = DeployConfig {
+            state_path: root.join("state.json"),
+            ..test_config(vec![libs], &["lib_addr"])
+        };
+        let report =
+            deploy_contracts_with_backend(&config, &MockBackend::new(), AccountAddress::ONE)
+                .await
+                .unwrap();
+
+        assert_eq!(report.info[0].status, DeployStatus::Deployed);
+        assert!(!config.state_path.exists());
+    }
+
+    #[tokio::test]
+    async fn test_faucet_fallback() {
+        let config = DeployConfig {
+            network: AptosNetwork::Devnet,
+            faucet_urls: vec!["http://127.0.0.1:1".to_string()],
+            rest_url: Some("http://127.0.0.1:3".to_string()),
+            retry: RetryPolicy {
+                max_attempts: 2,
+                initial_backoff_ms: 1,
+                max_backoff_ms: 1,
+            },
+            ..test_config(vec![], &[])
+        };
+        let devnet_faucet = AptosNetwork::Devnet.faucet_url().unwrap();
+        assert_eq!(
+            config.faucets(),
+            [devnet_faucet, "http://127.0.0.1:1".to_string()]
+        );
+
+        let config = DeployConfig {
+            faucet_url: Some("http://127.0.0.1:2".to_string()),
+            ..config
+        };
+        let err = fund_account(
+            &config.network,
+            &config.faucets(),
+            config.rest_url.clone(),
+            AccountAddress::ONE,
+            1,
+            &config.retry,
+        )
+        .await
+        .unwrap_err()
+        .to_string();
+        assert!(err.contains("http://127.0.0.1:2"));
+        assert!(err.contains("http://127.0.0.1:1"));
+    }
+
+    #[tokio::test]
+    async fn test_deploy_contracts() {
+        let localnet = LocalnetFixture::start().await.unwrap();
+        let temp_dir = tempfile::tempdir().unwrap();
+
+        let config = DeployConfig {
+            rest_url: Some(localnet.rest_url.clone()),
+            faucet_url: Some(localnet.faucet_url.clone()),
+            output_json: temp_dir.path().join("test.json"),
+            state_path: temp_dir.path().join(".jayce-state.json"),
+            generated_key_path: temp_dir.path().join(".jayce-generated-key"),
+            ..test_config(
+                vec![
+                    PathBuf::from("examples/contracts/navori/libs"),
+                    PathBuf::from("examples/contracts/navori/cpu-2"),
+                    PathBuf::from("examples/contracts/navori/cpu"),
+                    PathBuf::from("examples/contracts/navori/verifier"),
+                ],
+                &["lib_addr", "cpu_2_addr", "cpu_addr", "verifier_addr"],
+            )
+        };
+        deploy_contracts(config).await.unwrap();
     }
 }
@@ -0,0 +1,247 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+
+use aptos_sdk::move_types::account_address::AccountAddress;
+use strum_macros::Display;
+
+use crate::deploy_report::{DeployReport, DeployStatus, TxReport};
+use crate::error::Result;
+
+#[derive(Debug, Clone, PartialEq, Display)]
+#[strum(serialize_all = "snake_case")]
+enum PackageChange {
+    /// The package is only in the new report.
+    Added,
+    /// The package is only in the old report.
+    Removed,
+    /// The package was deployed at another address.
+    Moved,
+    /// The package was published again at the same address.
+    Upgraded,
+    /// The package was deployed by the same transaction in both reports.
+    Unchanged,
+}
+
+struct DiffRow {
+    address_name: String,
+    change: PackageChange,
+    /// The address in the new report, or in the old one for removed packages.
+    address: AccountAddress,
+    details: String,
+    old_gas: u64,
+    new_gas: u64,
+}
+
+/// Prints what changed between two deployment reports: the new, removed and moved packages, the
+/// upgraded ones with their upgrade numbers, and the difference of gas used by each package.
+pub fn diff_reports(old_path: &Path, new_path: &Path) -> Result<()> {
+    let old = DeployReport::from_path(old_path)?;
+    let new = DeployReport::from_path(new_path)?;
+    if old.network != new.network {
+        println!("Network: {} -> {}", old.network, new.network);
+    }
+    let rows = diff_rows(&old, &new);
+    println!(
+        "{:<20} {:<10} {:<68} {:>12}  {}",
+        "ADDRESS NAME", "CHANGE", "ADDRESS", "GAS DELTA", "DETAILS"
+    );
+    for row in &rows {
+        println!(
+            "{:<20} {:<10} {:<68} {:>12}  {}",
+            row.address_name,
+            row.change,
+            row.address.to_string(),
+            gas_delta(row.old_gas, row.new_gas),
+            row.details
+        );
+    }
+    let old_gas = rows.iter().map(|row| row.old_gas).sum();
+    let new_gas = rows.iter().map(|row| row.new_gas).sum();
+    println!(
+        "Total gas used: {} -> {} ({})",
+        old_gas,
+        new_gas,
+        gas_delta(old_gas, new_gas)
+    );
+    Ok(())
+}
+
+fn gas_delta(old_gas: u64, new_gas: u64) -> String {
+    format!("{:+}", new_gas as i128 - old_gas as i128)
+}
+
+/// The last deployment of each package of a report.
+fn deployed_packages(report: &DeployReport) -> BTreeMap<&str, &TxReport> {
+    report
+        .info
+        .iter()
+        .filter(|tx_report| tx_report.status == DeployStatus::Deployed)
+        .map(|tx_report| (tx_report.address_name.as_str(), tx_report))
+        .collect()
+}
+
+fn upgrade_number(tx_report: &TxReport) -> String {
+    tx_report
+        .upgrade_number
+        .map(|upgrade_number| upgrade_number.to_string())
+        .unwrap_or("?".to_string())
+}
+
+fn diff_rows(old: &DeployReport, new: &DeployReport) -> Vec<DiffRow> {
+    let old_packages = deployed_packages(old);
+    let new_packages = deployed_packages(new);
+    let address_names: BTreeSet<&str> = old_packages
+        .keys()
+        .chain(new_packages.keys())
+        .copied()
+        .collect();
+    address_names
+        .into_iter()
+        .map(|address_name| {
+            let old_package = old_packages.get(address_name);
+            let new_package = new_packages.get(address_name);
+            let old_gas = old_package.map_or(0, |package| package.gas_summary().gas_used);
+            let new_gas = new_package.map_or(0, |package| package.gas_summary().gas_used);
+            let (change, address, details) = match (old_package, new_package) {
+                (None, Some(new_package)) => {
+                    (PackageChange::Added, new_package.deployed_at, String::new())
+                }
+                (Some(old_package), None) => (
+                    PackageChange::Removed,
+                    old_package.deployed_at,
+                    String::new(),
+                ),
+                (Some(old_package), Some(new_package)) => {
+                    if old_package.deployed_at != new_package.deployed_at {
+                        (
+                            PackageChange::Moved,
+                            new_package.deployed_at,
+                            format!("from {}", old_package.deployed_at),
+                        )
+                    } else if old_package.tx_info.first().map(|tx| tx.transaction_hash)
+                        == new_package.tx_info.first().map(|tx| tx.transaction_hash)
+                    {
+                        (
+                            PackageChange::Unchanged,
+                            new_package.deployed_at,
+                            String::new(),
+                        )
+                    } else {
+                        (
+                            PackageChange::Upgraded,
+                            new_package.deployed_at,
+                            format!(
+                                "upgrade number {} -> {}",
+                                upgrade_number(old_package),
+                                upgrade_number(new_package)
+                            ),
+                        )
+                    }
+                }
+                (None, None) => unreachable!("address names come from the reports"),
+            };
+            DiffRow {
+                address_name: address_name.to_string(),
+                change,
+                address,
+                details,
+                old_gas,
+                new_gas,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::BTreeMap;
+    use std::path::PathBuf;
+
+    use aptos::common::types::TransactionSummary;
+    use aptos_sdk::crypto::HashValue;
+    use aptos_sdk::move_types::account_address::AccountAddress;
+
+    use crate::deploy_config::AptosNetwork;
+    use crate::deploy_report::{DeployReport, DeployStatus, TxReport};
+    use crate::tasks::diff::{diff_rows, PackageChange};
+
+    fn tx_report(
+        address_name: &str,
+        deployed_at: AccountAddress,
+        hash: &str,
+        gas_used: u64,
+    ) -> TxReport {
+        let mut tx_report = TxReport::not_deployed(
+            &PathBuf::from(address_name),
+            address_name,
+            DeployStatus::Deployed,
+            String::new(),
+        );
+        tx_report.deployed_at = deployed_at;
+        tx_report.error = None;
+        tx_report.tx_info = vec![TransactionSummary {
+            transaction_hash: HashValue::sha3_256_of(hash.as_bytes()),
+            gas_used: Some(gas_used),
+            gas_unit_price: Some(100),
+            pending: None,
+            sender: None,
+            sequence_number: None,
+            success: Some(true),
+            timestamp_us: None,
+            version: None,
+            vm_status: None,
+        }];
+        tx_report
+    }
+
+    fn report(info: Vec<TxReport>) -> DeployReport {
+        DeployReport {
+            account: AccountAddress::ONE,
+            fee_payer: None,
+            network: AptosNetwork::Testnet,
+            info,
+            gas: None,
+            git_commit: None,
+            git_commits: BTreeMap::new(),
+            signature: None,
+        }
+    }
+
+    #[test]
+    fn test_diff_rows() {
+        let old = report(vec![
+            tx_report("lib_addr", AccountAddress::ONE, "lib", 1_000),
+            tx_report("cpu_addr", AccountAddress::TWO, "cpu", 2_000),
+            tx_report("old_addr", AccountAddress::THREE, "old", 500),
+            tx_report("verifier_addr", AccountAddress::FOUR, "verifier", 3_000),
+        ]);
+        let mut upgraded = tx_report("lib_addr", AccountAddress::ONE, "lib 2", 1_500);
+        upgraded.upgrade_number = Some(1);
+        let new = report(vec![
+            upgraded,
+            tx_report("cpu_addr", AccountAddress::TWO, "cpu", 2_000),
+            tx_report("new_addr", AccountAddress::THREE, "new", 700),
+            tx_report("verifier_addr", AccountAddress::ONE, "verifier 2", 2_500),
+        ]);
+
+        let rows = diff_rows(&old, &new);
+        let changes: Vec<_> = rows
+            .iter()
+            .map(|row| (row.address_name.as_str(), row.change.clone()))
+            .collect();
+        assert_eq!(
+            changes,
+            [
+                ("cpu_addr", PackageChange::Unchanged),
+                ("lib_addr", PackageChange::Upgraded),
+                ("new_addr", PackageChange::Added),
+                ("old_addr", PackageChange::Removed),
+                ("verifier_addr", PackageChange::Moved),
+            ]
+        );
+        assert_eq!(rows[1].details, "upgrade number ? -> 1");
+        assert_eq!((rows[1].old_gas, rows[1].new_gas), (1_000, 1_500));
+        assert_eq!((rows[3].old_gas, rows[3].new_gas), (500, 0));
+        assert_eq!(rows[4].details, format!("from {}", AccountAddress::FOUR));
+    }
+}
@@ -0,0 +1,40 @@
+use aptos_sdk::move_types::account_address::AccountAddress;
+use tracing::info;
+
+use crate::deploy_config::AptosNetwork;
+use crate::error::{JayceError, Result};
+use crate::retry::RetryPolicy;
+use crate::utils::{fund_account, get_balance, resolve_rest_url};
+
+/// Funds an account from the faucet of the network, then checks that its balance covers the
+/// requested amount.
+pub async fn faucet(
+    network: &AptosNetwork,
+    faucet_urls: &[String],
+    rest_url: Option<String>,
+    address: AccountAddress,
+    amount: u64,
+    retry: &RetryPolicy,
+) -> Result<()> {
+    let rest_url = resolve_rest_url(network, rest_url)?;
+    info!("Funding account {} with {} octas...", address, amount);
+    fund_account(
+        network,
+        faucet_urls,
+        Some(rest_url.clone()),
+        address,
+        amount,
+        retry,
+    )
+    .await?;
+
+    let balance = get_balance(&rest_url, address).await?;
+    if balance < amount {
+        return Err(JayceError::Faucet(format!(
+            "Account {} has a balance of {} octas after being funded with {} octas",
+            address, balance, amount
+        )));
+    }
+    info!("Account {} funded, balance: {} octas", address, balance);
+    Ok(())
+}
@@ -0,0 +1,103 @@
+use std::collections::BTreeMap;
+
+use aptos_sdk::move_types::account_address::AccountAddress;
+use dialoguer::Confirm;
+use tracing::info;
+
+use crate::color::prompt_theme;
+use crate::deploy_config::{AptosNetwork, AptosProfile};
+use crate::deploy_report::format_apt;
+use crate::entry_function::{run_entry_function, EntryFunctionCall};
+use crate::error::{JayceError, Result};
+use crate::signer::{PrivateKeySigner, Signer};
+use crate::tasks::deploy_contracts::private_key_signer_args;
+use crate::utils::{get_balance, lookup_address, resolve_rest_url, rest_client};
+
+/// Transfers `amount` Octas to an account, e.g. a deployer on mainnet where there is no faucet,
+/// from the account of `private_key` or of an aptos CLI profile. The transfer is confirmed at a
+/// prompt unless `yes` is set.
+#[allow(clippy::too_many_arguments)]
+pub async fn fund(
+    private_key: Option<&str>,
+    profile: Option<&str>,
+    to: AccountAddress,
+    amount: u64,
+    network: &AptosNetwork,
+    rest_url: Option<String>,
+    yes: bool,
+) -> Result<()> {
+    let (from, signer_args, rest_url) = match (private_key, profile) {
+        (Some(private_key), _) => {
+            let client = rest_client(network, rest_url.clone())?;
+            let derived = PrivateKeySigner::from_private_key(private_key)?.address();
+            let from = lookup_address(&client, derived).await?;
+            let mut signer_args = private_key_signer_args(network, private_key, rest_url.clone())?;
+            if from != derived {
+                signer_args.push_str(&format!(" --sender-account {}", from));
+            }
+            (from, signer_args, resolve_rest_url(network, rest_url)?)
+        }
+        (None, Some(name)) => {
+            let profile = AptosProfile::load(name)?;
+            let from = match (profile.account, &profile.private_key) {
+                (Some(account), _) => account,
+                (None, Some(private_key)) => {
+                    PrivateKeySigner::from_private_key(private_key)?.address()
+                }
+                (None, None) => {
+                    return Err(JayceError::Config(format!(
+                        "The aptos CLI profile {} has no account",
+                        name
+                    )))
+                }
+            };
+            let rest_url = resolve_rest_url(network, rest_url.or(profile.rest_url))?;
+            (from, format!("--profile {}", name), rest_url)
+        }
+        (None, None) => {
+            return Err(JayceError::Config(
+                "A private key or a profile is required to fund an account".to_string(),
+            ))
+        }
+    };
+
+    let balance = get_balance(&rest_url, from).await?;
+    if balance < amount {
+        return Err(JayceError::InsufficientBalance(format!(
+            "account {} has {} Octas, which doesn't cover the transfer of {} Octas",
+            from, balance, amount
+        )));
+    }
+    let prompt = format!(
+        "Transfer {} APT from {} to {} on {}?",
+        format_apt(amount),
+        from,
+        to,
+        network
+    );
+    if !yes
+        && !Confirm::with_theme(prompt_theme().as_ref())
+            .with_prompt(prompt)
+            .default(false)
+            .interact()?
+    {
+        return Err(JayceError::Config("Transfer not confirmed".to_string()));
+    }
+
+    let call = EntryFunctionCall {
+        function_id: "0x1::aptos_account::transfer".to_string(),
+        type_args: vec![],
+        args: vec![format!("address:{}", to), format!("u64:{}", amount)],
+    };
+    let summary = run_entry_function(&call, &BTreeMap::new(), &signer_args, "", true).await?;
+    info!(
+        "Transferred {} APT to {} in transaction {}",
+        format_apt(amount),
+        to,
+        summary.transaction_hash
+    );
+
+    let balance = get_balance(&rest_url, to).await?;
+    info!("Balance of {}: {} APT", to, format_apt(balance));
+    Ok(())
+}
@@ -0,0 +1,183 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use clap::ValueEnum;
+use dialoguer::{Confirm, Input, MultiSelect, Select};
+use serde::Serialize;
+use tracing::info;
+
+use crate::color::prompt_theme;
+use crate::deploy_config::{AptosNetwork, DeployModuleType};
+use crate::error::{JayceError, Result};
+use crate::move_package::{find_packages, MoveTomlFile};
+
+/// Entries added to `.gitignore` so that key material and deployment state are never committed.
+const GITIGNORE_ENTRIES: [&str; 3] = ["*.key", "*.keystore.json", "/.jayce-state.json"];
+
+#[derive(Serialize)]
+struct InitConfig {
+    network: String,
+    module_type: String,
+    modules_path: Vec<PathBuf>,
+    addresses_name: Vec<String>,
+    yes: bool,
+    output_json: PathBuf,
+    publish_code: bool,
+}
+
+/// Walks the user through the creation of a deploy configuration file for the Move packages
+/// found under `root`.
+pub fn init(root: &Path, output: &Path) -> Result<()> {
+    let theme = prompt_theme();
+    if output.exists()
+        && !Confirm::with_theme(theme.as_ref())
+            .with_prompt(format!(
+                "{} already exists, do you want to overwrite it?",
+                output.to_str().unwrap()
+            ))
+            .default(false)
+            .interact()?
+    {
+        return Ok(());
+    }
+
+    let networks: Vec<String> = AptosNetwork::value_variants()
+        .iter()
+        .filter(|network| **network != AptosNetwork::Custom)
+        .map(|network| network.to_string())
+        .collect();
+    let network = Select::with_theme(theme.as_ref())
+        .with_prompt("Network")
+        .items(&networks)
+        .default(
+            networks
+                .iter()
+                .position(|network| network == "devnet")
+                .unwrap(),
+        )
+        .interact()?;
+    let module_types: Vec<String> = DeployModuleType::value_variants()
+        .iter()
+        .map(|module_type| module_type.to_string())
+        .collect();
+    let module_type = Select::with_theme(theme.as_ref())
+        .with_prompt("Module type")
+        .items(&module_types)
+        .default(
+            module_types
+                .iter()
+                .position(|name| name == "object")
+                .unwrap(),
+        )
+        .interact()?;
+
+    let packages = find_packages(root)?;
+    if packages.is_empty() {
+        return Err(JayceError::Package(format!(
+            "No Move package found under {}",
+            root.to_str().unwrap()
+        )));
+    }
+    let package_names: Vec<&str> = packages
+        .iter()
+        .map(|package| package.to_str().unwrap())
+        .collect();
+    let selected = MultiSelect::with_theme(theme.as_ref())
+        .with_prompt("Packages to deploy")
+        .items(&package_names)
+        .defaults(&vec![true; packages.len()])
+        .interact()?;
+
+    let mut modules_path = vec![];
+    let mut addresses_name = vec![];
+    for package in selected.into_iter().map(|index| &packages[index]) {
+        let mut addresses: Vec<(String, String)> = MoveTomlFile::from_package_dir(package)?
+            .addresses
+            .into_iter()
+            .collect();
+        addresses.sort();
+        if addresses.is_empty() {
+            return Err(JayceError::Package(format!(
+                "{}/Move.toml doesn't declare any address",
+                package.to_str().unwrap()
+            )));
+        }
+        // Addresses left as `_` are the ones assigned at deployment.
+        let default = addresses
+            .iter()
+            .position(|(_, value)| value == "_")
+            .unwrap_or_default();
+        let names: Vec<&str> = addresses.iter().map(|(name, _)| name.as_str()).collect();
+        let address_name = Select::with_theme(theme.as_ref())
+            .with_prompt(format!("Address name of {}", package.to_str().unwrap()))
+            .items(&names)
+            .default(default)
+            .interact()?;
+        modules_path.push(relative_to_config(package, output)?);
+        addresses_name.push(names[address_name].to_string());
+    }
+
+    let output_json: String = Input::with_theme(theme.as_ref())
+        .with_prompt("Deployment report path")
+        .default("deploy-report.json".to_string())
+        .interact_text()?;
+    let config = InitConfig {
+        network: networks[network].clone(),
+        module_type: module_types[module_type].clone(),
+        modules_path,
+        addresses_name,
+        yes: false,
+        output_json: PathBuf::from(output_json),
+        publish_code: false,
+    };
+    let content = format!(
+        "# The private key is read from the JAYCE_PRIVATE_KEY environment variable, or set\n\
+        # private_key = \"keystore:<PATH>\" to use an encrypted keystore.\n\n{}",
+        toml::to_string_pretty(&config).map_err(anyhow::Error::from)?
+    );
+    fs::write(output, content)?;
+    update_gitignore(&root.join(".gitignore"))?;
+    info!(
+        "Wrote {}, deploy with `jayce deploy --config-path {}`",
+        output.to_str().unwrap(),
+        output.to_str().unwrap()
+    );
+    Ok(())
+}
+
+/// Returns the path of a package relative to the directory of the configuration file, against
+/// which the paths of the configuration are resolved.
+fn relative_to_config(package: &Path, config_path: &Path) -> Result<PathBuf> {
+    let config_dir = match config_path.parent() {
+        Some(config_dir) if !config_dir.as_os_str().is_empty() => config_dir,
+        _ => Path::new("."),
+    };
+    let package = fs::canonicalize(package)?;
+    Ok(package
+        .strip_prefix(fs::canonicalize(config_dir)?)
+        .map(Path::to_path_buf)
+        .unwrap_or(package))
+}
+
+/// Adds the entries of key material missing from a `.gitignore` file, creating it if needed.
+fn update_gitignore(path: &Path) -> Result<()> {
+    let mut content = if path.exists() {
+        fs::read_to_string(path)?
+    } else {
+        String::new()
+    };
+    let missing: Vec<&str> = GITIGNORE_ENTRIES
+        .into_iter()
+        .filter(|entry| !content.lines().any(|line| line.trim() == *entry))
+        .collect();
+    if missing.is_empty() {
+        return Ok(());
+    }
+    if !content.is_empty() && !content.ends_with('\n') {
+        content.push('\n');
+    }
+    content.push_str(&missing.join("\n"));
+    content.push('\n');
+    fs::write(path, content)?;
+    Ok(())
+}
@@ -0,0 +1,54 @@
+use std::path::Path;
+
+use dialoguer::{Confirm, Password};
+use tracing::info;
+
+use crate::color::prompt_theme;
+use crate::error::{JayceError, Result};
+use crate::keyless::generate_ephemeral_key;
+use crate::keystore::{read_keystore, Keystore};
+
+/// Encrypts a private key into a keystore file, prompting for its passphrase.
+pub fn import_key(private_key: &str, keystore_path: &Path) -> Result<()> {
+    let passphrase = Password::with_theme(prompt_theme().as_ref())
+        .with_prompt("Passphrase")
+        .with_confirmation("Confirm passphrase", "Passphrases don't match")
+        .interact()?;
+    let keystore = Keystore::encrypt(private_key, &passphrase)?;
+    keystore.save(keystore_path)?;
+    info!(
+        "Saved the key of account {} to {}",
+        keystore.address,
+        keystore_path.to_str().unwrap()
+    );
+    Ok(())
+}
+
+/// Decrypts a keystore file and prints its private key, once the user confirms it unless `yes` is
+/// set.
+pub fn export_key(keystore_path: &Path, yes: bool) -> Result<()> {
+    if !yes
+        && !Confirm::with_theme(prompt_theme().as_ref())
+            .with_prompt("The private key will be printed in plain text, continue?")
+            .default(false)
+            .show_default(true)
+            .wait_for_newline(true)
+            .interact()?
+    {
+        return Err(JayceError::Config("Key export not confirmed".to_string()));
+    }
+    println!("{}", read_keystore(keystore_path)?);
+    Ok(())
+}
+
+/// Generates the ephemeral key of a keyless signer and prints the nonce to request its JWT with.
+pub fn ephemeral_key(path: &Path, expiry_secs: u64) -> Result<()> {
+    let nonce = generate_ephemeral_key(path, expiry_secs)?;
+    info!(
+        "Saved an ephemeral key valid for {} seconds to {}, request the JWT with its nonce:",
+        expiry_secs,
+        path.to_str().unwrap()
+    );
+    println!("{}", nonce);
+    Ok(())
+}
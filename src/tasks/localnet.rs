@@ -0,0 +1,294 @@
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+use std::{env, thread};
+
+use aptos::common::types::CliCommand;
+use aptos::node::NodeTool;
+use aptos::Tool;
+use aptos_sdk::rest_client::Client;
+use clap::Parser;
+use serde::{Deserialize, Serialize};
+use tracing::info;
+use url::Url;
+
+use crate::error::{JayceError, Result};
+
+/// REST API of a local network started with the default ports.
+pub const LOCALNET_REST_URL: &str = "http://127.0.0.1:8080/v1";
+/// Faucet of a local network started with the default ports.
+pub const LOCALNET_FAUCET_URL: &str = "http://127.0.0.1:8081";
+
+/// A local network running in the background, recorded by `jayce localnet start`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct LocalnetState {
+    pub pid: u32,
+    pub rest_url: String,
+    pub faucet_url: String,
+    pub test_dir: PathBuf,
+    pub log_path: PathBuf,
+}
+
+impl LocalnetState {
+    pub fn from_path(path: &Path) -> Result<LocalnetState> {
+        let content = fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&content)?)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Whether the local network is still running. The command line of the process is checked,
+    /// since its pid may have been reused by another process.
+    #[cfg(unix)]
+    pub fn is_running(&self) -> bool {
+        Command::new("ps")
+            .args(["-o", "args=", "-p", &self.pid.to_string()])
+            .stderr(Stdio::null())
+            .output()
+            .is_ok_and(|output| {
+                let args = String::from_utf8_lossy(&output.stdout);
+                output.status.success()
+                    && args.contains("localnet run")
+                    && args.contains(self.test_dir.to_str().unwrap())
+            })
+    }
+
+    #[cfg(not(unix))]
+    pub fn is_running(&self) -> bool {
+        true
+    }
+}
+
+/// Runs a local network in the foreground until it stops. With `force_restart`, the data of a
+/// previous local network in `test_dir` is deleted.
+pub async fn run_localnet(test_dir: &Path, force_restart: bool) -> Result<()> {
+    let args = format!(
+        "aptos node run-localnet \
+            --test-dir {} \
+            --assume-yes \
+            {}",
+        test_dir.to_str().unwrap(),
+        if force_restart { "--force-restart" } else { "" }
+    );
+    let args: Vec<&str> = args.split_whitespace().collect();
+    let tool = Tool::try_parse_from(&args)
+        .map_err(|err| JayceError::Config(format!("Invalid arguments to run localnet: {}", err)))?;
+    if let Tool::Node(NodeTool::RunLocalnet(cmd_executor)) = tool {
+        cmd_executor.execute().await?;
+        Ok(())
+    } else {
+        Err(JayceError::Config(format!(
+            "Wrong arguments to run localnet: {:?}",
+            args
+        )))
+    }
+}
+
+/// Starts a local network in the background and waits until its REST API and faucet are ready.
+/// The process is recorded in `state_path`, from which `stop_localnet` stops it.
+pub async fn start_localnet(
+    state_path: &Path,
+    test_dir: &Path,
+    force_restart: bool,
+    timeout: Duration,
+) -> Result<()> {
+    if state_path.exists() {
+        let state = LocalnetState::from_path(state_path)?;
+        if state.is_running() && !force_restart {
+            return Err(JayceError::Config(format!(
+                "A local network is already running (pid {}), stop it or use --force-restart",
+                state.pid
+            )));
+        }
+        stop_localnet(state_path)?;
+    }
+
+    fs::create_dir_all(test_dir)?;
+    let log_path = test_dir.with_extension("log");
+    let log = File::create(&log_path)?;
+    let mut command = Command::new(env::current_exe()?);
+    command
+        .args(["localnet", "run", "--test-dir", test_dir.to_str().unwrap()])
+        .stdin(Stdio::null())
+        .stdout(log.try_clone()?)
+        .stderr(log);
+    if force_restart {
+        command.arg("--force-restart");
+    }
+    // Keeps the local network running when the terminal that started it is closed.
+    #[cfg(unix)]
+    std::os::unix::process::CommandExt::process_group(&mut command, 0);
+    let mut child = command.spawn()?;
+    let state = LocalnetState {
+        pid: child.id(),
+        rest_url: LOCALNET_REST_URL.to_string(),
+        faucet_url: LOCALNET_FAUCET_URL.to_string(),
+        test_dir: test_dir.to_path_buf(),
+        log_path: log_path.clone(),
+    };
+    state.save(state_path)?;
+    info!("Starting local network (pid {})...", state.pid);
+
+    let started_at = Instant::now();
+    while !is_ready(&state.rest_url, &state.faucet_url).await {
+        if let Some(status) = child.try_wait()? {
+            fs::remove_file(state_path)?;
+            return Err(JayceError::Config(format!(
+                "The local network exited with {}, see {}",
+                status,
+                log_path.to_str().unwrap()
+            )));
+        }
+        if started_at.elapsed() > timeout {
+            stop_localnet(state_path)?;
+            return Err(JayceError::Config(format!(
+                "The local network wasn't ready after {:?}, see {}",
+                timeout,
+                log_path.to_str().unwrap()
+            )));
+        }
+        tokio::time::sleep(Duration::from_secs(1)).await;
+    }
+    info!(
+        "Local network ready, REST API: {}, faucet: {}",
+        state.rest_url, state.faucet_url
+    );
+    Ok(())
+}
+
+/// Stops the local network recorded in `state_path`, which is kept if the network is still
+/// running. Stopping it relies on the `ps` and `kill` commands, so it is only supported on Unix.
+pub fn stop_localnet(state_path: &Path) -> Result<()> {
+    if !state_path.exists() {
+        info!("No local network is running");
+        return Ok(());
+    }
+    let state = LocalnetState::from_path(state_path)?;
+    if state.is_running() {
+        kill(&state)?;
+        info!("Stopped local network (pid {})", state.pid);
+    }
+    fs::remove_file(state_path)?;
+    Ok(())
+}
+
+/// Terminates the process of a local network and waits for it to exit, for at most 30 seconds.
+#[cfg(unix)]
+fn kill(state: &LocalnetState) -> Result<()> {
+    let status = Command::new("kill").arg(state.pid.to_string()).status()?;
+    if !status.success() {
+        return Err(JayceError::Config(format!(
+            "Failed to stop the local network (pid {}), kill exited with {}",
+            state.pid, status
+        )));
+    }
+    let stopping_at = Instant::now();
+    while state.is_running() {
+        if stopping_at.elapsed() > Duration::from_secs(30) {
+            return Err(JayceError::Config(format!(
+                "The local network (pid {}) is still running 30 seconds after being stopped",
+                state.pid
+            )));
+        }
+        thread::sleep(Duration::from_millis(200));
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn kill(state: &LocalnetState) -> Result<()> {
+    Err(JayceError::Config(format!(
+        "Stopping a local network is only supported on Unix, stop the process {} instead",
+        state.pid
+    )))
+}
+
+/// Prints whether the local network recorded in `state_path` is running and ready.
+pub async fn localnet_status(state_path: &Path) -> Result<()> {
+    if !state_path.exists() {
+        println!("stopped");
+        return Ok(());
+    }
+    let state = LocalnetState::from_path(state_path)?;
+    let status = if !state.is_running() {
+        "stopped"
+    } else if is_ready(&state.rest_url, &state.faucet_url).await {
+        "ready"
+    } else {
+        "starting"
+    };
+    println!("{}", status);
+    if status != "stopped" {
+        println!("pid: {}", state.pid);
+        println!("REST API: {}", state.rest_url);
+        println!("faucet: {}", state.faucet_url);
+        println!("logs: {}", state.log_path.to_str().unwrap());
+    }
+    Ok(())
+}
+
+/// Whether the REST API and the faucet of a local network answer.
+pub async fn is_ready(rest_url: &str, faucet_url: &str) -> bool {
+    let Ok(rest_url) = Url::from_str(rest_url) else {
+        return false;
+    };
+    Client::new(rest_url).get_index().await.is_ok()
+        && reqwest::get(faucet_url)
+            .await
+            .is_ok_and(|response| response.status().is_success())
+}
+
+#[cfg(all(test, unix))]
+mod test {
+    use std::process::Command;
+
+    use crate::tasks::localnet::{stop_localnet, LocalnetState};
+
+    #[test]
+    fn test_stop_localnet() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let root = temp_dir.path();
+        let test_dir = root.join("localnet");
+        let state_path = root.join("localnet.json");
+        let mut process = Command::new("sh")
+            .args([
+                "-c",
+                "sleep 30; true",
+                "jayce",
+                "localnet",
+                "run",
+                "--test-dir",
+            ])
+            .arg(&test_dir)
+            .spawn()
+            .unwrap();
+        let state = LocalnetState {
+            pid: process.id(),
+            rest_url: String::new(),
+            faucet_url: String::new(),
+            test_dir: test_dir.clone(),
+            log_path: root.join("localnet.log"),
+        };
+        state.save(&state_path).unwrap();
+        assert!(state.is_running());
+
+        // A process that isn't the local network, e.g. one that reused its pid, isn't killed.
+        let other = LocalnetState {
+            pid: std::process::id(),
+            ..state.clone()
+        };
+        assert!(!other.is_running());
+
+        stop_localnet(&state_path).unwrap();
+        process.wait().unwrap();
+        assert!(!state_path.exists());
+    }
+}
@@ -1 +1,19 @@
+pub mod account;
+pub mod build;
+pub mod call;
+pub mod config;
 pub mod deploy_contracts;
+pub mod diff;
+pub mod faucet;
+pub mod fund;
+pub mod init;
+pub mod key;
+pub mod localnet;
+pub mod multisig;
+pub mod plan;
+pub mod report;
+pub mod reproduce;
+pub mod run_script;
+pub mod status;
+pub mod verify;
+pub mod view;
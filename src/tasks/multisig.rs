@@ -0,0 +1,256 @@
+use std::path::Path;
+
+use aptos::account::MultisigAccountTool;
+use aptos::common::types::{CliCommand, TransactionSummary};
+use aptos::move_tool::MoveTool;
+use aptos::Tool;
+use aptos_sdk::move_types::account_address::AccountAddress;
+use clap::Parser;
+use tracing::info;
+
+use crate::deploy_config::DeployConfig;
+use crate::deploy_report::{DeployReport, DeployStatus, PackageChecksums, TxReport, JAYCE_VERSION};
+use crate::deployer::write_publish_payload;
+use crate::error::{JayceError, Result};
+use crate::move_package::sort_packages;
+use crate::tasks::deploy_contracts::{
+    gas_args, private_key_signer_args, resolve_named_addresses, resolve_upgrade_policy,
+    save_artifacts, signer_args,
+};
+use crate::utils::resolve_rest_url;
+
+/// Creates a multisig transaction proposing the publication of every package at the multisig
+/// account, instead of publishing them.
+pub(crate) async fn propose_deployment(
+    config: &DeployConfig,
+    multisig_address: AccountAddress,
+    report_info: &mut Vec<TxReport>,
+) -> Result<()> {
+    let rest_url = resolve_rest_url(&config.network, config.rest_url.clone())?;
+    let mut deployed_addresses = config.deployed_addresses.clone();
+    for (package_dir, address_name) in &sort_packages(
+        &config.modules_path,
+        &config.addresses_name,
+        &config.package_options,
+    )? {
+        if deployed_addresses.contains_key(address_name) {
+            info!(
+                "Address name {} already deployed, skipping...",
+                address_name
+            );
+            continue;
+        }
+        info!(
+            "Proposing package {} with address name {}...",
+            package_dir.to_str().unwrap(),
+            address_name
+        );
+        let upgrade_policy = resolve_upgrade_policy(config, package_dir, address_name)?;
+        let named_addresses = resolve_named_addresses(
+            config,
+            package_dir,
+            address_name,
+            &deployed_addresses,
+            multisig_address,
+        )?;
+        let payload_path = config
+            .output_json
+            .with_file_name(format!("{}-payload.json", address_name));
+        write_publish_payload(
+            package_dir,
+            &named_addresses,
+            &config.compiler_options(address_name),
+            config.upgrade_policy(address_name),
+            &config.included_artifacts(address_name).to_string(),
+            &payload_path,
+        )
+        .await?;
+        let artifacts_path = save_artifacts(config, None, package_dir, address_name)?;
+
+        let proposal_id = next_proposal_id(multisig_address, &rest_url).await?;
+        let args = format!(
+            "aptos multisig create-transaction \
+                --multisig-address {} \
+                --json-file {} \
+                --store-hash-only \
+                {} \
+                {} \
+                {}",
+            multisig_address,
+            payload_path.to_str().unwrap(),
+            signer_args(config)?,
+            gas_args(config),
+            if config.yes { "--assume-yes" } else { "" }
+        );
+        let tx_info = run_multisig_command(&args).await?;
+        info!(
+            "Created proposal {} for address name {}",
+            proposal_id, address_name
+        );
+
+        deployed_addresses.insert(address_name.clone(), multisig_address);
+        let mut tx_report = TxReport {
+            module_path: package_dir.clone(),
+            address_name: address_name.clone(),
+            deployed_at: multisig_address,
+            sender: None,
+            tx_info: vec![tx_info],
+            freeze_tx_info: None,
+            init_tx_info: vec![],
+            registry_tx_info: None,
+            proposal_id: Some(proposal_id),
+            payload_path: Some(payload_path),
+            explorer_url: None,
+            tx_explorer_urls: vec![],
+            upgrade_policy: Some(upgrade_policy),
+            upgrade_number: None,
+            artifacts_path,
+            checksums: Some(PackageChecksums::from_build(package_dir)?),
+            compiler: Some(config.compiler_options(address_name)),
+            included_artifacts: Some(config.included_artifacts(address_name)),
+            jayce_version: Some(JAYCE_VERSION.to_string()),
+            status: DeployStatus::Deployed,
+            error: None,
+        };
+        tx_report.set_explorer_urls(config);
+        tx_report.print_explorer_urls();
+        report_info.push(tx_report);
+    }
+    Ok(())
+}
+
+/// Approves every proposal recorded in the deployment report.
+pub async fn approve_proposals(
+    report_path: &Path,
+    private_key: &str,
+    rest_url: Option<String>,
+    yes: bool,
+) -> Result<()> {
+    let mut report = DeployReport::from_path(report_path)?;
+    let signer_args = private_key_signer_args(&report.network, private_key, rest_url)?;
+    let result = async {
+        for tx_report in report.info.iter_mut() {
+            let Some(proposal_id) = tx_report.proposal_id else {
+                continue;
+            };
+            info!(
+                "Approving proposal {} for address name {}...",
+                proposal_id, tx_report.address_name
+            );
+            let args = format!(
+                "aptos multisig approve \
+                    --multisig-address {} \
+                    --sequence-number {} \
+                    {} \
+                    {}",
+                tx_report.deployed_at,
+                proposal_id,
+                signer_args,
+                if yes { "--assume-yes" } else { "" }
+            );
+            tx_report.tx_info.push(run_multisig_command(&args).await?);
+        }
+        Ok(())
+    }
+    .await;
+    report.save(report_path)?;
+    result
+}
+
+/// Executes every proposal recorded in the deployment report, in deployment order.
+pub async fn execute_proposals(
+    report_path: &Path,
+    private_key: &str,
+    rest_url: Option<String>,
+    yes: bool,
+) -> Result<()> {
+    let mut report = DeployReport::from_path(report_path)?;
+    let signer_args = private_key_signer_args(&report.network, private_key, rest_url)?;
+    let result = async {
+        for tx_report in report.info.iter_mut() {
+            let (Some(proposal_id), Some(payload_path)) =
+                (tx_report.proposal_id, &tx_report.payload_path)
+            else {
+                continue;
+            };
+            info!(
+                "Executing proposal {} for address name {}...",
+                proposal_id, tx_report.address_name
+            );
+            let args = format!(
+                "aptos multisig execute-with-payload \
+                    --multisig-address {} \
+                    --json-file {} \
+                    {} \
+                    {}",
+                tx_report.deployed_at,
+                payload_path.to_str().unwrap(),
+                signer_args,
+                if yes { "--assume-yes" } else { "" }
+            );
+            tx_report.tx_info.push(run_multisig_command(&args).await?);
+        }
+        Ok(())
+    }
+    .await;
+    report.save(report_path)?;
+    result
+}
+
+async fn next_proposal_id(multisig_address: AccountAddress, rest_url: &str) -> Result<u64> {
+    let args = format!(
+        "aptos move view \
+            --function-id 0x1::multisig_account::next_sequence_number \
+            --args address:{} \
+            --url {}",
+        multisig_address, rest_url
+    );
+    let args: Vec<&str> = args.split_whitespace().collect();
+    let tool = Tool::try_parse_from(&args).map_err(|err| {
+        JayceError::Config(format!("Invalid arguments to view function: {}", err))
+    })?;
+    if let Tool::Move(MoveTool::View(cmd_executor)) = tool {
+        let result = cmd_executor.execute().await?;
+        result
+            .first()
+            .and_then(|value| value.as_str())
+            .and_then(|value| value.parse().ok())
+            .ok_or_else(|| {
+                JayceError::Publish(format!(
+                    "Failed to read the next sequence number of multisig account {}",
+                    multisig_address
+                ))
+            })
+    } else {
+        Err(JayceError::Config(format!(
+            "Wrong arguments to view function: {:?}",
+            args
+        )))
+    }
+}
+
+async fn run_multisig_command(args: &str) -> Result<TransactionSummary> {
+    let args: Vec<&str> = args.split_whitespace().collect();
+    let tool = Tool::try_parse_from(&args).map_err(|err| {
+        JayceError::Config(format!(
+            "Invalid arguments to run multisig command: {}",
+            err
+        ))
+    })?;
+    let result = match tool {
+        Tool::Multisig(MultisigAccountTool::CreateTransaction(cmd_executor)) => {
+            cmd_executor.execute().await
+        }
+        Tool::Multisig(MultisigAccountTool::Approve(cmd_executor)) => cmd_executor.execute().await,
+        Tool::Multisig(MultisigAccountTool::ExecuteWithPayload(cmd_executor)) => {
+            cmd_executor.execute().await
+        }
+        _ => {
+            return Err(JayceError::Config(format!(
+                "Wrong arguments to run multisig command: {:?}",
+                args
+            )))
+        }
+    };
+    result.map_err(|err| JayceError::Publish(err.to_string()))
+}
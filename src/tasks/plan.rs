@@ -0,0 +1,244 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use aptos::common::types::TransactionSummary;
+use aptos_sdk::move_types::account_address::AccountAddress;
+use tracing::warn;
+
+use crate::deploy_config::{DeployConfig, DeployModuleType};
+use crate::error::Result;
+use crate::progress::{DeployProgress, Phase};
+use crate::publish_backend::{AptosBackend, PublishBackend, PublishRequest};
+use crate::tasks::deploy_contracts::{deployment_order, package_sender, prepare_package};
+use crate::utils::create_object_code_deployment_address;
+
+struct PlanRow {
+    address_name: String,
+    package: String,
+    address: AccountAddress,
+    /// The account publishing the package.
+    sender: AccountAddress,
+    already_deployed: bool,
+    estimated_gas: Option<u64>,
+    note: String,
+}
+
+/// Simulates the publication of every package and prints the predicted addresses and gas costs,
+/// without submitting any transaction.
+pub async fn plan_deployment(config: &DeployConfig, sender_addr: AccountAddress) -> Result<()> {
+    let rows = simulate_packages(config, &AptosBackend::new(config)?, sender_addr).await?;
+    println!(
+        "{:<20} {:<68} {:>16}  {}",
+        "ADDRESS NAME", "PREDICTED ADDRESS", "GAS (OCTAS)", "PACKAGE"
+    );
+    for row in &rows {
+        let address = row.address.to_string();
+        println!(
+            "{:<20} {:<68} {:>16}  {} {}",
+            row.address_name,
+            address,
+            row.estimated_gas
+                .map(|gas| gas.to_string())
+                .unwrap_or("-".to_string()),
+            row.package,
+            row.note
+        );
+    }
+    println!(
+        "Total estimated gas: {} Octas",
+        rows.iter().filter_map(|row| row.estimated_gas).sum::<u64>()
+    );
+    Ok(())
+}
+
+/// Estimates the cost of the deployment in Octas for every account publishing packages, by
+/// simulating the publication of every package. Packages that can't be simulated, e.g. because
+/// their dependencies aren't deployed yet, are counted at `max_gas * gas_unit_price` when both are
+/// set, and left out otherwise.
+pub async fn estimate_deployment_cost<B: PublishBackend>(
+    config: &DeployConfig,
+    backend: &B,
+    sender_addr: AccountAddress,
+) -> Result<BTreeMap<AccountAddress, u64>> {
+    let mut costs = BTreeMap::from([(sender_addr, 0)]);
+    for row in simulate_packages(config, backend, sender_addr).await? {
+        let cost = match (row.estimated_gas, config.max_gas, config.gas_unit_price) {
+            (Some(estimated_gas), _, _) => estimated_gas,
+            _ if row.already_deployed => 0,
+            (None, Some(max_gas), Some(gas_unit_price)) => max_gas * gas_unit_price,
+            (None, _, _) => {
+                warn!(
+                    "Package {} can't be simulated, its cost is left out of the estimate",
+                    row.address_name
+                );
+                0
+            }
+        };
+        *costs.entry(row.sender).or_default() += cost;
+    }
+    Ok(costs)
+}
+
+/// The fee of transactions in Octas, the gas they used at their gas unit price.
+pub(crate) fn transactions_fee(tx_info: &[TransactionSummary]) -> u64 {
+    tx_info
+        .iter()
+        .map(|tx| tx.gas_used.unwrap_or(0) * tx.gas_unit_price.unwrap_or(0))
+        .sum()
+}
+
+/// The number of transactions sent to deploy a package published in a single transaction: by the
+/// account publishing it, the publication, the freeze and the initializers, then by the deployer,
+/// the script hooks.
+pub(crate) fn package_transaction_counts(config: &DeployConfig, address_name: &str) -> (u64, u64) {
+    let freeze = config.package_module_type(address_name) == DeployModuleType::Object
+        && config
+            .package_options
+            .get(address_name)
+            .is_some_and(|options| options.freeze);
+    (
+        1 + freeze as u64
+            + config
+                .initializers
+                .get(address_name)
+                .map_or(0, |calls| calls.len() as u64),
+        config
+            .package_hooks
+            .get(address_name)
+            .map_or(0, |hooks| hooks.script_count()),
+    )
+}
+
+/// Advances the sequence numbers of the accounts sending the transactions deploying a package.
+fn advance_sequence_numbers(
+    config: &DeployConfig,
+    address_name: &str,
+    package_sender: AccountAddress,
+    deployer_addr: AccountAddress,
+    sequence_numbers: &mut BTreeMap<AccountAddress, u64>,
+) {
+    let (package_count, script_count) = package_transaction_counts(config, address_name);
+    *sequence_numbers.entry(package_sender).or_default() += package_count;
+    *sequence_numbers.entry(deployer_addr).or_default() += script_count;
+}
+
+/// Predicts the address of every object package that isn't deployed yet, from the sequence numbers
+/// of the accounts publishing them. Assumes that the accounts send no other transaction meanwhile,
+/// and that every package fits in a single transaction.
+pub(crate) fn predict_object_addresses(
+    config: &DeployConfig,
+    packages: &[(PathBuf, String)],
+    deployed_addresses: &BTreeMap<String, AccountAddress>,
+    deployer_addr: AccountAddress,
+    mut sequence_numbers: BTreeMap<AccountAddress, u64>,
+) -> Result<Vec<(String, AccountAddress)>> {
+    let mut predicted_addresses = vec![];
+    for (_, address_name) in packages
+        .iter()
+        .filter(|(_, address_name)| !deployed_addresses.contains_key(address_name))
+    {
+        let sender = package_sender(config, address_name, deployer_addr)?;
+        if config.package_module_type(address_name) == DeployModuleType::Object {
+            predicted_addresses.push((
+                address_name.clone(),
+                create_object_code_deployment_address(
+                    sender,
+                    sequence_numbers.get(&sender).copied().unwrap_or_default(),
+                ),
+            ));
+        }
+        advance_sequence_numbers(
+            config,
+            address_name,
+            sender,
+            deployer_addr,
+            &mut sequence_numbers,
+        );
+    }
+    Ok(predicted_addresses)
+}
+
+async fn simulate_packages<B: PublishBackend>(
+    config: &DeployConfig,
+    backend: &B,
+    deployer_addr: AccountAddress,
+) -> Result<Vec<PlanRow>> {
+    let mut sequence_numbers =
+        BTreeMap::from([(deployer_addr, backend.sequence_number(deployer_addr).await?)]);
+
+    let mut deployed_addresses = config.deployed_addresses.clone();
+    let mut rows = vec![];
+    let progress = DeployProgress::new();
+    for (package_dir, address_name) in &deployment_order(config, backend.bundle())? {
+        let package_progress = progress.package(address_name);
+        let sender_addr = package_sender(config, address_name, deployer_addr)?;
+        if let Some(address) = deployed_addresses.get(address_name) {
+            package_progress.finish(Phase::Skipped);
+            rows.push(PlanRow {
+                address_name: address_name.clone(),
+                package: package_dir.to_str().unwrap().to_string(),
+                address: *address,
+                sender: sender_addr,
+                already_deployed: true,
+                estimated_gas: None,
+                note: "already deployed".to_string(),
+            });
+            continue;
+        }
+        package_progress.set_phase(Phase::Simulating);
+        if !sequence_numbers.contains_key(&sender_addr) {
+            sequence_numbers.insert(sender_addr, backend.sequence_number(sender_addr).await?);
+        }
+        let predicted_address = match config.package_module_type(address_name) {
+            DeployModuleType::Account => sender_addr,
+            DeployModuleType::Object => {
+                create_object_code_deployment_address(sender_addr, sequence_numbers[&sender_addr])
+            }
+        };
+        let package = prepare_package(
+            config,
+            backend.bundle(),
+            package_dir,
+            address_name,
+            &deployed_addresses,
+            sender_addr,
+        )?;
+        let request = PublishRequest {
+            config,
+            package_dir,
+            address_name,
+            sender: sender_addr,
+            named_addresses: &package.named_addresses,
+            payload_named_addresses: &package.payload_named_addresses,
+            deployed_addresses: &deployed_addresses,
+        };
+
+        let (estimated_gas, note) = match backend.simulate(&request).await {
+            Ok(tx_info) => (Some(transactions_fee(&tx_info)), "".to_string()),
+            Err(err) => (None, format!("simulation failed: {}", err)),
+        };
+        package_progress.finish(if estimated_gas.is_some() {
+            Phase::Simulated
+        } else {
+            Phase::Failed
+        });
+        rows.push(PlanRow {
+            address_name: address_name.clone(),
+            package: package_dir.to_str().unwrap().to_string(),
+            address: predicted_address,
+            sender: sender_addr,
+            already_deployed: false,
+            estimated_gas,
+            note,
+        });
+        deployed_addresses.insert(address_name.clone(), predicted_address);
+        advance_sequence_numbers(
+            config,
+            address_name,
+            sender_addr,
+            deployer_addr,
+            &mut sequence_numbers,
+        );
+    }
+    Ok(rows)
+}
@@ -0,0 +1,168 @@
+use std::collections::BTreeSet;
+use std::path::Path;
+
+use aptos_sdk::crypto::ValidCryptoMaterialStringExt;
+use aptos_sdk::move_types::account_address::AccountAddress;
+use aptos_sdk::rest_client::Client;
+use aptos_sdk::types::transaction::authenticator::AuthenticationKey;
+use tracing::{info, warn};
+
+use crate::deploy_report::{DeployReport, DeployStatus, TxReport};
+use crate::error::{JayceError, Result};
+use crate::move_package::MoveTomlFile;
+use crate::package_registry::PackageRegistry;
+use crate::utils::{is_not_found, rest_client};
+
+/// The on-chain checks of a package of a report.
+struct VerifyRow {
+    address_name: String,
+    address: AccountAddress,
+    /// The account or object the package was published at exists.
+    exists: bool,
+    /// The package registry of the address contains the package.
+    published: bool,
+    /// The number of transactions of the report committed successfully, out of `transactions`.
+    committed: usize,
+    transactions: usize,
+}
+
+impl VerifyRow {
+    fn passed(&self) -> bool {
+        self.exists && self.published && self.committed == self.transactions
+    }
+}
+
+/// Checks the signature of a report written with `sign_report`, and that it was signed by the key
+/// of `expected_signer`, the account of the report by default.
+pub fn verify_report_signature(
+    report_path: &Path,
+    expected_signer: Option<AccountAddress>,
+) -> Result<()> {
+    let signature = DeployReport::verify_signature(report_path)?;
+    let report = DeployReport::from_path(report_path)?;
+    let signer = AuthenticationKey::ed25519(&signature.public_key).account_address();
+    let expected_signer = expected_signer.unwrap_or(report.account);
+    if signer != expected_signer {
+        return Err(JayceError::Verification(format!(
+            "The report was signed by public key {} (account {}), not by {}",
+            signature.public_key.to_encoded_string()?,
+            signer,
+            expected_signer
+        )));
+    }
+    println!(
+        "Valid signature by public key {} (account {})",
+        signature.public_key.to_encoded_string()?,
+        signer
+    );
+    Ok(())
+}
+
+/// Checks the packages deployed by a report against the chain: the account or object they were
+/// published at exists, its package registry contains the package and every transaction of the
+/// report was committed successfully. Prints a pass/fail table and fails if any check failed.
+pub async fn verify_report(report_path: &Path, rest_url: Option<String>) -> Result<()> {
+    let report = DeployReport::from_path(report_path)?;
+    let client = rest_client(&report.network, rest_url)?;
+
+    let mut rows = vec![];
+    for tx_report in report
+        .info
+        .iter()
+        .filter(|tx_report| tx_report.status == DeployStatus::Deployed)
+    {
+        info!("Verifying package {}...", tx_report.address_name);
+        rows.push(verify_package(&client, tx_report).await?);
+    }
+
+    println!(
+        "{:<20} {:<68} {:<8} {:<10} {:<14} {}",
+        "ADDRESS NAME", "ADDRESS", "EXISTS", "PUBLISHED", "TRANSACTIONS", "RESULT"
+    );
+    let check = |passed: bool| if passed { "yes" } else { "no" };
+    for row in &rows {
+        let transactions = format!("{}/{}", row.committed, row.transactions);
+        println!(
+            "{:<20} {:<68} {:<8} {:<10} {:<14} {}",
+            row.address_name,
+            row.address.to_string(),
+            check(row.exists),
+            check(row.published),
+            transactions,
+            if row.passed() { "pass" } else { "fail" }
+        );
+    }
+
+    let failed = rows.iter().filter(|row| !row.passed()).count();
+    if failed > 0 {
+        return Err(JayceError::Verification(format!(
+            "{} of {} package(s) failed verification",
+            failed,
+            rows.len()
+        )));
+    }
+    Ok(())
+}
+
+async fn verify_package(client: &Client, tx_report: &TxReport) -> Result<VerifyRow> {
+    let address = tx_report.deployed_at;
+    let exists = match client.get_account(address).await {
+        Ok(_) => true,
+        // Object packages are published at objects, which don't have an account resource.
+        Err(err) if is_not_found(&err) => client
+            .get_account_resource(address, "0x1::object::ObjectCore")
+            .await?
+            .into_inner()
+            .is_some(),
+        Err(err) => return Err(err.into()),
+    };
+
+    let registry = PackageRegistry::fetch(client, address).await?;
+    let published = match (
+        MoveTomlFile::from_package_dir(&tx_report.module_path),
+        &tx_report.checksums,
+    ) {
+        (Ok(manifest), _) => registry.package(&manifest.package.name).is_some(),
+        // Without the local sources, the package is the one with the modules of the report.
+        (Err(_), Some(checksums)) => registry.packages.iter().any(|package| {
+            let modules: BTreeSet<&String> =
+                package.modules.iter().map(|module| &module.name).collect();
+            modules == checksums.modules.keys().collect()
+        }),
+        (Err(err), None) => {
+            warn!(
+                "Package {} can't be identified without its sources: {}",
+                tx_report.address_name, err
+            );
+            false
+        }
+    };
+
+    let mut committed = 0;
+    let mut transactions = 0;
+    for tx in tx_report.transactions() {
+        transactions += 1;
+        match client.get_transaction_by_hash(tx.transaction_hash).await {
+            Ok(response) if !response.inner().is_pending() && response.inner().success() => {
+                committed += 1
+            }
+            Ok(_) => warn!(
+                "Transaction {} isn't committed successfully",
+                tx.transaction_hash
+            ),
+            Err(err) if is_not_found(&err) => {
+                warn!("Transaction {} not found", tx.transaction_hash)
+            }
+            Err(err) => return Err(err.into()),
+        }
+    }
+
+    Ok(VerifyRow {
+        address_name: tx_report.address_name.clone(),
+        address,
+        exists,
+        published,
+        committed,
+        transactions,
+    })
+}
@@ -0,0 +1,242 @@
+use std::collections::BTreeSet;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use strum_macros::Display;
+use tracing::{info, warn};
+
+use crate::artifact_cache::cache_dir;
+use crate::deploy_report::{DeployReport, DeployStatus, PackageChecksums, JAYCE_VERSION};
+use crate::deployer::build_publish_payload;
+use crate::error::{JayceError, Result};
+use crate::git::{repository_root, GitSource};
+use crate::move_package::{named_addresses_arg, upgrade_policy_override};
+
+#[derive(Debug, Clone, PartialEq, Display)]
+#[strum(serialize_all = "SCREAMING_SNAKE_CASE")]
+enum ArtifactStatus {
+    Match,
+    Mismatch,
+    /// Published, but not produced by the rebuild.
+    Missing,
+    /// Produced by the rebuild, but not published.
+    Extra,
+}
+
+/// Rebuilds every package of the deployment report from the commit it was deployed from, with the
+/// compiler options, upgrade policy and artifacts recorded for it, and compares the checksums of
+/// the rebuilt metadata and modules with the recorded ones. The commit is cloned into the cache
+/// directory, so the working tree is left untouched. Packages whose report doesn't record their
+/// checksums, compiler options and artifacts can't be rebuilt, and fail the reproduction.
+pub async fn reproduce(report_path: &Path) -> Result<()> {
+    let report = DeployReport::from_path(report_path)?;
+    let commit = report.git_commit.clone().ok_or_else(|| {
+        JayceError::Config(format!(
+            "The deployment report {} doesn't record the git commit it was deployed from",
+            report_path.to_str().unwrap()
+        ))
+    })?;
+    let current_dir = fs::canonicalize(env::current_dir()?)?;
+    let root = repository_root(&current_dir).ok_or_else(|| {
+        JayceError::Config("Packages can only be reproduced from a git repository".to_string())
+    })?;
+    let cache_dir = cache_dir()
+        .ok_or_else(|| JayceError::Config("No cache directory to clone into".to_string()))?;
+    let checkout_dir = GitSource {
+        url: root.to_str().unwrap().to_string(),
+        rev: commit.clone(),
+        path: None,
+    }
+    .checkout(&cache_dir.join("git"))?;
+    let deployed_addresses = report.deployed_addresses();
+
+    let mut mismatches = 0;
+    let mut skipped = vec![];
+    for tx_report in report
+        .info
+        .iter()
+        .filter(|tx_report| tx_report.status == DeployStatus::Deployed)
+    {
+        let (Some(checksums), Some(compiler), Some(included_artifacts)) = (
+            &tx_report.checksums,
+            &tx_report.compiler,
+            &tx_report.included_artifacts,
+        ) else {
+            warn!(
+                "The report doesn't record the checksums, compiler options and artifacts of \
+                 package {}, skipping it",
+                tx_report.address_name
+            );
+            skipped.push(tx_report.address_name.clone());
+            continue;
+        };
+        if compiler.compiler_version.is_none() {
+            match &tx_report.jayce_version {
+                Some(version) if version == JAYCE_VERSION => {}
+                Some(version) => warn!(
+                    "Package {} was compiled by the default compiler of jayce {}, it is rebuilt \
+                     with the one of jayce {}",
+                    tx_report.address_name, version, JAYCE_VERSION
+                ),
+                None => warn!(
+                    "Package {} was compiled by the default compiler of an unknown version of \
+                     jayce, it is rebuilt with the one of jayce {}",
+                    tx_report.address_name, JAYCE_VERSION
+                ),
+            }
+        }
+        let package_dir =
+            package_dir_at_commit(&tx_report.module_path, &current_dir, &root, &checkout_dir);
+        info!(
+            "Rebuilding package {} at {}...",
+            tx_report.address_name, commit
+        );
+        build_publish_payload(
+            &package_dir,
+            &tx_report.address_name,
+            &named_addresses_arg(&package_dir, &tx_report.address_name, &deployed_addresses)?,
+            compiler,
+            upgrade_policy_override(&package_dir, tx_report.upgrade_policy.as_ref())?,
+            &included_artifacts.to_string(),
+            false,
+        )
+        .await?;
+        let rebuilt = PackageChecksums::from_build(&package_dir)?;
+        for (artifact, status) in compare_checksums(checksums, &rebuilt) {
+            if status != ArtifactStatus::Match {
+                mismatches += 1;
+            }
+            info!("  [{}] {}::{}", status, tx_report.address_name, artifact);
+        }
+    }
+
+    if mismatches > 0 {
+        return Err(JayceError::Verification(format!(
+            "{} artifact(s) can't be reproduced from commit {}",
+            mismatches, commit
+        )));
+    }
+    if !skipped.is_empty() {
+        return Err(JayceError::Verification(format!(
+            "{} package(s) can't be rebuilt from the report: {}",
+            skipped.len(),
+            skipped.join(", ")
+        )));
+    }
+    info!("Every package was reproduced from commit {}", commit);
+    Ok(())
+}
+
+/// The directory of a package of the report in the checkout of its commit. The module paths of the
+/// report are relative to the directory jayce ran in, assumed to be the current one. Packages
+/// outside of the repository, e.g. checked out from another repository, are rebuilt in place.
+fn package_dir_at_commit(
+    module_path: &Path,
+    current_dir: &Path,
+    root: &Path,
+    checkout_dir: &Path,
+) -> PathBuf {
+    let path = current_dir.join(module_path);
+    match path.strip_prefix(root) {
+        Ok(relative) => checkout_dir.join(relative),
+        Err(_) => path,
+    }
+}
+
+/// Compares the recorded checksums of the metadata and modules of a package with the rebuilt ones.
+fn compare_checksums(
+    recorded: &PackageChecksums,
+    rebuilt: &PackageChecksums,
+) -> Vec<(String, ArtifactStatus)> {
+    let status = |recorded: Option<&String>, rebuilt: Option<&String>| match (recorded, rebuilt) {
+        (Some(recorded), Some(rebuilt)) if recorded == rebuilt => ArtifactStatus::Match,
+        (Some(_), Some(_)) => ArtifactStatus::Mismatch,
+        (Some(_), None) => ArtifactStatus::Missing,
+        (None, _) => ArtifactStatus::Extra,
+    };
+    let mut statuses = vec![(
+        "package-metadata.bcs".to_string(),
+        status(Some(&recorded.metadata), Some(&rebuilt.metadata)),
+    )];
+    let module_names: BTreeSet<&String> = recorded
+        .modules
+        .keys()
+        .chain(rebuilt.modules.keys())
+        .collect();
+    for module_name in module_names {
+        statuses.push((
+            module_name.clone(),
+            status(
+                recorded.modules.get(module_name),
+                rebuilt.modules.get(module_name),
+            ),
+        ));
+    }
+    statuses
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::BTreeMap;
+    use std::path::{Path, PathBuf};
+
+    use crate::deploy_report::PackageChecksums;
+    use crate::tasks::reproduce::{compare_checksums, package_dir_at_commit, ArtifactStatus};
+
+    #[test]
+    fn test_package_dir_at_commit() {
+        let root = Path::new("/repo");
+        let checkout_dir = Path::new("/cache/git/abc-123");
+        assert_eq!(
+            package_dir_at_commit(
+                Path::new("contracts/verifier"),
+                Path::new("/repo/move"),
+                root,
+                checkout_dir
+            ),
+            PathBuf::from("/cache/git/abc-123/move/contracts/verifier")
+        );
+        assert_eq!(
+            package_dir_at_commit(
+                Path::new("/repo/libs"),
+                Path::new("/repo"),
+                root,
+                checkout_dir
+            ),
+            PathBuf::from("/cache/git/abc-123/libs")
+        );
+        assert_eq!(
+            package_dir_at_commit(
+                Path::new("/cache/git/def-456/lib"),
+                Path::new("/repo"),
+                root,
+                checkout_dir
+            ),
+            PathBuf::from("/cache/git/def-456/lib")
+        );
+    }
+
+    #[test]
+    fn test_compare_checksums() {
+        let checksums = |modules: &[(&str, &str)]| PackageChecksums {
+            metadata: "aa".to_string(),
+            modules: modules
+                .iter()
+                .map(|(name, checksum)| (name.to_string(), checksum.to_string()))
+                .collect::<BTreeMap<_, _>>(),
+        };
+        let recorded = checksums(&[("lib", "01"), ("math", "02"), ("vec", "03")]);
+        let rebuilt = checksums(&[("lib", "01"), ("math", "ff"), ("utils", "04")]);
+        assert_eq!(
+            compare_checksums(&recorded, &rebuilt),
+            vec![
+                ("package-metadata.bcs".to_string(), ArtifactStatus::Match),
+                ("lib".to_string(), ArtifactStatus::Match),
+                ("math".to_string(), ArtifactStatus::Mismatch),
+                ("utils".to_string(), ArtifactStatus::Extra),
+                ("vec".to_string(), ArtifactStatus::Missing),
+            ]
+        );
+    }
+}
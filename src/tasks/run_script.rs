@@ -0,0 +1,39 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use aptos_sdk::move_types::account_address::AccountAddress;
+
+use crate::deploy_config::AptosNetwork;
+use crate::entry_function::{run_script, ScriptCall};
+use crate::error::Result;
+use crate::tasks::call::load_deployed_addresses;
+use crate::tasks::deploy_contracts::private_key_signer_args;
+
+/// Compiles and runs a Move script, resolving the named addresses of its package and the address
+/// names of its arguments from the deployment report. The script is signed with `private_key`,
+/// or with an aptos CLI profile.
+#[allow(clippy::too_many_arguments)]
+pub async fn run_script_command(
+    call: &ScriptCall,
+    private_key: Option<&str>,
+    profile: Option<&str>,
+    network: Option<AptosNetwork>,
+    rest_url: Option<String>,
+    report_path: &Path,
+    deployed_addresses: BTreeMap<String, AccountAddress>,
+    yes: bool,
+) -> Result<()> {
+    let (deployed_addresses, report_network) =
+        load_deployed_addresses(report_path, deployed_addresses)?;
+    let network = network.or(report_network).unwrap_or(AptosNetwork::Devnet);
+
+    let signer_args = match (private_key, profile) {
+        (Some(private_key), _) => private_key_signer_args(&network, private_key, rest_url)?,
+        (None, Some(profile)) => format!("--profile {}", profile),
+        (None, None) => "".to_string(),
+    };
+    let result = run_script(call, &deployed_addresses, &signer_args, "", yes).await?;
+
+    println!("{}", serde_json::to_string_pretty(&result)?);
+    Ok(())
+}
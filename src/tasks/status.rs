@@ -0,0 +1,176 @@
+use std::path::Path;
+
+use aptos_sdk::move_types::account_address::AccountAddress;
+use strum_macros::Display;
+use tracing::info;
+
+use crate::address_book::AddressBook;
+use crate::deploy_config::AptosNetwork;
+use crate::deploy_report::{DeployReport, DeployStatus};
+use crate::deployer::build_publish_payload;
+use crate::error::{JayceError, Result};
+use crate::move_package::{named_addresses_arg, upgrade_policy_override, MoveTomlFile};
+use crate::package_registry::{matches_published_modules, PackageRegistry};
+use crate::utils::rest_client;
+
+#[derive(Debug, Clone, PartialEq, Display)]
+#[strum(serialize_all = "snake_case")]
+enum PackageStatus {
+    /// The published modules match the local sources.
+    UpToDate,
+    /// The local sources produce different bytecode than the published modules.
+    Drift,
+    /// No package is published at the address.
+    Missing,
+    /// A package is published, but there are no local sources to compare it with.
+    Published,
+    /// The report doesn't record the compiler options the package was published with, so the
+    /// local sources can't be compared with it.
+    Unknown,
+}
+
+struct StatusRow {
+    address_name: String,
+    address: AccountAddress,
+    package: String,
+    upgrade_number: Option<u64>,
+    status: PackageStatus,
+}
+
+/// Checks the packages recorded in a deployment report against the chain, comparing the published
+/// modules with the local sources built with the compiler options and upgrade policy recorded for
+/// them.
+pub async fn status_from_report(report_path: &Path, rest_url: Option<String>) -> Result<()> {
+    let report = DeployReport::from_path(report_path)?;
+    let client = rest_client(&report.network, rest_url)?;
+    let deployed_addresses = report.deployed_addresses();
+
+    let mut rows = vec![];
+    for tx_report in report
+        .info
+        .iter()
+        .filter(|tx_report| tx_report.status == DeployStatus::Deployed)
+    {
+        info!("Checking package {}...", tx_report.address_name);
+        let registry = PackageRegistry::fetch(&client, tx_report.deployed_at).await?;
+        let row = |package: String, upgrade_number, status| StatusRow {
+            address_name: tx_report.address_name.clone(),
+            address: tx_report.deployed_at,
+            package,
+            upgrade_number,
+            status,
+        };
+        if !tx_report.module_path.join("Move.toml").is_file() {
+            rows.extend(registry.packages.into_iter().map(|package| {
+                row(
+                    package.name,
+                    Some(package.upgrade_number),
+                    PackageStatus::Published,
+                )
+            }));
+            continue;
+        }
+        let package_name = MoveTomlFile::from_package_dir(&tx_report.module_path)?
+            .package
+            .name;
+        let Some(package) = registry.package(&package_name) else {
+            rows.push(row(package_name, None, PackageStatus::Missing));
+            continue;
+        };
+        let Some(compiler) = &tx_report.compiler else {
+            rows.push(row(
+                package_name,
+                Some(package.upgrade_number),
+                PackageStatus::Unknown,
+            ));
+            continue;
+        };
+        let (_, code) = build_publish_payload(
+            &tx_report.module_path,
+            &tx_report.address_name,
+            &named_addresses_arg(
+                &tx_report.module_path,
+                &tx_report.address_name,
+                &deployed_addresses,
+            )?,
+            compiler,
+            upgrade_policy_override(&tx_report.module_path, tx_report.upgrade_policy.as_ref())?,
+            "none",
+            true,
+        )
+        .await?;
+        let status =
+            if matches_published_modules(&client, tx_report.deployed_at, package, &code).await? {
+                PackageStatus::UpToDate
+            } else {
+                PackageStatus::Drift
+            };
+        rows.push(row(package_name, Some(package.upgrade_number), status));
+    }
+    print_status(&rows)
+}
+
+/// Checks that the addresses of an address book have packages published on chain.
+pub async fn status_from_address_book(
+    address_book_path: &Path,
+    network: &AptosNetwork,
+    network_name: &str,
+    rest_url: Option<String>,
+) -> Result<()> {
+    let address_book = AddressBook::from_path(address_book_path)?;
+    let client = rest_client(network, rest_url)?;
+
+    let mut rows = vec![];
+    for (address_name, address) in address_book.addresses(network_name) {
+        let registry = PackageRegistry::fetch(&client, address).await?;
+        if registry.packages.is_empty() {
+            rows.push(StatusRow {
+                address_name,
+                address,
+                package: "-".to_string(),
+                upgrade_number: None,
+                status: PackageStatus::Missing,
+            });
+            continue;
+        }
+        rows.extend(registry.packages.into_iter().map(|package| StatusRow {
+            address_name: address_name.clone(),
+            address,
+            package: package.name,
+            upgrade_number: Some(package.upgrade_number),
+            status: PackageStatus::Published,
+        }));
+    }
+    print_status(&rows)
+}
+
+fn print_status(rows: &[StatusRow]) -> Result<()> {
+    println!(
+        "{:<20} {:<68} {:<24} {:>8}  {}",
+        "ADDRESS NAME", "ADDRESS", "PACKAGE", "UPGRADE", "STATUS"
+    );
+    for row in rows {
+        println!(
+            "{:<20} {:<68} {:<24} {:>8}  {}",
+            row.address_name,
+            row.address.to_string(),
+            row.package,
+            row.upgrade_number
+                .map(|upgrade_number| upgrade_number.to_string())
+                .unwrap_or("-".to_string()),
+            row.status
+        );
+    }
+
+    let outdated = rows
+        .iter()
+        .filter(|row| matches!(row.status, PackageStatus::Drift | PackageStatus::Missing))
+        .count();
+    if outdated > 0 {
+        return Err(JayceError::Verification(format!(
+            "{} package(s) are missing or out of date",
+            outdated
+        )));
+    }
+    Ok(())
+}
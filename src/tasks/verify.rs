@@ -0,0 +1,278 @@
+use std::fs;
+use std::path::Path;
+
+use aptos::common::types::CliCommand;
+use aptos::move_tool::MoveTool;
+use aptos::Tool;
+use aptos_sdk::move_types::account_address::AccountAddress;
+use clap::Parser;
+use strum_macros::Display;
+use tracing::{error, info, warn};
+
+use crate::deploy_config::{CompilerOptions, UpgradePolicy};
+use crate::deploy_report::{DeployReport, DeployStatus, TxReport};
+use crate::deployer::build_publish_payload;
+use crate::docker::run_aptos_in_docker;
+use crate::error::{JayceError, Result};
+use crate::move_package::{
+    built_module_names, named_addresses_arg, package_dir_args, upgrade_policy_override,
+    MoveTomlFile, PackageCopy,
+};
+use crate::package_registry::PackageRegistry;
+use crate::utils::{is_not_found, resolve_rest_url, rest_client};
+
+#[derive(Debug, Clone, PartialEq, Display)]
+#[strum(serialize_all = "SCREAMING_SNAKE_CASE")]
+enum ModuleStatus {
+    Match,
+    Mismatch,
+    Missing,
+    /// Published in the package, but not built from the local sources.
+    Extra,
+}
+
+/// Compiles every package recorded in the deployment report, with the compiler options and
+/// upgrade policy it was published with, and compares the resulting modules and upgrade policy
+/// with the package published on chain.
+pub async fn verify(report_path: &Path, rest_url: Option<String>) -> Result<()> {
+    let report = DeployReport::from_path(report_path)?;
+    let client = rest_client(&report.network, rest_url)?;
+    let deployed_addresses = report.deployed_addresses();
+
+    let mut mismatches = 0;
+    for tx_report in report
+        .info
+        .iter()
+        .filter(|tx_report| tx_report.status == DeployStatus::Deployed)
+    {
+        info!(
+            "Verifying package {} with address name {}...",
+            tx_report.module_path.to_str().unwrap(),
+            tx_report.address_name
+        );
+        let manifest = MoveTomlFile::from_package_dir(&tx_report.module_path)?;
+        let upgrade_policy = match &tx_report.upgrade_policy {
+            Some(upgrade_policy) => upgrade_policy.clone(),
+            None => manifest
+                .package
+                .upgrade_policy
+                .unwrap_or(UpgradePolicy::Compatible),
+        };
+        let (_, code) = build_publish_payload(
+            &tx_report.module_path,
+            &tx_report.address_name,
+            &named_addresses_arg(
+                &tx_report.module_path,
+                &tx_report.address_name,
+                &deployed_addresses,
+            )?,
+            &recorded_compiler(tx_report),
+            upgrade_policy_override(&tx_report.module_path, Some(&upgrade_policy))?,
+            "none",
+            true,
+        )
+        .await?;
+        let local_modules = built_module_names(&tx_report.module_path, &code)?;
+
+        let registry = PackageRegistry::fetch(&client, tx_report.deployed_at).await?;
+        let published_modules: Vec<&str> = match registry.package(&manifest.package.name) {
+            Some(package) => {
+                if package.upgrade_policy.policy != upgrade_policy.code() {
+                    mismatches += 1;
+                    error!(
+                        "Package {} isn't published with the {} upgrade policy",
+                        manifest.package.name, upgrade_policy
+                    );
+                }
+                package
+                    .modules
+                    .iter()
+                    .map(|module| module.name.as_str())
+                    .collect()
+            }
+            None => {
+                error!(
+                    "Package {} isn't published at {}",
+                    manifest.package.name, tx_report.deployed_at
+                );
+                vec![]
+            }
+        };
+
+        for (module_name, local_bytecode) in local_modules.iter().zip(&code) {
+            let status = if !published_modules.contains(&module_name.as_str()) {
+                ModuleStatus::Missing
+            } else {
+                match client
+                    .get_account_module(tx_report.deployed_at, module_name)
+                    .await
+                {
+                    Ok(response)
+                        if response.inner().bytecode.inner() == local_bytecode.as_slice() =>
+                    {
+                        ModuleStatus::Match
+                    }
+                    Ok(_) => ModuleStatus::Mismatch,
+                    Err(err) if is_not_found(&err) => ModuleStatus::Missing,
+                    Err(err) => return Err(err.into()),
+                }
+            };
+            if status != ModuleStatus::Match {
+                mismatches += 1;
+            }
+            info!("  [{}] {}::{}", status, tx_report.address_name, module_name);
+        }
+        for module_name in published_modules
+            .into_iter()
+            .filter(|module_name| !local_modules.iter().any(|local| local == *module_name))
+        {
+            mismatches += 1;
+            info!(
+                "  [{}] {}::{}",
+                ModuleStatus::Extra,
+                tx_report.address_name,
+                module_name
+            );
+        }
+    }
+
+    if mismatches > 0 {
+        return Err(JayceError::Verification(format!(
+            "{} module(s) or upgrade policies do not match the published packages",
+            mismatches
+        )));
+    }
+    info!("All modules and upgrade policies match the published packages");
+    Ok(())
+}
+
+/// The compiler options a package of the report was published with, or the default ones if the
+/// report doesn't record them.
+fn recorded_compiler(tx_report: &TxReport) -> CompilerOptions {
+    tx_report.compiler.clone().unwrap_or_else(|| {
+        warn!(
+            "The report doesn't record the compiler options of package {}, it is compiled with the \
+             default ones",
+            tx_report.address_name
+        );
+        CompilerOptions::default()
+    })
+}
+
+/// Verifies the published sources of every package recorded in the deployment report with the
+/// source verification of the aptos CLI, building them with the compiler options and upgrade policy
+/// they were published with.
+pub async fn verify_source(report_path: &Path, rest_url: Option<String>) -> Result<()> {
+    let report = DeployReport::from_path(report_path)?;
+    let rest_url = resolve_rest_url(&report.network, rest_url)?;
+    let deployed_addresses = report.deployed_addresses();
+
+    let mut failed = vec![];
+    for tx_report in report
+        .info
+        .iter()
+        .filter(|tx_report| tx_report.status == DeployStatus::Deployed)
+    {
+        info!(
+            "Verifying the sources of package {} with address name {}...",
+            tx_report.module_path.to_str().unwrap(),
+            tx_report.address_name
+        );
+        let result = verify_package_source(
+            &tx_report.module_path,
+            tx_report.deployed_at,
+            &named_addresses_arg(
+                &tx_report.module_path,
+                &tx_report.address_name,
+                &deployed_addresses,
+            )?,
+            &recorded_compiler(tx_report),
+            upgrade_policy_override(&tx_report.module_path, tx_report.upgrade_policy.as_ref())?,
+            &rest_url,
+        )
+        .await;
+        if let Err(err) = result {
+            error!("{}", err);
+            failed.push(tx_report.address_name.clone());
+        }
+    }
+
+    if !failed.is_empty() {
+        return Err(JayceError::Verification(format!(
+            "The sources of {} don't match the published packages",
+            failed.join(", ")
+        )));
+    }
+    info!("The sources of every package match the published packages");
+    Ok(())
+}
+
+/// Checks with `aptos move verify-package` that the package published at `account` was built from
+/// the sources of `package_dir`. The package must have been published with its sources, e.g. with
+/// `--included-artifacts sparse`. An `upgrade_policy` overrides the one of the `Move.toml`.
+pub(crate) async fn verify_package_source(
+    package_dir: &Path,
+    account: AccountAddress,
+    named_addresses: &str,
+    compiler: &CompilerOptions,
+    upgrade_policy: Option<&UpgradePolicy>,
+    rest_url: &str,
+) -> Result<()> {
+    if let Some(image) = &compiler.docker_image {
+        let package_dir = fs::canonicalize(package_dir)?;
+        let (package_dir_args, copy) = package_dir_args(&package_dir, upgrade_policy)?;
+        let args = format!(
+            "move verify-package {} --account {} --included-artifacts sparse \
+            --url {} {} {}",
+            package_dir_args,
+            account,
+            rest_url,
+            named_addresses,
+            compiler.args()
+        );
+        let args: Vec<&str> = args.split_whitespace().collect();
+        let outputs: Vec<&Path> = copy.iter().map(PackageCopy::dir).collect();
+        return run_aptos_in_docker(image, &package_dir, &args, &outputs).map_err(|err| {
+            JayceError::Verification(format!(
+                "Failed to verify the sources of {}: {}",
+                package_dir.to_str().unwrap(),
+                err
+            ))
+        });
+    }
+    let (package_dir_args, _copy) = package_dir_args(package_dir, upgrade_policy)?;
+    let args = format!(
+        "aptos move verify-package \
+            {} \
+            --account {} \
+            --included-artifacts sparse \
+            --url {} \
+            {} \
+            {} \
+            ",
+        package_dir_args,
+        account,
+        rest_url,
+        named_addresses,
+        compiler.args()
+    );
+    let args: Vec<&str> = args.split_whitespace().collect();
+    let tool = Tool::try_parse_from(&args).map_err(|err| {
+        JayceError::Config(format!("Invalid arguments to verify package: {}", err))
+    })?;
+    if let Tool::Move(MoveTool::VerifyPackage(cmd_executor)) = tool {
+        cmd_executor.execute().await.map_err(|err| {
+            JayceError::Verification(format!(
+                "Failed to verify the sources of {}: {}",
+                package_dir.to_str().unwrap(),
+                err
+            ))
+        })?;
+        Ok(())
+    } else {
+        Err(JayceError::Config(format!(
+            "Wrong arguments to verify package: {:?}",
+            args
+        )))
+    }
+}
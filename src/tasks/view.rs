@@ -0,0 +1,30 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use aptos_sdk::move_types::account_address::AccountAddress;
+
+use crate::deploy_config::AptosNetwork;
+use crate::entry_function::{run_view_function, EntryFunctionCall};
+use crate::error::{JayceError, Result};
+use crate::tasks::call::load_deployed_addresses;
+
+/// Executes a view function, resolving the address names from the deployment report, and prints
+/// its result as JSON.
+pub async fn view(
+    call: &EntryFunctionCall,
+    network: Option<AptosNetwork>,
+    rest_url: Option<String>,
+    report_path: &Path,
+    deployed_addresses: BTreeMap<String, AccountAddress>,
+) -> Result<()> {
+    let (deployed_addresses, report_network) =
+        load_deployed_addresses(report_path, deployed_addresses)?;
+    let network = network.or(report_network).unwrap_or(AptosNetwork::Devnet);
+    let rest_url = rest_url.or_else(|| network.rest_url()).ok_or_else(|| {
+        JayceError::Config(format!("REST URL not found for network: {}", network))
+    })?;
+
+    let result = run_view_function(call, &deployed_addresses, &rest_url).await?;
+    println!("{}", serde_json::to_string_pretty(&result)?);
+    Ok(())
+}
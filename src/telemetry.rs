@@ -0,0 +1,109 @@
+use std::sync::OnceLock;
+
+use opentelemetry::metrics::{Counter, Histogram};
+use opentelemetry::trace::TracerProvider as _;
+use opentelemetry::{global, KeyValue};
+use opentelemetry_otlp::{MetricExporter, SpanExporter, WithExportConfig};
+use opentelemetry_sdk::metrics::{PeriodicReader, SdkMeterProvider};
+use opentelemetry_sdk::trace::TracerProvider;
+use opentelemetry_sdk::{runtime, Resource};
+use tracing::warn;
+use tracing_opentelemetry::OpenTelemetryLayer;
+use tracing_subscriber::registry::LookupSpan;
+
+use crate::error::{JayceError, Result};
+
+/// The instruments of a deployment. They record nothing until an exporter is installed with
+/// [`init_telemetry`].
+pub struct Metrics {
+    /// Duration of each phase of the deployment of a package, in seconds.
+    pub phase_duration: Histogram<f64>,
+    /// Gas used by the transactions of the deployment.
+    pub gas_used: Counter<u64>,
+    /// Retries of the requests failing with transient errors.
+    pub retries: Counter<u64>,
+}
+
+/// Returns the instruments of the deployment.
+pub fn metrics() -> &'static Metrics {
+    static METRICS: OnceLock<Metrics> = OnceLock::new();
+    METRICS.get_or_init(|| {
+        let meter = global::meter("jayce");
+        Metrics {
+            phase_duration: meter
+                .f64_histogram("jayce.package.phase.duration")
+                .with_unit("s")
+                .with_description("Duration of each phase of the deployment of a package")
+                .build(),
+            gas_used: meter
+                .u64_counter("jayce.gas.used")
+                .with_description("Gas used by the transactions of the deployment")
+                .build(),
+            retries: meter
+                .u64_counter("jayce.retries")
+                .with_description("Retries of requests failing with transient errors")
+                .build(),
+        }
+    })
+}
+
+/// Exports the spans and metrics of jayce to an OpenTelemetry collector. Everything recorded is
+/// flushed when it is dropped.
+pub struct Telemetry {
+    tracer_provider: TracerProvider,
+    meter_provider: SdkMeterProvider,
+}
+
+impl Telemetry {
+    /// Returns the layer forwarding the spans of `tracing` to the collector.
+    pub fn layer<S>(&self) -> OpenTelemetryLayer<S, opentelemetry_sdk::trace::Tracer>
+    where
+        S: tracing::Subscriber + for<'span> LookupSpan<'span>,
+    {
+        tracing_opentelemetry::layer().with_tracer(self.tracer_provider.tracer("jayce"))
+    }
+}
+
+impl Drop for Telemetry {
+    fn drop(&mut self) {
+        if let Err(err) = self.tracer_provider.shutdown() {
+            warn!("Failed to export the spans: {}", err);
+        }
+        if let Err(err) = self.meter_provider.shutdown() {
+            warn!("Failed to export the metrics: {}", err);
+        }
+    }
+}
+
+/// Installs the OTLP/HTTP exporters of the spans and metrics, sending them to
+/// `<endpoint>/v1/traces` and `<endpoint>/v1/metrics`. Must be called from the Tokio runtime.
+pub fn init_telemetry(endpoint: &str) -> Result<Telemetry> {
+    let endpoint = endpoint.trim_end_matches('/');
+    let resource = Resource::new(vec![
+        KeyValue::new("service.name", "jayce"),
+        KeyValue::new("service.version", env!("CARGO_PKG_VERSION")),
+    ]);
+    let span_exporter = SpanExporter::builder()
+        .with_http()
+        .with_endpoint(format!("{}/v1/traces", endpoint))
+        .build()
+        .map_err(|err| JayceError::Config(format!("Invalid OTLP endpoint: {}", err)))?;
+    let tracer_provider = TracerProvider::builder()
+        .with_batch_exporter(span_exporter, runtime::Tokio)
+        .with_resource(resource.clone())
+        .build();
+    let metric_exporter = MetricExporter::builder()
+        .with_http()
+        .with_endpoint(format!("{}/v1/metrics", endpoint))
+        .build()
+        .map_err(|err| JayceError::Config(format!("Invalid OTLP endpoint: {}", err)))?;
+    let meter_provider = SdkMeterProvider::builder()
+        .with_reader(PeriodicReader::builder(metric_exporter, runtime::Tokio).build())
+        .with_resource(resource)
+        .build();
+    global::set_meter_provider(meter_provider.clone());
+    Ok(Telemetry {
+        tracer_provider,
+        meter_provider,
+    })
+}
@@ -0,0 +1,105 @@
+use std::fs::{self, File};
+use std::path::PathBuf;
+use std::process::{Child, Command, Stdio};
+use std::time::{Duration, Instant};
+use std::{env, thread};
+
+use crate::error::{JayceError, Result};
+use crate::tasks::localnet::{is_ready, LOCALNET_FAUCET_URL, LOCALNET_REST_URL};
+
+/// A local network started for a test, stopped when the fixture is dropped.
+///
+/// The network runs in a process of the aptos CLI, which must be installed, so that stopping it
+/// frees its ports. It listens on the default ports, so only one fixture can run at a time:
+///
+/// ```no_run
+/// # async fn example() -> jayce::error::Result<()> {
+/// use jayce::testing::LocalnetFixture;
+///
+/// let localnet = LocalnetFixture::start().await?;
+/// println!("REST API: {}, faucet: {}", localnet.rest_url, localnet.faucet_url);
+/// # Ok(())
+/// # }
+/// ```
+pub struct LocalnetFixture {
+    pub rest_url: String,
+    pub faucet_url: String,
+    /// The directory holding the data of the network, deleted when it starts and when the
+    /// fixture is dropped.
+    pub test_dir: PathBuf,
+    /// The output of the network, deleted when the fixture is dropped.
+    pub log_path: PathBuf,
+    process: Child,
+}
+
+impl LocalnetFixture {
+    /// Starts a local network from an empty chain and waits until its REST API and faucet are
+    /// ready, for at most two minutes.
+    pub async fn start() -> Result<LocalnetFixture> {
+        Self::start_with_timeout(Duration::from_secs(120)).await
+    }
+
+    pub async fn start_with_timeout(timeout: Duration) -> Result<LocalnetFixture> {
+        let test_dir = env::temp_dir().join(format!("jayce-localnet-{}", std::process::id()));
+        let log_path = test_dir.with_extension("log");
+        let log = File::create(&log_path)?;
+        let process = Command::new("aptos")
+            .args(["node", "run-localnet", "--test-dir"])
+            .arg(&test_dir)
+            .args(["--force-restart", "--assume-yes"])
+            .stdin(Stdio::null())
+            .stdout(log.try_clone()?)
+            .stderr(log)
+            .spawn()
+            .map_err(|err| JayceError::Config(format!("Failed to run the aptos CLI: {}", err)))?;
+        // Stops the network if it isn't ready.
+        let mut fixture = LocalnetFixture {
+            rest_url: LOCALNET_REST_URL.to_string(),
+            faucet_url: LOCALNET_FAUCET_URL.to_string(),
+            test_dir,
+            log_path,
+            process,
+        };
+        let started_at = Instant::now();
+        while !is_ready(&fixture.rest_url, &fixture.faucet_url).await {
+            if let Some(status) = fixture.process.try_wait()? {
+                return Err(JayceError::Config(format!(
+                    "The local network exited with {} before being ready: {}",
+                    status,
+                    fs::read_to_string(&fixture.log_path)
+                        .unwrap_or_default()
+                        .trim()
+                )));
+            }
+            if started_at.elapsed() > timeout {
+                return Err(JayceError::Config(format!(
+                    "The local network wasn't ready after {:?}",
+                    timeout
+                )));
+            }
+            tokio::time::sleep(Duration::from_millis(500)).await;
+        }
+        Ok(fixture)
+    }
+}
+
+impl Drop for LocalnetFixture {
+    /// Interrupts the network so that it stops its services, kills it if it didn't exit within
+    /// ten seconds, then deletes its data.
+    fn drop(&mut self) {
+        #[cfg(unix)]
+        let _ = Command::new("kill")
+            .args(["-INT", &self.process.id().to_string()])
+            .status();
+        let stopping_at = Instant::now();
+        while matches!(self.process.try_wait(), Ok(None))
+            && stopping_at.elapsed() < Duration::from_secs(10)
+        {
+            thread::sleep(Duration::from_millis(200));
+        }
+        let _ = self.process.kill();
+        let _ = self.process.wait();
+        let _ = fs::remove_dir_all(&self.test_dir);
+        let _ = fs::remove_file(&self.log_path);
+    }
+}
@@ -0,0 +1,387 @@
+use std::collections::VecDeque;
+use std::future;
+use std::io::{self, Write};
+use std::pin::pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use aptos_sdk::rest_client::Client;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyModifiers};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Style};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Paragraph, Row, Table};
+use ratatui::{DefaultTerminal, Frame};
+use tokio::sync::Notify;
+
+use crate::color::colors_enabled;
+use crate::deploy_report::format_apt;
+use crate::progress::Phase;
+
+/// The number of log lines kept by the dashboard.
+const MAX_LOGS: usize = 1000;
+
+const REFRESH_INTERVAL: Duration = Duration::from_millis(200);
+
+const NETWORK_STATUS_INTERVAL: Duration = Duration::from_secs(5);
+
+static DASHBOARD: OnceLock<Dashboard> = OnceLock::new();
+
+/// A full-screen dashboard of a deployment, showing the phase of every package, the logs, the gas
+/// spent so far and the status of the network, for deployments whose logs would scroll away.
+#[derive(Default)]
+pub struct Dashboard {
+    state: Mutex<DashboardState>,
+    running: AtomicBool,
+    render_thread: Mutex<Option<JoinHandle<()>>>,
+    /// Set when Ctrl-C is pressed, the terminal being in raw mode.
+    interrupted: AtomicBool,
+    interrupt: Notify,
+}
+
+#[derive(Default)]
+struct DashboardState {
+    network: String,
+    network_status: String,
+    packages: Vec<PackageRow>,
+    gas_used: u64,
+    /// The fee paid so far, in Octas.
+    fee: u64,
+    logs: VecDeque<String>,
+    /// The lines printed to stdout while the dashboard is shown, printed once it is stopped.
+    stdout: Vec<String>,
+}
+
+struct PackageRow {
+    address_name: String,
+    phase: Option<Phase>,
+    started_at: Option<Instant>,
+    /// How long the package took, once it is finished.
+    elapsed: Option<Duration>,
+}
+
+/// Returns the dashboard while it is shown.
+pub fn dashboard() -> Option<&'static Dashboard> {
+    DASHBOARD
+        .get()
+        .filter(|dashboard| dashboard.running.load(Ordering::SeqCst))
+}
+
+/// Switches the terminal to the dashboard, until [`stop_dashboard`] is called. Pressing Ctrl-C
+/// completes [`interrupted`].
+pub fn start_dashboard() -> io::Result<()> {
+    let terminal = ratatui::try_init()?;
+    let dashboard = DASHBOARD.get_or_init(Dashboard::default);
+    dashboard.running.store(true, Ordering::SeqCst);
+    *dashboard.render_thread.lock().unwrap() =
+        Some(thread::spawn(move || render_loop(dashboard, terminal)));
+    Ok(())
+}
+
+/// Restores the terminal and prints the logs shown by the dashboard, which are lost with the
+/// alternate screen otherwise, then the lines printed to stdout meanwhile.
+pub fn stop_dashboard() {
+    let Some(dashboard) = DASHBOARD.get() else {
+        return;
+    };
+    if !dashboard.running.swap(false, Ordering::SeqCst) {
+        return;
+    }
+    if let Some(render_thread) = dashboard.render_thread.lock().unwrap().take() {
+        let _ = render_thread.join();
+    }
+    ratatui::restore();
+    let state = dashboard.state.lock().unwrap();
+    let mut stderr = io::stderr();
+    for line in &state.logs {
+        let _ = writeln!(stderr, "{}", line);
+    }
+    let mut stdout = io::stdout();
+    for line in &state.stdout {
+        let _ = writeln!(stdout, "{}", line);
+    }
+}
+
+/// Completes once Ctrl-C is pressed while the dashboard is shown, never without a dashboard.
+pub async fn interrupted() {
+    let Some(dashboard) = DASHBOARD.get() else {
+        return future::pending().await;
+    };
+    let mut notified = pin!(dashboard.interrupt.notified());
+    // Registered before checking the flag, so that an interruption in between isn't missed.
+    notified.as_mut().enable();
+    if !dashboard.interrupted.load(Ordering::SeqCst) {
+        notified.await;
+    }
+}
+
+/// Prints a line to stdout, or once the dashboard is stopped while it is shown, since the
+/// dashboard would be drawn over it.
+pub fn print_stdout(line: &str) {
+    match dashboard() {
+        Some(dashboard) => dashboard
+            .state
+            .lock()
+            .unwrap()
+            .stdout
+            .push(line.to_string()),
+        None => println!("{}", line),
+    }
+}
+
+/// Prints a line to stderr, or with the logs once the dashboard is stopped while it is shown.
+pub fn print_stderr(line: &str) {
+    match dashboard() {
+        Some(dashboard) => dashboard.push_log(line),
+        None => eprintln!("{}", line),
+    }
+}
+
+fn render_loop(dashboard: &Dashboard, mut terminal: DefaultTerminal) {
+    while dashboard.running.load(Ordering::SeqCst) {
+        let _ = terminal.draw(|frame| dashboard.draw(frame));
+        if !event::poll(REFRESH_INTERVAL).unwrap_or(false) {
+            continue;
+        }
+        // The terminal is in raw mode, so Ctrl-C doesn't interrupt jayce by itself.
+        if let Ok(Event::Key(key)) = event::read() {
+            if key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL) {
+                dashboard.interrupted.store(true, Ordering::SeqCst);
+                dashboard.interrupt.notify_waiters();
+            }
+        }
+    }
+}
+
+impl Dashboard {
+    /// Lists the packages of the deployment, in deployment order.
+    pub fn set_packages(&self, address_names: impl IntoIterator<Item = String>) {
+        self.state.lock().unwrap().packages = address_names
+            .into_iter()
+            .map(|address_name| PackageRow {
+                address_name,
+                phase: None,
+                started_at: None,
+                elapsed: None,
+            })
+            .collect();
+    }
+
+    pub fn set_phase(&self, address_name: &str, phase: Phase) {
+        let mut state = self.state.lock().unwrap();
+        let index = match state
+            .packages
+            .iter()
+            .position(|package| package.address_name == address_name)
+        {
+            Some(index) => index,
+            None => {
+                state.packages.push(PackageRow {
+                    address_name: address_name.to_string(),
+                    phase: None,
+                    started_at: None,
+                    elapsed: None,
+                });
+                state.packages.len() - 1
+            }
+        };
+        let package = &mut state.packages[index];
+        let started_at = *package.started_at.get_or_insert_with(Instant::now);
+        package.phase = Some(phase);
+        if matches!(
+            phase,
+            Phase::Confirmed | Phase::Simulated | Phase::Skipped | Phase::Failed
+        ) {
+            package.elapsed = Some(started_at.elapsed());
+        }
+    }
+
+    /// Adds a transaction to the gas spent so far.
+    pub fn add_gas(&self, gas_used: u64, gas_unit_price: u64) {
+        let mut state = self.state.lock().unwrap();
+        state.gas_used += gas_used;
+        state.fee += gas_used * gas_unit_price;
+    }
+
+    /// Polls the status of the network of `client` until the returned task is dropped.
+    pub fn watch_network(&'static self, network: String, client: Client) -> NetworkStatusTask {
+        self.state.lock().unwrap().network = network;
+        NetworkStatusTask(tokio::spawn(async move {
+            loop {
+                let status = match client.get_index().await {
+                    Ok(index) => {
+                        let index = index.into_inner();
+                        format!(
+                            "chain id {}, block {}, ledger version {}",
+                            index.chain_id, index.block_height, index.ledger_version
+                        )
+                    }
+                    Err(err) => format!("unreachable: {}", err),
+                };
+                self.state.lock().unwrap().network_status = status;
+                tokio::time::sleep(NETWORK_STATUS_INTERVAL).await;
+            }
+        }))
+    }
+
+    fn push_log(&self, line: &str) {
+        let mut state = self.state.lock().unwrap();
+        if state.logs.len() == MAX_LOGS {
+            state.logs.pop_front();
+        }
+        state.logs.push_back(line.to_string());
+    }
+
+    fn draw(&self, frame: &mut Frame) {
+        let state = self.state.lock().unwrap();
+        let [header_area, packages_area, logs_area] = Layout::vertical([
+            Constraint::Length(3),
+            Constraint::Min(5),
+            Constraint::Percentage(40),
+        ])
+        .areas(frame.area());
+
+        let finished = state
+            .packages
+            .iter()
+            .filter(|package| package.elapsed.is_some())
+            .count();
+        let header = format!(
+            "Network: {} ({})  Gas used: {} ({} APT)  Packages: {}/{}",
+            state.network,
+            if state.network_status.is_empty() {
+                "connecting"
+            } else {
+                &state.network_status
+            },
+            state.gas_used,
+            format_apt(state.fee),
+            finished,
+            state.packages.len()
+        );
+        frame.render_widget(
+            Paragraph::new(header).block(Block::bordered().title("jayce")),
+            header_area,
+        );
+
+        let rows = state.packages.iter().map(|package| {
+            let elapsed = package
+                .elapsed
+                .or(package.started_at.map(|started_at| started_at.elapsed()))
+                .map(|elapsed| format!("{:.1}s", elapsed.as_secs_f64()))
+                .unwrap_or_default();
+            let color = match package.phase {
+                Some(Phase::Confirmed | Phase::Simulated) => Color::Green,
+                Some(Phase::Failed) => Color::Red,
+                Some(Phase::Skipped) | None => Color::DarkGray,
+                Some(_) => Color::Yellow,
+            };
+            Row::new(vec![
+                package.address_name.clone(),
+                package
+                    .phase
+                    .map(|phase| phase.to_string())
+                    .unwrap_or_else(|| "pending".to_string()),
+                elapsed,
+            ])
+            .style(if colors_enabled() {
+                Style::default().fg(color)
+            } else {
+                Style::default()
+            })
+        });
+        frame.render_widget(
+            Table::new(
+                rows,
+                [
+                    Constraint::Percentage(50),
+                    Constraint::Length(14),
+                    Constraint::Length(10),
+                ],
+            )
+            .header(Row::new(vec!["Package", "Phase", "Elapsed"]))
+            .block(Block::bordered().title("Packages")),
+            packages_area,
+        );
+
+        let height = logs_area.height.saturating_sub(2) as usize;
+        let logs: Vec<Line> = state
+            .logs
+            .iter()
+            .skip(state.logs.len().saturating_sub(height))
+            .map(|line| Line::raw(line.as_str()))
+            .collect();
+        frame.render_widget(
+            Paragraph::new(logs).block(Block::bordered().title("Logs")),
+            logs_area,
+        );
+    }
+}
+
+/// The task polling the status of the network, aborted when dropped.
+pub struct NetworkStatusTask(tokio::task::JoinHandle<()>);
+
+impl Drop for NetworkStatusTask {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+/// A writer adding the logs to the dashboard while it is shown, and writing them to stderr
+/// otherwise.
+pub struct DashboardWriter;
+
+impl Write for DashboardWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match dashboard() {
+            Some(dashboard) => {
+                for line in String::from_utf8_lossy(buf).lines() {
+                    if !line.is_empty() {
+                        dashboard.push_log(line);
+                    }
+                }
+                Ok(buf.len())
+            }
+            None => io::stderr().write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        io::stderr().flush()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ratatui::backend::TestBackend;
+    use ratatui::Terminal;
+
+    use crate::progress::Phase;
+    use crate::tui::Dashboard;
+
+    #[test]
+    fn test_dashboard() {
+        let dashboard = Dashboard::default();
+        dashboard.set_packages(["lib_addr".to_string(), "verifier_addr".to_string()]);
+        dashboard.set_phase("lib_addr", Phase::Confirmed);
+        dashboard.set_phase("verifier_addr", Phase::Submitting);
+        dashboard.add_gas(1000, 100);
+        dashboard.push_log("Deploying verifier_addr");
+
+        let mut terminal = Terminal::new(TestBackend::new(100, 20)).unwrap();
+        terminal.draw(|frame| dashboard.draw(frame)).unwrap();
+        let content: String = terminal
+            .backend()
+            .buffer()
+            .content()
+            .iter()
+            .map(|cell| cell.symbol())
+            .collect();
+        assert!(content.contains("Gas used: 1000 (0.00100000 APT)  Packages: 1/2"));
+        assert!(content.contains("confirmed"));
+        assert!(content.contains("submitting"));
+        assert!(content.contains("Deploying verifier_addr"));
+    }
+}
@@ -1,46 +1,259 @@
+use std::collections::BTreeMap;
 use std::str::FromStr;
+use std::time::{Duration, Instant};
 
 use anyhow::anyhow;
-use aptos_sdk::rest_client::FaucetClient;
+use aptos_sdk::bcs;
+use aptos_sdk::crypto::HashValue;
+use aptos_sdk::move_types::account_address::AccountAddress;
+use aptos_sdk::rest_client::aptos_api_types::{AptosErrorCode, Transaction};
+use aptos_sdk::rest_client::error::RestError;
+use aptos_sdk::rest_client::{Client, FaucetClient};
 use aptos_sdk::types::LocalAccount;
 use rand::rngs::OsRng;
+use tracing::warn;
 use url::Url;
 
 use crate::deploy_config::AptosNetwork;
+use crate::entry_function::{run_view_function, EntryFunctionCall};
+use crate::error::{JayceError, Result};
+use crate::retry::{RetryPolicy, Transient};
 
 pub const DEFAULT_FAUCET_AMOUNT: u64 = 100_000_000;
 
+const OBJECT_CODE_DEPLOYMENT_DOMAIN_SEPARATOR: &[u8] = b"aptos_framework::object_code_deployment";
+const OBJECT_FROM_SEED_ADDRESS_SCHEME: u8 = 0xFE;
+
 pub async fn generate_account_and_faucet(
     network: &AptosNetwork,
-    mut faucet_url: Option<String>,
-    mut rest_url: Option<String>,
-) -> anyhow::Result<LocalAccount> {
+    faucet_urls: &[String],
+    rest_url: Option<String>,
+    retry: &RetryPolicy,
+) -> Result<LocalAccount> {
     let account = LocalAccount::generate(&mut OsRng);
-    if faucet_url.is_none() {
-        faucet_url = network.faucet_url();
+    fund_account(
+        network,
+        faucet_urls,
+        rest_url,
+        account.address(),
+        DEFAULT_FAUCET_AMOUNT,
+        retry,
+    )
+    .await?;
+    Ok(account)
+}
+
+/// Funds an account with `amount` octas from the first of `faucet_urls` that succeeds, or from the
+/// faucet of the network when none is given. Each faucet is retried with the backoff of the retry
+/// policy before falling back on the next one.
+pub async fn fund_account(
+    network: &AptosNetwork,
+    faucet_urls: &[String],
+    rest_url: Option<String>,
+    address: AccountAddress,
+    amount: u64,
+    retry: &RetryPolicy,
+) -> Result<()> {
+    let faucet_urls = match faucet_urls {
+        [] => vec![network.faucet_url().ok_or_else(|| {
+            JayceError::Config(format!("Faucet URL not found for network: {}", network))
+        })?],
+        faucet_urls => faucet_urls.to_vec(),
+    };
+    let rest_url = Url::from_str(&resolve_rest_url(network, rest_url)?)
+        .map_err(|err| JayceError::Config(err.to_string()))?;
+    let mut errors = vec![];
+    for faucet_url in faucet_urls {
+        let faucet_client = FaucetClient::new(
+            Url::from_str(&faucet_url).map_err(|err| JayceError::Config(err.to_string()))?,
+            rest_url.clone(),
+        );
+        match retry.run(|| faucet_client.fund(address, amount)).await {
+            Ok(()) => return Ok(()),
+            Err(err) => {
+                warn!("Faucet {} failed: {}", faucet_url, err);
+                errors.push(format!("{}: {}", faucet_url, err));
+            }
+        }
     }
-    if faucet_url.is_none() {
-        return Err(anyhow!(format!(
-            "Faucet URL not found for network: {}",
-            network
-        )));
+    Err(JayceError::Faucet(errors.join("; ")))
+}
+
+/// Funds an account from the faucet of the network if it doesn't exist on chain yet, as
+/// `aptos init` does.
+pub async fn fund_account_if_missing(
+    network: &AptosNetwork,
+    faucet_urls: &[String],
+    rest_url: Option<String>,
+    address: AccountAddress,
+    retry: &RetryPolicy,
+) -> Result<()> {
+    if faucet_urls.is_empty() && network.faucet_url().is_none() {
+        return Ok(());
     }
-    if rest_url.is_none() {
-        rest_url = network.rest_url();
+    if rest_client(network, rest_url.clone())?
+        .get_account(address)
+        .await
+        .is_ok()
+    {
+        return Ok(());
     }
-    if rest_url.is_none() {
-        return Err(anyhow!(format!(
-            "REST URL not found for network: {}",
-            network
+    fund_account(
+        network,
+        faucet_urls,
+        rest_url,
+        address,
+        DEFAULT_FAUCET_AMOUNT,
+        retry,
+    )
+    .await
+}
+
+/// Returns the APT balance of an account, in octas.
+pub async fn get_balance(rest_url: &str, address: AccountAddress) -> Result<u64> {
+    let call = EntryFunctionCall {
+        function_id: "0x1::coin::balance".to_string(),
+        type_args: vec!["0x1::aptos_coin::AptosCoin".to_string()],
+        args: vec![format!("address:{}", address)],
+    };
+    run_view_function(&call, &BTreeMap::new(), rest_url)
+        .await?
+        .first()
+        .and_then(|value| value.as_str())
+        .and_then(|value| value.parse().ok())
+        .ok_or_else(|| anyhow!("Failed to read the balance of account {}", address).into())
+}
+
+/// Returns `rest_url` when it is provided, otherwise the REST URL of the network.
+pub fn resolve_rest_url(network: &AptosNetwork, rest_url: Option<String>) -> Result<String> {
+    match rest_url {
+        None => network.rest_url().ok_or_else(|| {
+            JayceError::Config(format!("REST URL not found for network: {}", network))
+        }),
+        Some(rest_url) => Ok(rest_url),
+    }
+}
+
+/// Creates a REST client for the network, preferring `rest_url` when it is provided.
+pub fn rest_client(network: &AptosNetwork, rest_url: Option<String>) -> Result<Client> {
+    let rest_url = resolve_rest_url(network, rest_url)?;
+    Ok(Client::new(
+        Url::from_str(&rest_url).map_err(|err| JayceError::Config(err.to_string()))?,
+    ))
+}
+
+/// Checks that the REST API of the network serves the chain with the expected chain id.
+pub async fn check_chain_id(
+    network: &AptosNetwork,
+    rest_url: Option<String>,
+    expected_chain_id: u8,
+) -> Result<()> {
+    let chain_id = rest_client(network, rest_url)?
+        .get_index()
+        .await?
+        .into_inner()
+        .chain_id;
+    if chain_id != expected_chain_id {
+        return Err(JayceError::Config(format!(
+            "Expected chain id {}, but the REST API serves chain id {}",
+            expected_chain_id, chain_id
         )));
     }
-    let faucet_client = FaucetClient::new(
-        Url::from_str(&faucet_url.unwrap())?,
-        Url::from_str(&rest_url.unwrap())?,
-    );
+    Ok(())
+}
 
-    faucet_client
-        .fund(account.address(), DEFAULT_FAUCET_AMOUNT)
-        .await?;
-    Ok(account)
+/// Polls a transaction every `poll_interval` until it is committed. Fails with the hash of the
+/// transaction if it is still pending after `timeout`, or if it was committed but failed.
+pub async fn wait_for_transaction(
+    client: &Client,
+    hash: HashValue,
+    timeout: Duration,
+    poll_interval: Duration,
+) -> Result<Transaction> {
+    let started_at = Instant::now();
+    loop {
+        match client.get_transaction_by_hash(hash).await {
+            Ok(response) if !response.inner().is_pending() => {
+                let transaction = response.into_inner();
+                if !transaction.success() {
+                    return Err(JayceError::Publish(format!(
+                        "Transaction {} failed: {}",
+                        hash,
+                        transaction.vm_status()
+                    )));
+                }
+                return Ok(transaction);
+            }
+            Ok(_) => {}
+            // The transaction isn't known until it reaches the mempool of the node.
+            Err(RestError::Api(err))
+                if err.error.error_code == AptosErrorCode::TransactionNotFound => {}
+            Err(err) if err.is_transient() => {}
+            Err(err) => return Err(err.into()),
+        }
+        if started_at.elapsed() >= timeout {
+            return Err(JayceError::Publish(format!(
+                "Transaction still pending after {} seconds, hash = {}",
+                timeout.as_secs(),
+                hash
+            )));
+        }
+        tokio::time::sleep(poll_interval).await;
+    }
+}
+
+/// Whether a request failed because the account, resource, module or transaction doesn't exist,
+/// rather than because the node couldn't be reached.
+pub fn is_not_found(err: &RestError) -> bool {
+    match err {
+        RestError::Api(err) => err.status_code.as_u16() == 404,
+        RestError::Http(status_code, _) => status_code.as_u16() == 404,
+        _ => false,
+    }
+}
+
+/// Returns the account whose authentication key was rotated to the key of `address`, the address
+/// derived from that key, or `address` itself if no key was rotated to it.
+pub async fn lookup_address(client: &Client, address: AccountAddress) -> Result<AccountAddress> {
+    let resource = client
+        .get_account_resource(AccountAddress::ONE, "0x1::account::OriginatingAddress")
+        .await?
+        .into_inner();
+    let Some(handle) = resource
+        .as_ref()
+        .and_then(|resource| resource.data.pointer("/address_map/handle"))
+        .and_then(|handle| handle.as_str())
+        .and_then(|handle| AccountAddress::from_hex_literal(handle).ok())
+    else {
+        return Ok(address);
+    };
+    match client
+        .get_table_item(handle, "address", "address", address.to_hex_literal())
+        .await
+    {
+        Ok(response) => response
+            .into_inner()
+            .as_str()
+            .and_then(|originating| AccountAddress::from_hex_literal(originating).ok())
+            .ok_or_else(|| {
+                JayceError::Config(format!("Invalid originating address of {}", address))
+            }),
+        Err(RestError::Api(err)) if err.error.error_code == AptosErrorCode::TableItemNotFound => {
+            Ok(address)
+        }
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Derives the address of the object created by `object_code_deployment::publish` when it is
+/// sent by `creator` in the transaction with sequence number `sequence_number`.
+pub fn create_object_code_deployment_address(
+    creator: AccountAddress,
+    sequence_number: u64,
+) -> AccountAddress {
+    let mut input = creator.to_vec();
+    input.extend(bcs::to_bytes(OBJECT_CODE_DEPLOYMENT_DOMAIN_SEPARATOR).unwrap());
+    input.extend(bcs::to_bytes(&(sequence_number + 1)).unwrap());
+    input.push(OBJECT_FROM_SEED_ADDRESS_SCHEME);
+    AccountAddress::from_bytes(HashValue::sha3_256_of(&input).to_vec()).unwrap()
 }
@@ -0,0 +1,194 @@
+/// Hints for the VM statuses a publication commonly fails with.
+const STATUS_HINTS: [(&str, &str); 6] = [
+    (
+        "OUT_OF_GAS",
+        "the transaction ran out of gas, raise --max-gas",
+    ),
+    (
+        "LINKER_ERROR",
+        "a module depends on a module that isn't published, check the named addresses of the \
+        dependencies",
+    ),
+    (
+        "BACKWARD_INCOMPATIBLE_MODULE_UPDATE",
+        "the upgrade isn't compatible with the published package",
+    ),
+    (
+        "INSUFFICIENT_BALANCE_FOR_TRANSACTION_FEE",
+        "the sender can't pay the fee of the transaction",
+    ),
+    (
+        "MAX_GAS_UNITS_EXCEEDS_MAX_GAS_UNITS_BOUND",
+        "--max-gas is above the maximum of the network",
+    ),
+    (
+        "SEQUENCE_NUMBER_TOO_OLD",
+        "another transaction of the sender was committed meanwhile",
+    ),
+];
+
+/// Categories of the abort codes of the Aptos framework, the upper 16 bits of a code.
+const ABORT_CATEGORIES: [&str; 13] = [
+    "INVALID_ARGUMENT",
+    "OUT_OF_RANGE",
+    "INVALID_STATE",
+    "UNAUTHENTICATED",
+    "PERMISSION_DENIED",
+    "NOT_FOUND",
+    "ABORTED",
+    "ALREADY_EXISTS",
+    "RESOURCE_EXHAUSTED",
+    "CANCELLED",
+    "INTERNAL",
+    "NOT_IMPLEMENTED",
+    "UNAVAILABLE",
+];
+
+/// Decodes the VM status of a failed transaction, as returned by the REST API or by a local
+/// simulation, into the module, function and abort code it failed at. Statuses that can't be
+/// decoded are returned unchanged.
+pub fn describe_vm_status(vm_status: &str) -> String {
+    if let Some(abort) = parse_move_abort(vm_status) {
+        return abort;
+    }
+    if let Some(failure) = between(vm_status, "Execution failed in ", " at code offset") {
+        let offset = after(vm_status, " at code offset ").unwrap_or_default();
+        return format!(
+            "execution failed in function {} at code offset {}",
+            failure,
+            offset.trim()
+        );
+    }
+    for (status, hint) in STATUS_HINTS {
+        if vm_status.contains(status) {
+            return format!("{}: {}", status, hint);
+        }
+    }
+    vm_status.to_string()
+}
+
+/// Decodes `Move abort in <module>: <NAME>(<code>): <description>` (REST API),
+/// `MoveAbort { location: <module>, code: <code>, info: ... }` (local simulation) and
+/// `... with sub status <code>`.
+fn parse_move_abort(vm_status: &str) -> Option<String> {
+    let (module, code, reason_name, description) =
+        if let Some(rest) = after(vm_status, "Move abort in ") {
+            let (module, rest) = rest.split_once(": ")?;
+            match rest.split_once('(') {
+                Some((reason_name, rest)) => {
+                    let (code, description) = rest.split_once(')')?;
+                    (
+                        module.to_string(),
+                        parse_code(code)?,
+                        Some(reason_name.to_string()),
+                        description.trim_start_matches(':').trim().to_string(),
+                    )
+                }
+                None => (
+                    module.to_string(),
+                    parse_code(rest.trim())?,
+                    None,
+                    String::new(),
+                ),
+            }
+        } else if let Some(module) = between(vm_status, "MoveAbort { location: ", ", code: ") {
+            let code = between(vm_status, ", code: ", ",")
+                .or_else(|| between(vm_status, ", code: ", " }"))?;
+            (
+                module.to_string(),
+                parse_code(code)?,
+                between(vm_status, "reason_name: \"", "\"").map(str::to_string),
+                between(vm_status, "description: \"", "\"")
+                    .unwrap_or_default()
+                    .to_string(),
+            )
+        } else {
+            let code = after(vm_status, "with sub status ")?;
+            let code = parse_code(code.split_whitespace().next()?)?;
+            return Some(format!(
+                "Move abort with code {}",
+                describe_abort_code(code)
+            ));
+        };
+    let mut message = format!(
+        "Move abort in module {} with code {}",
+        module,
+        describe_abort_code(code)
+    );
+    if let Some(reason_name) = reason_name {
+        message.push_str(&format!(": {}", reason_name));
+    }
+    if !description.is_empty() {
+        message.push_str(&format!(", {}", description));
+    }
+    Some(message)
+}
+
+/// Describes an abort code with the category and reason of the Aptos framework convention, e.g.
+/// `0x10005 (INVALID_ARGUMENT, reason 5)`.
+fn describe_abort_code(code: u64) -> String {
+    let category = code >> 16;
+    match ABORT_CATEGORIES.get((category as usize).wrapping_sub(1)) {
+        Some(category) if code >> 24 == 0 => {
+            format!("{:#x} ({}, reason {})", code, category, code & 0xffff)
+        }
+        _ => format!("{:#x}", code),
+    }
+}
+
+fn parse_code(code: &str) -> Option<u64> {
+    match code.strip_prefix("0x") {
+        Some(hex) => u64::from_str_radix(hex, 16).ok(),
+        None => code.parse().ok(),
+    }
+}
+
+fn after<'a>(value: &'a str, prefix: &str) -> Option<&'a str> {
+    value
+        .find(prefix)
+        .map(|index| &value[index + prefix.len()..])
+}
+
+fn between<'a>(value: &'a str, prefix: &str, suffix: &str) -> Option<&'a str> {
+    let rest = after(value, prefix)?;
+    rest.find(suffix).map(|index| &rest[..index])
+}
+
+#[cfg(test)]
+mod test {
+    use crate::vm_status::describe_vm_status;
+
+    #[test]
+    fn test_describe_vm_status() {
+        assert_eq!(
+            describe_vm_status(
+                "Move abort in 0x1::code: EUPGRADE_IMMUTABLE(0x50003): Cannot upgrade an \
+                immutable package"
+            ),
+            "Move abort in module 0x1::code with code 0x50003 (PERMISSION_DENIED, reason 3): \
+            EUPGRADE_IMMUTABLE, Cannot upgrade an immutable package"
+        );
+        assert_eq!(
+            describe_vm_status("Move abort in 0xcafe::verifier: 0x7"),
+            "Move abort in module 0xcafe::verifier with code 0x7"
+        );
+        assert_eq!(
+            describe_vm_status(
+                "MoveAbort { location: 0x1::code, code: 65541, info: Some(AbortInfo { \
+                reason_name: \"EDEP_WEAKER_POLICY\", description: \"\" }) }"
+            ),
+            "Move abort in module 0x1::code with code 0x10005 (INVALID_ARGUMENT, reason 5): \
+            EDEP_WEAKER_POLICY"
+        );
+        assert_eq!(
+            describe_vm_status("status ABORTED of type Execution with sub status 196609"),
+            "Move abort with code 0x30001 (INVALID_STATE, reason 1)"
+        );
+        assert_eq!(
+            describe_vm_status("Execution failed in 0xcafe::cpu::init at code offset 12"),
+            "execution failed in function 0xcafe::cpu::init at code offset 12"
+        );
+        assert!(describe_vm_status("Out of gas (OUT_OF_GAS)").contains("--max-gas"));
+        assert_eq!(describe_vm_status("Unknown"), "Unknown");
+    }
+}
@@ -0,0 +1,155 @@
+use std::time::Duration;
+
+use aptos_sdk::move_types::account_address::AccountAddress;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use strum_macros::Display;
+use tracing::warn;
+
+/// The payload posted to a webhook.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Display)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum WebhookFormat {
+    /// The event as a JSON object, with its name in the `event` field.
+    #[default]
+    Generic,
+    /// A Slack incoming webhook message.
+    Slack,
+    /// A Discord webhook message.
+    Discord,
+}
+
+/// A webhook notified of the progress of a deployment, read from a `[[webhooks]]` table.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct Webhook {
+    pub url: String,
+    #[serde(default)]
+    pub format: WebhookFormat,
+}
+
+/// The events of a deployment posted to the webhooks.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum WebhookEvent {
+    DeploymentStarted {
+        network: String,
+        account: AccountAddress,
+        packages: Vec<String>,
+    },
+    PackageDeployed {
+        network: String,
+        address_name: String,
+        deployed_at: AccountAddress,
+        explorer_url: Option<String>,
+    },
+    DeploymentFailed {
+        network: String,
+        error: String,
+    },
+}
+
+impl WebhookEvent {
+    /// A human readable description of the event, posted to chat webhooks.
+    pub fn message(&self) -> String {
+        match self {
+            WebhookEvent::DeploymentStarted {
+                network,
+                account,
+                packages,
+            } => format!(
+                "Deploying {} to {} from {}",
+                packages.join(", "),
+                network,
+                account
+            ),
+            WebhookEvent::PackageDeployed {
+                network,
+                address_name,
+                deployed_at,
+                explorer_url,
+            } => format!(
+                "{} deployed on {} at {}",
+                address_name,
+                network,
+                explorer_url.clone().unwrap_or(deployed_at.to_string())
+            ),
+            WebhookEvent::DeploymentFailed { network, error } => {
+                format!("Deployment to {} failed: {}", network, error)
+            }
+        }
+    }
+}
+
+impl Webhook {
+    pub fn payload(&self, event: &WebhookEvent) -> serde_json::Value {
+        match self.format {
+            WebhookFormat::Generic => serde_json::to_value(event).unwrap(),
+            WebhookFormat::Slack => json!({ "text": event.message() }),
+            WebhookFormat::Discord => json!({ "content": event.message() }),
+        }
+    }
+}
+
+/// Posts an event to every webhook. Failures are logged, since notifications don't affect the
+/// deployment. The URLs are left out of the logs, as they often embed a secret token.
+pub async fn notify_webhooks(webhooks: &[Webhook], event: &WebhookEvent) {
+    if webhooks.is_empty() {
+        return;
+    }
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(10))
+        .build()
+        .unwrap_or_default();
+    for webhook in webhooks {
+        let result = client
+            .post(&webhook.url)
+            .json(&webhook.payload(event))
+            .send()
+            .await
+            .and_then(|response| response.error_for_status());
+        if let Err(err) = result {
+            warn!(
+                "Failed to notify a {} webhook: {}",
+                webhook.format,
+                err.without_url()
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use aptos_sdk::move_types::account_address::AccountAddress;
+
+    use crate::webhooks::{Webhook, WebhookEvent, WebhookFormat};
+
+    #[test]
+    fn test_webhook_payloads() {
+        let event = WebhookEvent::PackageDeployed {
+            network: "testnet".to_string(),
+            address_name: "cpu_addr".to_string(),
+            deployed_at: AccountAddress::ONE,
+            explorer_url: None,
+        };
+        let webhook = |format| Webhook {
+            url: "https://example.com".to_string(),
+            format,
+        };
+
+        let generic = webhook(WebhookFormat::Generic).payload(&event);
+        assert_eq!(generic["event"], "package_deployed");
+        assert_eq!(generic["address_name"], "cpu_addr");
+
+        let message = format!("cpu_addr deployed on testnet at {}", AccountAddress::ONE);
+        assert_eq!(
+            webhook(WebhookFormat::Slack).payload(&event)["text"],
+            message
+        );
+        assert_eq!(
+            webhook(WebhookFormat::Discord).payload(&event)["content"],
+            message
+        );
+    }
+}